@@ -67,7 +67,77 @@ fn layout(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, layout, load_font_system);
+fn edit_large_buffer(c: &mut Criterion) {
+    use ct::Edit;
+
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    buffer.set_size(&mut fs, 800.0, 600.0);
+    let text: String = (0..100_000)
+        .map(|i| format!("line {i} of a buffer with many lines\n"))
+        .collect();
+    buffer.set_text(&mut fs, &text, ct::Attrs::new(), ct::Shaping::Advanced);
+    let mut editor = ct::Editor::new(buffer);
+    editor.shape_as_needed(&mut fs);
+
+    let mut group = c.benchmark_group("Edit 100k lines");
+    group.bench_function("type at the top", |b| {
+        b.iter(|| {
+            editor.set_cursor(ct::Cursor::new(0, 0));
+            editor.action(&mut fs, ct::Action::Insert('x'));
+            editor.action(&mut fs, ct::Action::Backspace);
+            editor.shape_as_needed(&mut fs);
+            black_box(editor.buffer().damaged_lines());
+            editor.buffer_mut().clear_damage();
+        })
+    });
+    group.bench_function("type at the bottom", |b| {
+        b.iter(|| {
+            editor.set_cursor(ct::Cursor::new(99_999, 0));
+            editor.action(&mut fs, ct::Action::Insert('x'));
+            editor.action(&mut fs, ct::Action::Backspace);
+            editor.shape_as_needed(&mut fs);
+            black_box(editor.buffer().damaged_lines());
+            editor.buffer_mut().clear_damage();
+        })
+    });
+    group.bench_function("estimate height", |b| {
+        b.iter(|| black_box(editor.buffer().estimated_height()))
+    });
+}
+
+fn shape_large_file(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    buffer.set_size(&mut fs, 800.0, f32::MAX);
+    let attrs = ct::Attrs::new().family(ct::Family::Monospace);
+
+    // Source code has many repeated words, like an editor opening a large file
+    let mut group = c.benchmark_group("Shape a source file");
+    group.sample_size(10);
+    for (name, limit) in [("uncached", 0), ("shape run cache", 4096)] {
+        fs.set_shape_run_cache_limit(limit);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                buffer.set_text(
+                    &mut fs,
+                    include_str!("../src/buffer.rs"),
+                    attrs,
+                    ct::Shaping::Advanced,
+                );
+                buffer.shape_until_scroll(&mut fs);
+            })
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    layout,
+    edit_large_buffer,
+    shape_large_file,
+    load_font_system
+);
 
 criterion_main!(benches);
 
@@ -31,5 +31,9 @@ build --no-default-features --features std,vi
 echo Build with all features
 build --all-features
 
+echo Build for wasm32 without system fonts
+rustup target add wasm32-unknown-unknown
+build --target wasm32-unknown-unknown --no-default-features --features std,swash,wasm-web
+
 echo Run tests
 cargo test
@@ -71,7 +71,12 @@ impl fmt::Display for FontSize {
     }
 }
 
-static WRAP_MODE: &[Wrap] = &[Wrap::None, Wrap::Glyph, Wrap::Word];
+static WRAP_MODE: &[Wrap] = &[
+    Wrap::None,
+    Wrap::Glyph,
+    Wrap::Word,
+    Wrap::WordKeepPunctuation,
+];
 
 fn main() -> cosmic::iced::Result {
     env_logger::init();
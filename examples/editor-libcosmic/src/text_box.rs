@@ -6,7 +6,11 @@ use cosmic::{
     theme::{Theme, ThemeType},
 };
 use cosmic_text::{Action, Edit, SwashCache};
-use std::{cmp, sync::Mutex, time::Instant};
+use std::{
+    cmp,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use crate::FONT_SYSTEM;
 
@@ -82,28 +86,24 @@ fn draw_pixel(
     }
 
     let offset = (y as usize * width as usize + x as usize) * 4;
-
-    let mut current = buffer[offset + 2] as u32
-        | (buffer[offset + 1] as u32) << 8
-        | (buffer[offset + 0] as u32) << 16
-        | (buffer[offset + 3] as u32) << 24;
-
-    if alpha >= 255 || current == 0 {
-        // Alpha is 100% or current is null, replace with no blending
-        current = color.0;
+    let pixel = &mut buffer[offset..offset + 4];
+
+    // Every pixel can have its own color, like those of emoji, so each channel is blended with the
+    // color of the pixel instead of the text color
+    let current_alpha = pixel[3] as u32;
+    if alpha >= 255 || current_alpha == 0 {
+        // Alpha is 100% or current is transparent, replace with no blending
+        pixel.copy_from_slice(&color.as_rgba());
     } else {
-        // Alpha blend with current value
-        let n_alpha = 255 - alpha;
-        let rb = ((n_alpha * (current & 0x00FF00FF)) + (alpha * (color.0 & 0x00FF00FF))) >> 8;
-        let ag = (n_alpha * ((current & 0xFF00FF00) >> 8))
-            + (alpha * (0x01000000 | ((color.0 & 0x0000FF00) >> 8)));
-        current = (rb & 0x00FF00FF) | (ag & 0xFF00FF00);
+        // Alpha blend over current value, with straight alpha
+        let current_weight = current_alpha * (255 - alpha) / 255;
+        let new_alpha = alpha + current_weight;
+        for (channel, value) in [color.r(), color.g(), color.b()].into_iter().enumerate() {
+            pixel[channel] =
+                ((value as u32 * alpha + pixel[channel] as u32 * current_weight) / new_alpha) as u8;
+        }
+        pixel[3] = new_alpha as u8;
     }
-
-    buffer[offset + 2] = current as u8;
-    buffer[offset + 1] = (current >> 8) as u8;
-    buffer[offset + 0] = (current >> 16) as u8;
-    buffer[offset + 3] = (current >> 24) as u8;
 }
 
 impl<'a, 'editor, Editor, Message, Renderer> Widget<Message, Renderer> for TextBox<'a, Editor>
@@ -131,22 +131,13 @@ where
     fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
         let limits = limits.width(Length::Fill).height(Length::Fill);
 
-        //TODO: allow lazy shape
         let mut editor = self.editor.lock().unwrap();
         editor
             .borrow_with(&mut FONT_SYSTEM.lock().unwrap())
             .buffer_mut()
-            .shape_until(i32::max_value());
+            .shape_until_scroll();
 
-        let mut layout_lines = 0;
-        for line in editor.buffer().lines.iter() {
-            match line.layout_opt() {
-                Some(layout) => layout_lines += layout.len(),
-                None => (),
-            }
-        }
-
-        let height = layout_lines as f32 * editor.buffer().metrics().line_height;
+        let height = editor.buffer().estimated_height();
         let size = Size::new(limits.max().width, height);
         log::info!("size {:?}", size);
 
@@ -269,7 +260,7 @@ where
         layout: Layout<'_>,
         cursor_position: mouse::Cursor,
         _renderer: &Renderer,
-        _clipboard: &mut dyn Clipboard,
+        clipboard: &mut dyn Clipboard,
         _shell: &mut Shell<'_, Message>,
     ) -> Status {
         let state = tree.state.downcast_mut::<State>();
@@ -279,37 +270,70 @@ where
 
         let mut status = Status::Ignored;
         match event {
-            Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => match key_code {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) => match key_code {
+                KeyCode::A if modifiers.command() => {
+                    editor.action(Action::SelectAll);
+                    status = Status::Captured;
+                }
+                KeyCode::C if modifiers.command() => {
+                    if let Some(text) = editor.copy_selection() {
+                        clipboard.write(text);
+                    }
+                    status = Status::Captured;
+                }
+                KeyCode::X if modifiers.command() => {
+                    if let Some(text) = editor.cut_selection() {
+                        clipboard.write(text);
+                    }
+                    status = Status::Captured;
+                }
+                KeyCode::V if modifiers.command() => {
+                    if let Some(text) = clipboard.read() {
+                        editor.action(Action::Paste(text));
+                    }
+                    status = Status::Captured;
+                }
+                KeyCode::Left if modifiers.control() => {
+                    editor.action(motion(modifiers, Action::LeftWord));
+                    status = Status::Captured;
+                }
+                KeyCode::Right if modifiers.control() => {
+                    editor.action(motion(modifiers, Action::RightWord));
+                    status = Status::Captured;
+                }
                 KeyCode::Left => {
-                    editor.action(Action::Left);
+                    editor.action(motion(modifiers, Action::Left));
                     status = Status::Captured;
                 }
                 KeyCode::Right => {
-                    editor.action(Action::Right);
+                    editor.action(motion(modifiers, Action::Right));
                     status = Status::Captured;
                 }
                 KeyCode::Up => {
-                    editor.action(Action::Up);
+                    editor.action(motion(modifiers, Action::Up));
                     status = Status::Captured;
                 }
                 KeyCode::Down => {
-                    editor.action(Action::Down);
+                    editor.action(motion(modifiers, Action::Down));
                     status = Status::Captured;
                 }
                 KeyCode::Home => {
-                    editor.action(Action::Home);
+                    editor.action(motion(modifiers, Action::Home));
                     status = Status::Captured;
                 }
                 KeyCode::End => {
-                    editor.action(Action::End);
+                    editor.action(motion(modifiers, Action::End));
                     status = Status::Captured;
                 }
                 KeyCode::PageUp => {
-                    editor.action(Action::PageUp);
+                    editor.action(motion(modifiers, Action::PageUp));
                     status = Status::Captured;
                 }
                 KeyCode::PageDown => {
-                    editor.action(Action::PageDown);
+                    editor.action(motion(modifiers, Action::PageDown));
                     status = Status::Captured;
                 }
                 KeyCode::Escape => {
@@ -320,6 +344,14 @@ where
                     editor.action(Action::Enter);
                     status = Status::Captured;
                 }
+                KeyCode::Backspace if modifiers.control() => {
+                    editor.action(Action::DeleteWordBackward);
+                    status = Status::Captured;
+                }
+                KeyCode::Delete if modifiers.control() => {
+                    editor.action(Action::DeleteWordForward);
+                    status = Status::Captured;
+                }
                 KeyCode::Backspace => {
                     editor.action(Action::Backspace);
                     status = Status::Captured;
@@ -330,15 +362,21 @@ where
                 }
                 _ => (),
             },
-            Event::Keyboard(keyboard::Event::CharacterReceived(character)) => {
+            Event::Keyboard(keyboard::Event::CharacterReceived(character))
+                if !character.is_control() =>
+            {
                 editor.action(Action::Insert(character));
                 status = Status::Captured;
             }
             Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
                 if let Some(position_in) = cursor_position.position_in(layout.bounds()) {
-                    editor.action(Action::Click {
-                        x: position_in.x as i32 - self.padding.left as i32,
-                        y: position_in.y as i32 - self.padding.top as i32,
+                    let x = position_in.x as i32 - self.padding.left as i32;
+                    let y = position_in.y as i32 - self.padding.top as i32;
+                    let clicks = state.click(x, y);
+                    editor.action(match clicks {
+                        1 => Action::Click { x, y },
+                        2 => Action::DoubleClick { x, y },
+                        _ => Action::TripleClick { x, y },
                     });
                     state.is_dragging = true;
                     status = Status::Captured;
@@ -364,7 +402,12 @@ where
                     });
                     status = Status::Captured;
                 }
-                _ => (),
+                mouse::ScrollDelta::Pixels { y, .. } => {
+                    let mut buffer = editor.buffer_mut();
+                    let scroll_pixels = buffer.scroll_pixels() - y;
+                    buffer.set_scroll_pixels(scroll_pixels);
+                    status = Status::Captured;
+                }
             },
             _ => (),
         }
@@ -385,8 +428,24 @@ where
     }
 }
 
+/// Maximum time between clicks counted as a double or triple click
+const MULTI_CLICK_TIME: Duration = Duration::from_millis(500);
+/// Maximum distance in pixels between clicks counted as a double or triple click
+const MULTI_CLICK_DISTANCE: i32 = 4;
+
+/// Extend the selection with a movement action while shift is held
+fn motion(modifiers: keyboard::Modifiers, action: Action) -> Action {
+    if modifiers.shift() {
+        Action::Select(Box::new(action))
+    } else {
+        action
+    }
+}
+
 pub struct State {
     is_dragging: bool,
+    /// Time, position, and count of the last click
+    last_click: Option<(Instant, i32, i32, u8)>,
     cache: Mutex<SwashCache>,
 }
 
@@ -395,7 +454,26 @@ impl State {
     pub fn new() -> State {
         State {
             is_dragging: false,
+            last_click: None,
             cache: Mutex::new(SwashCache::new()),
         }
     }
+
+    /// Record a click, returning 1 for a single click, 2 for a double click, or 3 for a triple
+    /// click. A fourth click starts again from a single click.
+    fn click(&mut self, x: i32, y: i32) -> u8 {
+        let now = Instant::now();
+        let clicks = match self.last_click {
+            Some((instant, last_x, last_y, clicks))
+                if now.duration_since(instant) <= MULTI_CLICK_TIME
+                    && (x - last_x).abs() <= MULTI_CLICK_DISTANCE
+                    && (y - last_y).abs() <= MULTI_CLICK_DISTANCE =>
+            {
+                clicks % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some((now, x, y, clicks));
+        clicks
+    }
 }
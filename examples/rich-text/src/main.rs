@@ -1,8 +1,8 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use cosmic_text::{
-    Action, Attrs, AttrsList, Buffer, BufferLine, Color, Edit, Editor, Family, FontSystem, Metrics,
-    Shaping, Style, SwashCache, Weight,
+    Action, Attrs, Buffer, Color, Edit, Editor, Family, FontSystem, Metrics, Shaping, Style,
+    SwashCache, Weight,
 };
 use orbclient::{EventOption, Renderer, Window, WindowFlag};
 use std::{
@@ -52,8 +52,6 @@ fn main() {
     let mono_attrs = attrs.family(Family::Monospace);
     let comic_attrs = attrs.family(Family::Name("Comic Neue"));
 
-    editor.buffer_mut().lines.clear();
-
     let lines: &[&[(&str, Attrs)]] = &[
         &[
             ("B", attrs.weight(Weight::BOLD)),
@@ -68,7 +66,7 @@ fn main() {
             ("i ", attrs),
         ],
         &[
-            ("Sans-Serif Normal ", attrs),
+            ("\nSans-Serif Normal ", attrs),
             ("Sans-Serif Bold ", attrs.weight(Weight::BOLD)),
             ("Sans-Serif Italic ", attrs.style(Style::Italic)),
             (
@@ -77,7 +75,7 @@ fn main() {
             ),
         ],
         &[
-            ("Serif Normal ", serif_attrs),
+            ("\nSerif Normal ", serif_attrs),
             ("Serif Bold ", serif_attrs.weight(Weight::BOLD)),
             ("Serif Italic ", serif_attrs.style(Style::Italic)),
             (
@@ -86,7 +84,7 @@ fn main() {
             ),
         ],
         &[
-            ("Mono Normal ", mono_attrs),
+            ("\nMono Normal ", mono_attrs),
             ("Mono Bold ", mono_attrs.weight(Weight::BOLD)),
             ("Mono Italic ", mono_attrs.style(Style::Italic)),
             (
@@ -95,7 +93,7 @@ fn main() {
             ),
         ],
         &[
-            ("Comic Normal ", comic_attrs),
+            ("\nComic Normal ", comic_attrs),
             ("Comic Bold ", comic_attrs.weight(Weight::BOLD)),
             ("Comic Italic ", comic_attrs.style(Style::Italic)),
             (
@@ -104,7 +102,7 @@ fn main() {
             ),
         ],
         &[
-            ("R", attrs.color(Color::rgb(0xFF, 0x00, 0x00))),
+            ("\nR", attrs.color(Color::rgb(0xFF, 0x00, 0x00))),
             ("A", attrs.color(Color::rgb(0xFF, 0x7F, 0x00))),
             ("I", attrs.color(Color::rgb(0xFF, 0xFF, 0x00))),
             ("N", attrs.color(Color::rgb(0x00, 0xFF, 0x00))),
@@ -127,24 +125,15 @@ fn main() {
             ("N", attrs.color(Color::rgb(0xFF, 0x00, 0x00))),
         ],
         &[(
-            "生活,삶,जिंदगी 😀 FPS",
+            "\n生活,삶,जिंदगी 😀 FPS",
             attrs.color(Color::rgb(0xFF, 0x00, 0x00)),
         )],
     ];
-    for &line in lines {
-        let mut line_text = String::new();
-        let mut attrs_list = AttrsList::new(attrs);
-        for &(text, attrs) in line {
-            let start = line_text.len();
-            line_text.push_str(text);
-            let end = line_text.len();
-            attrs_list.add_span(start..end, attrs);
-        }
-        editor
-            .buffer_mut()
-            .lines
-            .push(BufferLine::new(line_text, attrs_list, Shaping::Advanced));
-    }
+    editor.buffer_mut().set_rich_text(
+        lines.iter().flat_map(|line| line.iter().copied()),
+        attrs,
+        Shaping::Advanced,
+    );
 
     let mut swash_cache = SwashCache::new();
 
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use cosmic_text::{fontdb, Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, SwashCache};
+use std::{cmp, sync::Arc};
+use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+// There are no system fonts in the browser, so the fonts are bundled with the example
+static FONT: &[u8] = include_bytes!("../../../fonts/FiraMono-Medium.ttf");
+
+/// Draw text into the RGBA pixels of a `width` by `height` image
+pub fn render(text: &str, width: u32, height: u32) -> Vec<u8> {
+    let mut font_system =
+        FontSystem::new_with_embedded_fonts([fontdb::Source::Binary(Arc::new(FONT))]);
+    let mut swash_cache = SwashCache::new();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(32.0, 44.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(width as f32, height as f32);
+    buffer.set_text(
+        text,
+        Attrs::new().family(Family::Monospace),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll();
+
+    // Start with an opaque black background
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = 0xFF;
+    }
+
+    let text_color = Color::rgb(0xFF, 0xFF, 0xFF);
+    buffer.draw(&mut swash_cache, text_color, |x, y, w, h, color| {
+        let alpha = color.a() as u32;
+        // Only the part of the rectangle inside the image is drawn
+        for row in cmp::max(y, 0)..cmp::min(y + h as i32, height as i32) {
+            for col in cmp::max(x, 0)..cmp::min(x + w as i32, width as i32) {
+                let i = ((row as u32 * width + col as u32) * 4) as usize;
+                for (channel, value) in [color.r(), color.g(), color.b()].into_iter().enumerate() {
+                    let old = pixels[i + channel] as u32;
+                    pixels[i + channel] =
+                        ((value as u32 * alpha + old * (255 - alpha)) / 255) as u8;
+                }
+            }
+        }
+    });
+
+    pixels
+}
+
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document")?;
+    let canvas: HtmlCanvasElement = document
+        .get_element_by_id("canvas")
+        .ok_or("no canvas element")?
+        .dyn_into()?;
+    let context: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or("no 2d context")?
+        .dyn_into()?;
+
+    let (width, height) = (canvas.width(), canvas.height());
+    let pixels = render("Hi, Rust!\nCOSMIC TEXT in the browser", width, height);
+    let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), width, height)?;
+    context.put_image_data(&image, 0.0, 0.0)
+}
@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::cmp;
+
+use crate::{CacheKey, FontSystem, HashMap, SwashCache, SwashContent};
+
+/// Pixels left empty to the right of and below each glyph, so that sampling one glyph with
+/// linear filtering does not bleed into its neighbours
+const PADDING: u32 = 1;
+
+/// A glyph image placed in a [`GlyphAtlas`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasGlyph {
+    /// Index of the page with the image, see [`GlyphAtlas::pages`]
+    pub page: usize,
+    /// X position of the image in the page, in pixels
+    pub x: u32,
+    /// Y position of the image in the page, in pixels
+    pub y: u32,
+    /// Width of the image, in pixels
+    pub width: u32,
+    /// Height of the image, in pixels
+    pub height: u32,
+    /// Horizontal offset of the image from the glyph position, in pixels
+    pub left: i32,
+    /// Vertical offset of the top of the image from the glyph position, in pixels, measured upward
+    pub top: i32,
+    /// Content of the image, which is the same as the content of its page
+    pub content: SwashContent,
+}
+
+impl AtlasGlyph {
+    /// Get the texture coordinates of the image in a page of `page_size` pixels, from 0.0 to 1.0,
+    /// as the left, top, right, and bottom edges
+    pub fn uv(&self, page_size: u32) -> [f32; 4] {
+        let size = page_size as f32;
+        [
+            self.x as f32 / size,
+            self.y as f32 / size,
+            (self.x + self.width) as f32 / size,
+            (self.y + self.height) as f32 / size,
+        ]
+    }
+
+    /// Get the rectangle to draw the image at, for a glyph at `x` and `y`, such as the position of
+    /// a [`crate::PhysicalGlyph`] offset by the line y of its run
+    pub fn rect(&self, x: i32, y: i32) -> (i32, i32, u32, u32) {
+        (x + self.left, y - self.top, self.width, self.height)
+    }
+}
+
+/// A row of glyphs in an [`AtlasPage`]
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+/// A texture page of a [`GlyphAtlas`]
+///
+/// Pages are square, with the size of [`GlyphAtlas::page_size`]. Each page only has images of one
+/// [`SwashContent`]. Pages of alpha masks have one byte per pixel, and pages of color images and
+/// subpixel masks have four, in RGBA order.
+#[derive(Debug)]
+pub struct AtlasPage {
+    content: SwashContent,
+    size: u32,
+    data: Vec<u8>,
+    shelves: Vec<Shelf>,
+    damage: Option<(u32, u32, u32, u32)>,
+}
+
+impl AtlasPage {
+    fn new(content: SwashContent, size: u32) -> Self {
+        Self {
+            content,
+            size,
+            data: vec![0; size as usize * size as usize * Self::bytes(content)],
+            shelves: Vec::new(),
+            damage: None,
+        }
+    }
+
+    /// Get the number of bytes per pixel of images with `content`
+    fn bytes(content: SwashContent) -> usize {
+        match content {
+            SwashContent::Mask => 1,
+            SwashContent::Color | SwashContent::SubpixelMask => 4,
+        }
+    }
+
+    /// Get the content of the images in the page
+    pub fn content(&self) -> SwashContent {
+        self.content
+    }
+
+    /// Get the width and height of the page, in pixels
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Get the pixels of the page, row by row
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Get the rectangle of pixels that changed since [`GlyphAtlas::clear_damage`], as x, y,
+    /// width, and height, or None if nothing changed
+    ///
+    /// A renderer only needs to upload this part of the page to its texture. New pages are
+    /// empty apart from their damage.
+    pub fn damaged(&self) -> Option<(u32, u32, u32, u32)> {
+        self.damage
+            .map(|(x0, y0, x1, y1)| (x0, y0, x1 - x0, y1 - y0))
+    }
+
+    /// Find room for an image, returning its position
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let w = width + PADDING;
+        let h = height + PADDING;
+        if w > self.size || h > self.size {
+            return None;
+        }
+
+        // Use the shortest shelf the image fits in, or start a new one below the others
+        let mut best_opt: Option<usize> = None;
+        for (i, shelf) in self.shelves.iter().enumerate() {
+            if shelf.height >= h
+                && self.size - shelf.x >= w
+                && best_opt.is_none_or(|best| shelf.height < self.shelves[best].height)
+            {
+                best_opt = Some(i);
+            }
+        }
+        let i = match best_opt {
+            Some(some) => some,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map_or(0, |shelf| shelf.y + shelf.height);
+                if self.size - y < h {
+                    return None;
+                }
+                self.shelves.push(Shelf { y, height: h, x: 0 });
+                self.shelves.len() - 1
+            }
+        };
+
+        let shelf = &mut self.shelves[i];
+        let position = (shelf.x, shelf.y);
+        shelf.x += w;
+        Some(position)
+    }
+
+    /// Copy an image with rows of `width` pixels to a position of the page
+    fn write(&mut self, x: u32, y: u32, width: u32, height: u32, image: &[u8]) {
+        let bytes = Self::bytes(self.content);
+        let row = width as usize * bytes;
+        for image_y in 0..height as usize {
+            let start = ((y as usize + image_y) * self.size as usize + x as usize) * bytes;
+            self.data[start..start + row]
+                .copy_from_slice(&image[image_y * row..(image_y + 1) * row]);
+        }
+
+        let (x1, y1) = (x + width, y + height);
+        self.damage = Some(match self.damage {
+            Some((damage_x0, damage_y0, damage_x1, damage_y1)) => (
+                cmp::min(damage_x0, x),
+                cmp::min(damage_y0, y),
+                cmp::max(damage_x1, x1),
+                cmp::max(damage_y1, y1),
+            ),
+            None => (x, y, x1, y1),
+        });
+    }
+}
+
+/// Rasterized glyphs packed into texture pages, for renderers that draw glyphs from textures
+///
+/// Each glyph is rasterized by a [`SwashCache`] and copied into a page once. Renderers upload
+/// the pages to textures, updating only their [`AtlasPage::damaged`] parts, and draw each glyph
+/// as a quad with the texture coordinates of [`AtlasGlyph::uv`].
+#[derive(Debug)]
+pub struct GlyphAtlas {
+    page_size: u32,
+    pages: Vec<AtlasPage>,
+    glyphs: HashMap<CacheKey, Option<AtlasGlyph>>,
+}
+
+impl GlyphAtlas {
+    /// Create a new atlas with square pages of `page_size` pixels
+    pub fn new(page_size: u32) -> Self {
+        Self {
+            page_size,
+            pages: Vec::new(),
+            glyphs: HashMap::default(),
+        }
+    }
+
+    /// Get the width and height of each page, in pixels
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Get the pages, in the order they were added
+    pub fn pages(&self) -> &[AtlasPage] {
+        &self.pages
+    }
+
+    /// Get where the image of a glyph is in the atlas, rasterizing it with `swash_cache` and
+    /// adding it to a page if it is not in the atlas yet
+    ///
+    /// New pages are added when the image does not fit in the existing ones. Returns None if the
+    /// glyph has no image, such as spaces, or if the image is larger than a page.
+    pub fn get_glyph(
+        &mut self,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        cache_key: CacheKey,
+    ) -> Option<AtlasGlyph> {
+        if let Some(glyph_opt) = self.glyphs.get(&cache_key) {
+            return *glyph_opt;
+        }
+        let glyph_opt = self.add_glyph(font_system, swash_cache, cache_key);
+        self.glyphs.insert(cache_key, glyph_opt);
+        glyph_opt
+    }
+
+    /// Mark every page as unchanged, after a renderer uploaded their damage
+    pub fn clear_damage(&mut self) {
+        for page in self.pages.iter_mut() {
+            page.damage = None;
+        }
+    }
+
+    /// Remove every glyph and page, such as when the pages are full of glyphs that are no longer
+    /// drawn
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.glyphs.clear();
+    }
+
+    fn add_glyph(
+        &mut self,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        cache_key: CacheKey,
+    ) -> Option<AtlasGlyph> {
+        // The atlas keeps the pixels, so the swash cache does not need to
+        let image = swash_cache.get_image_uncached(font_system, cache_key)?;
+        let content = image.content;
+        let (width, height) = (image.placement.width, image.placement.height);
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let mut position_opt = None;
+        for (page_i, page) in self.pages.iter_mut().enumerate() {
+            if page.content == content {
+                if let Some((x, y)) = page.allocate(width, height) {
+                    position_opt = Some((page_i, x, y));
+                    break;
+                }
+            }
+        }
+        let (page_i, x, y) = match position_opt {
+            Some(some) => some,
+            None => {
+                let mut page = AtlasPage::new(content, self.page_size);
+                let (x, y) = page.allocate(width, height)?;
+                self.pages.push(page);
+                (self.pages.len() - 1, x, y)
+            }
+        };
+        self.pages[page_i].write(x, y, width, height, &image.data);
+
+        Some(AtlasGlyph {
+            page: page_i,
+            x,
+            y,
+            width,
+            height,
+            left: image.placement.left,
+            top: image.placement.top,
+            content,
+        })
+    }
+}
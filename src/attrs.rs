@@ -9,6 +9,8 @@ use core::ops::Range;
 
 pub use fontdb::{Family, Stretch, Style, Weight};
 use rangemap::RangeMap;
+pub use rustybuzz::ttf_parser::Tag;
+pub use unicode_script::Script;
 
 /// Text color
 #[derive(Clone, Copy, Debug, PartialOrd, Ord, Eq, Hash, PartialEq)]
@@ -62,6 +64,14 @@ impl Color {
     pub fn a(&self) -> u8 {
         ((self.0 & 0xFF_00_00_00) >> 24) as u8
     }
+
+    /// Get this color with the red, green, and blue components multiplied by alpha
+    #[inline]
+    pub fn premultiplied(self) -> Self {
+        let a = self.a() as u32;
+        let mul = |c: u8| ((c as u32 * a + 127) / 255) as u8;
+        Self::rgba(mul(self.r()), mul(self.g()), mul(self.b()), self.a())
+    }
 }
 
 /// An owned version of [`Family`]
@@ -99,6 +109,86 @@ impl FamilyOwned {
     }
 }
 
+/// Style of an underline
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UnderlineStyle {
+    /// A continuous line
+    Solid,
+    /// A line of square dots
+    Dotted,
+    /// A line of short dashes
+    Dashed,
+    /// A wavy line, commonly used to mark spelling errors
+    Wavy,
+}
+
+/// A placeholder box in the flow of text, such as for an inline image or icon
+///
+/// Text with an inline box is shaped as a single cluster with the width of the box, which a
+/// renderer can fill in. Sizes are multiples of the font size, and the box should be applied to
+/// a single character, such as U+FFFC OBJECT REPLACEMENT CHARACTER.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InlineBox {
+    /// Width of the box, which is the advance of the cluster
+    pub width: f32,
+    /// Height of the box, which reserves space in the line
+    pub height: f32,
+    /// Distance the bottom of the box is below the baseline
+    pub baseline_offset: f32,
+}
+
+impl InlineBox {
+    pub fn new(width: f32, height: f32, baseline_offset: f32) -> Self {
+        Self {
+            width,
+            height,
+            baseline_offset,
+        }
+    }
+}
+
+// Sizes are compared by their bits, so that attributes can be used as keys
+impl Eq for InlineBox {}
+
+impl core::hash::Hash for InlineBox {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.width.to_bits().hash(state);
+        self.height.to_bits().hash(state);
+        self.baseline_offset.to_bits().hash(state);
+    }
+}
+
+/// A coordinate on a variation axis of a font, such as `wght` for weight or `wdth` for width
+///
+/// Variable fonts are shaped and rasterized at the given coordinates instead of their default
+/// instance. Axes that a font does not have are ignored, so static fonts are not affected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Variation {
+    /// Tag of the axis
+    pub tag: Tag,
+    /// Coordinate on the axis, in its own units, such as 350.0 for `wght`
+    pub value: f32,
+}
+
+impl Variation {
+    pub const fn new(tag: &[u8; 4], value: f32) -> Self {
+        Self {
+            tag: Tag::from_bytes(tag),
+            value,
+        }
+    }
+}
+
+// Coordinates are compared by their bits, so that attributes can be used as keys
+impl Eq for Variation {}
+
+impl core::hash::Hash for Variation {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.tag.0.hash(state);
+        self.value.to_bits().hash(state);
+    }
+}
+
 /// Text attributes
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Attrs<'a> {
@@ -109,6 +199,11 @@ pub struct Attrs<'a> {
     pub style: Style,
     pub weight: Weight,
     pub metadata: usize,
+    pub underline_opt: Option<UnderlineStyle>,
+    pub strikethrough: bool,
+    pub inline_box_opt: Option<InlineBox>,
+    pub script_opt: Option<Script>,
+    pub variations: &'a [Variation],
 }
 
 impl<'a> Attrs<'a> {
@@ -123,6 +218,11 @@ impl<'a> Attrs<'a> {
             style: Style::Normal,
             weight: Weight::NORMAL,
             metadata: 0,
+            underline_opt: None,
+            strikethrough: false,
+            inline_box_opt: None,
+            script_opt: None,
+            variations: &[],
         }
     }
 
@@ -162,6 +262,42 @@ impl<'a> Attrs<'a> {
         self
     }
 
+    /// Set [`UnderlineStyle`]
+    pub fn underline(mut self, underline: UnderlineStyle) -> Self {
+        self.underline_opt = Some(underline);
+        self
+    }
+
+    /// Set whether the text is struck through with a line at half of its x-height
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    /// Set [`InlineBox`]
+    pub fn inline_box(mut self, inline_box: InlineBox) -> Self {
+        self.inline_box_opt = Some(inline_box);
+        self
+    }
+
+    /// Set the [Script] to shape text with, instead of detecting it from the characters
+    ///
+    /// This also selects the fallback fonts for the script, for example to render a font
+    /// specimen with the shaping rules of a script even for characters shared between scripts.
+    pub fn script(mut self, script: Script) -> Self {
+        self.script_opt = Some(script);
+        self
+    }
+
+    /// Set the [Variation] coordinates of variable fonts
+    ///
+    /// Fonts are still matched by [Weight], [Style] and [Stretch], so the weight should be set
+    /// too when setting the `wght` axis, in case the text falls back to static fonts.
+    pub fn variations(mut self, variations: &'a [Variation]) -> Self {
+        self.variations = variations;
+        self
+    }
+
     /// Check if font matches
     pub fn matches(&self, face: &fontdb::FaceInfo) -> bool {
         //TODO: smarter way of including emoji
@@ -177,6 +313,9 @@ impl<'a> Attrs<'a> {
             && self.stretch == other.stretch
             && self.style == other.style
             && self.weight == other.weight
+            && self.inline_box_opt == other.inline_box_opt
+            && self.script_opt == other.script_opt
+            && self.variations == other.variations
     }
 }
 
@@ -190,6 +329,11 @@ pub struct AttrsOwned {
     pub style: Style,
     pub weight: Weight,
     pub metadata: usize,
+    pub underline_opt: Option<UnderlineStyle>,
+    pub strikethrough: bool,
+    pub inline_box_opt: Option<InlineBox>,
+    pub script_opt: Option<Script>,
+    pub variations: Vec<Variation>,
 }
 
 impl AttrsOwned {
@@ -201,6 +345,11 @@ impl AttrsOwned {
             style: attrs.style,
             weight: attrs.weight,
             metadata: attrs.metadata,
+            underline_opt: attrs.underline_opt,
+            strikethrough: attrs.strikethrough,
+            inline_box_opt: attrs.inline_box_opt,
+            script_opt: attrs.script_opt,
+            variations: attrs.variations.to_vec(),
         }
     }
 
@@ -212,6 +361,11 @@ impl AttrsOwned {
             style: self.style,
             weight: self.weight,
             metadata: self.metadata,
+            underline_opt: self.underline_opt,
+            strikethrough: self.strikethrough,
+            inline_box_opt: self.inline_box_opt,
+            script_opt: self.script_opt,
+            variations: &self.variations,
         }
     }
 }
@@ -258,6 +412,15 @@ impl AttrsList {
         self.spans.insert(range, AttrsOwned::new(attrs));
     }
 
+    /// Get whether every index in `range` already has the provided [Attrs], in which case adding
+    /// a span over it would not change the attributes of any text
+    pub fn range_matches(&self, range: Range<usize>, attrs: Attrs) -> bool {
+        self.spans
+            .overlapping(&range)
+            .all(|(_, span)| span.as_attrs() == attrs)
+            && (self.defaults.as_attrs() == attrs || self.spans.gaps(&range).next().is_none())
+    }
+
     /// Get the attribute span for an index
     ///
     /// This returns a span that contains the index
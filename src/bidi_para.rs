@@ -38,3 +38,36 @@ impl<'text> Iterator for BidiParagraphs<'text> {
         }
     }
 }
+
+/// Check if a character is an explicit bidi formatting control
+///
+/// These are the embedding, override, and isolate characters (LRE, RLE, PDF, LRO, RLO, LRI, RLI,
+/// FSI, and PDI) which are normally invisible but change the visual order of surrounding text.
+pub fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// The direction of text
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    /// Left-to-right
+    Ltr,
+    /// Right-to-left
+    Rtl,
+}
+
+/// Find the direction of the first strong character in `text`, skipping characters inside isolates
+/// as in rule P2 of the Unicode Bidirectional Algorithm
+pub(crate) fn first_strong_direction(text: &str) -> Option<Direction> {
+    let mut isolates = 0usize;
+    for c in text.chars() {
+        match bidi_class(c) {
+            BidiClass::LRI | BidiClass::RLI | BidiClass::FSI => isolates += 1,
+            BidiClass::PDI => isolates = isolates.saturating_sub(1),
+            BidiClass::L if isolates == 0 => return Some(Direction::Ltr),
+            BidiClass::R | BidiClass::AL if isolates == 0 => return Some(Direction::Rtl),
+            _ => (),
+        }
+    }
+    None
+}
@@ -1,16 +1,23 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
 #[cfg(not(feature = "std"))]
 use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
-use core::{cmp, fmt};
+use core::{cmp, fmt, iter, ops::Range};
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
+#[cfg(feature = "swash")]
+use crate::CacheKey;
 use crate::{
-    Attrs, AttrsList, BidiParagraphs, BorrowedWithFontSystem, BufferLine, Color, FontSystem,
-    LayoutGlyph, LayoutLine, ShapeBuffer, ShapeLine, Shaping, Wrap,
+    bidi_para::first_strong_direction, Align, Attrs, AttrsList, AttrsOwned, BorrowedWithFontSystem,
+    BufferLine, Color, Direction, FontSystem, LayoutGlyph, LayoutLine, LineEnding, LineIter,
+    Overflow, PreparedRun, ShapeBuffer, ShapeLine, ShapeOptions, Shaping, UnderlineStyle, Wrap,
+    WritingMode,
 };
 
 /// Current cursor location
@@ -110,8 +117,87 @@ impl LayoutCursor {
     }
 }
 
+/// The carets of a cursor that may be at a boundary between bidi runs, see
+/// [`Buffer::split_caret_positions`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SplitCaret {
+    /// Position next to the glyph on the side of the [`Affinity`] of the cursor, which is the
+    /// [`Buffer::cursor_position`]
+    pub primary: (i32, i32),
+    /// Position next to the glyph on the other side, or None if it is at the primary position
+    pub secondary_opt: Option<(i32, i32)>,
+}
+
+/// A rectangle that drawing is clipped to, see [`Buffer::draw_clipped`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ClipRect {
+    /// X offset of the left edge
+    pub x: i32,
+    /// Y offset of the top edge
+    pub y: i32,
+    /// Width of the rectangle
+    pub width: u32,
+    /// Height of the rectangle
+    pub height: u32,
+}
+
+impl ClipRect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Get the part of the rectangle at `x`, `y` with size `w`, `h` that is inside the clip
+    /// rectangle, or None if there is no overlap
+    pub fn intersect(&self, x: i32, y: i32, w: u32, h: u32) -> Option<(i32, i32, u32, u32)> {
+        let left = cmp::max(x, self.x);
+        let top = cmp::max(y, self.y);
+        let right = cmp::min(
+            x.saturating_add(w as i32),
+            self.x.saturating_add(self.width as i32),
+        );
+        let bottom = cmp::min(
+            y.saturating_add(h as i32),
+            self.y.saturating_add(self.height as i32),
+        );
+        if left < right && top < bottom {
+            Some((left, top, (right - left) as u32, (bottom - top) as u32))
+        } else {
+            None
+        }
+    }
+}
+
+/// A rectangle in buffer coordinates, see [`Buffer::content_bounds`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rectangle {
+    /// X offset of the left edge
+    pub x: f32,
+    /// Y offset of the top edge
+    pub y: f32,
+    /// Width of the rectangle
+    pub width: f32,
+    /// Height of the rectangle
+    pub height: f32,
+}
+
+impl Rectangle {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
 /// A line of visible text for rendering
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct LayoutRun<'a> {
     /// The index of the original text line
     pub line_i: usize,
@@ -121,12 +207,29 @@ pub struct LayoutRun<'a> {
     pub rtl: bool,
     /// The array of layout glyphs to draw
     pub glyphs: &'a [LayoutGlyph],
+    /// The array of ruby annotation glyphs to draw, see [`LayoutLine::ruby_glyphs`]
+    pub ruby_glyphs: &'a [LayoutGlyph],
+    /// The array of line prefix glyphs to draw, see [`LayoutLine::prefix_glyphs`]
+    pub prefix_glyphs: &'a [LayoutGlyph],
+    /// Width of the line prefix, the start of the editable text on this run
+    pub prefix_w: f32,
+    /// The wrap indicator glyphs to draw at the right edge, only present if the line is soft
+    /// wrapped after this run, see [`Buffer::set_wrap_indicator`]
+    pub wrap_indicator_glyphs: &'a [LayoutGlyph],
+    /// The shaped tab leader, positioned at zero, see [`Self::tab_leader_glyphs`]
+    pub tab_leader: &'a [LayoutGlyph],
     /// Y offset to baseline of line
     pub line_y: f32,
     /// Y offset to top of line
     pub line_top: f32,
     /// Width of line
     pub line_w: f32,
+    /// Maximum ascent of the glyphs in line, see [`LayoutLine::max_ascent`]
+    pub max_ascent: f32,
+    /// Maximum descent of the glyphs in line, see [`LayoutLine::max_descent`]
+    pub max_descent: f32,
+    /// Maximum line gap of the fonts in line, see [`LayoutLine::max_line_gap`]
+    pub max_line_gap: f32,
 }
 
 impl<'a> LayoutRun<'a> {
@@ -168,6 +271,111 @@ impl<'a> LayoutRun<'a> {
         }
     }
 
+    /// Draw the underlines and strikethroughs of glyphs in this run, see [`Attrs::underline`] and
+    /// [`Attrs::strikethrough`]
+    ///
+    /// Glyphs without a color override use `color`. Adjacent glyphs with the same underline
+    /// style and color share one underline, and likewise for strikethroughs.
+    pub fn draw_underlines<F>(&self, color: Color, mut f: F)
+    where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        self.decoration_spans(
+            color,
+            |glyph| glyph.underline_opt,
+            |style, left, right, font_size, color| {
+                draw_underline(style, left, right, self.line_y, font_size, color, &mut f);
+            },
+        );
+        self.decoration_spans(
+            color,
+            |glyph| glyph.strikethrough.then_some(()),
+            |(), left, right, font_size, color| {
+                // Strike through at about half of the x-height
+                let thickness = cmp::max(1, libm::roundf(font_size / 14.0) as i32);
+                let y = libm::roundf(self.line_y - font_size / 4.0) as i32;
+                let x_start = libm::roundf(left) as i32;
+                let x_end = libm::roundf(right) as i32;
+                f(
+                    x_start,
+                    y,
+                    cmp::max(0, x_end - x_start) as u32,
+                    thickness as u32,
+                    color,
+                );
+            },
+        );
+    }
+
+    /// Call `f` with the left and right edges, largest font size, and color of each span of
+    /// adjacent glyphs with the same `decoration` and color
+    fn decoration_spans<T, D, F>(&self, color: Color, decoration: D, mut f: F)
+    where
+        T: PartialEq,
+        D: Fn(&LayoutGlyph) -> Option<T>,
+        F: FnMut(T, f32, f32, f32, Color),
+    {
+        let mut i = 0;
+        while let Some(glyph) = self.glyphs.get(i) {
+            i += 1;
+            let style = match decoration(glyph) {
+                Some(some) => some,
+                None => continue,
+            };
+            let glyph_color = glyph.color_opt.unwrap_or(color);
+            let mut left = glyph.x;
+            let mut right = glyph.x + glyph.w;
+            let mut font_size = glyph.font_size;
+            while let Some(next) = self.glyphs.get(i) {
+                if decoration(next).as_ref() != Some(&style)
+                    || next.color_opt.unwrap_or(color) != glyph_color
+                {
+                    break;
+                }
+                left = left.min(next.x);
+                right = right.max(next.x + next.w);
+                font_size = font_size.max(next.font_size);
+                i += 1;
+            }
+            f(style, left, right, font_size, glyph_color);
+        }
+    }
+
+    /// Return the tab leader glyphs filling the gap of each tab in this run, see
+    /// [`Buffer::set_tab_leader`]
+    ///
+    /// Leaders are placed at multiples of their width so that they line up across lines, and
+    /// only whole leaders that fit within the tab are returned.
+    pub fn tab_leader_glyphs(&self) -> Vec<LayoutGlyph> {
+        let mut glyphs = Vec::new();
+        let leader_w: f32 = self.tab_leader.iter().map(|glyph| glyph.w).sum();
+        if leader_w <= 0.0 {
+            return glyphs;
+        }
+        for tab in self
+            .glyphs
+            .iter()
+            .filter(|glyph| self.text.get(glyph.start..glyph.end) == Some("\t"))
+        {
+            let mut x = libm::ceilf(tab.x / leader_w) * leader_w;
+            while x + leader_w <= tab.x + tab.w {
+                for leader in self.tab_leader.iter() {
+                    let mut glyph = leader.clone();
+                    glyph.start = tab.start;
+                    glyph.end = tab.end;
+                    glyph.x += x;
+                    glyph.level = tab.level;
+                    glyph.color_opt = tab.color_opt;
+                    glyph.metadata = tab.metadata;
+                    glyph.underline_opt = None;
+                    glyphs.push(glyph);
+                }
+                x += leader_w;
+            }
+        }
+        glyphs
+    }
+
     fn cursor_from_glyph_left(&self, glyph: &LayoutGlyph) -> Cursor {
         if self.rtl {
             Cursor::new_with_affinity(self.line_i, glyph.end, Affinity::Before)
@@ -185,6 +393,50 @@ impl<'a> LayoutRun<'a> {
     }
 }
 
+/// Draw an underline from `left` to `right` below the baseline at `line_y`
+fn draw_underline<F>(
+    style: UnderlineStyle,
+    left: f32,
+    right: f32,
+    line_y: f32,
+    font_size: f32,
+    color: Color,
+    f: &mut F,
+) where
+    F: FnMut(i32, i32, u32, u32, Color),
+{
+    let thickness = cmp::max(1, libm::roundf(font_size / 14.0) as i32);
+    let y = libm::roundf(line_y + font_size / 10.0) as i32;
+    let x_start = libm::roundf(left) as i32;
+    let x_end = libm::roundf(right) as i32;
+
+    // Draws dashes of `dash` pixels separated by `gap` pixels
+    let mut dashes = |dash: i32, gap: i32| {
+        let mut x = x_start;
+        while x < x_end {
+            let w = cmp::min(dash, x_end - x);
+            f(x, y, w as u32, thickness as u32, color);
+            x += dash + gap;
+        }
+    };
+
+    match style {
+        UnderlineStyle::Solid => dashes(x_end - x_start, 0),
+        UnderlineStyle::Dotted => dashes(thickness, thickness),
+        UnderlineStyle::Dashed => dashes(3 * thickness, 2 * thickness),
+        UnderlineStyle::Wavy => {
+            // Sample a sine wave for every pixel column
+            let amplitude = thickness as f32;
+            let period = 6.0 * thickness as f32;
+            for x in x_start..x_end {
+                let phase = (x - x_start) as f32 / period * core::f32::consts::TAU;
+                let dy = libm::roundf(amplitude * libm::sinf(phase)) as i32;
+                f(x, y + dy, 1, thickness as u32, color);
+            }
+        }
+    }
+}
+
 /// An iterator of visible text lines, see [`LayoutRun`]
 #[derive(Debug)]
 pub struct LayoutRunIter<'b> {
@@ -193,6 +445,7 @@ pub struct LayoutRunIter<'b> {
     layout_i: usize,
     remaining_len: usize,
     total_layout: i32,
+    line_top: f32,
 }
 
 impl<'b> LayoutRunIter<'b> {
@@ -209,10 +462,11 @@ impl<'b> LayoutRunIter<'b> {
             .sum();
         let top_cropped_layout_lines =
             total_layout_lines.saturating_sub(buffer.scroll.try_into().unwrap_or_default());
+        // Part of one more line is visible when scrolled into the first line
         let maximum_lines = if buffer.metrics.line_height == 0.0 {
             0
         } else {
-            (buffer.height / buffer.metrics.line_height) as i32
+            ((buffer.height + buffer.scroll_y) / buffer.line_pitch()) as i32
         };
         let bottom_cropped_layout_lines =
             if top_cropped_layout_lines > maximum_lines.try_into().unwrap_or_default() {
@@ -227,6 +481,7 @@ impl<'b> LayoutRunIter<'b> {
             layout_i: 0,
             remaining_len: bottom_cropped_layout_lines,
             total_layout: 0,
+            line_top: 0.0,
         }
     }
 }
@@ -251,14 +506,28 @@ impl<'b> Iterator for LayoutRunIter<'b> {
                     continue;
                 }
 
-                let line_top = self
-                    .total_layout
-                    .saturating_sub(self.buffer.scroll)
-                    .saturating_sub(1) as f32
-                    * self.buffer.metrics.line_height;
+                // Lines with ruby annotations are taller, with the annotations above the glyphs
+                let line_top = self.line_top;
+                self.line_top += self.buffer.metrics.line_height + layout_line.ruby_height;
                 let glyph_height = layout_line.max_ascent + layout_line.max_descent;
                 let centering_offset = (self.buffer.metrics.line_height - glyph_height) / 2.0;
-                let line_y = line_top + centering_offset + layout_line.max_ascent;
+                let mut line_y = line_top + layout_line.baseline_y(self.buffer.metrics.line_height);
+
+                // Move the line down so its baseline sits on the grid
+                let line_top = match self.buffer.baseline_grid {
+                    Some(grid) if grid > 0.0 => {
+                        let grid_y = libm::ceilf(line_y / grid) * grid;
+                        let offset = grid_y - line_y;
+                        line_y = grid_y;
+                        self.line_top += offset;
+                        line_top + offset
+                    }
+                    _ => line_top,
+                };
+
+                // Apply the pixel offset into the first line after aligning to the grid
+                let line_top = line_top - self.buffer.scroll_y;
+                line_y -= self.buffer.scroll_y;
 
                 if line_top + centering_offset > self.buffer.height {
                     return None;
@@ -271,9 +540,21 @@ impl<'b> Iterator for LayoutRunIter<'b> {
                         text: line.text(),
                         rtl: shape.rtl,
                         glyphs: &layout_line.glyphs,
+                        ruby_glyphs: &layout_line.ruby_glyphs,
+                        prefix_glyphs: &layout_line.prefix_glyphs,
+                        prefix_w: layout_line.prefix_w,
+                        wrap_indicator_glyphs: if self.layout_i < layout.len() {
+                            self.buffer.wrap_indicator_glyphs.as_slice()
+                        } else {
+                            &[]
+                        },
+                        tab_leader: &self.buffer.tab_leader_glyphs,
                         line_y,
                         line_top,
                         line_w: layout_line.w,
+                        max_ascent: layout_line.max_ascent,
+                        max_descent: layout_line.max_descent,
+                        max_line_gap: layout_line.max_line_gap,
                     }
                 });
             }
@@ -310,6 +591,17 @@ impl Metrics {
             line_height: self.line_height * scale,
         }
     }
+
+    /// Set the line height to `ratio` times the font size, such as 1.5 for one and a half line
+    /// spacing
+    ///
+    /// The extra leading is split equally above and below the glyphs of each line.
+    pub fn with_line_spacing_ratio(self, ratio: f32) -> Self {
+        Self {
+            font_size: self.font_size,
+            line_height: self.font_size * ratio,
+        }
+    }
 }
 
 impl fmt::Display for Metrics {
@@ -318,18 +610,130 @@ impl fmt::Display for Metrics {
     }
 }
 
+/// Get the number of monospace grid cells occupied by `c`, where East Asian Ambiguous characters
+/// occupy `ambiguous_width` cells
+fn char_cells(c: char, ambiguous_width: u8) -> usize {
+    let cells = if ambiguous_width >= 2 {
+        c.width_cjk()
+    } else {
+        c.width()
+    };
+    cells.unwrap_or(0)
+}
+
+/// Extend the range of damaged lines to include `line_i`
+fn add_damage(damage: &mut Option<Range<usize>>, line_i: usize) {
+    *damage = Some(match damage.take() {
+        Some(some) => some.start.min(line_i)..some.end.max(line_i + 1),
+        None => line_i..line_i + 1,
+    });
+}
+
+/// Get the width and wrapping used to lay out a shaped `line`, taking wrap columns into account
+fn layout_width_wrap(
+    line: &BufferLine,
+    font_size: f32,
+    width: f32,
+    wrap: Wrap,
+    wrap_columns: Option<usize>,
+    ambiguous_width: u8,
+) -> (f32, Wrap) {
+    let columns = match wrap_columns {
+        Some(some) => some,
+        None => return (width, wrap),
+    };
+    let text = line.text();
+    let column_width = line.shape_opt().as_ref().map_or(0.0, |shape| {
+        shape
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .fold(0.0, |w: f32, glyph| {
+                // Wide characters span several columns, so they do not widen the grid
+                let cells = text[glyph.start..]
+                    .chars()
+                    .next()
+                    .map_or(1, |c| char_cells(c, ambiguous_width))
+                    .max(1);
+                w.max(font_size * glyph.x_advance / cells as f32)
+            })
+    });
+    // Leave some room for rounding errors when advances are summed
+    (
+        columns as f32 * column_width + column_width / 1024.0,
+        Wrap::Glyph,
+    )
+}
+
+/// How line endings in text inserted into a single line [`Buffer`] are handled, see
+/// [`Buffer::set_single_line`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SingleLineNewlines {
+    /// Replace each line ending with a space
+    #[default]
+    Space,
+    /// Drop the text from the first line ending on
+    Truncate,
+}
+
+impl SingleLineNewlines {
+    /// Remove the line endings from `text`, which are the same as those split by [`LineIter`]
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        let first = match LineIter::new(text).next() {
+            Some((first, ending)) if ending != LineEnding::None => first,
+            _ => return Cow::Borrowed(text),
+        };
+        match self {
+            Self::Space => {
+                let mut string = String::with_capacity(text.len());
+                for (line, ending) in LineIter::new(text) {
+                    string.push_str(line);
+                    if ending != LineEnding::None {
+                        string.push(' ');
+                    }
+                }
+                Cow::Owned(string)
+            }
+            Self::Truncate => Cow::Borrowed(first),
+        }
+    }
+}
+
 /// A buffer of text that is shaped and laid out
 #[derive(Debug)]
 pub struct Buffer {
     /// [BufferLine]s (or paragraphs) of text in the buffer
     pub lines: Vec<BufferLine>,
     metrics: Metrics,
+    /// Length of lines, which is the physical height in [`WritingMode::VerticalRl`]
     width: f32,
+    /// Length of the text across lines, which is the physical width in
+    /// [`WritingMode::VerticalRl`]
     height: f32,
     scroll: i32,
+    scroll_y: f32,
+    scroll_x: f32,
+    overscan: i32,
     /// True if a redraw is requires. Set to false after processing
     redraw: bool,
+    content_version: u64,
+    damage: Option<Range<usize>>,
+    damage_lines_len: usize,
     wrap: Wrap,
+    wrap_columns: Option<usize>,
+    overflow: Overflow,
+    max_lines: Option<usize>,
+    ambiguous_width: u8,
+    baseline_grid: Option<f32>,
+    wrap_indicator: Option<char>,
+    wrap_indicator_glyphs: Vec<LayoutGlyph>,
+    tab_leader: Option<char>,
+    tab_leader_glyphs: Vec<LayoutGlyph>,
+    shape_options: ShapeOptions,
+    default_color: Option<Color>,
+    single_line: bool,
+    single_line_newlines: SingleLineNewlines,
 
     /// Scratch buffer for shaping and laying out.
     scratch: ShapeBuffer,
@@ -355,12 +759,42 @@ impl Buffer {
             width: 0.0,
             height: 0.0,
             scroll: 0,
+            scroll_y: 0.0,
+            scroll_x: 0.0,
+            overscan: 0,
             redraw: false,
+            content_version: 0,
+            damage: None,
+            damage_lines_len: 0,
             wrap: Wrap::Word,
+            wrap_columns: None,
+            overflow: Overflow::Visible,
+            max_lines: None,
+            ambiguous_width: 1,
+            baseline_grid: None,
+            wrap_indicator: None,
+            wrap_indicator_glyphs: Vec::new(),
+            tab_leader: None,
+            tab_leader_glyphs: Vec::new(),
+            shape_options: ShapeOptions::default(),
+            default_color: None,
+            single_line: false,
+            single_line_newlines: SingleLineNewlines::Space,
             scratch: ShapeBuffer::default(),
         }
     }
 
+    /// Reset all settings for reuse as a new empty buffer with `metrics`, keeping the allocated
+    /// lines and scratch buffer
+    pub(crate) fn recycle(&mut self, metrics: Metrics) {
+        let mut lines = core::mem::take(&mut self.lines);
+        lines.clear();
+        let scratch = core::mem::take(&mut self.scratch);
+        *self = Self::new_empty(metrics);
+        self.lines = lines;
+        self.scratch = scratch;
+    }
+
     /// Create a new [`Buffer`] with the provided [`FontSystem`] and [`Metrics`]
     ///
     /// # Panics
@@ -387,10 +821,29 @@ impl Buffer {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
         let instant = std::time::Instant::now();
 
-        for line in &mut self.lines {
+        for (line_i, line) in self.lines.iter_mut().enumerate() {
             if line.shape_opt().is_some() {
                 line.reset_layout();
-                line.layout(font_system, self.metrics.font_size, self.width, self.wrap);
+                line.set_overflow(self.overflow);
+                line.set_max_lines(self.max_lines);
+                if line.layout_opt().is_none() {
+                    add_damage(&mut self.damage, line_i);
+                }
+                let (width, wrap) = layout_width_wrap(
+                    line,
+                    self.metrics.font_size,
+                    self.width,
+                    self.wrap,
+                    self.wrap_columns,
+                    self.ambiguous_width,
+                );
+                line.layout_with_options(
+                    font_system,
+                    self.metrics.font_size,
+                    width,
+                    wrap,
+                    self.shape_options,
+                );
             }
         }
 
@@ -407,7 +860,7 @@ impl Buffer {
 
         let mut reshaped = 0;
         let mut total_layout = 0;
-        for line in &mut self.lines {
+        for (line_i, line) in self.lines.iter_mut().enumerate() {
             if total_layout >= lines {
                 break;
             }
@@ -415,12 +868,27 @@ impl Buffer {
             if line.shape_opt().is_none() {
                 reshaped += 1;
             }
-            let layout = line.layout_in_buffer(
-                &mut self.scratch,
-                font_system,
+            line.shape_in_buffer_with_options(&mut self.scratch, font_system, self.shape_options);
+            line.set_overflow(self.overflow);
+            line.set_max_lines(self.max_lines);
+            if line.layout_opt().is_none() {
+                add_damage(&mut self.damage, line_i);
+            }
+            let (width, wrap) = layout_width_wrap(
+                line,
                 self.metrics.font_size,
                 self.width,
                 self.wrap,
+                self.wrap_columns,
+                self.ambiguous_width,
+            );
+            let layout = line.layout_in_buffer_with_options(
+                &mut self.scratch,
+                font_system,
+                self.metrics.font_size,
+                width,
+                wrap,
+                self.shape_options,
             );
             total_layout += layout.len() as i32;
         }
@@ -434,6 +902,63 @@ impl Buffer {
         total_layout
     }
 
+    /// Shape and lay out lines in order until `deadline` has passed, returning true if there are
+    /// still lines left to lay out
+    ///
+    /// This allows spreading the work for large buffers over several frames. At least one line
+    /// is laid out per call, so progress is always made.
+    ///
+    /// This is not available on `wasm32`, where [`std::time::Instant`] is not supported.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn shape_until_deadline(
+        &mut self,
+        font_system: &mut FontSystem,
+        deadline: std::time::Instant,
+    ) -> bool {
+        let mut reshaped = 0;
+        let mut remaining = false;
+        for (line_i, line) in self.lines.iter_mut().enumerate() {
+            if line.layout_opt().is_some() {
+                continue;
+            }
+            if reshaped > 0 && std::time::Instant::now() >= deadline {
+                remaining = true;
+                break;
+            }
+
+            line.shape_in_buffer_with_options(&mut self.scratch, font_system, self.shape_options);
+            line.set_overflow(self.overflow);
+            line.set_max_lines(self.max_lines);
+            if line.layout_opt().is_none() {
+                add_damage(&mut self.damage, line_i);
+            }
+            let (width, wrap) = layout_width_wrap(
+                line,
+                self.metrics.font_size,
+                self.width,
+                self.wrap,
+                self.wrap_columns,
+                self.ambiguous_width,
+            );
+            line.layout_in_buffer_with_options(
+                &mut self.scratch,
+                font_system,
+                self.metrics.font_size,
+                width,
+                wrap,
+                self.shape_options,
+            );
+            reshaped += 1;
+        }
+
+        if reshaped > 0 {
+            log::debug!("shape_until_deadline {}", reshaped);
+            self.redraw = true;
+        }
+
+        remaining
+    }
+
     /// Shape lines until cursor, also scrolling to include cursor in view
     pub fn shape_until_cursor(&mut self, font_system: &mut FontSystem, cursor: Cursor) {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
@@ -441,6 +966,7 @@ impl Buffer {
 
         let mut reshaped = 0;
         let mut layout_i = 0;
+        let mut cursor_x = 0.0;
         for (line_i, line) in self.lines.iter_mut().enumerate() {
             if line_i > cursor.line {
                 break;
@@ -449,16 +975,32 @@ impl Buffer {
             if line.shape_opt().is_none() {
                 reshaped += 1;
             }
-            let layout = line.layout_in_buffer(
-                &mut self.scratch,
-                font_system,
+            line.shape_in_buffer_with_options(&mut self.scratch, font_system, self.shape_options);
+            line.set_overflow(self.overflow);
+            line.set_max_lines(self.max_lines);
+            if line.layout_opt().is_none() {
+                add_damage(&mut self.damage, line_i);
+            }
+            let (width, wrap) = layout_width_wrap(
+                line,
                 self.metrics.font_size,
                 self.width,
                 self.wrap,
+                self.wrap_columns,
+                self.ambiguous_width,
+            );
+            let layout = line.layout_in_buffer_with_options(
+                &mut self.scratch,
+                font_system,
+                self.metrics.font_size,
+                width,
+                wrap,
+                self.shape_options,
             );
             if line_i == cursor.line {
                 let layout_cursor = self.layout_cursor(&cursor);
                 layout_i += layout_cursor.layout as i32;
+                cursor_x = self.layout_cursor_x(&layout_cursor);
                 break;
             } else {
                 layout_i += layout.len() as i32;
@@ -471,47 +1013,110 @@ impl Buffer {
             self.redraw = true;
         }
 
+        // A line partly scrolled out of view at the top is scrolled back in
         let lines = self.visible_lines();
-        if layout_i < self.scroll {
+        if layout_i < self.scroll || (layout_i == self.scroll && self.scroll_y > 0.0) {
             self.scroll = layout_i;
+            self.scroll_y = 0.0;
         } else if layout_i >= self.scroll + lines {
-            self.scroll = layout_i - (lines - 1);
+            self.scroll = self.scroll_ending_at(layout_i);
+            self.scroll_y = 0.0;
+        }
+
+        // Leave room for the caret at the right edge
+        let mut scroll_x = self.scroll_x;
+        if cursor_x < scroll_x {
+            scroll_x = cursor_x;
+        } else if cursor_x + 1.0 > scroll_x + self.width {
+            scroll_x = cursor_x + 1.0 - self.width;
         }
+        self.set_scroll_x(scroll_x);
 
         self.shape_until_scroll(font_system);
     }
 
+    /// Get the x position of a [`LayoutCursor`], relative to the start of its layout line
+    fn layout_cursor_x(&self, cursor: &LayoutCursor) -> f32 {
+        let layout_line = match self.lines[cursor.line]
+            .layout_opt()
+            .as_ref()
+            .and_then(|layout| layout.get(cursor.layout))
+        {
+            Some(some) => some,
+            None => return 0.0,
+        };
+        match layout_line.glyphs.get(cursor.glyph) {
+            Some(glyph) => {
+                // Start of detected glyph
+                if glyph.level.is_rtl() {
+                    glyph.x + glyph.w
+                } else {
+                    glyph.x
+                }
+            }
+            None => match layout_line.glyphs.last() {
+                Some(glyph) => {
+                    // End of last glyph
+                    if glyph.level.is_rtl() {
+                        glyph.x
+                    } else {
+                        glyph.x + glyph.w
+                    }
+                }
+                None => layout_line.prefix_w,
+            },
+        }
+    }
+
     /// Shape lines until scroll
+    ///
+    /// Only the lines up to the end of the view, plus [`Self::overscan`] layout lines, are shaped,
+    /// so later lines stay unshaped until scrolled to.
     pub fn shape_until_scroll(&mut self, font_system: &mut FontSystem) {
         let lines = self.visible_lines();
 
-        let scroll_end = self.scroll + lines;
+        let partial = i32::from(self.scroll_y > 0.0);
+        let scroll_end = self.scroll + lines + partial + self.overscan;
         let total_layout = self.shape_until(font_system, scroll_end);
 
-        self.scroll = cmp::max(0, cmp::min(total_layout - (lines - 1), self.scroll));
+        // The view can end one empty line past the last layout line
+        let max_scroll = self.scroll_ending_at(total_layout);
+        if self.scroll >= max_scroll {
+            self.scroll_y = 0.0;
+        }
+        self.scroll = cmp::max(0, cmp::min(max_scroll, self.scroll));
     }
 
+    /// Convert a [`Cursor`] to a [`LayoutCursor`]
+    ///
+    /// At a soft wrap, where the end of one layout line and the start of the next have the same
+    /// index, the affinity of the cursor chooses between them: [`Affinity::Before`] is at the end
+    /// of the upper line and [`Affinity::After`] is at the start of the lower line. If no edge
+    /// of a glyph has the affinity of the cursor, the first edge with its index is used.
     pub fn layout_cursor(&self, cursor: &Cursor) -> LayoutCursor {
         let line = &self.lines[cursor.line];
 
         //TODO: ensure layout is done?
         let layout = line.layout_opt().as_ref().expect("layout not found");
-        for (layout_i, layout_line) in layout.iter().enumerate() {
-            for (glyph_i, glyph) in layout_line.glyphs.iter().enumerate() {
-                let cursor_end =
-                    Cursor::new_with_affinity(cursor.line, glyph.end, Affinity::Before);
-                let cursor_start =
-                    Cursor::new_with_affinity(cursor.line, glyph.start, Affinity::After);
-                let (cursor_left, cursor_right) = if glyph.level.is_ltr() {
-                    (cursor_start, cursor_end)
-                } else {
-                    (cursor_end, cursor_start)
-                };
-                if *cursor == cursor_left {
-                    return LayoutCursor::new(cursor.line, layout_i, glyph_i);
-                }
-                if *cursor == cursor_right {
-                    return LayoutCursor::new(cursor.line, layout_i, glyph_i + 1);
+        for match_affinity in [true, false] {
+            let matches = |index: usize, affinity: Affinity| {
+                cursor.index == index && (!match_affinity || cursor.affinity == affinity)
+            };
+            for (layout_i, layout_line) in layout.iter().enumerate() {
+                for (glyph_i, glyph) in layout_line.glyphs.iter().enumerate() {
+                    let cursor_end = (glyph.end, Affinity::Before);
+                    let cursor_start = (glyph.start, Affinity::After);
+                    let (cursor_left, cursor_right) = if glyph.level.is_ltr() {
+                        (cursor_start, cursor_end)
+                    } else {
+                        (cursor_end, cursor_start)
+                    };
+                    if matches(cursor_left.0, cursor_left.1) {
+                        return LayoutCursor::new(cursor.line, layout_i, glyph_i);
+                    }
+                    if matches(cursor_right.0, cursor_right.1) {
+                        return LayoutCursor::new(cursor.line, layout_i, glyph_i + 1);
+                    }
                 }
             }
         }
@@ -521,6 +1126,30 @@ impl Buffer {
         LayoutCursor::new(cursor.line, 0, 0)
     }
 
+    /// Reset the shaping and layout of the provided line index, so it is shaped again by the next
+    /// shaping pass, such as [`Self::shape_until_scroll`]
+    ///
+    /// This is needed after changing a line in [`Self::lines`] in a way that its cache does not
+    /// detect. Other lines keep their shaping.
+    pub fn reset_shaping(&mut self, line_i: usize) {
+        if let Some(line) = self.lines.get_mut(line_i) {
+            line.reset();
+            self.redraw = true;
+        }
+    }
+
+    /// Reset the shaping and layout of every line, so they are shaped again by the next shaping
+    /// pass
+    ///
+    /// This is needed for text to use fonts that were added to the [`FontSystem`] after it was
+    /// shaped, such as for glyphs that were missing before.
+    pub fn reset_shaping_all(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.reset();
+        }
+        self.redraw = true;
+    }
+
     /// Shape the provided line index and return the result
     pub fn line_shape(
         &mut self,
@@ -528,7 +1157,7 @@ impl Buffer {
         line_i: usize,
     ) -> Option<&ShapeLine> {
         let line = self.lines.get_mut(line_i)?;
-        Some(line.shape(font_system))
+        Some(line.shape_with_options(font_system, self.shape_options))
     }
 
     /// Lay out the provided line index and return the result
@@ -538,7 +1167,258 @@ impl Buffer {
         line_i: usize,
     ) -> Option<&[LayoutLine]> {
         let line = self.lines.get_mut(line_i)?;
-        Some(line.layout(font_system, self.metrics.font_size, self.width, self.wrap))
+        line.shape_with_options(font_system, self.shape_options);
+        line.set_overflow(self.overflow);
+        line.set_max_lines(self.max_lines);
+        if line.layout_opt().is_none() {
+            add_damage(&mut self.damage, line_i);
+        }
+        let (width, wrap) = layout_width_wrap(
+            line,
+            self.metrics.font_size,
+            self.width,
+            self.wrap,
+            self.wrap_columns,
+            self.ambiguous_width,
+        );
+        Some(line.layout_with_options(
+            font_system,
+            self.metrics.font_size,
+            width,
+            wrap,
+            self.shape_options,
+        ))
+    }
+
+    /// Get the byte indices where the provided line index would wrap at `width`, using the
+    /// current [`Wrap`] mode, without changing the layout of the line
+    ///
+    /// Each index is the start of a visual line after the first. Returns an empty list if the line
+    /// fits, if wrapping is disabled, or if the line does not exist.
+    pub fn wrap_preview(
+        &mut self,
+        font_system: &mut FontSystem,
+        line_i: usize,
+        width: f32,
+    ) -> Vec<usize> {
+        let line = match self.lines.get_mut(line_i) {
+            Some(some) => some,
+            None => return Vec::new(),
+        };
+        let align = line.align();
+        let shape =
+            line.shape_in_buffer_with_options(&mut self.scratch, font_system, self.shape_options);
+        let mut layout_lines = Vec::with_capacity(1);
+        shape.layout_to_buffer(
+            &mut self.scratch,
+            self.metrics.font_size,
+            width,
+            self.wrap,
+            align,
+            &mut layout_lines,
+        );
+        layout_lines
+            .iter()
+            .skip(1)
+            .filter_map(|layout_line| layout_line.glyphs.iter().map(|glyph| glyph.start).min())
+            .collect()
+    }
+
+    /// Measure how much of `text` fits on a single line of `max_width` at the buffer font size,
+    /// without changing the contents of the buffer
+    ///
+    /// Returns the byte length of the longest start of the text that ends at a grapheme boundary
+    /// and fits, and true if the rest of the text overflowed. The width of a cluster with several
+    /// graphemes, such as a ligature, is split evenly between them.
+    pub fn fit_single_line(
+        &mut self,
+        font_system: &mut FontSystem,
+        text: &str,
+        attrs: Attrs,
+        max_width: f32,
+    ) -> (usize, bool) {
+        let shape = ShapeLine::new_in_buffer_with_options(
+            &mut self.scratch,
+            font_system,
+            text,
+            &AttrsList::new(attrs),
+            Shaping::Advanced,
+            ShapeOptions {
+                reveal_bidi_controls: false,
+                isolated_mark_display: false,
+                ..self.shape_options
+            },
+        );
+
+        // Start index of each cluster, with its end index and width
+        let mut clusters = BTreeMap::new();
+        for glyph in shape
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+        {
+            clusters.entry(glyph.start).or_insert((glyph.end, 0.0)).1 +=
+                self.metrics.font_size * glyph.x_advance;
+        }
+
+        let mut w = 0.0;
+        for (start, (end, cluster_w)) in clusters {
+            let cluster = &text[start..end];
+            let egc_w = cluster_w / cluster.graphemes(true).count() as f32;
+            for (egc_i, _) in cluster.grapheme_indices(true) {
+                w += egc_w;
+                if w > max_width {
+                    return (start + egc_i, true);
+                }
+            }
+        }
+        (text.len(), false)
+    }
+
+    /// Get the width of the widest laid out line, for example to size a horizontal scrollbar when
+    /// wrapping is disabled
+    ///
+    /// Only lines that have been laid out are included, see [`Self::shape_until`]. The cached
+    /// layout of each line is used, so this reflects edits once lines are laid out again.
+    pub fn content_width(&self) -> f32 {
+        self.lines
+            .iter()
+            .filter_map(|line| line.layout_opt().as_ref())
+            .flat_map(|layout| layout.iter())
+            .fold(0.0, |width, layout_line| width.max(layout_line.w))
+    }
+
+    /// Estimate the height of all lines, for example to size a vertical scrollbar without shaping
+    /// the whole buffer
+    ///
+    /// Lines that have been laid out count the height of their layout lines, including ruby
+    /// annotations, as placed by [`Self::layout_runs`]. Other lines count the average number of
+    /// layout lines of the laid out lines, or one if no line has been laid out. The estimate is
+    /// refined as more lines are laid out, for example by scrolling.
+    pub fn estimated_height(&self) -> f32 {
+        let mut laid_out = 0;
+        let mut layout_lines = 0;
+        let mut laid_out_height = 0.0;
+        for layout in self
+            .lines
+            .iter()
+            .filter_map(|line| line.layout_opt().as_ref())
+        {
+            laid_out += 1;
+            layout_lines += layout.len();
+            for layout_line in layout.iter() {
+                laid_out_height += self.row_height(layout_line.ruby_height);
+            }
+        }
+        let average = if laid_out > 0 {
+            layout_lines as f32 / laid_out as f32
+        } else {
+            1.0
+        };
+        let estimated_lines = (self.lines.len() - laid_out) as f32 * average;
+        laid_out_height + estimated_lines * self.line_pitch()
+    }
+
+    /// Estimate the size of all lines, the [`Self::content_width`] of the laid out lines and the
+    /// [`Self::estimated_height`] of all lines, for example to size a scrollable area
+    pub fn estimated_size(&self) -> (f32, f32) {
+        (self.content_width(), self.estimated_height())
+    }
+
+    /// Get the tight bounding box of the glyphs in the visible layout runs, such as for drawing a
+    /// focus ring or background around the text, or an empty rectangle at the origin if there
+    /// are no glyphs
+    ///
+    /// Horizontally this covers the advances of the glyphs. Vertically it covers the ascent and
+    /// descent of each run, and the ruby annotations above it, but not the rest of the line
+    /// height. Coordinates are the same as those of [`LayoutRun::line_y`], without horizontal
+    /// scroll.
+    pub fn content_bounds(&self) -> Rectangle {
+        let mut min = (f32::MAX, f32::MAX);
+        let mut max = (f32::MIN, f32::MIN);
+        for run in self.layout_runs() {
+            let mut glyphs = run
+                .glyphs
+                .iter()
+                .chain(run.ruby_glyphs.iter())
+                .chain(run.prefix_glyphs.iter())
+                .chain(run.wrap_indicator_glyphs.iter())
+                .peekable();
+            if glyphs.peek().is_none() {
+                continue;
+            }
+            for glyph in glyphs {
+                min.0 = min.0.min(glyph.x);
+                max.0 = max.0.max(glyph.x + glyph.w);
+            }
+
+            // Ruby annotations fill the space reserved above the glyphs
+            let top = if run.ruby_glyphs.is_empty() {
+                run.line_y - run.max_ascent
+            } else {
+                run.line_top
+            };
+            min.1 = min.1.min(top);
+            max.1 = max.1.max(run.line_y + run.max_descent);
+        }
+
+        if min.0 > max.0 {
+            return Rectangle::default();
+        }
+        Rectangle::new(min.0, min.1, max.0 - min.0, max.1 - min.1)
+    }
+
+    /// Get every font used by the laid out lines, with the sorted glyph ids used from each, for
+    /// example to subset fonts when exporting
+    ///
+    /// Only lines that have been laid out are included, use [`Self::shape_until`] with
+    /// [`i32::MAX`] to lay out the whole buffer first. Ruby annotation and prefix glyphs are
+    /// included, as are the wrap indicator and tab leader glyphs of lines that draw them. Fonts
+    /// are sorted by id.
+    pub fn used_fonts(&self) -> Vec<(fontdb::ID, Vec<u16>)> {
+        let mut used = BTreeMap::<fontdb::ID, BTreeSet<u16>>::new();
+        for (line, layout) in self
+            .lines
+            .iter()
+            .filter_map(|line| Some((line, line.layout_opt().as_ref()?)))
+        {
+            for (layout_i, layout_line) in layout.iter().enumerate() {
+                // The wrap indicator follows every visual line but the last one, like in layout runs
+                let wrap_indicator: &[LayoutGlyph] = if layout_i + 1 < layout.len() {
+                    &self.wrap_indicator_glyphs
+                } else {
+                    &[]
+                };
+                let has_tab = layout_line
+                    .glyphs
+                    .iter()
+                    .any(|glyph| line.text().get(glyph.start..glyph.end) == Some("\t"));
+                let tab_leader: &[LayoutGlyph] = if has_tab {
+                    &self.tab_leader_glyphs
+                } else {
+                    &[]
+                };
+                for glyph in layout_line
+                    .glyphs
+                    .iter()
+                    .chain(layout_line.ruby_glyphs.iter())
+                    .chain(layout_line.prefix_glyphs.iter())
+                    .chain(wrap_indicator.iter())
+                    .chain(tab_leader.iter())
+                {
+                    if glyph.inline_box_opt.is_some() {
+                        continue;
+                    }
+                    used.entry(glyph.font_id)
+                        .or_default()
+                        .insert(glyph.glyph_id);
+                }
+            }
+        }
+        used.into_iter()
+            .map(|(font_id, glyph_ids)| (font_id, glyph_ids.into_iter().collect()))
+            .collect()
     }
 
     /// Get the current [`Metrics`]
@@ -554,8 +1434,16 @@ impl Buffer {
     pub fn set_metrics(&mut self, font_system: &mut FontSystem, metrics: Metrics) {
         if metrics != self.metrics {
             assert_ne!(metrics.font_size, 0.0, "font size cannot be 0");
+            let font_size_changed = metrics.font_size != self.metrics.font_size;
             self.metrics = metrics;
-            self.relayout(font_system);
+            if font_size_changed {
+                self.relayout(font_system);
+                self.shape_wrap_indicator(font_system);
+                self.shape_tab_leader(font_system);
+            } else {
+                // Only the line height changed, which does not affect the layout of the glyphs
+                self.redraw = true;
+            }
             self.shape_until_scroll(font_system);
         }
     }
@@ -566,6 +1454,8 @@ impl Buffer {
     }
 
     /// Set the current [`Wrap`]
+    ///
+    /// Lines are wrapped at the buffer width, or at [`Buffer::set_wrap_columns`] if set.
     pub fn set_wrap(&mut self, font_system: &mut FontSystem, wrap: Wrap) {
         if wrap != self.wrap {
             self.wrap = wrap;
@@ -574,40 +1464,621 @@ impl Buffer {
         }
     }
 
-    /// Get the current buffer dimensions (width, height)
-    pub fn size(&self) -> (f32, f32) {
-        (self.width, self.height)
+    /// Get the number of columns to wrap at, if any
+    pub fn wrap_columns(&self) -> Option<usize> {
+        self.wrap_columns
     }
 
-    /// Set the current buffer dimensions
-    pub fn set_size(&mut self, font_system: &mut FontSystem, width: f32, height: f32) {
-        let clamped_width = width.max(0.0);
-        let clamped_height = height.max(0.0);
+    /// Set the number of columns to wrap at, or None to wrap at the buffer width
+    ///
+    /// This is intended for monospace fonts. Lines are broken by glyph once they reach the
+    /// given number of columns, where a column is the widest glyph advance in the line divided
+    /// by the number of cells of its character, see [`Buffer::cell_width`]. The
+    /// [`Wrap`] setting and buffer width are ignored while this is set.
+    pub fn set_wrap_columns(&mut self, font_system: &mut FontSystem, wrap_columns: Option<usize>) {
+        if wrap_columns != self.wrap_columns {
+            self.wrap_columns = wrap_columns;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system);
+        }
+    }
 
-        if clamped_width != self.width || clamped_height != self.height {
-            self.width = clamped_width;
-            self.height = clamped_height;
+    /// Get what is done with text that does not fit
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Set what is done with text that does not fit in the buffer width, or that is past the
+    /// maximum number of layout lines of a line, see [`BufferLine::set_overflow`]
+    ///
+    /// With [`Wrap::None`] and [`Overflow::Ellipsis`], lines wider than the buffer are cut off
+    /// with an ellipsis (`…`) shaped in the attributes of the text it replaces.
+    pub fn set_overflow(&mut self, font_system: &mut FontSystem, overflow: Overflow) {
+        if overflow != self.overflow {
+            self.overflow = overflow;
             self.relayout(font_system);
             self.shape_until_scroll(font_system);
         }
     }
 
-    /// Get the current scroll location
-    pub fn scroll(&self) -> i32 {
-        self.scroll
+    /// Get the maximum number of layout lines of each line, if any
+    pub fn max_lines(&self) -> Option<usize> {
+        self.max_lines
     }
 
-    /// Set the current scroll location
-    pub fn set_scroll(&mut self, scroll: i32) {
-        if scroll != self.scroll {
-            self.scroll = scroll;
-            self.redraw = true;
+    /// Set the maximum number of layout lines that each line wraps to, or None for no limit, see
+    /// [`BufferLine::set_max_lines`]
+    pub fn set_max_lines(&mut self, font_system: &mut FontSystem, max_lines: Option<usize>) {
+        if max_lines != self.max_lines {
+            self.max_lines = max_lines;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system);
         }
     }
 
-    /// Get the number of lines that can be viewed in the buffer
-    pub fn visible_lines(&self) -> i32 {
-        (self.height / self.metrics.line_height) as i32
+    /// Check if the visible layout of this buffer is the same as that of `other`
+    ///
+    /// This compares the visual lines from [`Buffer::layout_runs`], including the ids, positions,
+    /// and colors of their glyphs, so layout regression tests do not need to compare pixels.
+    pub fn layout_equals(&self, other: &Buffer) -> bool {
+        self.layout_runs().eq(other.layout_runs())
+    }
+
+    /// Get the number of cells that East Asian Ambiguous characters occupy in the monospace grid
+    pub fn ambiguous_width(&self) -> u8 {
+        self.ambiguous_width
+    }
+
+    /// Set the number of cells, 1 or 2, that East Asian Ambiguous characters such as `±` or `─`
+    /// occupy in the monospace grid used by [`Buffer::set_wrap_columns`]
+    ///
+    /// This should match the setting of the terminal being emulated. Values are clamped to 1 or 2.
+    pub fn set_ambiguous_width(&mut self, font_system: &mut FontSystem, ambiguous_width: u8) {
+        let ambiguous_width = ambiguous_width.clamp(1, 2);
+        if ambiguous_width != self.ambiguous_width {
+            self.ambiguous_width = ambiguous_width;
+            if self.wrap_columns.is_some() {
+                self.relayout(font_system);
+                self.shape_until_scroll(font_system);
+            }
+        }
+    }
+
+    /// Get the number of monospace grid cells occupied by `c`, taking
+    /// [`Buffer::ambiguous_width`] into account
+    ///
+    /// Control characters and zero width characters such as combining marks occupy no cells.
+    pub fn cell_width(&self, c: char) -> usize {
+        char_cells(c, self.ambiguous_width)
+    }
+
+    /// Get the character drawn at soft wrap points
+    pub fn wrap_indicator(&self) -> Option<char> {
+        self.wrap_indicator
+    }
+
+    /// Set a character, such as `↵`, to draw at the right edge of each visual line that is
+    /// followed by a soft wrap, or None to disable it
+    ///
+    /// Lines ending in a hard line break do not have an indicator. The indicator is shaped with
+    /// the default [`Attrs`] and the buffer font size. Its glyphs are available in
+    /// [`LayoutRun::wrap_indicator_glyphs`].
+    pub fn set_wrap_indicator(
+        &mut self,
+        font_system: &mut FontSystem,
+        wrap_indicator: Option<char>,
+    ) {
+        if wrap_indicator != self.wrap_indicator {
+            self.wrap_indicator = wrap_indicator;
+            self.shape_wrap_indicator(font_system);
+            self.redraw = true;
+        }
+    }
+
+    /// Shape the wrap indicator and place it at the right edge of the buffer
+    fn shape_wrap_indicator(&mut self, font_system: &mut FontSystem) {
+        self.wrap_indicator_glyphs.clear();
+        let wrap_indicator = match self.wrap_indicator {
+            Some(some) => some,
+            None => return,
+        };
+
+        if let Some(layout_line) = self.shape_char(font_system, wrap_indicator) {
+            let x = self.width - layout_line.w;
+            for mut glyph in layout_line.glyphs {
+                glyph.x += x;
+                self.wrap_indicator_glyphs.push(glyph);
+            }
+        }
+    }
+
+    /// Get the character repeated across the gap of each tab
+    pub fn tab_leader(&self) -> Option<char> {
+        self.tab_leader
+    }
+
+    /// Set a character, such as `.`, to repeat across the gap of each tab when drawing, or None
+    /// to disable it
+    ///
+    /// The leader is shaped with the default [`Attrs`] and the buffer font size, and takes the
+    /// color of the tab it fills. Its glyphs are available from
+    /// [`LayoutRun::tab_leader_glyphs`].
+    pub fn set_tab_leader(&mut self, font_system: &mut FontSystem, tab_leader: Option<char>) {
+        if tab_leader != self.tab_leader {
+            self.tab_leader = tab_leader;
+            self.shape_tab_leader(font_system);
+            self.redraw = true;
+        }
+    }
+
+    /// Shape the tab leader at an X offset of zero
+    fn shape_tab_leader(&mut self, font_system: &mut FontSystem) {
+        self.tab_leader_glyphs.clear();
+        let tab_leader = match self.tab_leader {
+            Some(some) => some,
+            None => return,
+        };
+
+        if let Some(layout_line) = self.shape_char(font_system, tab_leader) {
+            self.tab_leader_glyphs = layout_line.glyphs;
+        }
+    }
+
+    /// Shape and lay out a single character with the default [`Attrs`]
+    fn shape_char(&mut self, font_system: &mut FontSystem, c: char) -> Option<LayoutLine> {
+        let mut text = String::new();
+        text.push(c);
+        let shape = ShapeLine::new_in_buffer_with_options(
+            &mut self.scratch,
+            font_system,
+            &text,
+            &AttrsList::new(Attrs::new()),
+            Shaping::Advanced,
+            ShapeOptions {
+                reveal_bidi_controls: false,
+                isolated_mark_display: false,
+                ..self.shape_options
+            },
+        );
+        let mut layout = Vec::with_capacity(1);
+        shape.layout_to_buffer(
+            &mut self.scratch,
+            self.metrics.font_size,
+            f32::MAX,
+            Wrap::None,
+            Some(Align::Left),
+            &mut layout,
+        );
+        layout.into_iter().next()
+    }
+
+    /// Get the spacing of the baseline grid
+    pub fn baseline_grid(&self) -> Option<f32> {
+        self.baseline_grid
+    }
+
+    /// Set the spacing of the baseline grid, or None to disable it
+    ///
+    /// Each layout line is moved down so that its baseline is on the next multiple of the spacing.
+    /// This keeps baselines aligned across lines of different heights, but also reduces the
+    /// number of lines that fit in the buffer when the spacing does not divide the line height.
+    pub fn set_baseline_grid(&mut self, font_system: &mut FontSystem, baseline_grid: Option<f32>) {
+        if baseline_grid != self.baseline_grid {
+            self.baseline_grid = baseline_grid;
+            self.redraw = true;
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get the vertical distance between consecutive baselines of lines without ruby annotations
+    fn line_pitch(&self) -> f32 {
+        self.row_height(0.0)
+    }
+
+    /// Get the vertical space taken by a layout line with ruby annotations of `ruby_height`
+    fn row_height(&self, ruby_height: f32) -> f32 {
+        let line_height = self.metrics.line_height + ruby_height;
+        match self.baseline_grid {
+            Some(grid) if grid > 0.0 => libm::ceilf(line_height / grid).max(1.0) * grid,
+            _ => line_height,
+        }
+    }
+
+    /// Get the vertical space taken by each visual line from the start of the buffer, until the
+    /// first line that is not laid out
+    fn row_heights(&self) -> impl Iterator<Item = f32> + Clone + '_ {
+        self.lines
+            .iter()
+            .map_while(|line| line.layout_opt().as_ref())
+            .flat_map(|layout| layout.iter())
+            .map(|layout_line| self.row_height(layout_line.ruby_height))
+    }
+
+    /// Get the number of visual lines from visual line `start` that fit in the height of the
+    /// buffer, with lines that are not laid out taking the line pitch
+    fn lines_fitting(&self, start: i32) -> i32 {
+        let mut height = self.height;
+        let mut lines = 0;
+        for row_height in self.row_heights().skip(cmp::max(start, 0) as usize) {
+            if row_height > height {
+                return lines;
+            }
+            height -= row_height;
+            lines += 1;
+        }
+        lines.saturating_add((height / self.line_pitch()) as i32)
+    }
+
+    /// Get the scroll of the view that ends with visual line `last`, with as many lines before it
+    /// as fit, and lines that are not laid out taking the line pitch
+    fn scroll_ending_at(&self, last: i32) -> i32 {
+        let rows = self
+            .row_heights()
+            .chain(iter::repeat(self.line_pitch()))
+            .take(cmp::max(last, 0) as usize + 1);
+        let mut start_rows = rows.clone();
+        let mut scroll = 0;
+        let mut height = 0.0;
+        for row_height in rows {
+            height += row_height;
+            while height > self.height && scroll < last {
+                height -= start_rows.next().unwrap_or_default();
+                scroll += 1;
+            }
+        }
+        scroll
+    }
+
+    /// Get the metrics a visual line is laid out with, counting the layout lines of every text
+    /// line from the start of the buffer
+    ///
+    /// The font size is that of the buffer. The line height is the vertical space the visual line
+    /// takes, including any ruby annotations above it, rounded up to a multiple of the baseline
+    /// grid if one is set. Visual lines that are not laid out report the buffer [`Metrics`].
+    pub fn line_metrics(&self, visual_line_i: usize) -> Metrics {
+        let layout_line_opt = self
+            .lines
+            .iter()
+            .map_while(|line| line.layout_opt().as_ref())
+            .flat_map(|layout| layout.iter())
+            .nth(visual_line_i);
+        match layout_line_opt {
+            Some(layout_line) => Metrics::new(
+                self.metrics.font_size,
+                self.row_height(layout_line.ruby_height),
+            ),
+            None => self.metrics,
+        }
+    }
+
+    /// Get the primary direction of the text, for aligning it as a whole
+    ///
+    /// This is the direction of the first strong character of the first line that has one, skipping
+    /// characters inside isolates as the Unicode Bidirectional Algorithm does when finding the
+    /// direction of a paragraph. Text with no strong characters is left-to-right.
+    pub fn primary_direction(&self) -> Direction {
+        self.lines
+            .iter()
+            .find_map(|line| first_strong_direction(line.text()))
+            .unwrap_or(Direction::Ltr)
+    }
+
+    /// Get whether bidi control characters are revealed
+    pub fn reveal_bidi_controls(&self) -> bool {
+        self.shape_options.reveal_bidi_controls
+    }
+
+    /// Set whether bidi control characters are revealed
+    ///
+    /// When enabled, the normally invisible embedding, override, and isolate controls are shaped
+    /// as visible, highlighted placeholders, so that hidden changes to the direction of text can
+    /// be reviewed.
+    pub fn set_reveal_bidi_controls(&mut self, font_system: &mut FontSystem, reveal: bool) {
+        if reveal != self.shape_options.reveal_bidi_controls {
+            self.shape_options.reveal_bidi_controls = reveal;
+            for line in &mut self.lines {
+                line.reset();
+            }
+            self.redraw = true;
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get the options used to shape the lines of this buffer, which are set with
+    /// [`Self::set_reveal_bidi_controls`], [`Self::set_isolated_mark_display`],
+    /// [`Self::set_writing_mode`], and [`Self::set_tab_width`]
+    pub fn shape_options(&self) -> ShapeOptions {
+        self.shape_options
+    }
+
+    /// Shape lines as the given runs instead of itemizing them by direction, script, and font
+    ///
+    /// Each run is assigned to the line at its [`PreparedRun::line_i`], replacing the runs of
+    /// every line, and shaped with its font, without font fallback, and its direction and script.
+    /// See [`BufferLine::set_prepared_runs`] for how the runs of a line are used. Lines without
+    /// runs are itemized as usual.
+    pub fn set_prepared_runs(&mut self, font_system: &mut FontSystem, runs: Vec<PreparedRun>) {
+        let mut line_runs: Vec<Vec<PreparedRun>> = self.lines.iter().map(|_| Vec::new()).collect();
+        for run in runs {
+            match line_runs.get_mut(run.line_i) {
+                Some(some) => some.push(run),
+                None => log::warn!("ignoring prepared run for missing line {}", run.line_i),
+            }
+        }
+        let mut reset = false;
+        for (line, runs) in self.lines.iter_mut().zip(line_runs) {
+            reset |= line.set_prepared_runs(runs);
+        }
+        if reset {
+            self.redraw = true;
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get whether combining marks without a base are shown on a dotted circle
+    pub fn isolated_mark_display(&self) -> bool {
+        self.shape_options.isolated_mark_display
+    }
+
+    /// Set whether combining marks without a base are shown on a dotted circle
+    ///
+    /// When enabled, a combining mark at the start of a line is shaped on a dotted circle (U+25CC),
+    /// so that it is visible rather than overlapping the edge of the line. The dotted circle shares
+    /// the cluster of the mark, so cursor movement and selection are unchanged.
+    pub fn set_isolated_mark_display(&mut self, font_system: &mut FontSystem, display: bool) {
+        if display != self.shape_options.isolated_mark_display {
+            self.shape_options.isolated_mark_display = display;
+            for line in &mut self.lines {
+                line.reset();
+            }
+            self.redraw = true;
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get the direction lines are laid out in
+    pub fn writing_mode(&self) -> WritingMode {
+        self.shape_options.writing_mode
+    }
+
+    /// Set the direction lines are laid out in
+    ///
+    /// In [`WritingMode::VerticalRl`], lines run from top to bottom and stack from right to left.
+    /// Han, kana, and other East Asian characters are shaped upright with the vertical forms of
+    /// the font, while other text, like Latin, is rotated 90 degrees clockwise. Right-to-left
+    /// paragraphs are kept horizontal. The physical size of the buffer is kept, so lines wrap at
+    /// its height. [`Self::draw`], [`Self::hit`], and the caret positions handle the rotation, but
+    /// [`crate::Editor`] still draws its cursor and selection horizontally.
+    pub fn set_writing_mode(&mut self, font_system: &mut FontSystem, writing_mode: WritingMode) {
+        if writing_mode != self.shape_options.writing_mode {
+            let (width, height) = self.size();
+            self.shape_options.writing_mode = writing_mode;
+            match writing_mode {
+                WritingMode::HorizontalTb => {
+                    self.width = width;
+                    self.height = height;
+                }
+                WritingMode::VerticalRl => {
+                    self.width = height;
+                    self.height = width;
+                }
+            }
+            for line in &mut self.lines {
+                line.reset();
+            }
+            self.redraw = true;
+            self.shape_wrap_indicator(font_system);
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get the distance between tab stops, in spaces
+    pub fn tab_width(&self) -> u16 {
+        self.shape_options.tab_width
+    }
+
+    /// Set the distance between tab stops, in spaces
+    ///
+    /// Each tab is widened so that the text after it starts on the next multiple of `tab_width`
+    /// times the width of a space. Tab stops are measured from the start of the text line, so they
+    /// do not restart on wrapped layout runs. A `tab_width` of 0 is ignored.
+    pub fn set_tab_width(&mut self, font_system: &mut FontSystem, tab_width: u16) {
+        if tab_width == 0 {
+            log::warn!("ignoring tab width of 0");
+            return;
+        }
+        if tab_width != self.shape_options.tab_width {
+            self.shape_options.tab_width = tab_width;
+            for line in &mut self.lines {
+                line.reset();
+            }
+            self.redraw = true;
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get the default text color
+    pub fn default_color(&self) -> Option<Color> {
+        self.default_color
+    }
+
+    /// Set the default text color, used when drawing glyphs whose [`Attrs`] have no color
+    ///
+    /// This takes precedence over the color passed to `draw`. Setting to None will use the color
+    /// passed to `draw` again.
+    pub fn set_default_color(&mut self, color: Option<Color>) {
+        if color != self.default_color {
+            self.default_color = color;
+            self.redraw = true;
+        }
+    }
+
+    /// Get whether editing keeps the text on one line, see [`Self::set_single_line`]
+    pub fn single_line(&self) -> bool {
+        self.single_line
+    }
+
+    /// Set whether editing keeps the text on one line, such as in a search box
+    ///
+    /// When enabled, [`crate::Action::Enter`] does nothing in an [`crate::Editor`], and line
+    /// endings in inserted and pasted text are removed as set by
+    /// [`Self::set_single_line_newlines`]. Text that is already in the buffer is not changed.
+    pub fn set_single_line(&mut self, single_line: bool) {
+        self.single_line = single_line;
+    }
+
+    /// Get how line endings are removed from text inserted into a single line buffer
+    pub fn single_line_newlines(&self) -> SingleLineNewlines {
+        self.single_line_newlines
+    }
+
+    /// Set how line endings are removed from text inserted into a single line buffer, see
+    /// [`Self::set_single_line`]
+    pub fn set_single_line_newlines(&mut self, single_line_newlines: SingleLineNewlines) {
+        self.single_line_newlines = single_line_newlines;
+    }
+
+    /// Get the current buffer dimensions (width, height)
+    pub fn size(&self) -> (f32, f32) {
+        match self.shape_options.writing_mode {
+            WritingMode::HorizontalTb => (self.width, self.height),
+            WritingMode::VerticalRl => (self.height, self.width),
+        }
+    }
+
+    /// Set the current buffer dimensions
+    pub fn set_size(&mut self, font_system: &mut FontSystem, width: f32, height: f32) {
+        let (width, height) = match self.shape_options.writing_mode {
+            WritingMode::HorizontalTb => (width, height),
+            WritingMode::VerticalRl => (height, width),
+        };
+        let clamped_width = width.max(0.0);
+        let clamped_height = height.max(0.0);
+
+        if clamped_width != self.width || clamped_height != self.height {
+            self.width = clamped_width;
+            self.height = clamped_height;
+            self.relayout(font_system);
+            self.shape_wrap_indicator(font_system);
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Get the current scroll location
+    pub fn scroll(&self) -> i32 {
+        self.scroll
+    }
+
+    /// Set the current scroll location, also removing any offset into the first line set by
+    /// [`Self::set_scroll_pixels`]
+    pub fn set_scroll(&mut self, scroll: i32) {
+        if scroll != self.scroll || self.scroll_y != 0.0 {
+            self.scroll = scroll;
+            self.scroll_y = 0.0;
+            self.redraw = true;
+        }
+    }
+
+    /// Get the current vertical scroll location in pixels
+    ///
+    /// This is the height of the layout lines before [`Self::scroll`], including their ruby
+    /// annotations, plus the offset into the first visible layout line. Layout lines that are
+    /// not laid out count as the line pitch.
+    pub fn scroll_pixels(&self) -> f32 {
+        let scroll_height: f32 = self
+            .row_heights()
+            .chain(iter::repeat(self.line_pitch()))
+            .take(cmp::max(self.scroll, 0) as usize)
+            .sum();
+        scroll_height + self.scroll_y
+    }
+
+    /// Set the current vertical scroll location in pixels, for smooth scrolling with touchpads
+    /// or kinetic scrolling
+    ///
+    /// Every layout line counts its height, including ruby annotations, aligned to the baseline
+    /// grid, if any. Layout lines that are not laid out count as the line pitch. The visible
+    /// layout runs, and so everything drawn from them, are moved up by the part of the scroll
+    /// that is less than a line. The scroll is clamped like that of [`Self::shape_until_scroll`].
+    pub fn set_scroll_pixels(&mut self, font_system: &mut FontSystem, scroll_pixels: f32) {
+        let line_pitch = self.line_pitch();
+        let mut scroll = 0;
+        let mut scroll_y = scroll_pixels.max(0.0);
+        let mut in_layout = false;
+        for row_height in self.row_heights() {
+            if scroll_y < row_height {
+                in_layout = true;
+                break;
+            }
+            scroll_y -= row_height;
+            scroll += 1;
+        }
+        if !in_layout {
+            let lines = libm::floorf(scroll_y / line_pitch);
+            scroll += lines as i32;
+            scroll_y -= lines * line_pitch;
+        }
+        if scroll != self.scroll || scroll_y != self.scroll_y {
+            self.scroll = scroll;
+            self.scroll_y = scroll_y;
+            self.redraw = true;
+        }
+        self.shape_until_scroll(font_system);
+    }
+
+    /// Get the current horizontal scroll location
+    pub fn scroll_x(&self) -> f32 {
+        self.scroll_x
+    }
+
+    /// Set the current horizontal scroll location, clamped so the widest laid out line is not
+    /// scrolled further than the right edge of the buffer, see [`Self::content_width`]
+    pub fn set_scroll_x(&mut self, scroll_x: f32) {
+        let max_scroll_x = (self.content_width() - self.width).max(0.0);
+        let scroll_x = scroll_x.min(max_scroll_x).max(0.0);
+        if scroll_x != self.scroll_x {
+            self.scroll_x = scroll_x;
+            self.redraw = true;
+        }
+    }
+
+    /// Get the number of lines that can be viewed in the buffer
+    ///
+    /// This counts the layout lines from [`Self::scroll`] that fit in the height, including their
+    /// ruby annotations. Layout lines that are not laid out count as the line pitch.
+    pub fn visible_lines(&self) -> i32 {
+        self.lines_fitting(self.scroll)
+    }
+
+    /// Get the number of layout lines shaped past the end of the view
+    pub fn overscan(&self) -> i32 {
+        self.overscan
+    }
+
+    /// Set the number of layout lines shaped past the end of the view by
+    /// [`Self::shape_until_scroll`], so that scrolling a little does not have to wait for shaping
+    pub fn set_overscan(&mut self, font_system: &mut FontSystem, overscan: i32) {
+        let overscan = overscan.max(0);
+        if overscan != self.overscan {
+            self.overscan = overscan;
+            self.shape_until_scroll(font_system);
+        }
+    }
+
+    /// Set text of buffer from bytes that may not be valid UTF-8, using provided attributes for
+    /// each line by default
+    ///
+    /// Invalid sequences are replaced with U+FFFD REPLACEMENT CHARACTER, which is shaped like any
+    /// other text.
+    pub fn set_text_lossy(
+        &mut self,
+        font_system: &mut FontSystem,
+        bytes: &[u8],
+        attrs: Attrs,
+        shaping: Shaping,
+    ) {
+        let text = String::from_utf8_lossy(bytes);
+        self.set_text(font_system, &text, attrs, shaping);
     }
 
     /// Set text of buffer, using provided attributes for each line by default
@@ -618,28 +2089,156 @@ impl Buffer {
         attrs: Attrs,
         shaping: Shaping,
     ) {
+        self.set_text_lines(text, attrs, shaping);
+        self.shape_until_scroll(font_system);
+    }
+
+    /// Set text of buffer from spans of text with their own attributes, using `default_attrs` for
+    /// each line by default
+    ///
+    /// Spans are split into lines the same way as [`Self::set_text`], so a span may continue over
+    /// several lines and a line may be made of several spans.
+    pub fn set_rich_text<'r, 's, I>(
+        &mut self,
+        font_system: &mut FontSystem,
+        spans: I,
+        default_attrs: Attrs,
+        shaping: Shaping,
+    ) where
+        I: IntoIterator<Item = (&'s str, Attrs<'r>)>,
+    {
+        self.lines.clear();
+        let mut line_text = String::new();
+        let mut attrs_list = AttrsList::new(default_attrs);
+        for (text, attrs) in spans {
+            for (line, ending) in LineIter::new(text) {
+                let start = line_text.len();
+                line_text.push_str(line);
+                attrs_list.add_span(start..line_text.len(), attrs);
+                if ending != LineEnding::None {
+                    let mut buffer_line = BufferLine::new(
+                        core::mem::take(&mut line_text),
+                        core::mem::replace(&mut attrs_list, AttrsList::new(default_attrs)),
+                        shaping,
+                    );
+                    buffer_line.set_ending(ending);
+                    self.lines.push(buffer_line);
+                }
+            }
+        }
+        // Text after the last line ending is its own line, and there is always one line
+        if !line_text.is_empty() || self.lines.is_empty() {
+            let mut buffer_line = BufferLine::new(line_text, attrs_list, shaping);
+            buffer_line.set_ending(LineEnding::None);
+            self.lines.push(buffer_line);
+        }
+
+        self.scroll = 0;
+        self.scroll_y = 0.0;
+        self.scroll_x = 0.0;
+        self.set_content_changed();
+        self.shape_until_scroll(font_system);
+    }
+
+    /// Replace the lines of the buffer with `text` and reset scroll, without shaping
+    fn set_text_lines(&mut self, text: &str, attrs: Attrs, shaping: Shaping) {
         self.lines.clear();
-        for line in BidiParagraphs::new(text) {
-            self.lines.push(BufferLine::new(
-                line.to_string(),
-                AttrsList::new(attrs),
-                shaping,
-            ));
+        for (line, ending) in LineIter::new(text) {
+            let mut buffer_line = BufferLine::new(line.to_string(), AttrsList::new(attrs), shaping);
+            buffer_line.set_ending(ending);
+            self.lines.push(buffer_line);
         }
         // Make sure there is always one line
         if self.lines.is_empty() {
-            self.lines.push(BufferLine::new(
-                String::new(),
-                AttrsList::new(attrs),
-                shaping,
-            ));
+            let mut buffer_line = BufferLine::new(String::new(), AttrsList::new(attrs), shaping);
+            buffer_line.set_ending(LineEnding::None);
+            self.lines.push(buffer_line);
+        }
+
+        self.scroll = 0;
+        self.scroll_y = 0.0;
+        self.scroll_x = 0.0;
+        self.set_content_changed();
+    }
+
+    /// Set text of buffer from a [`ropey::Rope`], using provided attributes for each line by
+    /// default
+    ///
+    /// Lines are split the same way as [`Self::set_text`], but without collecting the whole rope
+    /// into one string first. The rope is only read here, the buffer still keeps the text of
+    /// each line in its [`BufferLine`], so edits do not go through the rope. Use
+    /// [`Self::to_rope`] to save the edited text.
+    #[cfg(feature = "ropey")]
+    pub fn set_rope(
+        &mut self,
+        font_system: &mut FontSystem,
+        rope: &ropey::Rope,
+        attrs: Attrs,
+        shaping: Shaping,
+    ) {
+        self.lines.clear();
+        // Text after the last line ending, which continues into the next rope line
+        let mut pending = String::new();
+        for rope_line in rope.lines() {
+            for chunk in rope_line.chunks() {
+                pending.push_str(chunk);
+            }
+
+            let mut remainder = String::new();
+            for (line, ending) in LineIter::new(&pending) {
+                if ending == LineEnding::None {
+                    remainder.push_str(line);
+                } else {
+                    let mut buffer_line =
+                        BufferLine::new(line.to_string(), AttrsList::new(attrs), shaping);
+                    buffer_line.set_ending(ending);
+                    self.lines.push(buffer_line);
+                }
+            }
+            pending = remainder;
+        }
+        // Make sure there is always one line
+        if !pending.is_empty() || self.lines.is_empty() {
+            let mut buffer_line = BufferLine::new(pending, AttrsList::new(attrs), shaping);
+            buffer_line.set_ending(LineEnding::None);
+            self.lines.push(buffer_line);
         }
 
         self.scroll = 0;
+        self.scroll_y = 0.0;
+        self.scroll_x = 0.0;
+        self.set_content_changed();
 
         self.shape_until_scroll(font_system);
     }
 
+    /// Get the text of the buffer as a [`ropey::Rope`], with each line followed by its original
+    /// line ending
+    #[cfg(feature = "ropey")]
+    pub fn to_rope(&self) -> ropey::Rope {
+        let mut builder = ropey::RopeBuilder::new();
+        let mut ending = String::new();
+        for line in self.lines.iter() {
+            builder.append(line.text());
+            ending.clear();
+            line.ending().push_to(&mut ending);
+            builder.append(&ending);
+        }
+        builder.finish()
+    }
+
+    /// Get the text of the buffer, with each line followed by its original line ending
+    ///
+    /// For text set with [`Self::set_text`], this reproduces the original text exactly.
+    pub fn to_string_with_endings(&self) -> String {
+        let mut string = String::new();
+        for line in self.lines.iter() {
+            string.push_str(line.text());
+            line.ending().push_to(&mut string);
+        }
+        string
+    }
+
     /// True if a redraw is needed
     pub fn redraw(&self) -> bool {
         self.redraw
@@ -650,31 +2249,213 @@ impl Buffer {
         self.redraw = redraw;
     }
 
+    /// Get a number that changes whenever the text or attributes of the buffer change, but not
+    /// when only the view changes, such as scrolling, resizing, or moving the cursor
+    ///
+    /// Comparing this to the value when the text was last saved gives a modified indicator.
+    pub fn content_version(&self) -> u64 {
+        self.content_version
+    }
+
+    /// Mark the text or attributes of the buffer as changed, updating
+    /// [`Self::content_version`]
+    ///
+    /// This is done by [`Self::set_text`] and the editing done by [`crate::Editor`], and must be
+    /// called after modifying [`Self::lines`] directly.
+    pub fn set_content_changed(&mut self) {
+        self.content_version = self.content_version.wrapping_add(1);
+    }
+
+    /// Get the range of lines that have been laid out again since [`Self::clear_damage`], or
+    /// None if no line has
+    ///
+    /// Lines keep their layout until their text, attributes, or the layout settings change, so
+    /// renderers that cache what they draw for each line only have to redraw these lines. If the
+    /// number of lines has changed, the range extends to the last line, as the lines after an
+    /// insertion or removal have moved. Scrolling does not damage any lines, see
+    /// [`Self::redraw`].
+    pub fn damaged_lines(&self) -> Option<Range<usize>> {
+        if self.lines.len() == self.damage_lines_len {
+            return self.damage.clone();
+        }
+        let len = self.lines.len();
+        let start = self.damage.as_ref().map_or(0, |damage| damage.start);
+        Some(start.min(len)..len)
+    }
+
+    /// Clear the damaged lines, after redrawing them, see [`Self::damaged_lines`]
+    pub fn clear_damage(&mut self) {
+        self.damage = None;
+        self.damage_lines_len = self.lines.len();
+    }
+
     /// Get the visible layout runs for rendering and other tasks
     pub fn layout_runs(&self) -> LayoutRunIter {
         LayoutRunIter::new(self)
     }
 
+    /// Get the word at a [`Cursor`], as its byte range in the line and its text
+    ///
+    /// Words are found using the Unicode word boundary rules, which are also used for moving the
+    /// cursor by words. A cursor at either end of a word is considered to be in it. Returns None
+    /// if the cursor is not in a word, for example in whitespace or punctuation.
+    pub fn word_at(&self, cursor: Cursor) -> Option<(Range<usize>, &str)> {
+        let line = self.lines.get(cursor.line)?;
+        line.text()
+            .unicode_word_indices()
+            .take_while(|(i, _)| *i <= cursor.index)
+            .find(|(i, word)| cursor.index <= i + word.len())
+            .map(|(i, word)| (i..i + word.len(), word))
+    }
+
+    /// Find the matches of `query` in the text of each line, as start and end cursors
+    ///
+    /// Matches do not span lines and do not overlap: after a match, the search continues from its
+    /// end. If `case_sensitive` is false, characters are compared by their lowercase mappings, and
+    /// the cursors are still byte offsets into the original text. An empty query has no matches.
+    pub fn search(&self, query: &str, case_sensitive: bool) -> Vec<(Cursor, Cursor)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line_i, line)| {
+                line_matches(line.text(), query, case_sensitive)
+                    .into_iter()
+                    .map(move |range| {
+                        (
+                            Cursor::new(line_i, range.start),
+                            Cursor::new(line_i, range.end),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Find the first match of `query` at or after `from`, wrapping around to the start of the
+    /// buffer, see [`Self::search`]
+    pub fn search_next(
+        &self,
+        from: Cursor,
+        query: &str,
+        case_sensitive: bool,
+    ) -> Option<(Cursor, Cursor)> {
+        let len = self.lines.len();
+        if len == 0 {
+            return None;
+        }
+
+        // Search the rest of the cursor line, the following lines, and then wrap around to the
+        // cursor line again
+        for n in 0..=len {
+            let line_i = (from.line.min(len - 1) + n) % len;
+            let mut matches = line_matches(self.lines[line_i].text(), query, case_sensitive)
+                .into_iter()
+                .filter(|range| n > 0 || range.start >= from.index);
+            if let Some(range) = matches.next() {
+                return Some((
+                    Cursor::new(line_i, range.start),
+                    Cursor::new(line_i, range.end),
+                ));
+            }
+        }
+        None
+    }
+
+    /// Find the last match of `query` that starts before `from`, wrapping around to the end of
+    /// the buffer, see [`Self::search`]
+    pub fn search_previous(
+        &self,
+        from: Cursor,
+        query: &str,
+        case_sensitive: bool,
+    ) -> Option<(Cursor, Cursor)> {
+        let len = self.lines.len();
+        if len == 0 {
+            return None;
+        }
+
+        // Search the start of the cursor line, the preceding lines, and then wrap around to the
+        // cursor line again
+        for n in 0..=len {
+            let line_i = (from.line.min(len - 1) + len - n % len) % len;
+            let range_opt = line_matches(self.lines[line_i].text(), query, case_sensitive)
+                .into_iter()
+                .rev()
+                .find(|range| n > 0 || range.start < from.index);
+            if let Some(range) = range_opt {
+                return Some((
+                    Cursor::new(line_i, range.start),
+                    Cursor::new(line_i, range.end),
+                ));
+            }
+        }
+        None
+    }
+
+    /// Get the positions of brackets without a partner, such as `(` without a following `)`
+    ///
+    /// Parentheses, square brackets, and curly braces are matched across all lines, in order of
+    /// position. A closing bracket that does not match the innermost open bracket is unmatched.
+    pub fn unmatched_brackets(&self) -> Vec<Cursor> {
+        let mut open = Vec::new();
+        let mut unmatched = Vec::new();
+        for (line_i, line) in self.lines.iter().enumerate() {
+            for (index, c) in line.text().char_indices() {
+                let opening = match c {
+                    '(' | '[' | '{' => {
+                        open.push((Cursor::new(line_i, index), c));
+                        continue;
+                    }
+                    ')' => '(',
+                    ']' => '[',
+                    '}' => '{',
+                    _ => continue,
+                };
+                match open.last() {
+                    Some((_, open_c)) if *open_c == opening => {
+                        open.pop();
+                    }
+                    _ => unmatched.push(Cursor::new(line_i, index)),
+                }
+            }
+        }
+        unmatched.extend(open.into_iter().map(|(cursor, _)| cursor));
+        unmatched.sort();
+        unmatched
+    }
+
     /// Convert x, y position to Cursor (hit detection)
+    ///
+    /// The position is relative to the top left of the buffer, as drawn with the current scroll.
+    /// Each layout run is hit from its top to the top of the next run, and within a run the cursor
+    /// is placed on whichever side of a grapheme is closer, respecting its direction. Positions
+    /// before or after a run place the cursor at its start or end. This is the inverse of
+    /// [`Self::cursor_position`].
     pub fn hit(&self, x: f32, y: f32) -> Option<Cursor> {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
         let instant = std::time::Instant::now();
 
-        let font_size = self.metrics.font_size;
         let line_height = self.metrics.line_height;
+        // In vertical text, physical positions are rotated back into the horizontal layout
+        let (x, y) = match self.shape_options.writing_mode {
+            WritingMode::HorizontalTb => (x, y),
+            WritingMode::VerticalRl => (y, self.height - x),
+        };
+        let x = x + self.scroll_x;
 
         let mut new_cursor_opt = None;
 
         let mut runs = self.layout_runs().peekable();
         let mut first_run = true;
         while let Some(run) = runs.next() {
-            let line_y = run.line_y;
+            let run_bottom = runs
+                .peek()
+                .map_or(run.line_top + line_height, |next| next.line_top);
 
-            if first_run && y < line_y - font_size {
+            if first_run && y < run.line_top {
                 first_run = false;
                 let new_cursor = Cursor::new(run.line_i, 0);
                 new_cursor_opt = Some(new_cursor);
-            } else if y >= line_y - font_size && y < line_y - font_size + line_height {
+            } else if y >= run.line_top && y < run_bottom {
                 let mut new_cursor_glyph = run.glyphs.len();
                 let mut new_cursor_char = 0;
                 let mut new_cursor_affinity = Affinity::After;
@@ -684,7 +2465,7 @@ impl Buffer {
                 'hit: for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
                     if first_glyph {
                         first_glyph = false;
-                        if (run.rtl && x > glyph.x) || (!run.rtl && x < 0.0) {
+                        if (run.rtl && x > glyph.x) || (!run.rtl && x < glyph.x) {
                             new_cursor_glyph = 0;
                             new_cursor_char = 0;
                         }
@@ -756,7 +2537,141 @@ impl Buffer {
         new_cursor_opt
     }
 
+    /// Get the line index and the glyph under an x, y position, or None if there is no glyph there
+    ///
+    /// The position is relative to the buffer like [`Self::hit`], but unlike a cursor, only
+    /// positions inside the advance of a glyph and within its layout run hit it. This maps a
+    /// position back to the attributes of the text, such as a link set with
+    /// [`Attrs::metadata`], from [`LayoutGlyph::metadata`].
+    pub fn glyph_at(&self, x: f32, y: f32) -> Option<(usize, &LayoutGlyph)> {
+        let line_height = self.metrics.line_height;
+        let (x, y) = match self.shape_options.writing_mode {
+            WritingMode::HorizontalTb => (x, y),
+            WritingMode::VerticalRl => (y, self.height - x),
+        };
+        let x = x + self.scroll_x;
+
+        let mut runs = self.layout_runs().peekable();
+        while let Some(run) = runs.next() {
+            let run_bottom = runs
+                .peek()
+                .map_or(run.line_top + line_height, |next| next.line_top);
+            if y >= run.line_top && y < run_bottom {
+                return run
+                    .glyphs
+                    .iter()
+                    .find(|glyph| x >= glyph.x && x < glyph.x + glyph.w)
+                    .map(|glyph| (run.line_i, glyph));
+            }
+        }
+        None
+    }
+
+    /// Convert Cursor to the x, y position of the top of the caret, or None if the cursor is not
+    /// in a visible layout run
+    ///
+    /// The position is relative to the top left of the buffer, as drawn with the current scroll,
+    /// and the caret is as tall as the line height of the [`Metrics`]. In
+    /// [`WritingMode::VerticalRl`], the position is the left of a caret as wide as the line
+    /// height. This is the inverse of [`Self::hit`].
+    pub fn cursor_position(&self, cursor: &Cursor) -> Option<(i32, i32)> {
+        let scroll_x = libm::roundf(self.scroll_x) as i32;
+        self.layout_runs().find_map(|run| {
+            let x = cursor_x(&run, cursor)?;
+            Some(self.caret_position(x - scroll_x, run.line_top))
+        })
+    }
+
+    /// Rotate the position of a caret in the layout into the writing mode
+    fn caret_position(&self, x: i32, line_top: f32) -> (i32, i32) {
+        match self.shape_options.writing_mode {
+            WritingMode::HorizontalTb => (x, line_top as i32),
+            WritingMode::VerticalRl => (
+                (self.height - line_top - self.metrics.line_height) as i32,
+                x,
+            ),
+        }
+    }
+
+    /// Get the position and size of a one pixel wide caret at a cursor, or None if the cursor is
+    /// not in a visible layout run
+    ///
+    /// This is the [`Self::cursor_position`] of the cursor with the line height of the
+    /// [`Metrics`], for placing input method candidate windows or drawing carets outside of an
+    /// [`crate::Editor`].
+    pub fn caret_rect(&self, cursor: &Cursor) -> Option<(i32, i32, u32, u32)> {
+        let (x, y) = self.cursor_position(cursor)?;
+        let line_height = self.metrics.line_height as u32;
+        Some(match self.shape_options.writing_mode {
+            WritingMode::HorizontalTb => (x, y, 1, line_height),
+            WritingMode::VerticalRl => (x, y, line_height, 1),
+        })
+    }
+
+    /// Get the caret positions of a cursor that may be at a boundary between bidi runs, or None if
+    /// the cursor is not in a visible layout run
+    ///
+    /// A cursor between text of different directions is next to two glyphs that are apart on
+    /// screen. The [`SplitCaret::primary`] position is next to the glyph on the side of the
+    /// [`Affinity`] of the cursor, where text of that glyph's direction is inserted. The
+    /// [`SplitCaret::secondary_opt`] is next to the other glyph, so editors can draw a split caret.
+    pub fn split_caret_positions(&self, cursor: &Cursor) -> Option<SplitCaret> {
+        let scroll_x = libm::roundf(self.scroll_x) as i32;
+        let other = Cursor {
+            affinity: Affinity::from_before(!cursor.affinity.before()),
+            ..*cursor
+        };
+        self.layout_runs().find_map(|run| {
+            let x = cursor_x(&run, cursor)?;
+            // The other side of a soft wrap is in another run, so it is not a split caret
+            let secondary_opt = cursor_x(&run, &other)
+                .filter(|other_x| *other_x != x)
+                .map(|other_x| self.caret_position(other_x - scroll_x, run.line_top));
+            Some(SplitCaret {
+                primary: self.caret_position(x - scroll_x, run.line_top),
+                secondary_opt,
+            })
+        })
+    }
+
+    /// Get the x and y position and the advance of a grapheme, or None if the line has no such
+    /// grapheme or it is not in a visible layout run
+    ///
+    /// `grapheme_i` counts the grapheme clusters of the line from its start. The position is the
+    /// left edge of the grapheme and the top of its layout run, relative to the buffer like
+    /// [`Self::cursor_position`]. Graphemes shaped as one glyph, such as a ligature, share the
+    /// advance of the glyph equally.
+    pub fn grapheme_position(&self, line_i: usize, grapheme_i: usize) -> Option<(f32, f32, f32)> {
+        let line = self.lines.get(line_i)?;
+        let (index, _) = line.text().grapheme_indices(true).nth(grapheme_i)?;
+        self.layout_runs()
+            .filter(|run| run.line_i == line_i)
+            .find_map(|run| {
+                let glyph = run
+                    .glyphs
+                    .iter()
+                    .find(|glyph| glyph.start <= index && index < glyph.end)?;
+                let cluster = &run.text[glyph.start..glyph.end];
+                let total = cluster.grapheme_indices(true).count();
+                let n = cluster
+                    .grapheme_indices(true)
+                    .position(|(i, _)| glyph.start + i == index)?;
+                let c_w = glyph.w / total as f32;
+                // The graphemes of a right-to-left cluster start from its right edge
+                let x = if glyph.level.is_rtl() {
+                    glyph.x + glyph.w - (n + 1) as f32 * c_w
+                } else {
+                    glyph.x + n as f32 * c_w
+                };
+                Some((x - self.scroll_x, run.line_top, c_w))
+            })
+    }
+
     /// Draw the buffer
+    ///
+    /// Each rectangle has its own color. Pixels of color glyphs, like emoji from COLR, CBDT, or
+    /// sbix fonts, have the colors of the glyph image instead of the text color, so `f` must
+    /// blend every rectangle with the color it is given.
     #[cfg(feature = "swash")]
     pub fn draw<F>(
         &self,
@@ -767,8 +2682,30 @@ impl Buffer {
     ) where
         F: FnMut(i32, i32, u32, u32, Color),
     {
+        let color = self.default_color.unwrap_or(color);
+        let scroll_x = libm::roundf(self.scroll_x) as i32;
+        let writing_mode = self.shape_options.writing_mode;
+        // In vertical text, the layout is rotated clockwise so that lines stack from the right
+        let columns_right = self.height as i32;
+        let mut f = |x: i32, y: i32, w: u32, h: u32, color| match writing_mode {
+            WritingMode::HorizontalTb => f(x - scroll_x, y, w, h, color),
+            WritingMode::VerticalRl => f(columns_right - y - h as i32, x - scroll_x, h, w, color),
+        };
         for run in self.layout_runs() {
-            for glyph in run.glyphs.iter() {
+            let tab_leader_glyphs = run.tab_leader_glyphs();
+            for glyph in run
+                .glyphs
+                .iter()
+                .chain(run.ruby_glyphs.iter())
+                .chain(run.prefix_glyphs.iter())
+                .chain(run.wrap_indicator_glyphs.iter())
+                .chain(tab_leader_glyphs.iter())
+            {
+                if glyph.inline_box_opt.is_some() {
+                    // Inline boxes are filled in by the renderer
+                    continue;
+                }
+
                 let physical_glyph = glyph.physical((0., 0.), 1.0);
 
                 let glyph_color = match glyph.color_opt {
@@ -776,6 +2713,27 @@ impl Buffer {
                     None => color,
                 };
 
+                if glyph.upright {
+                    // Upright glyphs are not rotated, so they are placed in physical space,
+                    // centered on their column
+                    let font_size = glyph.font_size;
+                    let center = run.line_y - (run.max_ascent - run.max_descent) / 2.0;
+                    let (mut cache_key, x, y) = CacheKey::new(
+                        glyph.font_id,
+                        glyph.glyph_id,
+                        font_size,
+                        (
+                            columns_right as f32 - center + font_size * glyph.y_offset,
+                            glyph.x + font_size * glyph.x_offset,
+                        ),
+                    );
+                    cache_key.variations_id = glyph.variations_id;
+                    cache.with_pixels(font_system, cache_key, glyph_color, |px, py, color| {
+                        f(y + py, columns_right - (x + px) - 1, 1, 1, color);
+                    });
+                    continue;
+                }
+
                 cache.with_pixels(
                     font_system,
                     physical_glyph.cache_key,
@@ -791,7 +2749,208 @@ impl Buffer {
                     },
                 );
             }
+
+            run.draw_underlines(color, |x, y, w, h, color| {
+                f(x, y, w, h, cache.rect_color(color));
+            });
+        }
+    }
+
+    /// Draw the buffer, only emitting the parts of rectangles that are inside `clip`
+    #[cfg(feature = "swash")]
+    pub fn draw_clipped<F>(
+        &self,
+        font_system: &mut FontSystem,
+        cache: &mut crate::SwashCache,
+        color: Color,
+        clip: ClipRect,
+        mut f: F,
+    ) where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        self.draw(font_system, cache, color, |x, y, w, h, color| {
+            if let Some((x, y, w, h)) = clip.intersect(x, y, w, h) {
+                f(x, y, w, h, color);
+            }
+        });
+    }
+
+    /// Draw the buffer rotated clockwise by `angle` radians about `origin`, for example for chart
+    /// axis labels
+    ///
+    /// Each rectangle from [`Buffer::draw`] is rotated and emitted as the pixels whose centers it
+    /// covers. Rotations by multiples of 90 degrees reproduce the glyph bitmaps exactly, other
+    /// angles resample them without antialiasing.
+    #[cfg(feature = "swash")]
+    pub fn draw_rotated<F>(
+        &self,
+        font_system: &mut FontSystem,
+        cache: &mut crate::SwashCache,
+        color: Color,
+        angle: f32,
+        origin: (f32, f32),
+        mut f: F,
+    ) where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        let sin = libm::sinf(angle);
+        let cos = libm::cosf(angle);
+        self.draw(font_system, cache, color, |x, y, w, h, color| {
+            let x0 = x as f32 - origin.0;
+            let y0 = y as f32 - origin.1;
+            let x1 = x0 + w as f32;
+            let y1 = y0 + h as f32;
+
+            // Find the pixels that may be covered by the rotated rectangle
+            let mut min = (f32::MAX, f32::MAX);
+            let mut max = (f32::MIN, f32::MIN);
+            for (corner_x, corner_y) in [(x0, y0), (x1, y0), (x0, y1), (x1, y1)] {
+                let rotated_x = corner_x * cos - corner_y * sin + origin.0;
+                let rotated_y = corner_x * sin + corner_y * cos + origin.1;
+                min = (min.0.min(rotated_x), min.1.min(rotated_y));
+                max = (max.0.max(rotated_x), max.1.max(rotated_y));
+            }
+
+            for py in libm::floorf(min.1) as i32..libm::ceilf(max.1) as i32 {
+                for px in libm::floorf(min.0) as i32..libm::ceilf(max.0) as i32 {
+                    // Map the pixel center back onto the unrotated rectangle
+                    let center_x = px as f32 + 0.5 - origin.0;
+                    let center_y = py as f32 + 0.5 - origin.1;
+                    let source_x = center_x * cos + center_y * sin;
+                    let source_y = center_y * cos - center_x * sin;
+                    if source_x >= x0 && source_x < x1 && source_y >= y0 && source_y < y1 {
+                        f(px, py, 1, 1, color);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// A builder for a [`Buffer`], which sets all options before the text is shaped once
+///
+/// Configuring a [`Buffer`] with its setters can shape or lay out the text again after each
+/// call. This applies every option first, and then shapes the visible lines in [`Self::build`].
+#[derive(Debug)]
+pub struct BufferBuilder {
+    metrics: Metrics,
+    width: f32,
+    height: f32,
+    wrap: Wrap,
+    wrap_columns: Option<usize>,
+    overflow: Overflow,
+    max_lines: Option<usize>,
+    tab_width: u16,
+    writing_mode: WritingMode,
+    align: Option<Align>,
+    text: String,
+    attrs: AttrsOwned,
+    shaping: Shaping,
+}
+
+impl BufferBuilder {
+    /// Create a builder for an empty [`Buffer`] with the provided [`Metrics`]
+    ///
+    /// # Panics
+    ///
+    /// [`Self::build`] will panic if `metrics.line_height` is zero.
+    pub fn new(metrics: Metrics) -> Self {
+        Self {
+            metrics,
+            width: 0.0,
+            height: 0.0,
+            wrap: Wrap::Word,
+            wrap_columns: None,
+            overflow: Overflow::Visible,
+            max_lines: None,
+            tab_width: ShapeOptions::default().tab_width,
+            writing_mode: WritingMode::HorizontalTb,
+            align: None,
+            text: String::new(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            shaping: Shaping::Advanced,
+        }
+    }
+
+    /// Set the buffer dimensions, see [`Buffer::set_size`]
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the [`Wrap`] mode, see [`Buffer::set_wrap`]
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Set the number of columns to wrap at, see [`Buffer::set_wrap_columns`]
+    pub fn wrap_columns(mut self, wrap_columns: Option<usize>) -> Self {
+        self.wrap_columns = wrap_columns;
+        self
+    }
+
+    /// Set what is done with text that does not fit, see [`Buffer::set_overflow`]
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Set the maximum number of layout lines of each line, see [`Buffer::set_max_lines`]
+    pub fn max_lines(mut self, max_lines: Option<usize>) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// Set the distance between tab stops, in spaces, see [`Buffer::set_tab_width`]
+    pub fn tab_width(mut self, tab_width: u16) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Set the [`WritingMode`], see [`Buffer::set_writing_mode`]
+    pub fn writing_mode(mut self, writing_mode: WritingMode) -> Self {
+        self.writing_mode = writing_mode;
+        self
+    }
+
+    /// Set the [`Align`] of every line, see [`BufferLine::set_align`]
+    pub fn align(mut self, align: Option<Align>) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Set the text, using provided attributes for each line by default, see
+    /// [`Buffer::set_text`]
+    pub fn text(mut self, text: &str, attrs: Attrs, shaping: Shaping) -> Self {
+        self.text = text.to_string();
+        self.attrs = AttrsOwned::new(attrs);
+        self.shaping = shaping;
+        self
+    }
+
+    /// Build the [`Buffer`], shaping the visible lines
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `metrics.line_height` is zero.
+    pub fn build(self, font_system: &mut FontSystem) -> Buffer {
+        let mut buffer = Buffer::new_empty(self.metrics);
+        // The setters do not shape anything while the buffer has no lines
+        buffer.set_writing_mode(font_system, self.writing_mode);
+        buffer.set_size(font_system, self.width, self.height);
+        buffer.set_wrap(font_system, self.wrap);
+        buffer.set_wrap_columns(font_system, self.wrap_columns);
+        buffer.set_overflow(font_system, self.overflow);
+        buffer.set_max_lines(font_system, self.max_lines);
+        buffer.set_tab_width(font_system, self.tab_width);
+        buffer.set_text_lines(&self.text, self.attrs.as_attrs(), self.shaping);
+        for line in buffer.lines.iter_mut() {
+            line.set_align(self.align);
         }
+        buffer.shape_until_scroll(font_system);
+        buffer
     }
 }
 
@@ -801,6 +2960,13 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
         self.inner.shape_until(self.font_system, lines)
     }
 
+    /// Shape and lay out lines in order until `deadline` has passed, returning true if there are
+    /// still lines left to lay out
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn shape_until_deadline(&mut self, deadline: std::time::Instant) -> bool {
+        self.inner.shape_until_deadline(self.font_system, deadline)
+    }
+
     /// Shape lines until cursor, also scrolling to include cursor in view
     pub fn shape_until_cursor(&mut self, cursor: Cursor) {
         self.inner.shape_until_cursor(self.font_system, cursor);
@@ -821,6 +2987,18 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
         self.inner.line_layout(self.font_system, line_i)
     }
 
+    /// Get the byte indices where the provided line index would wrap at `width`, using the
+    /// current [`Wrap`] mode, without changing the layout of the line
+    pub fn wrap_preview(&mut self, line_i: usize, width: f32) -> Vec<usize> {
+        self.inner.wrap_preview(self.font_system, line_i, width)
+    }
+
+    /// Measure how much of `text` fits on a single line of `max_width` at the buffer font size
+    pub fn fit_single_line(&mut self, text: &str, attrs: Attrs, max_width: f32) -> (usize, bool) {
+        self.inner
+            .fit_single_line(self.font_system, text, attrs, max_width)
+    }
+
     /// Set the current [`Metrics`]
     ///
     /// # Panics
@@ -835,16 +3013,117 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
         self.inner.set_wrap(self.font_system, wrap);
     }
 
+    /// Set the current vertical scroll location in pixels
+    pub fn set_scroll_pixels(&mut self, scroll_pixels: f32) {
+        self.inner
+            .set_scroll_pixels(self.font_system, scroll_pixels);
+    }
+
+    /// Set the number of layout lines shaped past the end of the view
+    pub fn set_overscan(&mut self, overscan: i32) {
+        self.inner.set_overscan(self.font_system, overscan);
+    }
+
+    /// Set the number of columns to wrap at, or None to wrap at the buffer width
+    pub fn set_wrap_columns(&mut self, wrap_columns: Option<usize>) {
+        self.inner.set_wrap_columns(self.font_system, wrap_columns);
+    }
+
+    /// Set what is done with text that does not fit
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.inner.set_overflow(self.font_system, overflow);
+    }
+
+    /// Set the maximum number of layout lines of each line, or None for no limit
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.inner.set_max_lines(self.font_system, max_lines);
+    }
+
+    /// Set the number of cells that East Asian Ambiguous characters occupy in the monospace grid
+    pub fn set_ambiguous_width(&mut self, ambiguous_width: u8) {
+        self.inner
+            .set_ambiguous_width(self.font_system, ambiguous_width);
+    }
+
+    /// Set a character to draw at the right edge of each visual line that is followed by a soft
+    /// wrap, or None to disable it
+    pub fn set_wrap_indicator(&mut self, wrap_indicator: Option<char>) {
+        self.inner
+            .set_wrap_indicator(self.font_system, wrap_indicator);
+    }
+
+    /// Set a character to repeat across the gap of each tab when drawing, or None to disable it
+    pub fn set_tab_leader(&mut self, tab_leader: Option<char>) {
+        self.inner.set_tab_leader(self.font_system, tab_leader);
+    }
+
+    /// Set the spacing of the baseline grid, or None to disable it
+    pub fn set_baseline_grid(&mut self, baseline_grid: Option<f32>) {
+        self.inner
+            .set_baseline_grid(self.font_system, baseline_grid);
+    }
+
+    /// Set whether bidi control characters are revealed
+    pub fn set_reveal_bidi_controls(&mut self, reveal: bool) {
+        self.inner
+            .set_reveal_bidi_controls(self.font_system, reveal);
+    }
+
+    /// Shape lines as the given runs instead of itemizing them
+    pub fn set_prepared_runs(&mut self, runs: Vec<PreparedRun>) {
+        self.inner.set_prepared_runs(self.font_system, runs);
+    }
+
+    /// Set whether combining marks without a base are shown on a dotted circle
+    pub fn set_isolated_mark_display(&mut self, display: bool) {
+        self.inner
+            .set_isolated_mark_display(self.font_system, display);
+    }
+
+    /// Set the direction lines are laid out in
+    pub fn set_writing_mode(&mut self, writing_mode: WritingMode) {
+        self.inner.set_writing_mode(self.font_system, writing_mode);
+    }
+
+    /// Set the distance between tab stops, in spaces
+    pub fn set_tab_width(&mut self, tab_width: u16) {
+        self.inner.set_tab_width(self.font_system, tab_width);
+    }
+
     /// Set the current buffer dimensions
     pub fn set_size(&mut self, width: f32, height: f32) {
         self.inner.set_size(self.font_system, width, height);
     }
 
+    /// Set text of buffer from a [`ropey::Rope`], using provided attributes for each line by
+    /// default
+    #[cfg(feature = "ropey")]
+    pub fn set_rope(&mut self, rope: &ropey::Rope, attrs: Attrs, shaping: Shaping) {
+        self.inner.set_rope(self.font_system, rope, attrs, shaping);
+    }
+
     /// Set text of buffer, using provided attributes for each line by default
     pub fn set_text(&mut self, text: &str, attrs: Attrs, shaping: Shaping) {
         self.inner.set_text(self.font_system, text, attrs, shaping);
     }
 
+    /// Set text of buffer from spans of text with their own attributes, using `default_attrs` for
+    /// each line by default
+    pub fn set_rich_text<'r, 's, I>(&mut self, spans: I, default_attrs: Attrs, shaping: Shaping)
+    where
+        I: IntoIterator<Item = (&'s str, Attrs<'r>)>,
+    {
+        self.inner
+            .set_rich_text(self.font_system, spans, default_attrs, shaping);
+    }
+
+    /// Set text of buffer from bytes that may not be valid UTF-8, using provided attributes for
+    /// each line by default
+    pub fn set_text_lossy(&mut self, bytes: &[u8], attrs: Attrs, shaping: Shaping) {
+        self.inner
+            .set_text_lossy(self.font_system, bytes, attrs, shaping);
+    }
+
     /// Draw the buffer
     #[cfg(feature = "swash")]
     pub fn draw<F>(&mut self, cache: &mut crate::SwashCache, color: Color, f: F)
@@ -853,4 +3132,146 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
     {
         self.inner.draw(self.font_system, cache, color, f);
     }
+
+    /// Draw the buffer, only emitting the parts of rectangles that are inside `clip`
+    #[cfg(feature = "swash")]
+    pub fn draw_clipped<F>(
+        &mut self,
+        cache: &mut crate::SwashCache,
+        color: Color,
+        clip: ClipRect,
+        f: F,
+    ) where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        self.inner
+            .draw_clipped(self.font_system, cache, color, clip, f);
+    }
+
+    /// Draw the buffer rotated clockwise by `angle` radians about `origin`
+    #[cfg(feature = "swash")]
+    pub fn draw_rotated<F>(
+        &mut self,
+        cache: &mut crate::SwashCache,
+        color: Color,
+        angle: f32,
+        origin: (f32, f32),
+        f: F,
+    ) where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        self.inner
+            .draw_rotated(self.font_system, cache, color, angle, origin, f);
+    }
+}
+
+/// Get the byte ranges of the non-overlapping matches of `query` in `text`, see [`Buffer::search`]
+pub(crate) fn line_matches(text: &str, query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+
+    let same =
+        |a: char, b: char| a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()));
+    let mut search_start = 0;
+    for (start, _) in text.char_indices() {
+        if start < search_start {
+            continue;
+        }
+        let mut text_chars = text[start..].char_indices();
+        let mut end_opt = Some(start);
+        for query_c in query.chars() {
+            end_opt = match text_chars.next() {
+                Some((i, c)) if same(c, query_c) => Some(start + i + c.len_utf8()),
+                _ => None,
+            };
+            if end_opt.is_none() {
+                break;
+            }
+        }
+        if let Some(end) = end_opt {
+            matches.push(start..end);
+            search_start = end;
+        }
+    }
+    matches
+}
+
+/// Get the x position of the caret for `cursor` in `run`, or None if the cursor is not in it
+pub(crate) fn cursor_x(run: &LayoutRun, cursor: &Cursor) -> Option<i32> {
+    let cursor_glyph_opt = || -> Option<(usize, f32)> {
+        if cursor.line == run.line_i {
+            if cursor.affinity.before() {
+                // Place the cursor at the end of the glyph before it, which may not be next to the
+                // glyph after it at a boundary between bidi runs
+                for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
+                    if cursor.index == glyph.end && glyph.start < glyph.end {
+                        return Some((glyph_i, glyph.w));
+                    }
+                }
+            }
+            for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
+                if cursor.index == glyph.start {
+                    return Some((glyph_i, 0.0));
+                } else if cursor.index > glyph.start && cursor.index < glyph.end {
+                    // Guess x offset based on characters
+                    let mut before = 0;
+                    let mut total = 0;
+
+                    let cluster = &run.text[glyph.start..glyph.end];
+                    for (i, _) in cluster.grapheme_indices(true) {
+                        if glyph.start + i < cursor.index {
+                            before += 1;
+                        }
+                        total += 1;
+                    }
+
+                    let offset = glyph.w * (before as f32) / (total as f32);
+                    return Some((glyph_i, offset));
+                }
+            }
+            match run.glyphs.last() {
+                Some(glyph) => {
+                    // At a soft wrap, a cursor with after affinity is at the start of the next run
+                    if cursor.index == glyph.end
+                        && (cursor.affinity.before() || glyph.end == run.text.len())
+                    {
+                        return Some((run.glyphs.len(), 0.0));
+                    }
+                }
+                None => {
+                    return Some((0, 0.0));
+                }
+            }
+        }
+        None
+    };
+
+    let (cursor_glyph, cursor_glyph_offset) = cursor_glyph_opt()?;
+    let x = match run.glyphs.get(cursor_glyph) {
+        Some(glyph) => {
+            // Start of detected glyph
+            if glyph.level.is_rtl() {
+                (glyph.x + glyph.w - cursor_glyph_offset) as i32
+            } else {
+                (glyph.x + cursor_glyph_offset) as i32
+            }
+        }
+        None => match run.glyphs.last() {
+            Some(glyph) => {
+                // End of last glyph
+                if glyph.level.is_rtl() {
+                    glyph.x as i32
+                } else {
+                    (glyph.x + glyph.w) as i32
+                }
+            }
+            None => {
+                // Start of empty line, after any prefix
+                run.prefix_w as i32
+            }
+        },
+    };
+    Some(x)
 }
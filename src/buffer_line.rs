@@ -1,34 +1,72 @@
+use alloc::collections::BTreeMap;
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{Align, AttrsList, FontSystem, LayoutLine, ShapeBuffer, ShapeLine, Shaping, Wrap};
+use crate::{
+    text_needs_complex_shaping, Align, Attrs, AttrsList, AttrsOwned, Direction, FontSystem,
+    LayoutGlyph, LayoutLine, LineEnding, Overflow, PreparedRun, ShapeBuffer, ShapeLine,
+    ShapeOptions, Shaping, Truncate, Wrap,
+};
+
+/// Scale of ruby annotation text relative to the font size of the line
+const RUBY_FONT_SCALE: f32 = 0.5;
+
+/// Text that replaces the end of truncated text
+const ELLIPSIS: &str = "\u{2026}";
+
+/// A ruby annotation (such as furigana) laid out above a range of base text
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ruby {
+    /// Byte range of the annotated base text in the line
+    pub range: Range<usize>,
+    /// Annotation text
+    pub text: String,
+    /// Annotation attributes
+    pub attrs: AttrsOwned,
+}
 
 /// A line (or paragraph) of text that is shaped and laid out
 #[derive(Debug)]
 pub struct BufferLine {
     //TODO: make this not pub(crate)
     text: String,
+    ending: LineEnding,
     attrs_list: AttrsList,
     wrap: Wrap,
     align: Option<Align>,
+    overflow: Overflow,
+    max_lines: Option<usize>,
     shape_opt: Option<ShapeLine>,
     layout_opt: Option<Vec<LayoutLine>>,
     shaping: Shaping,
+    ruby: Vec<Ruby>,
+    prefix: Option<(String, AttrsOwned)>,
+    prepared_runs: Vec<PreparedRun>,
 }
 
 impl BufferLine {
     /// Create a new line with the given text and attributes list
     /// Cached shaping and layout can be done using the [`Self::shape`] and
     /// [`Self::layout`] functions
+    ///
+    /// The line ending defaults to [`LineEnding::Lf`], see [`Self::set_ending`]
     pub fn new<T: Into<String>>(text: T, attrs_list: AttrsList, shaping: Shaping) -> Self {
         Self {
             text: text.into(),
+            ending: LineEnding::default(),
             attrs_list,
             wrap: Wrap::Word,
             align: None,
+            overflow: Overflow::Visible,
+            max_lines: None,
             shape_opt: None,
             layout_opt: None,
             shaping,
+            ruby: Vec::new(),
+            prefix: None,
+            prepared_runs: Vec::new(),
         }
     }
 
@@ -37,6 +75,16 @@ impl BufferLine {
         &self.text
     }
 
+    /// Get the byte indices of the grapheme cluster boundaries in the text, which are the valid
+    /// cursor positions, including the start and end of the line
+    pub fn grapheme_boundaries(&self) -> Vec<usize> {
+        self.text
+            .grapheme_indices(true)
+            .map(|(i, _)| i)
+            .chain(core::iter::once(self.text.len()))
+            .collect()
+    }
+
     /// Set text and attributes list
     ///
     /// Will reset shape and layout if it differs from current text and attributes list.
@@ -44,6 +92,9 @@ impl BufferLine {
     pub fn set_text<T: AsRef<str>>(&mut self, text: T, attrs_list: AttrsList) -> bool {
         let text = text.as_ref();
         if text != self.text || attrs_list != self.attrs_list {
+            if text != self.text {
+                self.prepared_runs.clear();
+            }
             self.text.clear();
             self.text.push_str(text);
             self.attrs_list = attrs_list;
@@ -54,6 +105,25 @@ impl BufferLine {
         }
     }
 
+    /// Get the line ending that terminated this line in the original text
+    pub fn ending(&self) -> LineEnding {
+        self.ending
+    }
+
+    /// Set the line ending
+    ///
+    /// This does not affect shaping or layout, it is only used to reconstruct the original text
+    pub fn set_ending(&mut self, ending: LineEnding) {
+        self.ending = ending;
+    }
+
+    /// Check if the text of this line requires bidirectional processing or complex script shaping
+    ///
+    /// See [`text_needs_complex_shaping`]
+    pub fn needs_complex_shaping(&self) -> bool {
+        text_needs_complex_shaping(&self.text)
+    }
+
     /// Consume this line, returning only its text contents as a String.
     pub fn into_text(self) -> String {
         self.text
@@ -117,12 +187,166 @@ impl BufferLine {
         }
     }
 
+    /// Get what is done with text that does not fit
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Set what is done with text that does not fit in the layout width, or that is past the
+    /// maximum number of lines, see [`Self::set_max_lines`]
+    ///
+    /// With [`Overflow::Ellipsis`], the end of each layout line that is wider than the layout width
+    /// is replaced with an ellipsis, as is the end of the last line kept by the maximum number of
+    /// lines. The ellipsis is shaped with the attributes of the text where it starts to overflow,
+    /// and its glyphs cover the indices of the text it replaces.
+    ///
+    /// Will reset layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub fn set_overflow(&mut self, overflow: Overflow) -> bool {
+        if overflow != self.overflow {
+            self.overflow = overflow;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the maximum number of layout lines
+    pub fn max_lines(&self) -> Option<usize> {
+        self.max_lines
+    }
+
+    /// Set the maximum number of layout lines, or None for no limit
+    ///
+    /// Layout lines past the maximum are dropped, and at least one is kept. With
+    /// [`Overflow::Ellipsis`], the end of the last line is replaced with an ellipsis.
+    ///
+    /// Will reset layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) -> bool {
+        if max_lines != self.max_lines {
+            self.max_lines = max_lines;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get ruby annotations
+    pub fn ruby(&self) -> &[Ruby] {
+        &self.ruby
+    }
+
+    /// Attach a ruby annotation to the base text in `base_range`
+    ///
+    /// The annotation is laid out centered above the base text at half the font size, and the
+    /// layout line containing it grows to fit. Any annotations overlapping `base_range` are
+    /// replaced. Ranges that are empty, out of bounds, or not on character boundaries are
+    /// ignored. Will reset layout if the annotation is attached.
+    /// Returns true if the line was reset
+    pub fn set_ruby<T: Into<String>>(
+        &mut self,
+        base_range: Range<usize>,
+        ruby_text: T,
+        attrs: Attrs,
+    ) -> bool {
+        if base_range.start >= base_range.end || self.text.get(base_range.clone()).is_none() {
+            log::warn!("ignoring invalid ruby range {:?}", base_range);
+            return false;
+        }
+        self.ruby.retain(|ruby| {
+            ruby.range.end <= base_range.start || ruby.range.start >= base_range.end
+        });
+        self.ruby.push(Ruby {
+            range: base_range,
+            text: ruby_text.into(),
+            attrs: AttrsOwned::new(attrs),
+        });
+        self.ruby.sort_by_key(|ruby| ruby.range.start);
+        self.reset_layout();
+        true
+    }
+
+    /// Remove all ruby annotations
+    ///
+    /// Will reset layout if there were any annotations.
+    pub fn clear_ruby(&mut self) {
+        if !self.ruby.is_empty() {
+            self.ruby.clear();
+            self.reset_layout();
+        }
+    }
+
+    /// Get the runs the line is shaped as, see [`Self::set_prepared_runs`]
+    pub fn prepared_runs(&self) -> &[PreparedRun] {
+        &self.prepared_runs
+    }
+
+    /// Shape the line as the given runs instead of itemizing it by direction, script, and font
+    ///
+    /// Each run is shaped with its font, without font fallback, and its direction and script.
+    /// The first run sets the direction of the line, and text outside of the runs is itemized as
+    /// usual. Runs that are out of bounds, not on character boundaries, or overlapping an earlier
+    /// run are ignored, and the [`PreparedRun::line_i`] is not used. The runs are cleared when the
+    /// text changes. Will reset shape and layout if the runs differ from the current runs.
+    /// Returns true if the line was reset
+    pub fn set_prepared_runs(&mut self, mut runs: Vec<PreparedRun>) -> bool {
+        runs.sort_by_key(|run| run.range.start);
+        let mut end = 0;
+        runs.retain(|run| {
+            let valid = run.range.start >= end
+                && run.range.start < run.range.end
+                && self.text.get(run.range.clone()).is_some();
+            if valid {
+                end = run.range.end;
+            } else {
+                log::warn!("ignoring invalid prepared run {:?}", run.range);
+            }
+            valid
+        });
+        if runs != self.prepared_runs {
+            self.prepared_runs = runs;
+            self.reset();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the line prefix, see [`Self::set_prefix`]
+    pub fn prefix(&self) -> Option<(&str, Attrs<'_>)> {
+        self.prefix
+            .as_ref()
+            .map(|(text, attrs)| (text.as_str(), attrs.as_attrs()))
+    }
+
+    /// Set a prefix, such as a prompt, that is laid out before the text of the line but is not
+    /// part of it
+    ///
+    /// The prefix cannot be selected, edited, or deleted, and wrapped lines are indented by its
+    /// width. Will reset layout if it differs from the current prefix.
+    /// Returns true if the line was reset
+    pub fn set_prefix(&mut self, prefix: Option<(String, Attrs)>) -> bool {
+        let prefix = prefix.map(|(text, attrs)| (text, AttrsOwned::new(attrs)));
+        if prefix != self.prefix {
+            self.prefix = prefix;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
     /// Append line at end of this line
     ///
-    /// The wrap setting of the appended line will be lost
+    /// The wrap setting and prefix of the appended line will be lost, and the line ending of the
+    /// appended line is used for the joined line
     pub fn append(&mut self, other: Self) {
         let len = self.text.len();
         self.text.push_str(other.text());
+        self.ending = other.ending;
 
         if other.attrs_list.defaults() != self.attrs_list.defaults() {
             // If default formatting does not match, make a new span for it
@@ -136,17 +360,47 @@ impl BufferLine {
             self.attrs_list.add_span(range, attrs.as_attrs());
         }
 
+        for mut ruby in other.ruby {
+            ruby.range = ruby.range.start + len..ruby.range.end + len;
+            self.ruby.push(ruby);
+        }
+
+        self.prepared_runs.clear();
         self.reset();
     }
 
     /// Split off new line at index
+    ///
+    /// The new line keeps the current line ending. This line keeps it too, unless it was
+    /// [`LineEnding::None`], in which case it becomes [`LineEnding::Lf`]. The prefix stays with
+    /// this line
     pub fn split_off(&mut self, index: usize) -> Self {
         let text = self.text.split_off(index);
         let attrs_list = self.attrs_list.split_off(index);
+        self.prepared_runs.clear();
         self.reset();
 
         let mut new = Self::new(text, attrs_list, self.shaping);
         new.wrap = self.wrap;
+        new.ending = self.ending;
+        if self.ending == LineEnding::None {
+            self.ending = LineEnding::Lf;
+        }
+
+        // Annotations crossing the split point are dropped
+        let mut i = 0;
+        while i < self.ruby.len() {
+            if self.ruby[i].range.start >= index {
+                let mut ruby = self.ruby.remove(i);
+                ruby.range = ruby.range.start - index..ruby.range.end - index;
+                new.ruby.push(ruby);
+            } else if self.ruby[i].range.end > index {
+                self.ruby.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
         new
     }
 
@@ -169,7 +423,16 @@ impl BufferLine {
 
     /// Shape line, will cache results
     pub fn shape(&mut self, font_system: &mut FontSystem) -> &ShapeLine {
-        self.shape_in_buffer(&mut ShapeBuffer::default(), font_system)
+        self.shape_with_options(font_system, ShapeOptions::default())
+    }
+
+    /// Shape line with the given [`ShapeOptions`], will cache results
+    pub fn shape_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        options: ShapeOptions,
+    ) -> &ShapeLine {
+        self.shape_in_buffer_with_options(&mut ShapeBuffer::default(), font_system, options)
     }
 
     /// Shape a line using a pre-existing shape buffer.
@@ -177,15 +440,39 @@ impl BufferLine {
         &mut self,
         scratch: &mut ShapeBuffer,
         font_system: &mut FontSystem,
+    ) -> &ShapeLine {
+        self.shape_in_buffer_with_options(scratch, font_system, ShapeOptions::default())
+    }
+
+    /// Shape a line with the given [`ShapeOptions`] using a pre-existing shape buffer.
+    pub fn shape_in_buffer_with_options(
+        &mut self,
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        options: ShapeOptions,
     ) -> &ShapeLine {
         if self.shape_opt.is_none() {
-            self.shape_opt = Some(ShapeLine::new_in_buffer(
-                scratch,
-                font_system,
-                &self.text,
-                &self.attrs_list,
-                self.shaping,
-            ));
+            let shape = if self.prepared_runs.is_empty() {
+                ShapeLine::new_in_buffer_with_options(
+                    scratch,
+                    font_system,
+                    &self.text,
+                    &self.attrs_list,
+                    self.shaping,
+                    options,
+                )
+            } else {
+                ShapeLine::new_prepared(
+                    scratch,
+                    font_system,
+                    &self.text,
+                    &self.attrs_list,
+                    &self.prepared_runs,
+                    self.shaping,
+                    options,
+                )
+            };
+            self.shape_opt = Some(shape);
             self.layout_opt = None;
         }
         self.shape_opt.as_ref().expect("shape not found")
@@ -196,6 +483,20 @@ impl BufferLine {
         &self.shape_opt
     }
 
+    /// Get the base direction of the paragraph, as resolved by the Unicode Bidirectional
+    /// Algorithm when the line was shaped, or None if it is not shaped
+    ///
+    /// Each glyph has the embedding level of its run in [`LayoutGlyph::level`].
+    pub fn direction(&self) -> Option<Direction> {
+        self.shape_opt.as_ref().map(|shape| {
+            if shape.rtl {
+                Direction::Rtl
+            } else {
+                Direction::Ltr
+            }
+        })
+    }
+
     /// Layout line, will cache results
     pub fn layout(
         &mut self,
@@ -203,12 +504,42 @@ impl BufferLine {
         font_size: f32,
         width: f32,
         wrap: Wrap,
+    ) -> &[LayoutLine] {
+        self.layout_with_options(font_system, font_size, width, wrap, ShapeOptions::default())
+    }
+
+    /// Layout line with the given [`ShapeOptions`], will cache results
+    pub fn layout_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        wrap: Wrap,
+        options: ShapeOptions,
     ) -> &[LayoutLine] {
         if self.layout_opt.is_none() {
             self.wrap = wrap;
             let align = self.align;
-            let shape = self.shape(font_system);
-            let layout = shape.layout(font_size, width, wrap, align);
+            let prefix_opt =
+                self.layout_prefix(&mut ShapeBuffer::default(), font_system, font_size, options);
+            let prefix_w = prefix_opt.as_ref().map_or(0.0, |prefix| prefix.w);
+            let shape = self.shape_with_options(font_system, options);
+            let mut layout = shape.layout(font_size, (width - prefix_w).max(0.0), wrap, align);
+            Self::place_prefix(prefix_opt, &mut layout);
+            self.layout_overflow(
+                &mut ShapeBuffer::default(),
+                font_system,
+                font_size,
+                width,
+                &mut layout,
+            );
+            self.layout_ruby(
+                &mut ShapeBuffer::default(),
+                font_system,
+                font_size,
+                options,
+                &mut layout,
+            );
             self.layout_opt = Some(layout);
         }
         self.layout_opt.as_ref().expect("layout not found")
@@ -222,13 +553,45 @@ impl BufferLine {
         font_size: f32,
         width: f32,
         wrap: Wrap,
+    ) -> &[LayoutLine] {
+        self.layout_in_buffer_with_options(
+            scratch,
+            font_system,
+            font_size,
+            width,
+            wrap,
+            ShapeOptions::default(),
+        )
+    }
+
+    /// Layout a line with the given [`ShapeOptions`] using a pre-existing shape buffer.
+    pub fn layout_in_buffer_with_options(
+        &mut self,
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        wrap: Wrap,
+        options: ShapeOptions,
     ) -> &[LayoutLine] {
         if self.layout_opt.is_none() {
             self.wrap = wrap;
             let align = self.align;
-            let shape = self.shape_in_buffer(scratch, font_system);
+            let prefix_opt = self.layout_prefix(scratch, font_system, font_size, options);
+            let prefix_w = prefix_opt.as_ref().map_or(0.0, |prefix| prefix.w);
+            let shape = self.shape_in_buffer_with_options(scratch, font_system, options);
             let mut layout = Vec::with_capacity(1);
-            shape.layout_to_buffer(scratch, font_size, width, wrap, align, &mut layout);
+            shape.layout_to_buffer(
+                scratch,
+                font_size,
+                (width - prefix_w).max(0.0),
+                wrap,
+                align,
+                &mut layout,
+            );
+            Self::place_prefix(prefix_opt, &mut layout);
+            self.layout_overflow(scratch, font_system, font_size, width, &mut layout);
+            self.layout_ruby(scratch, font_system, font_size, options, &mut layout);
             self.layout_opt = Some(layout);
         }
         self.layout_opt.as_ref().expect("layout not found")
@@ -238,4 +601,427 @@ impl BufferLine {
     pub fn layout_opt(&self) -> &Option<Vec<LayoutLine>> {
         &self.layout_opt
     }
+
+    /// Get the text shortened with an ellipsis (`…`) so that it fits on one line of `width` at
+    /// `font_size`, or the whole text if it already fits
+    ///
+    /// The [`Truncate`] mode chooses which part of the text is replaced. The ellipsis uses the
+    /// default attributes of the line. If not even the ellipsis fits, this returns an empty
+    /// string. The line is shaped separately, without changing its shaping cache.
+    pub fn truncated_text(
+        &self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        truncate: Truncate,
+    ) -> String {
+        let mut scratch = ShapeBuffer::default();
+        let shape = if self.prepared_runs.is_empty() {
+            ShapeLine::new_in_buffer(
+                &mut scratch,
+                font_system,
+                &self.text,
+                &self.attrs_list,
+                self.shaping,
+            )
+        } else {
+            ShapeLine::new_prepared(
+                &mut scratch,
+                font_system,
+                &self.text,
+                &self.attrs_list,
+                &self.prepared_runs,
+                self.shaping,
+                ShapeOptions::default(),
+            )
+        };
+
+        // Start index of each cluster, with its end index and width
+        let mut clusters = BTreeMap::new();
+        for glyph in shape
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+        {
+            clusters.entry(glyph.start).or_insert((glyph.end, 0.0)).1 +=
+                font_size * glyph.x_advance;
+        }
+
+        let total: f32 = clusters.values().map(|(_, w)| w).sum();
+        if total <= width {
+            return self.text.clone();
+        }
+
+        let ellipsis_shape = ShapeLine::new_in_buffer(
+            &mut scratch,
+            font_system,
+            ELLIPSIS,
+            &AttrsList::new(self.attrs_list.defaults()),
+            self.shaping,
+        );
+        let ellipsis_w: f32 = ellipsis_shape
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .map(|glyph| font_size * glyph.x_advance)
+            .sum();
+        let available = width - ellipsis_w;
+        if available < 0.0 {
+            return String::new();
+        }
+
+        // Get the end index and width of the longest start of the text that fits in `limit`
+        let head = |limit: f32| {
+            let mut end = 0;
+            let mut w = 0.0;
+            for (_, (cluster_end, cluster_w)) in clusters.iter() {
+                if w + cluster_w > limit {
+                    break;
+                }
+                end = *cluster_end;
+                w += cluster_w;
+            }
+            (end, w)
+        };
+
+        // Get the start index of the longest end of the text that fits in `limit`
+        let tail = |limit: f32| {
+            let mut start = self.text.len();
+            let mut w = 0.0;
+            for (cluster_start, (_, cluster_w)) in clusters.iter().rev() {
+                if w + cluster_w > limit {
+                    break;
+                }
+                start = *cluster_start;
+                w += cluster_w;
+            }
+            start
+        };
+
+        let (head_end, tail_start) = match truncate {
+            Truncate::End => (head(available).0, self.text.len()),
+            Truncate::Start => (0, tail(available)),
+            Truncate::Middle => {
+                let (head_end, head_w) = head(available / 2.0);
+                (head_end, tail(available - head_w))
+            }
+            Truncate::Path => {
+                let segment_opt = self.text.rfind('/').and_then(|segment_start| {
+                    let segment_w: f32 = clusters.range(segment_start..).map(|(_, (_, w))| w).sum();
+                    if segment_w <= available {
+                        Some((segment_start, segment_w))
+                    } else {
+                        None
+                    }
+                });
+                match segment_opt {
+                    Some((segment_start, segment_w)) => {
+                        (head(available - segment_w).0, segment_start)
+                    }
+                    None => (0, tail(available)),
+                }
+            }
+        };
+
+        let mut text = String::with_capacity(self.text.len() + ELLIPSIS.len());
+        text.push_str(&self.text[..head_end.min(tail_start)]);
+        text.push_str(ELLIPSIS);
+        text.push_str(&self.text[tail_start..]);
+        text
+    }
+
+    /// Shape and lay out the prefix on a single line
+    fn layout_prefix(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        options: ShapeOptions,
+    ) -> Option<LayoutLine> {
+        let (text, attrs) = self.prefix.as_ref()?;
+        let shape = ShapeLine::new_in_buffer_with_options(
+            scratch,
+            font_system,
+            text,
+            &AttrsList::new(attrs.as_attrs()),
+            self.shaping,
+            options,
+        );
+        let mut prefix_layout = Vec::with_capacity(1);
+        shape.layout_to_buffer(
+            scratch,
+            font_size,
+            f32::MAX,
+            Wrap::None,
+            Some(Align::Left),
+            &mut prefix_layout,
+        );
+        prefix_layout.into_iter().next()
+    }
+
+    /// Offset every line in `layout` by the width of the prefix, and place the prefix glyphs on the
+    /// first line
+    fn place_prefix(prefix_opt: Option<LayoutLine>, layout: &mut [LayoutLine]) {
+        let prefix = match prefix_opt {
+            Some(some) => some,
+            None => return,
+        };
+
+        for layout_line in layout.iter_mut() {
+            for glyph in layout_line.glyphs.iter_mut() {
+                glyph.x += prefix.w;
+            }
+            layout_line.w += prefix.w;
+            layout_line.prefix_w = prefix.w;
+        }
+
+        if let Some(layout_line) = layout.first_mut() {
+            layout_line.max_ascent = layout_line.max_ascent.max(prefix.max_ascent);
+            layout_line.max_descent = layout_line.max_descent.max(prefix.max_descent);
+            layout_line.max_line_gap = layout_line.max_line_gap.max(prefix.max_line_gap);
+            layout_line.prefix_glyphs = prefix.glyphs;
+        }
+    }
+
+    /// Drop the layout lines past the maximum number of lines, and replace the text that does not
+    /// fit in `width` with an ellipsis if enabled
+    fn layout_overflow(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        layout: &mut Vec<LayoutLine>,
+    ) {
+        let mut truncated = false;
+        if let Some(max_lines) = self.max_lines {
+            let max_lines = max_lines.max(1);
+            if layout.len() > max_lines {
+                layout.truncate(max_lines);
+                truncated = true;
+            }
+        }
+        if self.overflow != Overflow::Ellipsis {
+            return;
+        }
+
+        let last = layout.len().saturating_sub(1);
+        for (layout_i, layout_line) in layout.iter_mut().enumerate() {
+            // The last line replaces the rest of the text after it
+            let hidden_end_opt = if truncated && layout_i == last {
+                Some(self.text.len())
+            } else {
+                None
+            };
+            self.ellipsize(
+                scratch,
+                font_system,
+                font_size,
+                width,
+                layout_line,
+                hidden_end_opt,
+            );
+        }
+    }
+
+    /// Replace the end of a layout line with an ellipsis if it is wider than `width`, or always if
+    /// `hidden_end_opt` is the end of text after the line that it replaces
+    fn ellipsize(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        layout_line: &mut LayoutLine,
+        hidden_end_opt: Option<usize>,
+    ) {
+        let rtl = self.shape_opt.as_ref().is_some_and(|shape| shape.rtl);
+        let glyphs = &layout_line.glyphs;
+        // The text starts after the prefix on the left, or ends at the right edge when RTL
+        let (left, right) = if rtl {
+            let right = glyphs
+                .iter()
+                .map(|glyph| glyph.x + glyph.w)
+                .fold(0.0, f32::max)
+                .min(width);
+            (0.0, right)
+        } else {
+            let left = glyphs
+                .iter()
+                .map(|glyph| glyph.x)
+                .fold(f32::MAX, f32::min)
+                .max(layout_line.prefix_w)
+                .min(width);
+            (left, width)
+        };
+        let available = right - left;
+
+        // Glyph indices in logical order
+        let mut order: Vec<usize> = (0..glyphs.len()).collect();
+        order.sort_by_key(|&i| glyphs[i].start);
+
+        // Whitespace at the end of a wrapped line may hang past the width
+        let glyphs_w: f32 = order
+            .iter()
+            .rev()
+            .skip_while(|&&i| {
+                self.text
+                    .get(glyphs[i].start..glyphs[i].end)
+                    .is_some_and(|text| text.chars().all(char::is_whitespace))
+            })
+            .map(|&i| glyphs[i].w)
+            .sum();
+        if hidden_end_opt.is_none() && glyphs_w <= available {
+            return;
+        }
+        // Get the position in `order` of the first glyph that does not fit in `limit`
+        let overflow_at = |limit: f32| {
+            let mut w = 0.0;
+            for (n, &i) in order.iter().enumerate() {
+                w += glyphs[i].w;
+                if w > limit {
+                    return n;
+                }
+            }
+            order.len()
+        };
+        let line_end = glyphs.iter().map(|glyph| glyph.end).max().unwrap_or(0);
+        let hidden_end = hidden_end_opt.unwrap_or(line_end);
+
+        // The ellipsis takes the attributes of the text where the line overflows
+        let attrs_index = order
+            .get(overflow_at(available))
+            .or(order.last())
+            .map_or(0, |&i| glyphs[i].start);
+        let shape = ShapeLine::new_in_buffer(
+            scratch,
+            font_system,
+            ELLIPSIS,
+            &AttrsList::new(self.attrs_list.get_span(attrs_index)),
+            self.shaping,
+        );
+        let mut ellipsis_layout = Vec::with_capacity(1);
+        shape.layout_to_buffer(
+            scratch,
+            font_size,
+            f32::MAX,
+            Wrap::None,
+            Some(Align::Left),
+            &mut ellipsis_layout,
+        );
+        let mut ellipsis = ellipsis_layout
+            .into_iter()
+            .next()
+            .map_or(Vec::new(), |line| line.glyphs);
+        let ellipsis_w: f32 = ellipsis.iter().map(|glyph| glyph.w).sum();
+
+        let (cut, mut kept) = if ellipsis_w > available {
+            // Not even the ellipsis fits
+            ellipsis.clear();
+            (0, Vec::new())
+        } else {
+            let cut = order
+                .get(overflow_at(available - ellipsis_w))
+                .map_or(line_end, |&i| glyphs[i].start);
+            let kept: Vec<LayoutGlyph> = glyphs
+                .iter()
+                .filter(|glyph| glyph.start < cut)
+                .cloned()
+                .collect();
+            (cut, kept)
+        };
+        for glyph in ellipsis.iter_mut() {
+            glyph.start = cut;
+            glyph.end = hidden_end;
+        }
+
+        // Place the ellipsis after the kept glyphs in the direction of the text
+        let mut glyphs = if rtl {
+            ellipsis.append(&mut kept);
+            ellipsis
+        } else {
+            kept.append(&mut ellipsis);
+            kept
+        };
+        let w: f32 = glyphs.iter().map(|glyph| glyph.w).sum();
+        let mut x = if rtl { right - w } else { left };
+        for glyph in glyphs.iter_mut() {
+            glyph.x = x;
+            x += glyph.w;
+        }
+        layout_line.glyphs = glyphs;
+        layout_line.w = layout_line.prefix_w + w;
+    }
+
+    /// Shape ruby annotations and place them above their base text in `layout`
+    fn layout_ruby(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        options: ShapeOptions,
+        layout: &mut [LayoutLine],
+    ) {
+        if self.ruby.is_empty() {
+            return;
+        }
+
+        let ruby_font_size = font_size * RUBY_FONT_SCALE;
+        let mut ruby_lines = Vec::with_capacity(self.ruby.len());
+        for ruby in self.ruby.iter() {
+            let shape = ShapeLine::new_in_buffer_with_options(
+                scratch,
+                font_system,
+                &ruby.text,
+                &AttrsList::new(ruby.attrs.as_attrs()),
+                self.shaping,
+                options,
+            );
+            let mut ruby_layout = Vec::with_capacity(1);
+            shape.layout_to_buffer(
+                scratch,
+                ruby_font_size,
+                f32::MAX,
+                Wrap::None,
+                Some(Align::Left),
+                &mut ruby_layout,
+            );
+            ruby_lines.push(ruby_layout.into_iter().next());
+        }
+
+        for layout_line in layout.iter_mut() {
+            for (ruby, ruby_line_opt) in self.ruby.iter().zip(ruby_lines.iter_mut()) {
+                // Find the extent of the base glyphs in this layout line
+                let mut base_opt: Option<(f32, f32)> = None;
+                for glyph in layout_line.glyphs.iter() {
+                    if glyph.start >= ruby.range.start && glyph.end <= ruby.range.end {
+                        let (left, right) = base_opt.unwrap_or((glyph.x, glyph.x + glyph.w));
+                        base_opt = Some((left.min(glyph.x), right.max(glyph.x + glyph.w)));
+                    }
+                }
+                let (left, right) = match base_opt {
+                    Some(some) => some,
+                    None => continue,
+                };
+                // Each annotation is placed once, above the first layout line of its base
+                let ruby_line = match ruby_line_opt.take() {
+                    Some(some) => some,
+                    None => continue,
+                };
+
+                let x = (left + right - ruby_line.w) / 2.0;
+                let y = -(layout_line.max_ascent + ruby_line.max_descent);
+                layout_line.ruby_height = layout_line
+                    .ruby_height
+                    .max(ruby_line.max_ascent + ruby_line.max_descent);
+                for mut glyph in ruby_line.glyphs {
+                    glyph.x += x;
+                    glyph.y += y;
+                    layout_line.ruby_glyphs.push(glyph);
+                }
+            }
+        }
+    }
 }
@@ -7,6 +7,8 @@ pub struct CacheKey {
     pub font_id: fontdb::ID,
     /// Glyph ID
     pub glyph_id: u16,
+    /// ID of the variation coordinates, see [`crate::FontSystem::variations`]
+    pub variations_id: u32,
     /// `f32` bits of font size
     pub font_size_bits: u32,
     /// Binning of fractional X offset
@@ -28,6 +30,7 @@ impl CacheKey {
             Self {
                 font_id,
                 glyph_id,
+                variations_id: 0,
                 font_size_bits: font_size.to_bits(),
                 x_bin,
                 y_bin,
@@ -1,20 +1,157 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
 #[cfg(not(feature = "std"))]
-use alloc::string::String;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{
     cmp::{self, Ordering},
     iter::once,
+    ops::Range,
 };
 use unicode_segmentation::UnicodeSegmentation;
 
 #[cfg(feature = "swash")]
-use crate::Color;
+use crate::buffer::{cursor_x, line_matches};
 use crate::{
-    Action, Affinity, AttrsList, Buffer, BufferLine, Cursor, Edit, FontSystem, LayoutCursor,
-    Shaping,
+    Action, Affinity, Attrs, AttrsList, Buffer, BufferLine, Color, Cursor, Edit, FontSystem,
+    LayoutCursor, LayoutGlyph, Rectangle, SearchOptions, SelectionStyle, Shaping, UnderlineStyle,
 };
 
+/// A change to the text of an [`Editor`], recorded so that it can be undone
+#[derive(Clone, Debug)]
+struct ChangeItem {
+    /// Start of the changed text
+    start: Cursor,
+    /// End of the changed text
+    end: Cursor,
+    /// The inserted or deleted text
+    text: String,
+    /// True if the text was inserted, false if it was deleted
+    insert: bool,
+}
+
+/// How a change was made, consecutive changes of the same kind can be merged
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ChangeKind {
+    Other,
+    /// Typing a character
+    Typing,
+    /// Deleting a character before the cursor
+    Backspace,
+    /// Deleting a character after the cursor
+    Delete,
+}
+
+/// A group of changes that are undone and redone together
+#[derive(Clone, Debug)]
+struct Change {
+    items: Vec<ChangeItem>,
+    /// Cursor before the change
+    cursor_before: Cursor,
+    /// Cursor after the change
+    cursor_after: Cursor,
+    kind: ChangeKind,
+}
+
+impl Change {
+    /// Merge a change of the same kind into this one, if it continues at the position this change
+    /// ended, returning true if it was merged
+    ///
+    /// Typing is merged up to the start of the next word, and deleting is merged until the
+    /// cursor is moved.
+    fn merge(&mut self, other: &Change) -> bool {
+        if self.kind != other.kind || self.kind == ChangeKind::Other {
+            return false;
+        }
+        let item = match other.items.as_slice() {
+            [item] => item,
+            _ => return false,
+        };
+        let last = match self.items.last_mut() {
+            Some(some) if some.insert == item.insert => some,
+            _ => return false,
+        };
+        match self.kind {
+            ChangeKind::Typing => {
+                if (last.end.line, last.end.index) != (item.start.line, item.start.index) {
+                    return false;
+                }
+                // Typing after whitespace starts a new word, and a new change
+                if last.text.ends_with(char::is_whitespace)
+                    && !item.text.starts_with(char::is_whitespace)
+                {
+                    return false;
+                }
+                last.text.push_str(&item.text);
+                last.end = item.end;
+            }
+            ChangeKind::Backspace => {
+                if (last.start.line, last.start.index) != (item.end.line, item.end.index) {
+                    return false;
+                }
+                last.text.insert_str(0, &item.text);
+                last.start = item.start;
+            }
+            ChangeKind::Delete => {
+                if (last.start.line, last.start.index) != (item.start.line, item.start.index) {
+                    return false;
+                }
+                last.text.push_str(&item.text);
+                last.end = text_end(last.start, &last.text);
+            }
+            ChangeKind::Other => return false,
+        }
+        self.cursor_after = other.cursor_after;
+        true
+    }
+}
+
+/// Remove the `\n` or `\r\n` from the end of a line of inserted text
+fn strip_newline(line: &str) -> &str {
+    match line.strip_suffix('\n') {
+        Some(some) => some.strip_suffix('\r').unwrap_or(some),
+        None => line,
+    }
+}
+
+/// Get the cursor at the end of `text` inserted at `start`
+fn text_end(start: Cursor, text: &str) -> Cursor {
+    match text.rfind('\n') {
+        Some(i) => Cursor::new(start.line + text.matches('\n').count(), text.len() - i - 1),
+        None => Cursor::new(start.line, start.index + text.len()),
+    }
+}
+
+/// Text being composed with an input method, shown in the buffer at the cursor
+#[derive(Clone, Debug)]
+struct Preedit {
+    /// Where the text was inserted, and where the cursor returns to when it is removed
+    start: Cursor,
+    text: String,
+    /// Byte range of the cursor in the text, or None if the cursor is hidden
+    cursor_opt: Option<Range<usize>>,
+}
+
+/// The amount that a drag extends the selection by, set by the click that started it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DragUnit {
+    Character,
+    /// Whole words, keeping the word selected by a double click
+    Word {
+        line: usize,
+        start: usize,
+        end: usize,
+    },
+    /// Whole lines, keeping the line selected by a triple click
+    Line {
+        line: usize,
+    },
+}
+
 /// A wrapper of [`Buffer`] for easy editing
 #[derive(Debug)]
 pub struct Editor {
@@ -23,6 +160,26 @@ pub struct Editor {
     cursor_x_opt: Option<i32>,
     select_opt: Option<Cursor>,
     cursor_moved: bool,
+    focused: bool,
+    selection_style: SelectionStyle,
+    select_lines_anchor: usize,
+    drag_unit: DragUnit,
+    selection_includes_newline: bool,
+    caret_opt: Option<(i32, i32, u32, u32)>,
+    prev_caret_opt: Option<(i32, i32, u32, u32)>,
+    undo_stack: Vec<Change>,
+    redo_stack: Vec<Change>,
+    history_limit: usize,
+    /// Content version of the buffer when the history was last updated
+    history_version: u64,
+    /// The change being recorded, and how many nested calls are recording it
+    change_opt: Option<Change>,
+    change_depth: usize,
+    /// True if typing or deleting can be merged into the last change
+    merging: bool,
+    preedit_opt: Option<Preedit>,
+    search_opt: Option<(String, SearchOptions)>,
+    search_color: Option<Color>,
 }
 
 impl Editor {
@@ -34,146 +191,804 @@ impl Editor {
             cursor_x_opt: None,
             select_opt: None,
             cursor_moved: false,
+            focused: true,
+            selection_style: SelectionStyle::default(),
+            select_lines_anchor: 0,
+            drag_unit: DragUnit::Character,
+            selection_includes_newline: false,
+            caret_opt: None,
+            prev_caret_opt: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            history_limit: usize::MAX,
+            history_version: 0,
+            change_opt: None,
+            change_depth: 0,
+            merging: false,
+            preedit_opt: None,
+            search_opt: None,
+            search_color: None,
         }
     }
 
-    fn set_layout_cursor(&mut self, font_system: &mut FontSystem, cursor: LayoutCursor) {
-        let layout = self
-            .buffer
-            .line_layout(font_system, cursor.line)
-            .expect("layout not found");
+    /// Get the maximum number of changes kept for [`Self::undo`]
+    pub fn history_limit(&self) -> usize {
+        self.history_limit
+    }
 
-        let layout_line = match layout.get(cursor.layout) {
+    /// Set the maximum number of changes kept for [`Self::undo`], dropping the oldest changes
+    /// beyond it
+    ///
+    /// The history is unlimited by default.
+    pub fn set_history_limit(&mut self, history_limit: usize) {
+        self.history_limit = history_limit;
+        self.limit_history();
+    }
+
+    /// Undo the last change to the text, returning false if there is nothing to undo
+    ///
+    /// Changes made with [`Edit::action`], [`Edit::insert_string`], and
+    /// [`Edit::delete_selection`] are recorded. Consecutive typed characters are undone together
+    /// up to the start of a word, and consecutive deleted characters are undone together until
+    /// the cursor is moved. Text that is inserted again takes the attributes of
+    /// the text before it. If the text of the buffer is changed directly, the history is cleared.
+    pub fn undo(&mut self) -> bool {
+        self.clear_preedit();
+        self.check_history();
+        let change = match self.undo_stack.pop() {
             Some(some) => some,
-            None => match layout.last() {
-                Some(some) => some,
-                None => todo!("layout cursor in line with no layouts"),
-            },
+            None => return false,
         };
 
-        let (new_index, new_affinity) = match layout_line.glyphs.get(cursor.glyph) {
-            Some(glyph) => (glyph.start, Affinity::After),
-            None => match layout_line.glyphs.last() {
-                Some(glyph) => (glyph.end, Affinity::Before),
-                //TODO: is this correct?
-                None => (0, Affinity::After),
-            },
+        // Applying the history is not recorded
+        let change_opt = self.change_opt.take();
+        for item in change.items.iter().rev() {
+            if item.insert {
+                self.delete_range(item.start, item.end);
+            } else {
+                self.cursor = item.start;
+                self.insert_raw(&item.text, None);
+            }
+        }
+        self.change_opt = change_opt;
+
+        self.finish_history(change.cursor_before);
+        self.redo_stack.push(change);
+        true
+    }
+
+    /// Redo the last change undone with [`Self::undo`], returning false if there is nothing to
+    /// redo
+    ///
+    /// Making a new change clears the changes that can be redone.
+    pub fn redo(&mut self) -> bool {
+        self.clear_preedit();
+        self.check_history();
+        let change = match self.redo_stack.pop() {
+            Some(some) => some,
+            None => return false,
         };
 
-        if self.cursor.line != cursor.line
-            || self.cursor.index != new_index
-            || self.cursor.affinity != new_affinity
-        {
-            self.cursor.line = cursor.line;
-            self.cursor.index = new_index;
-            self.cursor.affinity = new_affinity;
-            self.buffer.set_redraw(true);
+        // Applying the history is not recorded
+        let change_opt = self.change_opt.take();
+        for item in change.items.iter() {
+            if item.insert {
+                self.cursor = item.start;
+                self.insert_raw(&item.text, None);
+            } else {
+                self.delete_range(item.start, item.end);
+            }
         }
-    }
-}
+        self.change_opt = change_opt;
 
-impl Edit for Editor {
-    fn buffer(&self) -> &Buffer {
-        &self.buffer
+        self.finish_history(change.cursor_after);
+        self.undo_stack.push(change);
+        true
     }
 
-    fn buffer_mut(&mut self) -> &mut Buffer {
-        &mut self.buffer
+    /// Clear the history if the text of the buffer was changed without recording it
+    fn check_history(&mut self) {
+        if self.buffer.content_version() != self.history_version {
+            self.undo_stack.clear();
+            self.redo_stack.clear();
+            self.merging = false;
+            self.history_version = self.buffer.content_version();
+        }
     }
 
-    fn cursor(&self) -> Cursor {
-        self.cursor
+    /// Place the cursor after applying the history
+    fn finish_history(&mut self, cursor: Cursor) {
+        self.cursor = cursor;
+        self.cursor_moved = true;
+        self.select_opt = None;
+        self.cursor_x_opt = None;
+        self.merging = false;
+        self.history_version = self.buffer.content_version();
+        self.buffer.set_redraw(true);
     }
 
-    fn set_cursor(&mut self, cursor: Cursor) {
-        self.cursor = cursor;
+    /// Drop the oldest changes beyond the history limit
+    fn limit_history(&mut self) {
+        if self.undo_stack.len() > self.history_limit {
+            let excess = self.undo_stack.len() - self.history_limit;
+            self.undo_stack.drain(..excess);
+        }
     }
 
-    fn select_opt(&self) -> Option<Cursor> {
-        self.select_opt
+    /// Start recording a group of changes, nested calls are recorded in the same group
+    fn begin_change(&mut self) {
+        if self.change_depth == 0 {
+            self.check_history();
+            self.change_opt = Some(Change {
+                items: Vec::new(),
+                cursor_before: self.cursor,
+                cursor_after: self.cursor,
+                kind: ChangeKind::Other,
+            });
+        }
+        self.change_depth += 1;
     }
 
-    fn set_select_opt(&mut self, select_opt: Option<Cursor>) {
-        if self.select_opt != select_opt {
-            self.select_opt = select_opt;
-            self.buffer.set_redraw(true);
+    /// Record a change in the current group
+    fn record(&mut self, item: ChangeItem) {
+        if let Some(change) = self.change_opt.as_mut() {
+            change.items.push(item);
         }
     }
 
-    fn shape_as_needed(&mut self, font_system: &mut FontSystem) {
-        if self.cursor_moved {
-            self.buffer.shape_until_cursor(font_system, self.cursor);
-            self.cursor_moved = false;
-        } else {
-            self.buffer.shape_until_scroll(font_system);
+    /// Set how the change being recorded was made
+    fn set_change_kind(&mut self, kind: ChangeKind) {
+        if let Some(change) = self.change_opt.as_mut() {
+            change.kind = kind;
         }
     }
 
-    fn copy_selection(&mut self) -> Option<String> {
-        let select = self.select_opt?;
+    /// Finish recording a group of changes, adding it to the history if it is the outermost call
+    /// and the text changed
+    fn end_change(&mut self) {
+        self.change_depth -= 1;
+        if self.change_depth > 0 {
+            return;
+        }
+        self.history_version = self.buffer.content_version();
 
-        let (start, end) = match select.line.cmp(&self.cursor.line) {
-            cmp::Ordering::Greater => (self.cursor, select),
-            cmp::Ordering::Less => (select, self.cursor),
-            cmp::Ordering::Equal => {
-                /* select.line == self.cursor.line */
-                if select.index < self.cursor.index {
-                    (select, self.cursor)
-                } else {
-                    /* select.index >= self.cursor.index */
-                    (self.cursor, select)
+        let mut change = match self.change_opt.take() {
+            Some(some) if !some.items.is_empty() => some,
+            _ => return,
+        };
+        change.cursor_after = self.cursor;
+        self.redo_stack.clear();
+
+        if self.merging {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if last.merge(&change) {
+                    return;
                 }
             }
-        };
+        }
+        self.merging = change.kind != ChangeKind::Other;
+        self.undo_stack.push(change);
+        self.limit_history();
+    }
 
-        let mut selection = String::new();
-        // Take the selection from the first line
+    /// Get the text between two cursors, with lines joined by `\n`
+    fn text_range(&self, start: Cursor, end: Cursor) -> String {
+        let mut text = String::new();
+        // Take the text from the first line
         {
-            // Add selected part of line to string
             if start.line == end.line {
-                selection.push_str(&self.buffer.lines[start.line].text()[start.index..end.index]);
+                text.push_str(&self.buffer.lines[start.line].text()[start.index..end.index]);
             } else {
-                selection.push_str(&self.buffer.lines[start.line].text()[start.index..]);
-                selection.push('\n');
+                text.push_str(&self.buffer.lines[start.line].text()[start.index..]);
+                text.push('\n');
             }
         }
 
-        // Take the selection from all interior lines (if they exist)
+        // Take the text from all interior lines (if they exist)
         for line_i in start.line + 1..end.line {
-            selection.push_str(self.buffer.lines[line_i].text());
-            selection.push('\n');
+            text.push_str(self.buffer.lines[line_i].text());
+            text.push('\n');
         }
 
-        // Take the selection from the last line
+        // Take the text from the last line
         if end.line > start.line {
-            // Add selected part of line to string
-            selection.push_str(&self.buffer.lines[end.line].text()[..end.index]);
+            text.push_str(&self.buffer.lines[end.line].text()[..end.index]);
         }
 
-        Some(selection)
+        text
     }
 
-    fn delete_selection(&mut self) -> bool {
-        let select = match self.select_opt.take() {
+    /// Get whether the editor has focus
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Set whether the editor has focus
+    ///
+    /// When unfocused, [`Edit::draw`] draws the selection in a muted gray and does not draw the
+    /// cursor. Editors start focused.
+    pub fn set_focused(&mut self, focused: bool) {
+        if focused != self.focused {
+            self.focused = focused;
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    /// Get whether a selection ending at the end of a line includes the line terminator
+    pub fn selection_includes_newline(&self) -> bool {
+        self.selection_includes_newline
+    }
+
+    /// Set whether a selection ending at the end of a line includes the line terminator
+    ///
+    /// When enabled, selecting to the end of a line, for example with [`Action::End`], copies
+    /// and deletes the newline after it, as if the selection ended at the start of the next line.
+    /// This has no effect on the last line.
+    pub fn set_selection_includes_newline(&mut self, selection_includes_newline: bool) {
+        self.selection_includes_newline = selection_includes_newline;
+    }
+
+    /// Get the byte range of the word under a cursor from [`Buffer::hit`], see [`word_range`]
+    fn word_at(&self, cursor: Cursor) -> Range<usize> {
+        let text = self.buffer.lines[cursor.line].text();
+        // A hit on the right half of a character is after it
+        let index = if cursor.affinity.before() {
+            cursor.index.saturating_sub(1)
+        } else {
+            cursor.index
+        };
+        word_range(text, index)
+    }
+
+    /// Select from `select` to `cursor`, keeping the cursor color, or clear the selection if
+    /// they are the same
+    fn select_range(&mut self, select: Cursor, mut cursor: Cursor) {
+        cursor.color = self.cursor.color;
+        let select_opt = if (select.line, select.index) == (cursor.line, cursor.index) {
+            None
+        } else {
+            Some(select)
+        };
+        if self.select_opt != select_opt || self.cursor != cursor {
+            self.select_opt = select_opt;
+            self.cursor = cursor;
+            self.buffer.set_redraw(true);
+        }
+        self.cursor_x_opt = None;
+    }
+
+    /// Move the end of a non-empty selection past the newline, if enabled and it ends at the end
+    /// of a line
+    fn selection_end(&self, start: Cursor, end: Cursor) -> Cursor {
+        if self.selection_includes_newline
+            && (start.line, start.index) != (end.line, end.index)
+            && end.line + 1 < self.buffer.lines.len()
+            && end.index == self.buffer.lines[end.line].text().len()
+        {
+            Cursor::new(end.line + 1, 0)
+        } else {
+            end
+        }
+    }
+
+    /// Clear the selection if it is empty, so deleting removes text next to the cursor instead
+    fn clear_empty_selection(&mut self) {
+        if let Some(select) = self.select_opt {
+            if (select.line, select.index) == (self.cursor.line, self.cursor.index) {
+                self.select_opt = None;
+            }
+        }
+    }
+
+    /// Get whether the caret should be drawn, which it is when focused unless it is hidden by the
+    /// preedit text, see [`Self::set_preedit`]
+    pub fn caret_visible(&self) -> bool {
+        self.focused
+            && self
+                .preedit_opt
+                .as_ref()
+                .is_none_or(|preedit| preedit.cursor_opt.is_some())
+    }
+
+    /// Get the position and size of the caret in the visible layout runs, or None if the cursor
+    /// is not visible
+    pub fn caret_rect(&self) -> Option<(i32, i32, u32, u32)> {
+        self.buffer.caret_rect(&self.cursor)
+    }
+
+    /// Get the caret rectangle interpolated between its previous and current positions, where
+    /// `t` is the progress of the animation from 0.0 to 1.0
+    ///
+    /// The positions are updated by [`Edit::shape_as_needed`] when the caret moves. This returns
+    /// None if the caret is not visible.
+    pub fn caret_animation(&self, t: f32) -> Option<(i32, i32, u32, u32)> {
+        let (x, y, w, h) = self.caret_opt?;
+        let (prev_x, prev_y, _, _) = self.prev_caret_opt.unwrap_or((x, y, w, h));
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |from: i32, to: i32| libm::roundf(from as f32 + (to - from) as f32 * t) as i32;
+        Some((lerp(prev_x, x), lerp(prev_y, y), w, h))
+    }
+
+    /// Get the text being composed with an input method, see [`Self::set_preedit`]
+    pub fn preedit(&self) -> Option<&str> {
+        self.preedit_opt
+            .as_ref()
+            .map(|preedit| preedit.text.as_str())
+    }
+
+    /// Set the text being composed with an input method, such as from the `Ime::Preedit` event of
+    /// `winit`, replacing the previous preedit text
+    ///
+    /// The text is shown underlined at the cursor, but it is not part of the edited text: it is
+    /// not recorded for [`Self::undo`], and it is removed before any [`Action`], edit, or cursor
+    /// change.
+    /// `cursor_opt` is the byte range of the cursor in `text`, and the caret is placed at its end,
+    /// so after [`Edit::shape_as_needed`], [`Self::caret_rect`] can be used to place the candidate
+    /// window of the input method. If it is None, the caret is not drawn. An empty `text` cancels the composition, and
+    /// [`Action::Commit`] replaces it with the committed text.
+    ///
+    /// Starting a composition deletes the selection. Line breaks in `text` are shown as spaces.
+    pub fn set_preedit(&mut self, text: &str, cursor_opt: Option<Range<usize>>) {
+        self.clear_preedit();
+        if text.is_empty() {
+            return;
+        }
+        self.clear_empty_selection();
+        self.delete_selection();
+
+        let text = text.replace(['\r', '\n'], " ");
+        let start = self.cursor;
+        let line = &mut self.buffer.lines[start.line];
+        let after = line.split_off(start.index);
+        let attrs_list = AttrsList::new(
+            line.attrs_list()
+                .get_span(start.index)
+                .underline(UnderlineStyle::Solid),
+        );
+        line.append(BufferLine::new(&text, attrs_list, Shaping::Advanced));
+        line.append(after);
+
+        let caret = cursor_opt
+            .as_ref()
+            .map(|cursor| cursor.end)
+            .filter(|end| text.is_char_boundary(*end))
+            .unwrap_or(text.len());
+        self.cursor.index = start.index + caret;
+        self.cursor_moved = true;
+        self.preedit_opt = Some(Preedit {
+            start,
+            text,
+            cursor_opt,
+        });
+        self.preedit_changed();
+    }
+
+    /// Remove the preedit text from the buffer, returning the cursor to where it was inserted
+    fn clear_preedit(&mut self) -> bool {
+        let preedit = match self.preedit_opt.take() {
             Some(some) => some,
             None => return false,
         };
+        let line = &mut self.buffer.lines[preedit.start.line];
+        let after = line.split_off(preedit.start.index + preedit.text.len());
+        line.split_off(preedit.start.index);
+        line.append(after);
+        self.cursor = preedit.start;
+        self.cursor_moved = true;
+        self.preedit_changed();
+        true
+    }
 
-        let (start, end) = match select.line.cmp(&self.cursor.line) {
-            cmp::Ordering::Greater => (self.cursor, select),
-            cmp::Ordering::Less => (select, self.cursor),
-            cmp::Ordering::Equal => {
-                /* select.line == self.cursor.line */
-                if select.index < self.cursor.index {
-                    (select, self.cursor)
+    /// Mark the text of the buffer as changed by the preedit text, keeping the history
+    fn preedit_changed(&mut self) {
+        let history_current = self.history_version == self.buffer.content_version();
+        self.buffer.set_content_changed();
+        if history_current {
+            self.history_version = self.buffer.content_version();
+        }
+        self.buffer.set_redraw(true);
+    }
+
+    /// Get the text of the line the cursor is on, and the byte index of the cursor in it
+    pub fn current_line(&self) -> (&str, usize) {
+        (
+            self.buffer.lines[self.cursor.line].text(),
+            self.cursor.index,
+        )
+    }
+
+    /// Get the index of the visual line the cursor is on, counting wrapped lines across the
+    /// whole buffer
+    ///
+    /// Lines that have not been laid out count as one visual line.
+    pub fn cursor_visual_line(&self) -> usize {
+        let before: usize = self.buffer.lines[..self.cursor.line]
+            .iter()
+            .map(|line| line.layout_opt().as_ref().map_or(1, |layout| layout.len()))
+            .sum();
+        if self.buffer.lines[self.cursor.line].layout_opt().is_none() {
+            return before;
+        }
+        before + self.buffer.layout_cursor(&self.cursor).layout
+    }
+
+    /// Apply `attrs` to the selected text, doing nothing if there is no selection
+    ///
+    /// Only styling changes, so the cursor and selection keep their logical positions while the
+    /// affected lines are reshaped. Lines whose selected text already has `attrs` are not
+    /// reshaped.
+    pub fn apply_attrs(&mut self, attrs: Attrs) {
+        let select = match self.select_opt {
+            Some(some) => some,
+            None => return,
+        };
+        let start = cmp::min(select, self.cursor);
+        let end = cmp::max(select, self.cursor);
+
+        self.begin_change();
+        let mut changed = false;
+        for line_i in start.line..=end.line {
+            let line = match self.buffer.lines.get_mut(line_i) {
+                Some(some) => some,
+                None => break,
+            };
+            let start_index = if line_i == start.line { start.index } else { 0 };
+            let end_index = if line_i == end.line {
+                end.index
+            } else {
+                line.text().len()
+            };
+            if start_index < end_index
+                && !line
+                    .attrs_list()
+                    .range_matches(start_index..end_index, attrs)
+            {
+                let mut attrs_list = line.attrs_list().clone();
+                attrs_list.add_span(start_index..end_index, attrs);
+                changed |= line.set_attrs_list(attrs_list);
+            }
+        }
+
+        if changed {
+            self.buffer.set_content_changed();
+            self.buffer.set_redraw(true);
+        }
+        self.end_change();
+    }
+
+    /// Get the [`SelectionStyle`]
+    pub fn selection_style(&self) -> SelectionStyle {
+        self.selection_style
+    }
+
+    /// Set the [`SelectionStyle`] used when drawing the selection
+    pub fn set_selection_style(&mut self, selection_style: SelectionStyle) {
+        if selection_style != self.selection_style {
+            self.selection_style = selection_style;
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    /// Search the buffer for `pattern`, returning the start and end of each match in text order
+    ///
+    /// The pattern is kept for [`Action::SearchNext`] and [`Action::SearchPrevious`], and its
+    /// matches are highlighted by [`Edit::draw`] until [`Self::clear_search`]. Matches do not span
+    /// lines, see [`Buffer::search`]. An empty pattern has no matches and clears the search.
+    pub fn search(
+        &mut self,
+        pattern: &str,
+        options: SearchOptions,
+    ) -> impl Iterator<Item = (Cursor, Cursor)> {
+        let search_opt = if pattern.is_empty() {
+            None
+        } else {
+            Some((pattern.to_string(), options))
+        };
+        if search_opt != self.search_opt {
+            self.search_opt = search_opt;
+            self.buffer.set_redraw(true);
+        }
+        self.buffer
+            .search(pattern, options.case_sensitive)
+            .into_iter()
+    }
+
+    /// Get the pattern and options of the current search, see [`Self::search`]
+    pub fn search_pattern(&self) -> Option<(&str, SearchOptions)> {
+        self.search_opt
+            .as_ref()
+            .map(|(pattern, options)| (pattern.as_str(), *options))
+    }
+
+    /// Stop highlighting the matches of [`Self::search`]
+    pub fn clear_search(&mut self) {
+        if self.search_opt.take().is_some() {
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    /// Get the color search matches are highlighted with, see [`Self::set_search_color`]
+    pub fn search_color(&self) -> Option<Color> {
+        self.search_color
+    }
+
+    /// Set the color search matches are highlighted with, or None for a translucent amber
+    pub fn set_search_color(&mut self, search_color: Option<Color>) {
+        if search_color != self.search_color {
+            self.search_color = search_color;
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    /// Select the next or previous match of the search, with the cursor at its end
+    fn select_match(&mut self, next: bool) {
+        let (pattern, options) = match &self.search_opt {
+            Some((pattern, options)) => (pattern.as_str(), *options),
+            None => return,
+        };
+        // Search from after the selected match, or from before it when going backward
+        let from = match self.selection() {
+            Some((start, end)) => {
+                if next {
+                    end
                 } else {
-                    /* select.index >= self.cursor.index */
-                    (self.cursor, select)
+                    start
+                }
+            }
+            None => self.cursor,
+        };
+        let match_opt = if next {
+            self.buffer
+                .search_next(from, pattern, options.case_sensitive)
+        } else {
+            self.buffer
+                .search_previous(from, pattern, options.case_sensitive)
+        };
+        let (start, end) = match match_opt {
+            Some(some) => some,
+            None => return,
+        };
+
+        // A match on the other side of `from` was found by wrapping around the buffer
+        let before = (start.line, start.index) < (from.line, from.index);
+        let wrapped = if next { before } else { !before };
+        if wrapped && !options.wrap_around {
+            return;
+        }
+        self.select_opt = Some(start);
+        self.cursor = end;
+        self.cursor_x_opt = None;
+        self.buffer.set_redraw(true);
+    }
+
+    /// Get the rectangles of the selection highlight, relative to the buffer view like
+    /// [`Buffer::cursor_position`]
+    ///
+    /// There is one rectangle for each visually contiguous part of the selection in each layout
+    /// run, with the height of the line. Parts that touch, such as the two sides of a boundary
+    /// between left-to-right and right-to-left text, are merged, so the rectangles have no gaps or
+    /// overlaps. Where the selection continues on the next layout run, the rectangles extend to
+    /// the end of the line in the paragraph direction, which is the right edge of the view for
+    /// left-to-right text and the left edge of the text for right-to-left text.
+    pub fn selection_bounds(&self) -> Vec<Rectangle> {
+        let mut bounds = Vec::new();
+        let (start, end) = match self.selection() {
+            Some(some) => some,
+            None => return bounds,
+        };
+
+        let line_height = self.buffer.metrics().line_height;
+        let scroll_x = libm::roundf(self.buffer.scroll_x());
+        let view_w = self.buffer.size().0 + scroll_x;
+        let mut ranges: Vec<(f32, f32)> = Vec::new();
+        for run in self.buffer.layout_runs() {
+            let line_i = run.line_i;
+            if line_i < start.line || line_i > end.line {
+                continue;
+            }
+
+            ranges.clear();
+            for glyph in run.glyphs.iter() {
+                // Guess x offset based on characters
+                let cluster = &run.text[glyph.start..glyph.end];
+                let total = cluster.grapheme_indices(true).count();
+                let c_w = glyph.w / total as f32;
+                for (n, (i, c)) in cluster.grapheme_indices(true).enumerate() {
+                    let c_start = glyph.start + i;
+                    let c_end = glyph.start + i + c.len();
+                    if (start.line != line_i || c_end > start.index)
+                        && (end.line != line_i || c_start < end.index)
+                    {
+                        // The characters of a right-to-left cluster start from its right edge
+                        let c_x = if glyph.level.is_rtl() {
+                            glyph.x + glyph.w - (n + 1) as f32 * c_w
+                        } else {
+                            glyph.x + n as f32 * c_w
+                        };
+                        ranges.push((c_x, c_x + c_w));
+                    }
                 }
             }
+
+            // The selection continues past this run on later lines, or on later runs of a
+            // wrapped line
+            let run_end = run.glyphs.iter().map(|glyph| glyph.end).max().unwrap_or(0);
+            if end.line > line_i || end.index > run_end {
+                if run.glyphs.is_empty() {
+                    // Highlight all of internal empty lines
+                    ranges.push((0.0, view_w));
+                } else if !ranges.is_empty() {
+                    // Draw to end of line
+                    if run.rtl {
+                        let min = ranges.iter().fold(f32::MAX, |min, range| min.min(range.0));
+                        ranges.push((0.0, min));
+                    } else {
+                        let max = ranges.iter().fold(f32::MIN, |max, range| max.max(range.1));
+                        ranges.push((max, view_w));
+                    }
+                }
+            }
+
+            // Merge ranges that overlap or touch, which may come from different bidi runs
+            ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut merged: Option<(f32, f32)> = None;
+            for &(min, max) in ranges.iter() {
+                merged = match merged {
+                    Some((merged_min, merged_max)) if min <= merged_max + 0.5 => {
+                        Some((merged_min, merged_max.max(max)))
+                    }
+                    Some((merged_min, merged_max)) => {
+                        bounds.push(Rectangle::new(
+                            merged_min - scroll_x,
+                            run.line_top,
+                            merged_max - merged_min,
+                            line_height,
+                        ));
+                        Some((min, max))
+                    }
+                    None => Some((min, max)),
+                };
+            }
+            if let Some((min, max)) = merged {
+                bounds.push(Rectangle::new(
+                    min - scroll_x,
+                    run.line_top,
+                    max - min,
+                    line_height,
+                ));
+            }
+        }
+        bounds
+    }
+
+    /// Draw the selection highlight, calling `f` with the position, size, and border radius of
+    /// each rectangle in [`Self::selection_bounds`]
+    ///
+    /// This is used by [`Edit::draw`], and can be used by renderers that draw rounded
+    /// rectangles natively.
+    pub fn draw_selection<F>(&self, mut f: F)
+    where
+        F: FnMut(i32, i32, u32, u32, f32),
+    {
+        let border_radius = self.selection_style.border_radius;
+        for rect in self.selection_bounds() {
+            let min = libm::floorf(rect.x) as i32;
+            let max = libm::floorf(rect.x + rect.width) as i32;
+            f(
+                min,
+                rect.y as i32,
+                cmp::max(0, max - min) as u32,
+                rect.height as u32,
+                border_radius,
+            );
+        }
+    }
+
+    /// Get the [`LayoutCursor`] of the cursor, laying out its line first if needed
+    fn layout_cursor(&mut self, font_system: &mut FontSystem) -> LayoutCursor {
+        self.buffer.line_layout(font_system, self.cursor.line);
+        self.buffer.layout_cursor(&self.cursor)
+    }
+
+    fn set_layout_cursor(&mut self, font_system: &mut FontSystem, cursor: LayoutCursor) {
+        let layout = self
+            .buffer
+            .line_layout(font_system, cursor.line)
+            .expect("layout not found");
+
+        let layout_line = match layout.get(cursor.layout) {
+            Some(some) => some,
+            None => match layout.last() {
+                Some(some) => some,
+                None => todo!("layout cursor in line with no layouts"),
+            },
+        };
+
+        let (new_index, new_affinity) = match layout_line.glyphs.get(cursor.glyph) {
+            Some(glyph) => (glyph.start, Affinity::After),
+            None => match layout_line.glyphs.last() {
+                Some(glyph) => (glyph.end, Affinity::Before),
+                //TODO: is this correct?
+                None => (0, Affinity::After),
+            },
         };
 
-        // Reset cursor to start of selection
+        if self.cursor.line != cursor.line
+            || self.cursor.index != new_index
+            || self.cursor.affinity != new_affinity
+        {
+            self.cursor.line = cursor.line;
+            self.cursor.index = new_index;
+            self.cursor.affinity = new_affinity;
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    /// Get the cursor one position to the left or right of the cursor on screen, or None if the
+    /// cursor is at the edge of its layout line or the line is not laid out
+    fn visual_neighbor(&self, right: bool) -> Option<Cursor> {
+        let line = &self.buffer.lines[self.cursor.line];
+        let layout = line.layout_opt().as_ref()?;
+        let stops: Vec<Vec<Cursor>> = layout
+            .iter()
+            .map(|layout_line| visual_stops(self.cursor.line, line.text(), &layout_line.glyphs))
+            .collect();
+
+        // Prefer the layout line where the affinity matches, as at a soft wrap
+        let same = |stop: &Cursor| stop.index == self.cursor.index;
+        let (line_stops, i) = stops
+            .iter()
+            .find_map(|line_stops| {
+                let i = line_stops
+                    .iter()
+                    .position(|stop| same(stop) && stop.affinity == self.cursor.affinity)?;
+                Some((line_stops, i))
+            })
+            .or_else(|| {
+                stops.iter().find_map(|line_stops| {
+                    let i = line_stops.iter().position(same)?;
+                    Some((line_stops, i))
+                })
+            })?;
+        let i = if right { i + 1 } else { i.checked_sub(1)? };
+        line_stops.get(i).copied()
+    }
+
+    /// Move the cursor one position left or right on screen, moving to the previous or next line
+    /// at the edge of a layout line
+    fn move_visual(&mut self, font_system: &mut FontSystem, right: bool) {
+        match self.visual_neighbor(right) {
+            Some(cursor) => {
+                self.cursor.index = cursor.index;
+                self.cursor.affinity = cursor.affinity;
+                self.buffer.set_redraw(true);
+                self.cursor_x_opt = None;
+            }
+            None => {
+                // Without a layout, there are no visual stops, so move logically
+                let rtl = match self.buffer.lines[self.cursor.line].shape_opt() {
+                    Some(shape) => shape.rtl,
+                    None => false,
+                };
+                if rtl == right {
+                    self.action(font_system, Action::Previous);
+                } else {
+                    self.action(font_system, Action::Next);
+                }
+            }
+        }
+    }
+
+    /// Delete the text between two cursors, moving the cursor to the start
+    fn delete_range(&mut self, start: Cursor, end: Cursor) {
+        self.record(ChangeItem {
+            start,
+            end,
+            text: self.text_range(start, end),
+            insert: false,
+        });
+
+        // Reset cursor to start of range
         self.cursor = start;
 
         // Delete the selection from the last line
@@ -217,11 +1032,12 @@ impl Edit for Editor {
             }
         }
 
-        true
+        self.buffer.set_content_changed();
     }
 
-    fn insert_string(&mut self, data: &str, attrs_list: Option<AttrsList>) {
-        self.delete_selection();
+    /// Insert a string at the cursor, moving the cursor to the end of it
+    fn insert_raw(&mut self, data: &str, attrs_list: Option<AttrsList>) {
+        let start = self.cursor;
         let mut remaining_split_len = data.len();
         if remaining_split_len == 0 {
             return;
@@ -247,9 +1063,7 @@ impl Edit for Editor {
             remaining_split_len -= data_line.len();
             core::mem::swap(&mut these_attrs, &mut final_attrs);
             line.append(BufferLine::new(
-                data_line
-                    .strip_suffix(char::is_control)
-                    .unwrap_or(data_line),
+                strip_newline(data_line),
                 these_attrs,
                 Shaping::Advanced,
             ));
@@ -259,9 +1073,7 @@ impl Edit for Editor {
         if let Some(data_line) = lines_iter.next_back() {
             remaining_split_len -= data_line.len();
             let mut tmp = BufferLine::new(
-                data_line
-                    .strip_suffix(char::is_control)
-                    .unwrap_or(data_line),
+                strip_newline(data_line),
                 final_attrs.split_off(remaining_split_len),
                 Shaping::Advanced,
             );
@@ -274,9 +1086,7 @@ impl Edit for Editor {
         for data_line in lines_iter.rev() {
             remaining_split_len -= data_line.len();
             let tmp = BufferLine::new(
-                data_line
-                    .strip_suffix(char::is_control)
-                    .unwrap_or(data_line),
+                strip_newline(data_line),
                 final_attrs.split_off(remaining_split_len),
                 Shaping::Advanced,
             );
@@ -288,10 +1098,134 @@ impl Edit for Editor {
 
         // Append the text after insertion
         self.cursor.index = self.buffer.lines[self.cursor.line].text().len() - after_len;
+
+        self.record(ChangeItem {
+            start,
+            end: self.cursor,
+            text: data.to_string(),
+            insert: true,
+        });
+        self.buffer.set_content_changed();
+    }
+}
+
+impl Edit for Editor {
+    fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffer
+    }
+
+    fn cursor(&self) -> Cursor {
+        self.cursor
+    }
+
+    fn set_cursor(&mut self, mut cursor: Cursor) {
+        self.clear_preedit();
+        // Clamp line and index to the nearest valid position
+        cursor.line = cmp::min(cursor.line, self.buffer.lines.len().saturating_sub(1));
+        match self.buffer.lines.get(cursor.line) {
+            Some(line) => {
+                let text = line.text();
+                cursor.index = cmp::min(cursor.index, text.len());
+                while !text.is_char_boundary(cursor.index) {
+                    cursor.index -= 1;
+                }
+            }
+            None => cursor.index = 0,
+        }
+
+        if cursor != self.cursor {
+            self.cursor = cursor;
+            self.cursor_x_opt = None;
+            self.merging = false;
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    fn select_opt(&self) -> Option<Cursor> {
+        self.select_opt
+    }
+
+    fn set_select_opt(&mut self, select_opt: Option<Cursor>) {
+        if self.select_opt != select_opt {
+            self.select_opt = select_opt;
+            self.buffer.set_redraw(true);
+        }
+    }
+
+    fn shape_as_needed(&mut self, font_system: &mut FontSystem) {
+        if self.cursor_moved {
+            self.buffer.shape_until_cursor(font_system, self.cursor);
+            self.cursor_moved = false;
+        } else {
+            self.buffer.shape_until_scroll(font_system);
+        }
+
+        let caret_opt = self.caret_rect();
+        if caret_opt != self.caret_opt {
+            self.prev_caret_opt = self.caret_opt;
+            self.caret_opt = caret_opt;
+        }
+    }
+
+    fn copy_selection(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+
+        if (start.line, start.index) == (end.line, end.index) {
+            return None;
+        }
+
+        let end = self.selection_end(start, end);
+
+        Some(self.text_range(start, end))
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        self.clear_preedit();
+        let (start, end) = match self.selection() {
+            Some(some) => some,
+            None => return false,
+        };
+        self.select_opt = None;
+
+        let end = self.selection_end(start, end);
+
+        self.begin_change();
+        self.delete_range(start, end);
+        self.end_change();
+        true
+    }
+
+    fn insert_string(&mut self, data: &str, attrs_list: Option<AttrsList>) {
+        let data = if self.buffer.single_line() {
+            self.buffer.single_line_newlines().apply(data)
+        } else {
+            Cow::Borrowed(data)
+        };
+        self.clear_preedit();
+        self.begin_change();
+        self.delete_selection();
+        self.insert_raw(&data, attrs_list);
+        self.end_change();
     }
 
     fn action(&mut self, font_system: &mut FontSystem, action: Action) {
+        if self.clear_preedit() {
+            // Actions use the layout without the preedit text
+            self.buffer.shape_until_cursor(font_system, self.cursor);
+        }
         let old_cursor = self.cursor;
+        self.begin_change();
+        if !matches!(
+            action,
+            Action::Insert(_) | Action::Backspace | Action::Delete
+        ) {
+            // Only consecutive typing or deleting is merged into one change
+            self.merging = false;
+        }
 
         match action {
             Action::Previous => {
@@ -337,35 +1271,11 @@ impl Edit for Editor {
                 }
                 self.cursor_x_opt = None;
             }
-            Action::Left => {
-                let rtl_opt = self.buffer.lines[self.cursor.line]
-                    .shape_opt()
-                    .as_ref()
-                    .map(|shape| shape.rtl);
-                if let Some(rtl) = rtl_opt {
-                    if rtl {
-                        self.action(font_system, Action::Next);
-                    } else {
-                        self.action(font_system, Action::Previous);
-                    }
-                }
-            }
-            Action::Right => {
-                let rtl_opt = self.buffer.lines[self.cursor.line]
-                    .shape_opt()
-                    .as_ref()
-                    .map(|shape| shape.rtl);
-                if let Some(rtl) = rtl_opt {
-                    if rtl {
-                        self.action(font_system, Action::Previous);
-                    } else {
-                        self.action(font_system, Action::Next);
-                    }
-                }
-            }
+            Action::Left => self.move_visual(font_system, false),
+            Action::Right => self.move_visual(font_system, true),
             Action::Up => {
                 //TODO: make this preserve X as best as possible!
-                let mut cursor = self.buffer.layout_cursor(&self.cursor);
+                let mut cursor = self.layout_cursor(font_system);
 
                 if self.cursor_x_opt.is_none() {
                     self.cursor_x_opt = Some(
@@ -388,7 +1298,7 @@ impl Edit for Editor {
             }
             Action::Down => {
                 //TODO: make this preserve X as best as possible!
-                let mut cursor = self.buffer.layout_cursor(&self.cursor);
+                let mut cursor = self.layout_cursor(font_system);
 
                 let layout_len = self
                     .buffer
@@ -416,13 +1326,13 @@ impl Edit for Editor {
                 self.set_layout_cursor(font_system, cursor);
             }
             Action::Home => {
-                let mut cursor = self.buffer.layout_cursor(&self.cursor);
+                let mut cursor = self.layout_cursor(font_system);
                 cursor.glyph = 0;
                 self.set_layout_cursor(font_system, cursor);
                 self.cursor_x_opt = None;
             }
             Action::End => {
-                let mut cursor = self.buffer.layout_cursor(&self.cursor);
+                let mut cursor = self.layout_cursor(font_system);
                 cursor.glyph = usize::max_value();
                 self.set_layout_cursor(font_system, cursor);
                 self.cursor_x_opt = None;
@@ -465,6 +1375,15 @@ impl Edit for Editor {
                     self.buffer.set_redraw(true);
                 }
             }
+            Action::SelectAll => {
+                self.select_all();
+            }
+            Action::Select(motion) => {
+                // The selection starts where the cursor was if there was none
+                let select = self.select_opt.unwrap_or(self.cursor);
+                self.action(font_system, *motion);
+                self.set_select_opt(Some(select));
+            }
             Action::Insert(character) => {
                 if character.is_control() && !['\t', '\n', '\u{92}'].contains(&character) {
                     // Filter out special chars (except for tab), use Action instead
@@ -475,19 +1394,32 @@ impl Edit for Editor {
                     let mut str_buf = [0u8; 8];
                     let str_ref = character.encode_utf8(&mut str_buf);
                     self.insert_string(str_ref, None);
+                    self.set_change_kind(ChangeKind::Typing);
                 }
             }
+            Action::Enter if self.buffer.single_line() => {
+                log::debug!("Ignoring enter in single line buffer");
+            }
             Action::Enter => {
                 self.delete_selection();
 
+                let start = self.cursor;
                 let new_line = self.buffer.lines[self.cursor.line].split_off(self.cursor.index);
 
                 self.cursor.line += 1;
                 self.cursor.index = 0;
 
                 self.buffer.lines.insert(self.cursor.line, new_line);
+                self.record(ChangeItem {
+                    start,
+                    end: self.cursor,
+                    text: "\n".to_string(),
+                    insert: true,
+                });
+                self.buffer.set_content_changed();
             }
             Action::Backspace => {
+                self.clear_empty_selection();
                 if self.delete_selection() {
                     // Deleted selection
                 } else if self.cursor.index > 0 {
@@ -506,14 +1438,27 @@ impl Edit for Editor {
                         }
                     }
 
+                    let end = self.cursor;
                     self.cursor.index = prev_index;
 
                     // Remove character
+                    let removed = line.text()[self.cursor.index..].to_string();
                     line.split_off(self.cursor.index);
 
                     // Add text after cursor
                     line.append(after);
-                } else if self.cursor.line > 0 {
+                    self.record(ChangeItem {
+                        start: self.cursor,
+                        end,
+                        text: removed,
+                        insert: false,
+                    });
+                    self.set_change_kind(ChangeKind::Backspace);
+                    self.buffer.set_content_changed();
+                } else if self.cursor.line > 0
+                    && self.buffer.lines[self.cursor.line].prefix().is_none()
+                {
+                    // Lines with a prefix are not joined into the previous line
                     let mut line_index = self.cursor.line;
                     let old_line = self.buffer.lines.remove(line_index);
                     line_index -= 1;
@@ -524,9 +1469,18 @@ impl Edit for Editor {
                     self.cursor.index = line.text().len();
 
                     line.append(old_line);
+                    self.record(ChangeItem {
+                        start: self.cursor,
+                        end: Cursor::new(line_index + 1, 0),
+                        text: "\n".to_string(),
+                        insert: false,
+                    });
+                    self.set_change_kind(ChangeKind::Backspace);
+                    self.buffer.set_content_changed();
                 }
             }
             Action::Delete => {
+                self.clear_empty_selection();
                 if self.delete_selection() {
                     // Deleted selection
                 } else if self.cursor.index < self.buffer.lines[self.cursor.line].text().len() {
@@ -541,6 +1495,7 @@ impl Edit for Editor {
 
                     if let Some(range) = range_opt {
                         self.cursor.index = range.start;
+                        let removed = line.text()[range.clone()].to_string();
 
                         // Get text after deleted EGC
                         let after = line.split_off(range.end);
@@ -550,22 +1505,41 @@ impl Edit for Editor {
 
                         // Add text after deleted EGC
                         line.append(after);
+                        self.record(ChangeItem {
+                            start: self.cursor,
+                            end: Cursor::new(self.cursor.line, range.end),
+                            text: removed,
+                            insert: false,
+                        });
+                        self.set_change_kind(ChangeKind::Delete);
+                        self.buffer.set_content_changed();
                     }
-                } else if self.cursor.line + 1 < self.buffer.lines.len() {
+                } else if self.cursor.line + 1 < self.buffer.lines.len()
+                    && self.buffer.lines[self.cursor.line + 1].prefix().is_none()
+                {
+                    let start = Cursor::new(
+                        self.cursor.line,
+                        self.buffer.lines[self.cursor.line].text().len(),
+                    );
                     let old_line = self.buffer.lines.remove(self.cursor.line + 1);
                     self.buffer.lines[self.cursor.line].append(old_line);
+                    self.record(ChangeItem {
+                        start,
+                        end: Cursor::new(self.cursor.line + 1, 0),
+                        text: "\n".to_string(),
+                        insert: false,
+                    });
+                    self.set_change_kind(ChangeKind::Delete);
+                    self.buffer.set_content_changed();
                 }
             }
             Action::Click { x, y } => {
                 self.select_opt = None;
+                self.drag_unit = DragUnit::Character;
 
-                if let Some(new_cursor) = self.buffer.hit(x as f32, y as f32) {
-                    if new_cursor != self.cursor {
-                        let color = self.cursor.color;
-                        self.cursor = new_cursor;
-                        self.cursor.color = color;
-                        self.buffer.set_redraw(true);
-                    }
+                if let Some(mut new_cursor) = self.buffer.hit(x as f32, y as f32) {
+                    new_cursor.color = self.cursor.color;
+                    self.set_cursor(new_cursor);
                 }
             }
             Action::Drag { x, y } => {
@@ -574,13 +1548,97 @@ impl Edit for Editor {
                     self.buffer.set_redraw(true);
                 }
 
-                if let Some(new_cursor) = self.buffer.hit(x as f32, y as f32) {
-                    if new_cursor != self.cursor {
-                        let color = self.cursor.color;
+                let hit_opt = self.buffer.hit(x as f32, y as f32);
+                match (hit_opt, self.drag_unit) {
+                    (None, _) => {}
+                    (Some(hit), DragUnit::Character) => {
+                        if hit != self.cursor {
+                            let color = self.cursor.color;
+                            self.cursor = hit;
+                            self.cursor.color = color;
+                            self.buffer.set_redraw(true);
+                        }
+                    }
+                    (Some(hit), DragUnit::Word { line, start, end }) => {
+                        // Keep the first word selected, and extend to the start or end of the
+                        // word under the point
+                        let word = self.word_at(hit);
+                        let (select, cursor) = if (hit.line, word.end) > (line, start) {
+                            let cursor = cmp::max((line, end), (hit.line, word.end));
+                            ((line, start), cursor)
+                        } else {
+                            ((line, end), (hit.line, word.start))
+                        };
+                        self.select_range(
+                            Cursor::new(select.0, select.1),
+                            Cursor::new(cursor.0, cursor.1),
+                        );
+                    }
+                    (Some(hit), DragUnit::Line { line }) => {
+                        let line_end = |line_i: usize| self.buffer.lines[line_i].text().len();
+                        let (select, cursor) = if hit.line >= line {
+                            (
+                                Cursor::new(line, 0),
+                                Cursor::new(hit.line, line_end(hit.line)),
+                            )
+                        } else {
+                            (Cursor::new(line, line_end(line)), Cursor::new(hit.line, 0))
+                        };
+                        self.select_range(select, cursor);
+                    }
+                }
+            }
+            Action::DoubleClick { x, y } => {
+                if let Some(hit) = self.buffer.hit(x as f32, y as f32) {
+                    let word = self.word_at(hit);
+                    self.drag_unit = DragUnit::Word {
+                        line: hit.line,
+                        start: word.start,
+                        end: word.end,
+                    };
+                    self.select_range(
+                        Cursor::new(hit.line, word.start),
+                        Cursor::new(hit.line, word.end),
+                    );
+                }
+            }
+            Action::TripleClick { x, y } => {
+                if let Some(hit) = self.buffer.hit(x as f32, y as f32) {
+                    self.drag_unit = DragUnit::Line { line: hit.line };
+                    let end = self.buffer.lines[hit.line].text().len();
+                    self.select_range(Cursor::new(hit.line, 0), Cursor::new(hit.line, end));
+                }
+            }
+            Action::SelectLinesDrag { y } => {
+                if let Some(hit) = self.buffer.hit(0.0, y as f32) {
+                    if self.select_opt.is_none() {
+                        self.select_lines_anchor = hit.line;
+                    }
+                    let anchor = self.select_lines_anchor;
+
+                    // Start of a line, or end of the buffer after the last line
+                    let line_start = |line_i: usize| -> Cursor {
+                        match self.buffer.lines.get(line_i) {
+                            Some(_) => Cursor::new(line_i, 0),
+                            None => {
+                                let last = self.buffer.lines.len() - 1;
+                                Cursor::new(last, self.buffer.lines[last].text().len())
+                            }
+                        }
+                    };
+                    let (select, mut new_cursor) = if hit.line >= anchor {
+                        (line_start(anchor), line_start(hit.line + 1))
+                    } else {
+                        (line_start(anchor + 1), line_start(hit.line))
+                    };
+                    new_cursor.color = self.cursor.color;
+
+                    if self.select_opt != Some(select) || self.cursor != new_cursor {
+                        self.select_opt = Some(select);
                         self.cursor = new_cursor;
-                        self.cursor.color = color;
                         self.buffer.set_redraw(true);
                     }
+                    self.cursor_x_opt = None;
                 }
             }
             Action::Scroll { lines } => {
@@ -653,6 +1711,31 @@ impl Edit for Editor {
                     }
                 }
             }
+            Action::DeleteWordBackward => {
+                self.clear_empty_selection();
+                if self.select_opt.is_some() || self.cursor.index == 0 {
+                    self.action(font_system, Action::Backspace);
+                } else {
+                    let end = self.cursor;
+                    self.action(font_system, Action::PreviousWord);
+                    self.delete_range(self.cursor, end);
+                }
+            }
+            Action::DeleteWordForward => {
+                self.clear_empty_selection();
+                let line_len = self.buffer.lines[self.cursor.line].text().len();
+                if self.select_opt.is_some() || self.cursor.index == line_len {
+                    self.action(font_system, Action::Delete);
+                } else {
+                    let start = self.cursor;
+                    self.action(font_system, Action::NextWord);
+                    if self.cursor.index == start.index {
+                        // There are no words after the cursor, delete to the end of the line
+                        self.cursor.index = line_len;
+                    }
+                    self.delete_range(start, self.cursor);
+                }
+            }
             Action::BufferStart => {
                 self.cursor.line = 0;
                 self.cursor.index = 0;
@@ -663,6 +1746,25 @@ impl Edit for Editor {
                 self.cursor.index = self.buffer.lines[self.cursor.line].text().len();
                 self.cursor_x_opt = None;
             }
+            Action::Undo => {
+                self.undo();
+            }
+            Action::Redo => {
+                self.redo();
+            }
+            Action::Copy => {}
+            Action::Cut => {
+                self.cut_selection();
+            }
+            Action::Paste(data) | Action::Commit(data) => {
+                self.insert_string(&data, None);
+            }
+            Action::SearchNext => {
+                self.select_match(true);
+            }
+            Action::SearchPrevious => {
+                self.select_match(false);
+            }
         }
 
         if old_cursor != self.cursor {
@@ -684,6 +1786,8 @@ impl Edit for Editor {
             }
             */
         }
+
+        self.end_change();
     }
 
     /// Draw the editor
@@ -697,161 +1801,74 @@ impl Edit for Editor {
     ) where
         F: FnMut(i32, i32, u32, u32, Color),
     {
+        let color = self.buffer.default_color().unwrap_or(color);
         let line_height = self.buffer.metrics().line_height;
+        let selection_color = cache.rect_color(match self.selection_style.color {
+            Some(some) => some,
+            None if self.focused => Color::rgba(color.r(), color.g(), color.b(), 0x33),
+            None => Color::rgba(0x80, 0x80, 0x80, 0x33),
+        });
+
+        // Highlight selection
+        self.draw_selection(|x, y, w, h, border_radius| {
+            fill_rounded_rect(x, y, w, h, border_radius, selection_color, &mut f);
+        });
+
+        // Selection is already offset by the horizontal scroll
+        let scroll_x = libm::roundf(self.buffer.scroll_x()) as i32;
+        let mut f = |x, y, w, h, color| f(x - scroll_x, y, w, h, color);
+        let search_color = cache.rect_color(
+            self.search_color
+                .unwrap_or(Color::rgba(0xFF, 0xC0, 0x00, 0x66)),
+        );
 
         for run in self.buffer.layout_runs() {
-            let line_i = run.line_i;
             let line_y = run.line_y;
             let line_top = run.line_top;
 
-            let cursor_glyph_opt = |cursor: &Cursor| -> Option<(usize, f32)> {
-                if cursor.line == line_i {
-                    for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
-                        if cursor.index == glyph.start {
-                            return Some((glyph_i, 0.0));
-                        } else if cursor.index > glyph.start && cursor.index < glyph.end {
-                            // Guess x offset based on characters
-                            let mut before = 0;
-                            let mut total = 0;
-
-                            let cluster = &run.text[glyph.start..glyph.end];
-                            for (i, _) in cluster.grapheme_indices(true) {
-                                if glyph.start + i < cursor.index {
-                                    before += 1;
-                                }
-                                total += 1;
-                            }
-
-                            let offset = glyph.w * (before as f32) / (total as f32);
-                            return Some((glyph_i, offset));
-                        }
-                    }
-                    match run.glyphs.last() {
-                        Some(glyph) => {
-                            if cursor.index == glyph.end {
-                                return Some((run.glyphs.len(), 0.0));
-                            }
-                        }
-                        None => {
-                            return Some((0, 0.0));
-                        }
-                    }
-                }
-                None
-            };
-
-            // Highlight selection (TODO: HIGHLIGHT COLOR!)
-            if let Some(select) = self.select_opt {
-                let (start, end) = match select.line.cmp(&self.cursor.line) {
-                    cmp::Ordering::Greater => (self.cursor, select),
-                    cmp::Ordering::Less => (select, self.cursor),
-                    cmp::Ordering::Equal => {
-                        /* select.line == self.cursor.line */
-                        if select.index < self.cursor.index {
-                            (select, self.cursor)
-                        } else {
-                            /* select.index >= self.cursor.index */
-                            (self.cursor, select)
-                        }
-                    }
-                };
-
-                if line_i >= start.line && line_i <= end.line {
-                    let mut range_opt = None;
-                    for glyph in run.glyphs.iter() {
-                        // Guess x offset based on characters
-                        let cluster = &run.text[glyph.start..glyph.end];
-                        let total = cluster.grapheme_indices(true).count();
-                        let mut c_x = glyph.x;
-                        let c_w = glyph.w / total as f32;
-                        for (i, c) in cluster.grapheme_indices(true) {
-                            let c_start = glyph.start + i;
-                            let c_end = glyph.start + i + c.len();
-                            if (start.line != line_i || c_end > start.index)
-                                && (end.line != line_i || c_start < end.index)
-                            {
-                                range_opt = match range_opt.take() {
-                                    Some((min, max)) => Some((
-                                        cmp::min(min, c_x as i32),
-                                        cmp::max(max, (c_x + c_w) as i32),
-                                    )),
-                                    None => Some((c_x as i32, (c_x + c_w) as i32)),
-                                };
-                            } else if let Some((min, max)) = range_opt.take() {
-                                f(
-                                    min,
-                                    line_top as i32,
-                                    cmp::max(0, max - min) as u32,
-                                    line_height as u32,
-                                    Color::rgba(color.r(), color.g(), color.b(), 0x33),
-                                );
-                            }
-                            c_x += c_w;
-                        }
-                    }
-
-                    if run.glyphs.is_empty() && end.line > line_i {
-                        // Highlight all of internal empty lines
-                        range_opt = Some((0, self.buffer.size().0 as i32));
-                    }
-
-                    if let Some((mut min, mut max)) = range_opt.take() {
-                        if end.line > line_i {
-                            // Draw to end of line
-                            if run.rtl {
-                                min = 0;
-                            } else {
-                                max = self.buffer.size().0 as i32;
-                            }
-                        }
+            // Highlight search matches
+            if let Some((pattern, options)) = &self.search_opt {
+                for range in line_matches(run.text, pattern, options.case_sensitive) {
+                    let start = Cursor::new(run.line_i, range.start);
+                    let end = Cursor::new(run.line_i, range.end);
+                    if let Some((x, w)) = run.highlight(start, end) {
                         f(
-                            min,
+                            x as i32,
                             line_top as i32,
-                            cmp::max(0, max - min) as u32,
+                            w as u32,
                             line_height as u32,
-                            Color::rgba(color.r(), color.g(), color.b(), 0x33),
+                            search_color,
                         );
                     }
                 }
             }
 
-            // Draw cursor
-            if let Some((cursor_glyph, cursor_glyph_offset)) = cursor_glyph_opt(&self.cursor) {
-                let x = match run.glyphs.get(cursor_glyph) {
-                    Some(glyph) => {
-                        // Start of detected glyph
-                        if glyph.level.is_rtl() {
-                            (glyph.x + glyph.w - cursor_glyph_offset) as i32
-                        } else {
-                            (glyph.x + cursor_glyph_offset) as i32
-                        }
-                    }
-                    None => match run.glyphs.last() {
-                        Some(glyph) => {
-                            // End of last glyph
-                            if glyph.level.is_rtl() {
-                                glyph.x as i32
-                            } else {
-                                (glyph.x + glyph.w) as i32
-                            }
-                        }
-                        None => {
-                            // Start of empty line
-                            0
-                        }
-                    },
-                };
-
+            // Draw cursor, only when focused
+            if let Some(x) = cursor_x(&run, &self.cursor).filter(|_| self.caret_visible()) {
                 f(
                     x,
                     line_top as i32,
                     1,
                     line_height as u32,
-                    self.cursor.color.unwrap_or(color),
+                    cache.rect_color(self.cursor.color.unwrap_or(color)),
                 );
             }
 
-            for glyph in run.glyphs.iter() {
+            let tab_leader_glyphs = run.tab_leader_glyphs();
+
+            for glyph in run
+                .glyphs
+                .iter()
+                .chain(run.ruby_glyphs.iter())
+                .chain(run.prefix_glyphs.iter())
+                .chain(run.wrap_indicator_glyphs.iter())
+                .chain(tab_leader_glyphs.iter())
+            {
+                if glyph.inline_box_opt.is_some() {
+                    // Inline boxes are filled in by the renderer
+                    continue;
+                }
+
                 let physical_glyph = glyph.physical((0., 0.), 1.0);
 
                 let glyph_color = match glyph.color_opt {
@@ -874,6 +1891,137 @@ impl Edit for Editor {
                     },
                 );
             }
+
+            run.draw_underlines(color, |x, y, w, h, color| {
+                f(x, y, w, h, cache.rect_color(color));
+            });
+        }
+    }
+}
+
+/// Get the byte range of the word containing the character at `index` in `text`
+///
+/// Words are split at Unicode word boundaries, except that runs of whitespace and runs of
+/// punctuation are each one word. An index at the end of the text is in the last word.
+fn word_range(text: &str, index: usize) -> Range<usize> {
+    #[derive(PartialEq)]
+    enum Kind {
+        Whitespace,
+        Punctuation,
+        Word,
+    }
+
+    let mut range = 0..0;
+    let mut range_kind = None;
+    for (i, segment) in text.split_word_bound_indices() {
+        let kind = if segment.chars().all(char::is_whitespace) {
+            Kind::Whitespace
+        } else if segment.chars().any(char::is_alphanumeric) {
+            Kind::Word
+        } else {
+            Kind::Punctuation
+        };
+        if kind != Kind::Word && range_kind.as_ref() == Some(&kind) {
+            range.end = i + segment.len();
+        } else if index < range.end {
+            break;
+        } else {
+            range = i..i + segment.len();
+            range_kind = Some(kind);
+        }
+    }
+    range
+}
+
+/// Get the cursor positions between and within the glyphs of a layout line, from left to right
+///
+/// At a boundary between runs of different bidi levels, the position at the edge of the run with
+/// the higher level is used, so the cursor stays with the embedded run. Each text index is only
+/// used once, so positions at the same index inside a cluster are skipped.
+fn visual_stops(line_i: usize, text: &str, glyphs: &[LayoutGlyph]) -> Vec<Cursor> {
+    let mut stops = Vec::with_capacity(glyphs.len() + 1);
+    let mut seen = BTreeSet::new();
+    let mut push = |index: usize, affinity: Affinity| {
+        if seen.insert(index) {
+            stops.push(Cursor::new_with_affinity(line_i, index, affinity));
+        }
+    };
+
+    // The index and affinity of the right edge of the left glyph, or the left edge of the
+    // right glyph
+    let left_edge = |glyph: &LayoutGlyph| {
+        if glyph.level.is_rtl() {
+            (glyph.start, Affinity::After)
+        } else {
+            (glyph.end, Affinity::Before)
+        }
+    };
+    let right_edge = |glyph: &LayoutGlyph| {
+        if glyph.level.is_rtl() {
+            (glyph.end, Affinity::Before)
+        } else {
+            (glyph.start, Affinity::After)
+        }
+    };
+    for k in 0..=glyphs.len() {
+        let left_opt = k.checked_sub(1).and_then(|k| glyphs.get(k));
+        let right_opt = glyphs.get(k);
+
+        let edge_opt = match (left_opt, right_opt) {
+            (Some(left), Some(right)) if right.level > left.level => Some(right_edge(right)),
+            (Some(left), _) => Some(left_edge(left)),
+            (None, Some(right)) => Some(right_edge(right)),
+            (None, None) => None,
+        };
+        if let Some((index, affinity)) = edge_opt {
+            push(index, affinity);
+        }
+
+        // Positions between the graphemes of a cluster, such as a ligature
+        if let Some(glyph) = right_opt {
+            let cluster = &text[glyph.start..glyph.end];
+            let inner: Vec<usize> = cluster
+                .grapheme_indices(true)
+                .skip(1)
+                .map(|(i, _)| glyph.start + i)
+                .collect();
+            if glyph.level.is_rtl() {
+                for index in inner.into_iter().rev() {
+                    push(index, Affinity::Before);
+                }
+            } else {
+                for index in inner {
+                    push(index, Affinity::Before);
+                }
+            }
         }
     }
+    stops
+}
+
+/// Fill a rectangle with rounded corners, using one pixel high spans for the corners
+#[cfg(feature = "swash")]
+fn fill_rounded_rect<F>(x: i32, y: i32, w: u32, h: u32, border_radius: f32, color: Color, f: &mut F)
+where
+    F: FnMut(i32, i32, u32, u32, Color),
+{
+    let r = border_radius.min(w as f32 / 2.0).min(h as f32 / 2.0);
+    if r < 1.0 {
+        f(x, y, w, h, color);
+        return;
+    }
+
+    let corner_rows = cmp::min(libm::ceilf(r) as u32, h / 2);
+    for row in 0..corner_rows {
+        // Horizontal inset of the corner arc at the center of this row
+        let dy = r - (row as f32 + 0.5);
+        let inset = cmp::min(
+            libm::roundf(r - libm::sqrtf((r * r - dy * dy).max(0.0))) as u32,
+            w / 2,
+        );
+        let span_w = w - 2 * inset;
+        f(x + inset as i32, y + row as i32, span_w, 1, color);
+        f(x + inset as i32, y + (h - 1 - row) as i32, span_w, 1, color);
+    }
+    f(x, y + corner_rows as i32, w, h - 2 * corner_rows, color);
 }
@@ -1,9 +1,11 @@
 #[cfg(not(feature = "std"))]
-use alloc::string::String;
+use alloc::{boxed::Box, string::String};
+
+use core::cmp;
 
 #[cfg(feature = "swash")]
-use crate::Color;
-use crate::{AttrsList, BorrowedWithFontSystem, Buffer, Cursor, FontSystem};
+use crate::ClipRect;
+use crate::{AttrsList, BorrowedWithFontSystem, Buffer, Color, Cursor, FontSystem};
 
 pub use self::editor::*;
 mod editor;
@@ -19,15 +21,17 @@ pub use self::vi::*;
 mod vi;
 
 /// An action to perform on an [`Editor`]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Action {
     /// Move cursor to previous character ([Self::Left] in LTR, [Self::Right] in RTL)
     Previous,
     /// Move cursor to next character ([Self::Right] in LTR, [Self::Left] in RTL)
     Next,
-    /// Move cursor left
+    /// Move cursor visually left, which is [`Self::Next`] in right-to-left runs, even in a
+    /// left-to-right paragraph
     Left,
-    /// Move cursor right
+    /// Move cursor visually right, which is [`Self::Previous`] in right-to-left runs, even in a
+    /// left-to-right paragraph
     Right,
     /// Move cursor up
     Up,
@@ -49,18 +53,44 @@ pub enum Action {
     Vertical(i32),
     /// Escape, clears selection
     Escape,
+    /// Select the whole buffer, see [`Editor::select_all`]
+    SelectAll,
+    /// Perform a movement action, such as [`Self::Left`] or [`Self::NextWord`], extending the
+    /// selection from where the cursor was, like holding shift
+    ///
+    /// The selection starts at the cursor if there is none.
+    Select(Box<Action>),
     /// Insert character at cursor
     Insert(char),
     /// Create new line
     Enter,
-    /// Delete text behind cursor
+    /// Delete the selection, or text behind cursor
+    ///
+    /// At the start of a line, the line is joined to the end of the previous line, keeping the
+    /// attributes of both. At the start of the buffer, this does nothing.
     Backspace,
-    /// Delete text in front of cursor
+    /// Delete the selection, or text in front of cursor
+    ///
+    /// At the end of a line, the next line is joined to it, keeping the attributes of both. At the
+    /// end of the buffer, this does nothing.
     Delete,
     /// Mouse click at specified position
     Click { x: i32, y: i32 },
     /// Mouse drag to specified position
     Drag { x: i32, y: i32 },
+    /// Mouse double click at specified position, selecting the word under it
+    ///
+    /// Runs of whitespace and runs of punctuation are selected as one word, and a click past the
+    /// end of a line selects its last word. A following [`Self::Drag`] extends the selection by
+    /// whole words.
+    DoubleClick { x: i32, y: i32 },
+    /// Mouse triple click at specified position, selecting the line under it
+    ///
+    /// A following [`Self::Drag`] extends the selection by whole lines.
+    TripleClick { x: i32, y: i32 },
+    /// Mouse drag in a line gutter to specified vertical position, selecting whole lines from
+    /// where the drag started, including their line endings
+    SelectLinesDrag { y: i32 },
     /// Scroll specified number of lines
     Scroll { lines: i32 },
     /// Move cursor to previous word boundary
@@ -71,10 +101,72 @@ pub enum Action {
     LeftWord,
     /// Move cursor to next word boundary to the right
     RightWord,
+    /// Delete the selection, or the text from the cursor back to the previous word boundary
+    ///
+    /// At the start of a line, this is the same as [`Self::Backspace`].
+    DeleteWordBackward,
+    /// Delete the selection, or the text from the cursor to the next word boundary
+    ///
+    /// At the end of a line, this is the same as [`Self::Delete`].
+    DeleteWordForward,
     /// Move cursor to the start of the document
     BufferStart,
     /// Move cursor to the end of the document
     BufferEnd,
+    /// Undo the last change to the text
+    Undo,
+    /// Redo the last undone change to the text
+    Redo,
+    /// Copy the selection, which does not change the editor
+    ///
+    /// Use [`Edit::copy_selection`] to get the copied text.
+    Copy,
+    /// Delete the selection
+    ///
+    /// Use [`Edit::cut_selection`] to get the deleted text.
+    Cut,
+    /// Insert text at cursor, replacing the selection if there is one
+    ///
+    /// Newlines in the text split it into multiple lines.
+    Paste(String),
+    /// Replace the text being composed with an input method by the text it committed
+    ///
+    /// See [`Editor::set_preedit`].
+    Commit(String),
+    /// Select the next match of the pattern of [`Editor::search`] after the cursor or selection
+    SearchNext,
+    /// Select the previous match of the pattern of [`Editor::search`] before the cursor or
+    /// selection
+    SearchPrevious,
+}
+
+/// Style of the selection highlight drawn by [`Editor`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SelectionStyle {
+    /// Radius of the corners of each selection rectangle, in pixels
+    pub border_radius: f32,
+    /// Color of the selection, or None for the text color with transparency while focused and
+    /// gray while not
+    pub color: Option<Color>,
+}
+
+/// Options for [`Editor::search`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchOptions {
+    /// Compare characters exactly, rather than by their lowercase mappings
+    pub case_sensitive: bool,
+    /// Continue from the other end of the buffer when [`Action::SearchNext`] or
+    /// [`Action::SearchPrevious`] run out of matches
+    pub wrap_around: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            wrap_around: true,
+        }
+    }
 }
 
 /// A trait to allow easy replacements of [`Editor`], like `SyntaxEditor`
@@ -102,7 +194,10 @@ pub trait Edit {
     /// Get the current cursor
     fn cursor(&self) -> Cursor;
 
-    /// Set the current cursor
+    /// Set the current cursor, clamping it to a valid position in the buffer
+    ///
+    /// The line is clamped to the last line, and the index to the end of the line, moving back to
+    /// the nearest character boundary if needed.
     fn set_cursor(&mut self, cursor: Cursor);
 
     /// Get the current selection position
@@ -111,11 +206,50 @@ pub trait Edit {
     /// Set the current selection position
     fn set_select_opt(&mut self, select_opt: Option<Cursor>);
 
+    /// Get the start and end of the selection in text order, or None if there is no selection
+    ///
+    /// The selection is between the cursor and [`Self::select_opt`], and may be empty.
+    fn selection(&self) -> Option<(Cursor, Cursor)> {
+        let select = self.select_opt()?;
+        let cursor = self.cursor();
+        Some(match select.line.cmp(&cursor.line) {
+            cmp::Ordering::Greater => (cursor, select),
+            cmp::Ordering::Less => (select, cursor),
+            cmp::Ordering::Equal => {
+                /* select.line == cursor.line */
+                if select.index < cursor.index {
+                    (select, cursor)
+                } else {
+                    /* select.index >= cursor.index */
+                    (cursor, select)
+                }
+            }
+        })
+    }
+
+    /// Select the whole buffer, with the cursor at its end
+    fn select_all(&mut self) {
+        let line = self.buffer().lines.len() - 1;
+        let end = Cursor::new(line, self.buffer().lines[line].text().len());
+        self.set_select_opt(Some(Cursor::new(0, 0)));
+        self.set_cursor(end);
+    }
+
     /// Shape lines until scroll, after adjusting scroll if the cursor moved
     fn shape_as_needed(&mut self, font_system: &mut FontSystem);
 
-    /// Copy selection
-    fn copy_selection(&mut self) -> Option<String>;
+    /// Get the text between the selection and the cursor, in either order, or None if there is no
+    /// selection or it is empty
+    fn copy_selection(&self) -> Option<String>;
+
+    /// Delete the selection, returning its text, or None if there is no selection or it is empty
+    fn cut_selection(&mut self) -> Option<String> {
+        let text_opt = self.copy_selection();
+        if text_opt.is_some() {
+            self.delete_selection();
+        }
+        text_opt
+    }
 
     /// Delete selection, adjusting cursor and returning true if there was a selection
     // Also used by backspace, delete, insert, and enter when there is a selection
@@ -138,6 +272,25 @@ pub trait Edit {
         f: F,
     ) where
         F: FnMut(i32, i32, u32, u32, Color);
+
+    /// Draw the editor, only emitting the parts of rectangles that are inside `clip`
+    #[cfg(feature = "swash")]
+    fn draw_clipped<F>(
+        &self,
+        font_system: &mut FontSystem,
+        cache: &mut crate::SwashCache,
+        color: Color,
+        clip: ClipRect,
+        mut f: F,
+    ) where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        self.draw(font_system, cache, color, |x, y, w, h, color| {
+            if let Some((x, y, w, h)) = clip.intersect(x, y, w, h) {
+                f(x, y, w, h, color);
+            }
+        });
+    }
 }
 
 impl<'a, T: Edit> BorrowedWithFontSystem<'a, T> {
@@ -167,4 +320,19 @@ impl<'a, T: Edit> BorrowedWithFontSystem<'a, T> {
     {
         self.inner.draw(self.font_system, cache, color, f);
     }
+
+    /// Draw the editor, only emitting the parts of rectangles that are inside `clip`
+    #[cfg(feature = "swash")]
+    pub fn draw_clipped<F>(
+        &mut self,
+        cache: &mut crate::SwashCache,
+        color: Color,
+        clip: ClipRect,
+        f: F,
+    ) where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        self.inner
+            .draw_clipped(self.font_system, cache, color, clip, f);
+    }
 }
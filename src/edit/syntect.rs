@@ -1,5 +1,6 @@
 #[cfg(not(feature = "std"))]
-use alloc::{string::String, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
+use core::iter;
 #[cfg(feature = "std")]
 use std::{fs, io, path::Path};
 use syntect::highlighting::{
@@ -8,8 +9,8 @@ use syntect::highlighting::{
 use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 
 use crate::{
-    Action, AttrsList, BorrowedWithFontSystem, Buffer, Color, Cursor, Edit, Editor, FontSystem,
-    Shaping, Style, Weight, Wrap,
+    Action, AttrsList, AttrsOwned, BorrowedWithFontSystem, Buffer, Color, Cursor, Edit, Editor,
+    FontSystem, Shaping, Style, Weight, Wrap,
 };
 
 #[derive(Debug)]
@@ -29,6 +30,17 @@ impl SyntaxSystem {
     }
 }
 
+/// How line numbers are shown in the gutter of a [`SyntaxEditor`], see
+/// [`SyntaxEditor::set_gutter`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GutterStyle {
+    /// Number every line from 1
+    #[default]
+    Absolute,
+    /// Number lines by their distance from the cursor line, which shows its own number
+    Relative,
+}
+
 /// A wrapper of [`Editor`] with syntax highlighting provided by [`SyntaxSystem`]
 #[derive(Debug)]
 pub struct SyntaxEditor<'a> {
@@ -38,6 +50,9 @@ pub struct SyntaxEditor<'a> {
     theme: &'a Theme,
     highlighter: Highlighter<'a>,
     syntax_cache: Vec<(ParseState, HighlightState)>,
+    gutter: Option<(usize, GutterStyle)>,
+    /// Number of lines and cursor line the gutter was numbered for
+    gutter_state: Option<(usize, usize)>,
 }
 
 impl<'a> SyntaxEditor<'a> {
@@ -59,15 +74,22 @@ impl<'a> SyntaxEditor<'a> {
             theme,
             highlighter,
             syntax_cache: Vec::new(),
+            gutter: None,
+            gutter_state: None,
         })
     }
 
     /// Modifies the theme of the [`SyntaxEditor`], returning false if the theme is missing
+    ///
+    /// Every line is highlighted again by [`Edit::shape_as_needed`], and the buffer is marked
+    /// for redraw so the new background and foreground colors are used.
     pub fn update_theme(&mut self, theme_name: &str) -> bool {
         if let Some(theme) = self.syntax_system.theme_set.themes.get(theme_name) {
             self.theme = theme;
             self.highlighter = Highlighter::new(theme);
             self.syntax_cache.clear();
+            self.gutter_state = None;
+            self.editor.buffer_mut().set_redraw(true);
 
             true
         } else {
@@ -130,6 +152,116 @@ impl<'a> SyntaxEditor<'a> {
             Color::rgb(0xFF, 0xFF, 0xFF)
         }
     }
+
+    /// Get the background color of the gutter, or the default background color if the theme
+    /// does not set one
+    pub fn gutter_color(&self) -> Color {
+        if let Some(gutter) = self.theme.settings.gutter {
+            Color::rgba(gutter.r, gutter.g, gutter.b, gutter.a)
+        } else {
+            self.background_color()
+        }
+    }
+
+    /// Get the color of text in the gutter, like line numbers, or the default foreground color if
+    /// the theme does not set one
+    pub fn gutter_foreground_color(&self) -> Color {
+        if let Some(foreground) = self.theme.settings.gutter_foreground {
+            Color::rgba(foreground.r, foreground.g, foreground.b, foreground.a)
+        } else {
+            self.foreground_color()
+        }
+    }
+
+    /// Get the color the current line is highlighted with, if the theme sets one
+    pub fn line_highlight_color(&self) -> Option<Color> {
+        self.theme
+            .settings
+            .line_highlight
+            .map(|color| Color::rgba(color.r, color.g, color.b, color.a))
+    }
+
+    /// Get the minimum number of digits and the [`GutterStyle`] of the gutter, or None if there
+    /// is no gutter
+    pub fn gutter(&self) -> Option<(usize, GutterStyle)> {
+        self.gutter
+    }
+
+    /// Show line numbers in a gutter at the left of the text, at least `width_chars` digits wide,
+    /// or remove the gutter if `width_chars` is 0
+    ///
+    /// The numbers are laid out as the [`crate::BufferLine::prefix`] of every line, in the default
+    /// attributes of the line with the [`Self::gutter_foreground_color`], so the text and wrapped
+    /// lines are indented past the gutter and [`Action::Click`] and other hits are offset with
+    /// it. The gutter widens when the line numbers need more digits. [`Edit::shape_as_needed`]
+    /// updates the numbers and [`Edit::draw`] fills the gutter with the [`Self::gutter_color`]
+    /// and highlights the cursor line with the [`Self::line_highlight_color`].
+    pub fn set_gutter(&mut self, width_chars: usize, style: GutterStyle) {
+        let gutter = if width_chars == 0 {
+            None
+        } else {
+            Some((width_chars, style))
+        };
+        if gutter != self.gutter {
+            self.gutter = gutter;
+            self.gutter_state = None;
+            if gutter.is_none() {
+                for line in self.editor.buffer_mut().lines.iter_mut() {
+                    line.set_prefix(None);
+                }
+            }
+            self.editor.buffer_mut().set_redraw(true);
+        }
+    }
+
+    /// Set the line numbers in the gutter, if the lines or the cursor line changed
+    fn number_lines(&mut self) {
+        let (width_chars, style) = match self.gutter {
+            Some(some) => some,
+            None => return,
+        };
+        let cursor_line = self.editor.cursor().line;
+        let color = self.gutter_foreground_color();
+        let buffer = self.editor.buffer_mut();
+        let state = (buffer.lines.len(), cursor_line);
+        let stale = match self.gutter_state {
+            Some((lines, _)) if style == GutterStyle::Absolute => lines != state.0,
+            Some(gutter_state) => gutter_state != state,
+            None => true,
+        };
+        // New lines, such as those of replaced text, have no number yet
+        if !stale && buffer.lines.iter().all(|line| line.prefix().is_some()) {
+            return;
+        }
+
+        let width = width_chars.max(format!("{}", buffer.lines.len()).len());
+        let mut changed = false;
+        for (line_i, line) in buffer.lines.iter_mut().enumerate() {
+            let number = match style {
+                GutterStyle::Relative if line_i != cursor_line => line_i.abs_diff(cursor_line),
+                _ => line_i + 1,
+            };
+            let attrs = AttrsOwned::new(line.attrs_list().defaults().color(color));
+            let text = format!("{:>width$} ", number, width = width);
+            changed |= line.set_prefix(Some((text, attrs.as_attrs())));
+        }
+        if changed {
+            buffer.set_redraw(true);
+        }
+        self.gutter_state = Some(state);
+    }
+
+    /// Undo the last change to the text, see [`Editor::undo`]
+    ///
+    /// The changed lines are highlighted again by [`Edit::shape_as_needed`].
+    pub fn undo(&mut self) -> bool {
+        self.editor.undo()
+    }
+
+    /// Redo the last change undone with [`Self::undo`], see [`Editor::redo`]
+    pub fn redo(&mut self) -> bool {
+        self.editor.redo()
+    }
 }
 
 impl<'a> Edit for SyntaxEditor<'a> {
@@ -158,11 +290,33 @@ impl<'a> Edit for SyntaxEditor<'a> {
     }
 
     fn shape_as_needed(&mut self, font_system: &mut FontSystem) {
-        #[cfg(feature = "std")]
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
         let now = std::time::Instant::now();
 
+        self.number_lines();
         let buffer = self.editor.buffer_mut();
 
+        // Keep each cached state lined up with the line it follows when lines were inserted or
+        // removed, which is assumed to have happened at the first changed line
+        if buffer.lines.len() != self.syntax_cache.len() {
+            let first_opt = buffer.lines.iter().position(|line| line.is_reset());
+            match first_opt {
+                Some(first) if first < self.syntax_cache.len() => {
+                    if buffer.lines.len() > self.syntax_cache.len() {
+                        let inserted = buffer.lines.len() - self.syntax_cache.len();
+                        // Inserted lines are changed, so their states are replaced below
+                        let item = self.syntax_cache[first].clone();
+                        self.syntax_cache
+                            .splice(first..first, iter::repeat_n(item, inserted));
+                    } else {
+                        let removed = self.syntax_cache.len() - buffer.lines.len();
+                        self.syntax_cache.drain(first..first + removed);
+                    }
+                }
+                _ => self.syntax_cache.truncate(buffer.lines.len()),
+            }
+        }
+
         let mut highlighted = 0;
         for line_i in 0..buffer.lines.len() {
             let line = &mut buffer.lines[line_i];
@@ -239,7 +393,7 @@ impl<'a> Edit for SyntaxEditor<'a> {
 
         if highlighted > 0 {
             buffer.set_redraw(true);
-            #[cfg(feature = "std")]
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
             log::debug!(
                 "Syntax highlighted {} lines in {:?}",
                 highlighted,
@@ -250,7 +404,7 @@ impl<'a> Edit for SyntaxEditor<'a> {
         self.editor.shape_as_needed(font_system);
     }
 
-    fn copy_selection(&mut self) -> Option<String> {
+    fn copy_selection(&self) -> Option<String> {
         self.editor.copy_selection()
     }
 
@@ -278,7 +432,40 @@ impl<'a> Edit for SyntaxEditor<'a> {
         F: FnMut(i32, i32, u32, u32, Color),
     {
         let size = self.buffer().size();
-        f(0, 0, size.0 as u32, size.1 as u32, self.background_color());
+        f(
+            0,
+            0,
+            size.0 as u32,
+            size.1 as u32,
+            cache.rect_color(self.background_color()),
+        );
+        if self.gutter.is_some() {
+            let scroll_x = libm::roundf(self.buffer().scroll_x()) as i32;
+            let line_height = self.buffer().metrics().line_height as u32;
+            let cursor_line = self.cursor().line;
+            let line_highlight_opt = self.line_highlight_color();
+            for run in self.buffer().layout_runs() {
+                let y = run.line_top as i32;
+                if run.line_i == cursor_line {
+                    if let Some(line_highlight) = line_highlight_opt {
+                        f(
+                            0,
+                            y,
+                            size.0 as u32,
+                            line_height,
+                            cache.rect_color(line_highlight),
+                        );
+                    }
+                }
+                f(
+                    -scroll_x,
+                    y,
+                    run.prefix_w as u32,
+                    line_height,
+                    cache.rect_color(self.gutter_color()),
+                );
+            }
+        }
         self.editor
             .draw(font_system, cache, self.foreground_color(), f);
     }
@@ -16,10 +16,48 @@ enum Mode {
     SearchBackwards,
 }
 
+/// A cursor movement in normal mode, which operators apply to
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Motion {
+    /// Perform an editor action
+    Action(Action),
+    /// Move to the start of the next word
+    WordStart,
+    /// Move to the start of a line, clamped to the last line
+    Line(usize),
+}
+
+/// An operator applied to the text covered by the following motion, or to whole lines when
+/// doubled
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operator {
+    Change,
+    Delete,
+    Yank,
+}
+
+/// Text yanked or deleted by an operator
+#[derive(Clone, Debug)]
+struct Register {
+    text: String,
+    /// True if the text is whole lines, which are put on lines of their own
+    lines: bool,
+}
+
+/// A wrapper of [`SyntaxEditor`] with modal, vi-like key handling
+///
+/// Characters inserted with [`Action::Insert`] are commands in normal mode, such as counts,
+/// motions (`h`, `j`, `k`, `l`, `w`, `b`, `e`, `0`, `$`, `gg`, `G`), operators (`c`, `d`, `y`)
+/// that apply to the following motion, the visual selection, or whole lines when doubled, and
+/// putting yanked text with `p`. Other actions are passed to the editor.
 #[derive(Debug)]
 pub struct ViEditor<'a> {
     editor: SyntaxEditor<'a>,
     mode: Mode,
+    count_opt: Option<usize>,
+    operator_opt: Option<(Operator, usize)>,
+    pending_g: bool,
+    register_opt: Option<Register>,
 }
 
 impl<'a> ViEditor<'a> {
@@ -27,6 +65,10 @@ impl<'a> ViEditor<'a> {
         Self {
             editor,
             mode: Mode::Normal,
+            count_opt: None,
+            operator_opt: None,
+            pending_g: false,
+            register_opt: None,
         }
     }
 
@@ -41,6 +83,12 @@ impl<'a> ViEditor<'a> {
         self.editor.load_text(font_system, path, attrs)
     }
 
+    /// Modifies the theme, returning false if the theme is missing, see
+    /// [`SyntaxEditor::update_theme`]
+    pub fn update_theme(&mut self, theme_name: &str) -> bool {
+        self.editor.update_theme(theme_name)
+    }
+
     /// Get the default background color
     pub fn background_color(&self) -> Color {
         self.editor.background_color()
@@ -50,6 +98,369 @@ impl<'a> ViEditor<'a> {
     pub fn foreground_color(&self) -> Color {
         self.editor.foreground_color()
     }
+
+    /// Get the background color of the gutter
+    pub fn gutter_color(&self) -> Color {
+        self.editor.gutter_color()
+    }
+
+    /// Get the color of text in the gutter
+    pub fn gutter_foreground_color(&self) -> Color {
+        self.editor.gutter_foreground_color()
+    }
+
+    /// Undo the last change to the text, see [`crate::Editor::undo`]
+    pub fn undo(&mut self) -> bool {
+        self.editor.undo()
+    }
+
+    /// Redo the last change undone with [`Self::undo`], see [`crate::Editor::redo`]
+    pub fn redo(&mut self) -> bool {
+        self.editor.redo()
+    }
+
+    /// Get the text yanked or deleted by the last operator
+    pub fn register(&self) -> Option<&str> {
+        self.register_opt
+            .as_ref()
+            .map(|register| register.text.as_str())
+    }
+
+    /// Clear a pending count, operator, or `g` prefix
+    fn clear_pending(&mut self) {
+        self.count_opt = None;
+        self.operator_opt = None;
+        self.pending_g = false;
+    }
+
+    /// Handle a key in normal mode
+    fn normal(&mut self, font_system: &mut FontSystem, c: char) {
+        // Counts, where a leading zero is the motion to the start of the line
+        if let Some(digit) = c.to_digit(10) {
+            if digit > 0 || self.count_opt.is_some() {
+                let count = self.count_opt.unwrap_or(0);
+                self.count_opt = Some(count.saturating_mul(10).saturating_add(digit as usize));
+                return;
+            }
+        }
+        let count_opt = self.count_opt.take();
+        let count = count_opt.unwrap_or(1);
+
+        let pending_g = self.pending_g;
+        self.pending_g = false;
+        let motion_opt = match c {
+            'g' if pending_g => Some(Motion::Line(count_opt.map_or(0, |line| line - 1))),
+            'g' => {
+                // Wait for the second key of gg
+                self.count_opt = count_opt;
+                self.pending_g = true;
+                return;
+            }
+            _ if pending_g => {
+                self.clear_pending();
+                return;
+            }
+            'h' => Some(Motion::Action(Action::Left)),
+            'j' => Some(Motion::Action(Action::Down)),
+            'k' => Some(Motion::Action(Action::Up)),
+            'l' => Some(Motion::Action(Action::Right)),
+            'w' => Some(Motion::WordStart),
+            'b' => Some(Motion::Action(Action::PreviousWord)),
+            'e' => Some(Motion::Action(Action::NextWord)),
+            '0' => Some(Motion::Action(Action::Home)),
+            '$' => Some(Motion::Action(Action::End)),
+            //TODO: skip whitespace
+            '^' => Some(Motion::Action(Action::Home)),
+            'G' => Some(Motion::Line(count_opt.map_or(usize::MAX, |line| line - 1))),
+            _ => None,
+        };
+        if let Some(motion) = motion_opt {
+            match self.operator_opt.take() {
+                Some((operator, operator_count)) => {
+                    self.operate(font_system, operator, motion, operator_count * count);
+                }
+                None => self.motion(font_system, motion, count),
+            }
+            return;
+        }
+
+        let operator_opt = match c {
+            'c' => Some(Operator::Change),
+            'd' => Some(Operator::Delete),
+            'y' => Some(Operator::Yank),
+            _ => None,
+        };
+        if let Some(operator) = operator_opt {
+            if self.editor.select_opt().is_some() {
+                self.operate_selection(font_system, operator);
+            } else {
+                match self.operator_opt.take() {
+                    // A doubled operator works on whole lines
+                    Some((pending, operator_count)) if pending == operator => {
+                        self.operate_lines(font_system, operator, operator_count * count);
+                    }
+                    _ => self.operator_opt = Some((operator, count)),
+                }
+            }
+            return;
+        }
+
+        // Other keys cancel a pending operator
+        if self.operator_opt.take().is_some() {
+            return;
+        }
+
+        match c {
+            // Enter insert mode after cursor
+            'a' => {
+                self.editor.action(font_system, Action::Right);
+                self.mode = Mode::Insert;
+            }
+            // Enter insert mode at end of line
+            'A' => {
+                self.editor.action(font_system, Action::End);
+                self.mode = Mode::Insert;
+            }
+            // Enter insert mode at cursor
+            'i' => {
+                self.mode = Mode::Insert;
+            }
+            // Enter insert mode at start of line
+            'I' => {
+                //TODO: soft home, skip whitespace
+                self.editor.action(font_system, Action::Home);
+                self.mode = Mode::Insert;
+            }
+            // Create line after and enter insert mode
+            'o' => {
+                self.editor.action(font_system, Action::End);
+                self.editor.action(font_system, Action::Enter);
+                self.mode = Mode::Insert;
+            }
+            // Create line before and enter insert mode
+            'O' => {
+                self.editor.action(font_system, Action::Home);
+                self.editor.action(font_system, Action::Enter);
+                self.editor.shape_as_needed(font_system); // TODO: do not require this?
+                self.editor.action(font_system, Action::Up);
+                self.mode = Mode::Insert;
+            }
+            // Top of screen
+            //TODO: 'H' => self.editor.action(Action::ScreenHigh),
+            // Bottom of screen
+            //TODO: 'L' => self.editor.action(Action::ScreenLow),
+            // Middle of screen
+            //TODO: 'M' => self.editor.action(Action::ScreenMiddle),
+            // Enter visual mode
+            'v' => {
+                if self.editor.select_opt().is_some() {
+                    self.editor.set_select_opt(None);
+                } else {
+                    self.editor.set_select_opt(Some(self.editor.cursor()));
+                }
+            }
+            // Enter line visual mode
+            'V' => {
+                if self.editor.select_opt().is_some() {
+                    self.editor.set_select_opt(None);
+                } else {
+                    self.editor.action(font_system, Action::Home);
+                    self.editor.set_select_opt(Some(self.editor.cursor()));
+                    //TODO: set cursor_x_opt to max
+                    self.editor.action(font_system, Action::End);
+                }
+            }
+            // Remove characters at cursor
+            'x' => {
+                for _ in 0..count {
+                    self.editor.action(font_system, Action::Delete);
+                }
+            }
+            // Remove characters before cursor
+            'X' => {
+                for _ in 0..count {
+                    self.editor.action(font_system, Action::Backspace);
+                }
+            }
+            // Put the register after the cursor, or below the line if it holds whole lines
+            'p' => self.put(font_system, true),
+            // Put the register before the cursor, or above the line if it holds whole lines
+            'P' => self.put(font_system, false),
+            // Undo
+            'u' => {
+                for _ in 0..count {
+                    self.editor.action(font_system, Action::Undo);
+                }
+            }
+            // Enter command mode
+            ':' => {
+                self.mode = Mode::Command;
+            }
+            // Enter search mode
+            '/' => {
+                self.mode = Mode::Search;
+            }
+            // Enter search backwards mode
+            '?' => {
+                self.mode = Mode::SearchBackwards;
+            }
+            _ => (),
+        }
+    }
+
+    /// Shape and lay out the line of the cursor, which may be outside of the view
+    fn shape_cursor_line(&mut self, font_system: &mut FontSystem) {
+        let line_i = self.editor.cursor().line;
+        self.editor.buffer_mut().line_layout(font_system, line_i);
+    }
+
+    /// Move the cursor `count` times
+    fn motion(&mut self, font_system: &mut FontSystem, motion: Motion, count: usize) {
+        match motion {
+            Motion::Action(action) => {
+                for _ in 0..count {
+                    // Moving by layout lines needs the lines edited by earlier keys shaped
+                    self.shape_cursor_line(font_system);
+                    self.editor.action(font_system, action.clone());
+                }
+            }
+            Motion::WordStart => {
+                for _ in 0..count {
+                    let cursor = self.editor.cursor();
+                    let text = self.buffer().lines[cursor.line].text();
+                    let next_opt = text
+                        .unicode_word_indices()
+                        .map(|(i, _)| i)
+                        .find(|&i| i > cursor.index);
+                    let next = match next_opt {
+                        Some(index) => Cursor::new(cursor.line, index),
+                        None if cursor.line + 1 < self.buffer().lines.len() => {
+                            Cursor::new(cursor.line + 1, 0)
+                        }
+                        None => Cursor::new(cursor.line, text.len()),
+                    };
+                    self.editor.set_cursor(next);
+                }
+            }
+            Motion::Line(line) => {
+                let line = cmp::min(line, self.buffer().lines.len() - 1);
+                self.editor.set_cursor(Cursor::new(line, 0));
+            }
+        }
+    }
+
+    /// Apply an operator to the text between the cursor and where `motion` moves it
+    fn operate(
+        &mut self,
+        font_system: &mut FontSystem,
+        operator: Operator,
+        motion: Motion,
+        count: usize,
+    ) {
+        let anchor = self.editor.cursor();
+        self.motion(font_system, motion, count);
+        let cursor = self.editor.cursor();
+        if (cursor.line, cursor.index) != (anchor.line, anchor.index) {
+            self.editor.set_select_opt(Some(anchor));
+            self.operate_selection(font_system, operator);
+        } else if operator == Operator::Change {
+            self.mode = Mode::Insert;
+        }
+    }
+
+    /// Apply an operator to the selection, leaving the cursor at its start
+    fn operate_selection(&mut self, font_system: &mut FontSystem, operator: Operator) {
+        if let Some(text) = self.editor.copy_selection() {
+            self.register_opt = Some(Register { text, lines: false });
+        }
+        match operator {
+            Operator::Change => {
+                self.editor.action(font_system, Action::Delete);
+                self.mode = Mode::Insert;
+            }
+            Operator::Delete => self.editor.action(font_system, Action::Delete),
+            Operator::Yank => {
+                if let Some((start, _)) = self.editor.selection() {
+                    self.editor.set_select_opt(None);
+                    self.editor.set_cursor(start);
+                }
+            }
+        }
+    }
+
+    /// Apply an operator to `count` whole lines from the cursor
+    fn operate_lines(&mut self, font_system: &mut FontSystem, operator: Operator, count: usize) {
+        let lines = self.buffer().lines.len();
+        let first = self.editor.cursor().line;
+        let last = cmp::min(first.saturating_add(count), lines) - 1;
+        let last_end = Cursor::new(last, self.buffer().lines[last].text().len());
+
+        let mut text = String::new();
+        for line in self.buffer().lines[first..=last].iter() {
+            text.push_str(line.text());
+            text.push('\n');
+        }
+        self.register_opt = Some(Register { text, lines: true });
+
+        match operator {
+            Operator::Change => {
+                self.editor.set_cursor(last_end);
+                self.editor.set_select_opt(Some(Cursor::new(first, 0)));
+                self.editor.action(font_system, Action::Delete);
+                self.mode = Mode::Insert;
+            }
+            Operator::Delete => {
+                // Delete the line endings too, from the end of the line before for the last lines
+                let (start, end) = if last + 1 < lines {
+                    (Cursor::new(first, 0), Cursor::new(last + 1, 0))
+                } else if first > 0 {
+                    let before =
+                        Cursor::new(first - 1, self.buffer().lines[first - 1].text().len());
+                    (before, last_end)
+                } else {
+                    (Cursor::new(first, 0), last_end)
+                };
+                self.editor.set_cursor(end);
+                self.editor.set_select_opt(Some(start));
+                self.editor.action(font_system, Action::Delete);
+                self.editor.action(font_system, Action::Home);
+            }
+            Operator::Yank => {
+                self.editor.set_cursor(Cursor::new(first, 0));
+            }
+        }
+    }
+
+    /// Put the register after or before the cursor
+    fn put(&mut self, font_system: &mut FontSystem, after: bool) {
+        let register = match self.register_opt.clone() {
+            Some(some) => some,
+            None => return,
+        };
+        let cursor = self.editor.cursor();
+        if register.lines {
+            let text = register.text.strip_suffix('\n').unwrap_or(&register.text);
+            if after {
+                let mut data = String::from("\n");
+                data.push_str(text);
+                self.editor.action(font_system, Action::End);
+                self.editor.action(font_system, Action::Paste(data));
+                self.editor.set_cursor(Cursor::new(cursor.line + 1, 0));
+            } else {
+                let mut data = String::from(text);
+                data.push('\n');
+                self.editor.action(font_system, Action::Home);
+                self.editor.action(font_system, Action::Paste(data));
+                self.editor.set_cursor(Cursor::new(cursor.line, 0));
+            }
+        } else {
+            if after && cursor.index < self.buffer().lines[cursor.line].text().len() {
+                self.editor.action(font_system, Action::Right);
+            }
+            self.editor
+                .action(font_system, Action::Paste(register.text));
+        }
+    }
 }
 
 impl<'a> Edit for ViEditor<'a> {
@@ -81,7 +492,7 @@ impl<'a> Edit for ViEditor<'a> {
         self.editor.shape_as_needed(font_system);
     }
 
-    fn copy_selection(&mut self) -> Option<String> {
+    fn copy_selection(&self) -> Option<String> {
         self.editor.copy_selection()
     }
 
@@ -98,120 +509,15 @@ impl<'a> Edit for ViEditor<'a> {
 
         match self.mode {
             Mode::Normal => match action {
-                Action::Insert(c) => match c {
-                    // Enter insert mode after cursor
-                    'a' => {
-                        self.editor.action(font_system, Action::Right);
-                        self.mode = Mode::Insert;
-                    }
-                    // Enter insert mode at end of line
-                    'A' => {
-                        self.editor.action(font_system, Action::End);
-                        self.mode = Mode::Insert;
-                    }
-                    // Change mode
-                    'c' => {
-                        if self.editor.select_opt().is_some() {
-                            self.editor.action(font_system, Action::Delete);
-                            self.mode = Mode::Insert;
-                        } else {
-                            //TODO: change to next cursor movement
-                        }
-                    }
-                    // Delete mode
-                    'd' => {
-                        if self.editor.select_opt().is_some() {
-                            self.editor.action(font_system, Action::Delete);
-                        } else {
-                            //TODO: delete to next cursor movement
-                        }
-                    }
-                    // Enter insert mode at cursor
-                    'i' => {
-                        self.mode = Mode::Insert;
-                    }
-                    // Enter insert mode at start of line
-                    'I' => {
-                        //TODO: soft home, skip whitespace
-                        self.editor.action(font_system, Action::Home);
-                        self.mode = Mode::Insert;
-                    }
-                    // Create line after and enter insert mode
-                    'o' => {
-                        self.editor.action(font_system, Action::End);
-                        self.editor.action(font_system, Action::Enter);
-                        self.mode = Mode::Insert;
-                    }
-                    // Create line before and enter insert mode
-                    'O' => {
-                        self.editor.action(font_system, Action::Home);
-                        self.editor.action(font_system, Action::Enter);
-                        self.editor.shape_as_needed(font_system); // TODO: do not require this?
-                        self.editor.action(font_system, Action::Up);
-                        self.mode = Mode::Insert;
-                    }
-                    // Left
-                    'h' => self.editor.action(font_system, Action::Left),
-                    // Top of screen
-                    //TODO: 'H' => self.editor.action(Action::ScreenHigh),
-                    // Down
-                    'j' => self.editor.action(font_system, Action::Down),
-                    // Up
-                    'k' => self.editor.action(font_system, Action::Up),
-                    // Right
-                    'l' => self.editor.action(font_system, Action::Right),
-                    // Bottom of screen
-                    //TODO: 'L' => self.editor.action(Action::ScreenLow),
-                    // Middle of screen
-                    //TODO: 'M' => self.editor.action(Action::ScreenMiddle),
-                    // Enter visual mode
-                    'v' => {
-                        if self.editor.select_opt().is_some() {
-                            self.editor.set_select_opt(None);
-                        } else {
-                            self.editor.set_select_opt(Some(self.editor.cursor()));
-                        }
-                    }
-                    // Enter line visual mode
-                    'V' => {
-                        if self.editor.select_opt().is_some() {
-                            self.editor.set_select_opt(None);
-                        } else {
-                            self.editor.action(font_system, Action::Home);
-                            self.editor.set_select_opt(Some(self.editor.cursor()));
-                            //TODO: set cursor_x_opt to max
-                            self.editor.action(font_system, Action::End);
-                        }
-                    }
-                    // Remove character at cursor
-                    'x' => self.editor.action(font_system, Action::Delete),
-                    // Remove character before cursor
-                    'X' => self.editor.action(font_system, Action::Backspace),
-                    // Go to start of line
-                    '0' => self.editor.action(font_system, Action::Home),
-                    // Go to end of line
-                    '$' => self.editor.action(font_system, Action::End),
-                    // Go to start of line after whitespace
-                    //TODO: implement this
-                    '^' => self.editor.action(font_system, Action::Home),
-                    // Enter command mode
-                    ':' => {
-                        self.mode = Mode::Command;
-                    }
-                    // Enter search mode
-                    '/' => {
-                        self.mode = Mode::Search;
-                    }
-                    // Enter search backwards mode
-                    '?' => {
-                        self.mode = Mode::SearchBackwards;
-                    }
-                    _ => (),
-                },
-                _ => self.editor.action(font_system, action),
+                Action::Insert(c) => self.normal(font_system, c),
+                _ => {
+                    self.clear_pending();
+                    self.editor.action(font_system, action);
+                }
             },
             Mode::Insert => match action {
                 Action::Escape => {
+                    self.shape_cursor_line(font_system);
                     let cursor = self.cursor();
                     let layout_cursor = self.buffer().layout_cursor(&cursor);
                     if layout_cursor.glyph > 0 {
@@ -242,6 +548,11 @@ impl<'a> Edit for ViEditor<'a> {
     ) where
         F: FnMut(i32, i32, u32, u32, Color),
     {
+        let color = self.buffer().default_color().unwrap_or(color);
+        let highlight_color = cache.rect_color(Color::rgba(color.r(), color.g(), color.b(), 0x33));
+        let cursor_color = cache.rect_color(color);
+        let scroll_x = libm::roundf(self.buffer().scroll_x()) as i32;
+        let mut f = |x, y, w, h, color| f(x - scroll_x, y, w, h, color);
         let font_size = self.buffer().metrics().font_size;
         let line_height = self.buffer().metrics().line_height;
 
@@ -287,21 +598,7 @@ impl<'a> Edit for ViEditor<'a> {
             };
 
             // Highlight selection (TODO: HIGHLIGHT COLOR!)
-            if let Some(select) = self.select_opt() {
-                let (start, end) = match select.line.cmp(&self.cursor().line) {
-                    cmp::Ordering::Greater => (self.cursor(), select),
-                    cmp::Ordering::Less => (select, self.cursor()),
-                    cmp::Ordering::Equal => {
-                        /* select.line == self.cursor.line */
-                        if select.index < self.cursor().index {
-                            (select, self.cursor())
-                        } else {
-                            /* select.index >= self.cursor.index */
-                            (self.cursor(), select)
-                        }
-                    }
-                };
-
+            if let Some((start, end)) = self.selection() {
                 if line_i >= start.line && line_i <= end.line {
                     let mut range_opt = None;
                     for glyph in run.glyphs.iter() {
@@ -329,7 +626,7 @@ impl<'a> Edit for ViEditor<'a> {
                                     (line_y - font_size) as i32,
                                     cmp::max(0, max - min) as u32,
                                     line_height as u32,
-                                    Color::rgba(color.r(), color.g(), color.b(), 0x33),
+                                    highlight_color,
                                 );
                             }
                             c_x += c_w;
@@ -338,7 +635,7 @@ impl<'a> Edit for ViEditor<'a> {
 
                     if run.glyphs.is_empty() && end.line > line_i {
                         // Highlight all of internal empty lines
-                        range_opt = Some((0, self.buffer().size().0 as i32));
+                        range_opt = Some((0, self.buffer().size().0 as i32 + scroll_x));
                     }
 
                     if let Some((mut min, mut max)) = range_opt.take() {
@@ -347,7 +644,7 @@ impl<'a> Edit for ViEditor<'a> {
                             if run.rtl {
                                 min = 0;
                             } else {
-                                max = self.buffer().size().0 as i32;
+                                max = self.buffer().size().0 as i32 + scroll_x;
                             }
                         }
                         f(
@@ -355,7 +652,7 @@ impl<'a> Edit for ViEditor<'a> {
                             (line_y - font_size) as i32,
                             cmp::max(0, max - min) as u32,
                             line_height as u32,
-                            Color::rgba(color.r(), color.g(), color.b(), 0x33),
+                            highlight_color,
                         );
                     }
                 }
@@ -400,8 +697,8 @@ impl<'a> Edit for ViEditor<'a> {
                             }
                         }
                         None => {
-                            // Start of empty line
-                            (0, cursor_glyph_width as i32)
+                            // Start of empty line, after any prefix
+                            (run.prefix_w as i32, cursor_glyph_width as i32)
                         }
                     },
                 };
@@ -414,7 +711,7 @@ impl<'a> Edit for ViEditor<'a> {
                         (line_y - font_size) as i32,
                         (right_x - left_x) as u32,
                         line_height as u32,
-                        Color::rgba(color.r(), color.g(), color.b(), 0x33),
+                        highlight_color,
                     );
                 } else {
                     f(
@@ -422,12 +719,26 @@ impl<'a> Edit for ViEditor<'a> {
                         (line_y - font_size) as i32,
                         1,
                         line_height as u32,
-                        color,
+                        cursor_color,
                     );
                 }
             }
 
-            for glyph in run.glyphs.iter() {
+            let tab_leader_glyphs = run.tab_leader_glyphs();
+
+            for glyph in run
+                .glyphs
+                .iter()
+                .chain(run.ruby_glyphs.iter())
+                .chain(run.prefix_glyphs.iter())
+                .chain(run.wrap_indicator_glyphs.iter())
+                .chain(tab_leader_glyphs.iter())
+            {
+                if glyph.inline_box_opt.is_some() {
+                    // Inline boxes are filled in by the renderer
+                    continue;
+                }
+
                 let physical_glyph = glyph.physical((0., 0.), 1.0);
 
                 let glyph_color = match glyph.color_opt {
@@ -450,6 +761,10 @@ impl<'a> Edit for ViEditor<'a> {
                     },
                 );
             }
+
+            run.draw_underlines(color, |x, y, w, h, color| {
+                f(x, y, w, h, cache.rect_color(color));
+            });
         }
     }
 }
@@ -6,9 +6,10 @@ use alloc::vec::Vec;
 use fontdb::Family;
 use unicode_script::Script;
 
+use crate::shape::ShapeRunCache;
 use crate::{Font, FontSystem};
 
-use self::platform::*;
+use self::platform::{common_fallback, forbidden_fallback, script_fallback};
 
 #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows",)))]
 #[path = "other.rs"]
@@ -31,15 +32,54 @@ use log::debug as missing_warn;
 #[cfg(feature = "warn_on_missing_glyphs")]
 use log::warn as missing_warn;
 
+/// The families tried for glyphs that are missing from the requested family, see
+/// [`FontSystem::set_fallback`]
+///
+/// Families are tried in order: those for each script of the text, then the common ones. If none
+/// of them has the glyphs, every other font that is not forbidden is scanned.
+pub trait Fallback: Send + Sync {
+    /// Get the families to use after any script specific fallbacks
+    fn common_fallback(&self) -> &[&str];
+
+    /// Get the families to never use
+    fn forbidden_fallback(&self) -> &[&str];
+
+    /// Get the families to use for `script`, which may depend on the `locale`
+    fn script_fallback(&self, script: Script, locale: &str) -> &[&str];
+}
+
+/// The default [`Fallback`], with families that are commonly installed on the target platform
+///
+/// There are no preset families on platforms other than Linux, macOS, and Windows.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlatformFallback;
+
+impl Fallback for PlatformFallback {
+    fn common_fallback(&self) -> &[&str] {
+        common_fallback()
+    }
+
+    fn forbidden_fallback(&self) -> &[&str] {
+        forbidden_fallback()
+    }
+
+    fn script_fallback(&self, script: Script, locale: &str) -> &[&str] {
+        script_fallback(script, locale)
+    }
+}
+
 pub struct FontFallbackIter<'a> {
     font_system: &'a mut FontSystem,
+    fallback: Arc<dyn Fallback>,
     font_ids: &'a [fontdb::ID],
     default_families: &'a [&'a Family<'a>],
     default_i: usize,
     scripts: &'a [Script],
     script_i: (usize, usize),
     common_i: usize,
+    other_ids: Vec<fontdb::ID>,
     other_i: usize,
+    primary_done: bool,
     end: bool,
 }
 
@@ -51,6 +91,7 @@ impl<'a> FontFallbackIter<'a> {
         scripts: &'a [Script],
     ) -> Self {
         Self {
+            fallback: font_system.shared_fallback(),
             font_system,
             font_ids,
             default_families,
@@ -58,13 +99,22 @@ impl<'a> FontFallbackIter<'a> {
             scripts,
             script_i: (0, 0),
             common_i: 0,
+            other_ids: Vec::new(),
             other_i: 0,
+            primary_done: false,
             end: false,
         }
     }
 
     pub fn check_missing(&mut self, word: &str) {
-        if self.end {
+        if self.end && !self.font_system.fallback_enabled() {
+            missing_warn!(
+                "Fallback is disabled, drawing notdef for {:?} locale '{}': '{}'",
+                self.scripts,
+                self.font_system.locale(),
+                word
+            );
+        } else if self.end {
             missing_warn!(
                 "Failed to find any fallback for {:?} locale '{}': '{}'",
                 self.scripts,
@@ -76,11 +126,11 @@ impl<'a> FontFallbackIter<'a> {
                 "Failed to find preset fallback for {:?} locale '{}', used '{}': '{}'",
                 self.scripts,
                 self.font_system.locale(),
-                self.face_name(self.font_ids[self.other_i - 1]),
+                self.face_name(self.other_ids[self.other_i - 1]),
                 word
             );
         } else if !self.scripts.is_empty() && self.common_i > 0 {
-            let family = common_fallback()[self.common_i - 1];
+            let family = self.fallback.common_fallback()[self.common_i - 1];
             missing_warn!(
                 "Failed to find script fallback for {:?} locale '{}', used '{}': '{}'",
                 self.scripts,
@@ -91,6 +141,11 @@ impl<'a> FontFallbackIter<'a> {
         }
     }
 
+    /// Get the shape run cache of the [`FontSystem`]
+    pub(crate) fn shape_run_cache(&mut self) -> &mut ShapeRunCache {
+        self.font_system.shape_run_cache()
+    }
+
     pub fn face_name(&self, id: fontdb::ID) -> &str {
         if let Some(face) = self.font_system.db().face(id) {
             if let Some((name, _)) = face.families.first() {
@@ -115,6 +170,19 @@ impl<'a> FontFallbackIter<'a> {
 impl<'a> Iterator for FontFallbackIter<'a> {
     type Item = Arc<Font>;
     fn next(&mut self) -> Option<Self::Item> {
+        // Only the primary font is used when fallback is disabled
+        if self.primary_done && !self.font_system.fallback_enabled() {
+            self.end = true;
+            return None;
+        }
+        let font = self.next_font();
+        self.primary_done = true;
+        font
+    }
+}
+
+impl<'a> FontFallbackIter<'a> {
+    fn next_font(&mut self) -> Option<Arc<Font>> {
         while self.default_i < self.default_families.len() {
             self.default_i += 1;
             let mut monospace_fallback = None;
@@ -147,13 +215,15 @@ impl<'a> Iterator for FontFallbackIter<'a> {
         while self.script_i.0 < self.scripts.len() {
             let script = self.scripts[self.script_i.0];
 
-            let script_families = script_fallback(script, self.font_system.locale());
+            let script_families = self
+                .fallback
+                .script_fallback(script, self.font_system.locale());
             while self.script_i.1 < script_families.len() {
                 let script_family = script_families[self.script_i.1];
                 self.script_i.1 += 1;
                 for id in self.font_ids.iter() {
                     if self.face_contains_family(*id, script_family) {
-                        if let Some(font) = self.font_system.get_font(*id) {
+                        if let Some(font) = self.font_system.get_fallback_font(*id) {
                             return Some(font);
                         }
                     }
@@ -170,13 +240,13 @@ impl<'a> Iterator for FontFallbackIter<'a> {
             self.script_i.1 = 0;
         }
 
-        let common_families = common_fallback();
+        let common_families = self.fallback.common_fallback();
         while self.common_i < common_families.len() {
             let common_family = common_families[self.common_i];
             self.common_i += 1;
             for id in self.font_ids.iter() {
                 if self.face_contains_family(*id, common_family) {
-                    if let Some(font) = self.font_system.get_font(*id) {
+                    if let Some(font) = self.font_system.get_fallback_font(*id) {
                         return Some(font);
                     }
                 }
@@ -186,15 +256,32 @@ impl<'a> Iterator for FontFallbackIter<'a> {
 
         //TODO: do we need to do this?
         //TODO: do not evaluate fonts more than once!
-        let forbidden_families = forbidden_fallback();
-        while self.other_i < self.font_ids.len() {
-            let id = self.font_ids[self.other_i];
+        if self.other_i == 0 && self.other_ids.is_empty() {
+            // Try fonts that are already loaded first, as they are likely to have been used for
+            // fallback before and are cheap to evaluate
+            self.other_ids = self.font_ids.to_vec();
+            let font_system = &self.font_system;
+            self.other_ids
+                .sort_by_key(|id| !font_system.is_font_loaded(*id));
+        }
+        let forbidden_families = self.fallback.forbidden_fallback();
+        let scan_limit = self.font_system.fallback_scan_limit();
+        while self.other_i < self.other_ids.len() {
+            if self.other_i >= scan_limit {
+                log::debug!(
+                    "fallback scan limit of {} fonts reached for {:?}",
+                    scan_limit,
+                    self.scripts
+                );
+                break;
+            }
+            let id = self.other_ids[self.other_i];
             self.other_i += 1;
             if forbidden_families
                 .iter()
                 .all(|family_name| !self.face_contains_family(id, family_name))
             {
-                if let Some(font) = self.font_system.get_font(id) {
+                if let Some(font) = self.font_system.get_fallback_font(id) {
                     return Some(font);
                 }
             }
@@ -1,4 +1,5 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
+pub use self::fallback::{Fallback, PlatformFallback};
 pub(crate) mod fallback;
 
 use alloc::boxed::Box;
@@ -133,4 +134,12 @@ impl Font {
         }
         .try_build()
     }
+
+    /// Get the italic angle of the font, in degrees counter-clockwise from vertical, as stored in
+    /// its `post` table
+    ///
+    /// This is negative for fonts leaning to the right, and zero for upright fonts.
+    pub fn italic_angle(&self) -> f32 {
+        self.rustybuzz().italic_angle().unwrap_or(0.0)
+    }
 }
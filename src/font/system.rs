@@ -1,22 +1,109 @@
-use crate::{Attrs, AttrsOwned, Font};
+use crate::shape::{ShapeRunCache, DEFAULT_SHAPE_RUN_CACHE_LIMIT};
+use crate::{
+    Attrs, AttrsOwned, Buffer, Fallback, Font, Metrics, PlatformFallback, Shaping, Stretch, Style,
+    Variation, Weight,
+};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt;
 use core::ops::{Deref, DerefMut};
 
-type BuildHasher = core::hash::BuildHasherDefault<rustc_hash::FxHasher>;
+pub(crate) type BuildHasher = core::hash::BuildHasherDefault<rustc_hash::FxHasher>;
 
 #[cfg(feature = "std")]
-type HashMap<K, V> = std::collections::HashMap<K, V, BuildHasher>;
+pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V, BuildHasher>;
 #[cfg(not(feature = "std"))]
-type HashMap<K, V> = hashbrown::HashMap<K, V, BuildHasher>;
+pub(crate) type HashMap<K, V> = hashbrown::HashMap<K, V, BuildHasher>;
 
 // re-export fontdb and rustybuzz
 pub use fontdb;
 pub use rustybuzz;
 
+/// Find the faces of each family in `db` that best match `attrs`
+///
+/// Faces matching exactly are used where possible. Otherwise, the closest faces of the family
+/// are used, preferring the requested stretch, then style, then the closest weight in the order
+/// given by CSS font matching.
+fn font_matches(db: &fontdb::Database, attrs: Attrs) -> Vec<fontdb::ID> {
+    fn family_name(face: &fontdb::FaceInfo) -> &str {
+        match face.families.first() {
+            Some((name, _)) => name,
+            None => &face.post_script_name,
+        }
+    }
+
+    let match_key = |face: &fontdb::FaceInfo| -> (bool, bool, u8, u16) {
+        if attrs.matches(face) {
+            return (false, false, 0, 0);
+        }
+        let (rank, distance) = weight_rank(attrs.weight.0, face.weight.0);
+        (
+            face.stretch != attrs.stretch,
+            face.style != attrs.style,
+            rank,
+            distance,
+        )
+    };
+
+    let mut best_keys = BTreeMap::new();
+    for face in db.faces() {
+        let key = match_key(face);
+        best_keys
+            .entry(family_name(face))
+            .and_modify(|best| {
+                if key < *best {
+                    *best = key;
+                }
+            })
+            .or_insert(key);
+    }
+
+    db.faces()
+        .filter(|face| best_keys.get(family_name(face)) == Some(&match_key(face)))
+        .map(|face| face.id)
+        .collect()
+}
+
+/// Rank how well `weight` matches `requested`, lower is better
+fn weight_rank(requested: u16, weight: u16) -> (u8, u16) {
+    if weight == requested {
+        (0, 0)
+    } else if (400..=500).contains(&requested) {
+        // Heavier weights up to 500, then lighter weights, then heavier weights
+        if weight > requested && weight <= 500 {
+            (1, weight - requested)
+        } else if weight < requested {
+            (2, requested - weight)
+        } else {
+            (3, weight - requested)
+        }
+    } else if requested < 400 {
+        // Lighter weights, then heavier weights
+        if weight < requested {
+            (1, requested - weight)
+        } else {
+            (2, weight - requested)
+        }
+    } else if weight > requested {
+        // Heavier weights, then lighter weights
+        (1, weight - requested)
+    } else {
+        (2, requested - weight)
+    }
+}
+
+/// A hook called before a font is used for fallback, see [`FontSystem::set_font_load_hook`]
+pub type FontLoadHook = Box<dyn FnMut(&fontdb::FaceInfo) -> bool + Send + Sync>;
+
 /// Access to the system fonts.
+///
+/// Types that shape or rasterize text, like [`Buffer`] and `SwashCache`, take the font
+/// system as an argument instead of borrowing it, so they have no lifetime tied to it. The font
+/// system is `Send` and `Sync`: windows of an application can share one behind a lock such as a
+/// `Mutex`, and buffers can be shaped on a background thread and sent back to be drawn.
 pub struct FontSystem {
     /// The locale of the system.
     locale: String,
@@ -29,6 +116,27 @@ pub struct FontSystem {
 
     /// Cache for font matches.
     font_matches_cache: HashMap<AttrsOwned, Arc<Vec<fontdb::ID>>>,
+
+    /// Hook to allow or deny fallback fonts.
+    font_load_hook: Option<FontLoadHook>,
+
+    /// Families tried for glyphs missing from the requested family.
+    fallback: Arc<dyn Fallback>,
+
+    /// Maximum number of fonts scanned when no preset fallback has the needed glyphs.
+    fallback_scan_limit: usize,
+
+    /// Whether fonts other than the requested family are used for missing glyphs.
+    fallback_enabled: bool,
+
+    /// Cache of shaped runs, see [`Self::set_shape_run_cache_limit`].
+    shape_run_cache: ShapeRunCache,
+
+    /// Buffer kept for reuse by [`Self::scratch_buffer`].
+    scratch_buffer: Option<Buffer>,
+
+    /// Variation coordinates of shaped glyphs, see [`Self::variations_id`].
+    variations: Vec<Vec<Variation>>,
 }
 
 impl fmt::Debug for FontSystem {
@@ -48,6 +156,9 @@ impl FontSystem {
     /// This function takes some time to run. On the release build, it can take up to a second,
     /// while debug builds can take up to ten times longer. For this reason, it should only be
     /// called once, and the resulting [`FontSystem`] should be shared.
+    ///
+    /// On `wasm32`, no system fonts are loaded, so fonts must be provided with
+    /// [`Self::new_with_embedded_fonts`] or [`Self::db_mut`].
     pub fn new() -> Self {
         Self::new_with_fonts(core::iter::empty())
     }
@@ -59,11 +170,25 @@ impl FontSystem {
 
         let mut db = fontdb::Database::new();
         Self::load_fonts(&mut db, fonts.into_iter());
+        Self::set_default_families(&mut db);
 
-        //TODO: configurable default fonts
-        db.set_monospace_family("Fira Mono");
-        db.set_sans_serif_family("Fira Sans");
-        db.set_serif_family("DejaVu Serif");
+        Self::new_with_locale_and_db(locale, db)
+    }
+
+    /// Create a new [`FontSystem`] with only a pre-specified set of fonts, without loading any
+    /// installed system fonts
+    ///
+    /// This is useful for applications that bundle their fonts, such as games and web apps. More
+    /// fonts can be added later with [`Self::db_mut`].
+    pub fn new_with_embedded_fonts(fonts: impl IntoIterator<Item = fontdb::Source>) -> Self {
+        let locale = Self::get_locale();
+        log::debug!("Locale: {}", locale);
+
+        let mut db = fontdb::Database::new();
+        for source in fonts {
+            db.load_font_source(source);
+        }
+        Self::set_default_families(&mut db);
 
         Self::new_with_locale_and_db(locale, db)
     }
@@ -75,6 +200,13 @@ impl FontSystem {
             db,
             font_cache: HashMap::default(),
             font_matches_cache: HashMap::default(),
+            font_load_hook: None,
+            fallback: Arc::new(PlatformFallback),
+            fallback_scan_limit: usize::MAX,
+            fallback_enabled: true,
+            shape_run_cache: ShapeRunCache::new(DEFAULT_SHAPE_RUN_CACHE_LIMIT),
+            scratch_buffer: None,
+            variations: Vec::new(),
         }
     }
 
@@ -89,6 +221,9 @@ impl FontSystem {
     }
 
     /// Get a mutable reference to the database.
+    ///
+    /// Fonts can be added or removed at any time, and are used the next time text is shaped.
+    /// Lines that were already shaped keep their fonts, see [`Buffer::reset_shaping_all`].
     pub fn db_mut(&mut self) -> &mut fontdb::Database {
         self.font_matches_cache.clear();
         &mut self.db
@@ -99,6 +234,40 @@ impl FontSystem {
         (self.locale, self.db)
     }
 
+    /// Get the ID of a list of variation coordinates, as stored in [`crate::CacheKey`]
+    ///
+    /// Each distinct list gets its own ID, which stays valid as long as this [`FontSystem`]. The
+    /// empty list always has the ID 0.
+    pub fn variations_id(&mut self, variations: &[Variation]) -> u32 {
+        if variations.is_empty() {
+            return 0;
+        }
+
+        let i = match self
+            .variations
+            .iter()
+            .position(|existing| existing.as_slice() == variations)
+        {
+            Some(some) => some,
+            None => {
+                self.variations.push(variations.to_vec());
+                self.variations.len() - 1
+            }
+        };
+        i as u32 + 1
+    }
+
+    /// Get the variation coordinates with an ID from [`Self::variations_id`]
+    ///
+    /// Unknown IDs have no coordinates.
+    pub fn variations(&self, id: u32) -> &[Variation] {
+        (id as usize)
+            .checked_sub(1)
+            .and_then(|i| self.variations.get(i))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
     /// Get a font by its ID.
     pub fn get_font(&mut self, id: fontdb::ID) -> Option<Arc<Font>> {
         self.font_cache
@@ -120,6 +289,147 @@ impl FontSystem {
             .clone()
     }
 
+    /// Set a hook that is called before a font is used for fallback, when the requested family
+    /// is missing or lacks glyphs for some text
+    ///
+    /// The hook is given the face that fallback would use, and returns false to skip it. This
+    /// allows logging which fonts are pulled in by fallback, or denying fonts that need
+    /// permission to be loaded.
+    pub fn set_font_load_hook(&mut self, hook: FontLoadHook) {
+        self.font_load_hook = Some(hook);
+    }
+
+    /// Remove the hook set by [`Self::set_font_load_hook`]
+    pub fn clear_font_load_hook(&mut self) {
+        self.font_load_hook = None;
+    }
+
+    /// Get the families tried for glyphs missing from the requested family, see
+    /// [`Self::set_fallback`]
+    pub fn fallback(&self) -> &dyn Fallback {
+        &*self.fallback
+    }
+
+    /// Set the families tried for glyphs missing from the requested family, replacing the
+    /// [`PlatformFallback`] families
+    ///
+    /// This allows preferring bundled fonts, or choosing fallback fonts the same way on every
+    /// platform. Text that was already shaped keeps its fonts until it is shaped again.
+    pub fn set_fallback(&mut self, fallback: impl Fallback + 'static) {
+        self.fallback = Arc::new(fallback);
+    }
+
+    /// Get the fallback shared with each fallback iterator
+    pub(crate) fn shared_fallback(&self) -> Arc<dyn Fallback> {
+        self.fallback.clone()
+    }
+
+    /// Get the maximum number of fonts scanned for fallback, see
+    /// [`Self::set_fallback_scan_limit`]
+    pub fn fallback_scan_limit(&self) -> usize {
+        self.fallback_scan_limit
+    }
+
+    /// Set the maximum number of fonts scanned for fallback once the requested family and the
+    /// preset fallback families for the script are missing glyphs for some text
+    ///
+    /// Scanning every installed font can be slow on systems with thousands of fonts. Fonts that
+    /// are already loaded are tried first, and once `limit` fonts have been tried, the missing
+    /// glyphs are drawn as notdef. The default is [`usize::MAX`], scanning every font.
+    pub fn set_fallback_scan_limit(&mut self, limit: usize) {
+        self.fallback_scan_limit = limit;
+    }
+
+    /// Get whether font fallback is enabled, see [`Self::set_fallback_enabled`]
+    pub fn fallback_enabled(&self) -> bool {
+        self.fallback_enabled
+    }
+
+    /// Set whether font fallback is enabled
+    ///
+    /// When disabled, text is shaped only with the first font found for the requested family,
+    /// and glyphs missing from it are drawn as its notdef glyph. This gives the same rendering
+    /// regardless of the installed fonts, for example when testing a font. The default is true.
+    pub fn set_fallback_enabled(&mut self, enabled: bool) {
+        self.fallback_enabled = enabled;
+    }
+
+    /// Get an empty [`Buffer`] with the provided [`Metrics`] for shaping and measuring one-off text,
+    /// such as tooltips
+    ///
+    /// The buffer is returned to this [`FontSystem`] when the [`ScratchBuffer`] is dropped, and
+    /// its allocations are reused by the next call. Its settings are reset each time, and it is
+    /// separate from every other [`Buffer`].
+    pub fn scratch_buffer(&mut self, metrics: Metrics) -> ScratchBuffer<'_> {
+        let mut buffer = match self.scratch_buffer.take() {
+            Some(mut buffer) => {
+                buffer.recycle(metrics);
+                buffer
+            }
+            None => Buffer::new_empty(metrics),
+        };
+        buffer.set_text(self, "", Attrs::new(), Shaping::Advanced);
+        ScratchBuffer {
+            buffer: Some(buffer),
+            font_system: self,
+        }
+    }
+
+    /// Check if a font has already been loaded by [`Self::get_font`]
+    pub(crate) fn is_font_loaded(&self, id: fontdb::ID) -> bool {
+        matches!(self.font_cache.get(&id), Some(Some(_)))
+    }
+
+    /// Get the maximum number of shaped runs that are cached, see
+    /// [`Self::set_shape_run_cache_limit`]
+    pub fn shape_run_cache_limit(&self) -> usize {
+        self.shape_run_cache.limit()
+    }
+
+    /// Set the maximum number of shaped runs that are cached, dropping the least recently used
+    /// runs past it
+    ///
+    /// Runs of text shaped with the same font, direction, and script reuse the glyphs of the
+    /// first one, which speeds up text with many repeated words, like source code or tables. A
+    /// limit of 0 disables the cache. The default is 4096 runs.
+    pub fn set_shape_run_cache_limit(&mut self, limit: usize) {
+        self.shape_run_cache.set_limit(limit);
+    }
+
+    /// Drop every cached shaped run, see [`Self::set_shape_run_cache_limit`]
+    pub fn clear_shape_run_cache(&mut self) {
+        self.shape_run_cache.clear();
+    }
+
+    pub(crate) fn shape_run_cache(&mut self) -> &mut ShapeRunCache {
+        &mut self.shape_run_cache
+    }
+
+    /// Get a font for fallback, if allowed by the font load hook
+    pub(crate) fn get_fallback_font(&mut self, id: fontdb::ID) -> Option<Arc<Font>> {
+        if let Some(hook) = self.font_load_hook.as_mut() {
+            let face = self.db.face(id)?;
+            if !hook(face) {
+                log::debug!(
+                    "font load hook denied fallback to '{}'",
+                    face.post_script_name
+                );
+                return None;
+            }
+        }
+        self.get_font(id)
+    }
+
+    /// Get the weight, style, and stretch of a face, such as the face used for a
+    /// [`crate::LayoutGlyph`]
+    ///
+    /// These may differ from the requested [`Attrs`] when the font family has no face that
+    /// matches exactly, for example a weight of 500 may resolve to 400.
+    pub fn face_style(&self, id: fontdb::ID) -> Option<(Weight, Style, Stretch)> {
+        let face = self.db.face(id)?;
+        Some((face.weight, face.style, face.stretch))
+    }
+
     pub fn get_font_matches(&mut self, attrs: Attrs<'_>) -> Arc<Vec<fontdb::ID>> {
         self.font_matches_cache
             //TODO: do not create AttrsOwned unless entry does not already exist
@@ -128,12 +438,7 @@ impl FontSystem {
                 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
                 let now = std::time::Instant::now();
 
-                let ids = self
-                    .db
-                    .faces()
-                    .filter(|face| attrs.matches(face))
-                    .map(|face| face.id)
-                    .collect::<Vec<_>>();
+                let ids = font_matches(&self.db, attrs);
 
                 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
                 {
@@ -146,6 +451,13 @@ impl FontSystem {
             .clone()
     }
 
+    fn set_default_families(db: &mut fontdb::Database) {
+        //TODO: configurable default fonts
+        db.set_monospace_family("Fira Mono");
+        db.set_sans_serif_family("Fira Sans");
+        db.set_serif_family("DejaVu Serif");
+    }
+
     #[cfg(feature = "std")]
     fn get_locale() -> String {
         sys_locale::get_locale().unwrap_or_else(|| {
@@ -167,6 +479,8 @@ impl FontSystem {
         #[cfg(target_os = "redox")]
         db.load_fonts_dir("/ui/fonts");
 
+        // There are no system fonts to load in the browser
+        #[cfg(not(target_arch = "wasm32"))]
         db.load_system_fonts();
 
         for source in fonts {
@@ -189,6 +503,52 @@ impl FontSystem {
     }
 }
 
+/// A temporary [`Buffer`] borrowed from a [`FontSystem`], see [`FontSystem::scratch_buffer`]
+#[derive(Debug)]
+pub struct ScratchBuffer<'a> {
+    buffer: Option<Buffer>,
+    font_system: &'a mut FontSystem,
+}
+
+impl<'a> ScratchBuffer<'a> {
+    /// Borrow the buffer together with the [`FontSystem`] for more convenient methods
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the buffer was already returned to the [`FontSystem`], which only happens
+    /// when the scratch buffer is dropped.
+    pub fn borrow(&mut self) -> BorrowedWithFontSystem<'_, Buffer> {
+        BorrowedWithFontSystem {
+            inner: self.buffer.as_mut().expect("scratch buffer not found"),
+            font_system: self.font_system,
+        }
+    }
+}
+
+impl<'a> Deref for ScratchBuffer<'a> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.as_ref().expect("scratch buffer not found")
+    }
+}
+
+impl<'a> DerefMut for ScratchBuffer<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer.as_mut().expect("scratch buffer not found")
+    }
+}
+
+impl<'a> Drop for ScratchBuffer<'a> {
+    fn drop(&mut self) {
+        if let Some(mut buffer) = self.buffer.take() {
+            // Drop the shaped text, keeping the allocations for the next scratch buffer
+            buffer.lines.clear();
+            self.font_system.scratch_buffer = Some(buffer);
+        }
+    }
+}
+
 /// A value borrowed together with an [`FontSystem`]
 #[derive(Debug)]
 pub struct BorrowedWithFontSystem<'a, T> {
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{
+    Attrs, AttrsList, AttrsOwned, BidiParagraphs, BufferLine, FontSystem, HashMap, LayoutLine,
+    Metrics, Shaping, Wrap,
+};
+
+/// Default for [`LabelCache::max_len`]
+const DEFAULT_MAX_LEN: usize = 1024;
+
+/// A cached label of some text
+#[derive(Debug)]
+struct LabelEntry {
+    attrs: AttrsOwned,
+    font_size_bits: u32,
+    line_height_bits: u32,
+    width_bits: u32,
+    label: Arc<Label>,
+    /// Value of [`LabelCache::age`] when the label was last requested
+    last_used: u64,
+}
+
+/// A shaped and laid out label, see [`LabelCache`]
+#[derive(Debug)]
+pub struct Label {
+    /// The metrics the label was laid out with
+    pub metrics: Metrics,
+    /// [`BufferLine`]s (or paragraphs) of the label, with shaping and layout already done
+    pub lines: Vec<BufferLine>,
+    /// Width of the widest layout line
+    pub width: f32,
+    /// Height of all layout lines, using the line height from [`Metrics`]
+    pub height: f32,
+}
+
+impl Label {
+    /// Get the layout lines of all paragraphs in the label, in order
+    pub fn layout_lines(&self) -> impl Iterator<Item = &LayoutLine> {
+        self.lines
+            .iter()
+            .flat_map(|line| line.layout_opt().as_deref().unwrap_or_default())
+    }
+}
+
+/// Cache for shaping and laying out static text, such as menu items and button labels
+///
+/// Labels are keyed by their text, attributes, metrics, and width, so repeatedly requesting the
+/// same label returns the same shared result without shaping it again. When there are more than
+/// [`Self::max_len`] labels, the least recently requested one is removed.
+pub struct LabelCache {
+    cache: HashMap<String, Vec<LabelEntry>>,
+    len: usize,
+    max_len: usize,
+    age: u64,
+}
+
+impl fmt::Debug for LabelCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LabelCache")
+            .field("len", &self.len)
+            .field("max_len", &self.max_len)
+            .finish()
+    }
+}
+
+impl Default for LabelCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LabelCache {
+    /// Create a new label cache
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::default(),
+            len: 0,
+            max_len: DEFAULT_MAX_LEN,
+            age: 0,
+        }
+    }
+
+    /// Get the maximum number of cached labels, see [`Self::set_max_len`]
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Set the maximum number of cached labels, removing the least recently requested labels
+    /// if there are more
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+        while self.len > self.max_len {
+            self.remove_oldest();
+        }
+    }
+
+    /// Get a shaped and laid out label, shaping it only if it is not already cached
+    pub fn get(
+        &mut self,
+        font_system: &mut FontSystem,
+        text: &str,
+        attrs: Attrs,
+        metrics: Metrics,
+        width: f32,
+    ) -> Arc<Label> {
+        self.age += 1;
+        let font_size_bits = metrics.font_size.to_bits();
+        let line_height_bits = metrics.line_height.to_bits();
+        let width_bits = width.to_bits();
+
+        // Labels are found by the text without allocating a key
+        if let Some(entries) = self.cache.get_mut(text) {
+            if let Some(entry) = entries.iter_mut().find(|entry| {
+                entry.font_size_bits == font_size_bits
+                    && entry.line_height_bits == line_height_bits
+                    && entry.width_bits == width_bits
+                    && entry.attrs.as_attrs() == attrs
+            }) {
+                entry.last_used = self.age;
+                return entry.label.clone();
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut label_width: f32 = 0.0;
+        let mut layout_lines = 0;
+        for paragraph in BidiParagraphs::new(text) {
+            let mut line = BufferLine::new(paragraph, AttrsList::new(attrs), Shaping::Advanced);
+            for layout_line in line.layout(font_system, metrics.font_size, width, Wrap::Word) {
+                label_width = label_width.max(layout_line.w);
+                layout_lines += 1;
+            }
+            lines.push(line);
+        }
+
+        let label = Arc::new(Label {
+            metrics,
+            lines,
+            width: label_width,
+            height: layout_lines as f32 * metrics.line_height,
+        });
+        self.cache
+            .entry(text.to_string())
+            .or_default()
+            .push(LabelEntry {
+                attrs: AttrsOwned::new(attrs),
+                font_size_bits,
+                line_height_bits,
+                width_bits,
+                label: label.clone(),
+                last_used: self.age,
+            });
+        self.len += 1;
+        while self.len > self.max_len {
+            self.remove_oldest();
+        }
+        label
+    }
+
+    /// Remove the least recently requested label
+    fn remove_oldest(&mut self) {
+        let oldest_opt = self
+            .cache
+            .iter()
+            .flat_map(|(text, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, entry)| (entry.last_used, text, i))
+            })
+            .min_by_key(|(last_used, _, _)| *last_used)
+            .map(|(_, text, i)| (text.clone(), i));
+        let (text, i) = match oldest_opt {
+            Some(some) => some,
+            None => return,
+        };
+        if let Some(entries) = self.cache.get_mut(&text) {
+            entries.remove(i);
+            if entries.is_empty() {
+                self.cache.remove(&text);
+            }
+            self.len -= 1;
+        }
+    }
+
+    /// Get the number of cached labels
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if there are no cached labels
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all cached labels
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.len = 0;
+    }
+}
@@ -5,10 +5,10 @@ use core::fmt::Display;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-use crate::{CacheKey, Color};
+use crate::{CacheKey, Color, InlineBox, UnderlineStyle};
 
 /// A laid out glyph
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct LayoutGlyph {
     /// Start index of cluster in original line
     pub start: usize,
@@ -20,6 +20,8 @@ pub struct LayoutGlyph {
     pub font_id: fontdb::ID,
     /// Font id of the glyph
     pub glyph_id: u16,
+    /// Variation coordinates of the font, see [`crate::FontSystem::variations`]
+    pub variations_id: u32,
     /// X offset of hitbox
     pub x: f32,
     /// Y offset of hitbox
@@ -28,6 +30,10 @@ pub struct LayoutGlyph {
     pub w: f32,
     /// Unicode BiDi embedding level, character is left-to-right if `level` is divisible by 2
     pub level: unicode_bidi::Level,
+    /// Italic angle of the font in degrees, see [`crate::Font::italic_angle`]
+    ///
+    /// This can be used to slant the caret or decorations next to italic text.
+    pub italic_angle: f32,
     /// X offset in line
     ///
     /// If you are dealing with physical coordinates, use [`Self::physical`] to obtain a
@@ -50,6 +56,19 @@ pub struct LayoutGlyph {
     pub color_opt: Option<Color>,
     /// Metadata from `Attrs`
     pub metadata: usize,
+    /// Underline style from `Attrs`
+    pub underline_opt: Option<UnderlineStyle>,
+    /// Strikethrough from `Attrs`
+    pub strikethrough: bool,
+    /// Inline box from `Attrs`, this glyph only reserves space for it and is not drawn
+    pub inline_box_opt: Option<InlineBox>,
+    /// True if the glyph is upright in vertical text, see [`WritingMode::VerticalRl`]
+    ///
+    /// Vertical lines are laid out like horizontal ones rotated 90 degrees clockwise, so `x` and
+    /// `w` are measured down the column. Other glyphs are drawn rotated with the line, but upright
+    /// glyphs are drawn unrotated, with `x_offset` being the offset of their origin down from `x`
+    /// and `y_offset` the offset right from the center of the column.
+    pub upright: bool,
 }
 
 #[derive(Debug)]
@@ -67,7 +86,7 @@ impl LayoutGlyph {
         let x_offset = self.font_size * self.x_offset;
         let y_offset = self.font_size * self.y_offset;
 
-        let (cache_key, x, y) = CacheKey::new(
+        let (mut cache_key, x, y) = CacheKey::new(
             self.font_id,
             self.glyph_id,
             self.font_size * scale,
@@ -76,6 +95,7 @@ impl LayoutGlyph {
                 libm::truncf((self.y - y_offset) * scale + offset.1), // Hinting in Y axis
             ),
         );
+        cache_key.variations_id = self.variations_id;
 
         PhysicalGlyph { cache_key, x, y }
     }
@@ -90,19 +110,51 @@ pub struct LayoutLine {
     pub max_ascent: f32,
     /// Maximum descent of the glyphs in line
     pub max_descent: f32,
+    /// Maximum line gap of the fonts of the glyphs in line
+    ///
+    /// Like the ascent and descent, this comes from the fonts actually used for the glyphs,
+    /// including fallback fonts. It is not added to the line height.
+    pub max_line_gap: f32,
     /// Glyphs in line
     pub glyphs: Vec<LayoutGlyph>,
+    /// Height reserved above the glyphs for ruby annotations, added to the line height
+    pub ruby_height: f32,
+    /// Ruby annotation glyphs, positioned above the glyphs they annotate
+    ///
+    /// The `start` and `end` of these glyphs index into the ruby text, not the line.
+    pub ruby_glyphs: Vec<LayoutGlyph>,
+    /// Width of the line prefix, which offsets the glyphs of every layout line of the paragraph
+    pub prefix_w: f32,
+    /// Line prefix glyphs, only present on the first layout line of the paragraph
+    ///
+    /// The `start` and `end` of these glyphs index into the prefix text, not the line.
+    pub prefix_glyphs: Vec<LayoutGlyph>,
 }
 
-/// Wrapping mode
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+impl LayoutLine {
+    /// Get the offset of the baseline from the top of the line, when laid out with `line_height`
+    ///
+    /// The glyphs are centered vertically in the line height, below any ruby annotations. This is
+    /// the same as [`crate::LayoutRun::line_y`] minus [`crate::LayoutRun::line_top`].
+    pub fn baseline_y(&self, line_height: f32) -> f32 {
+        let glyph_height = self.max_ascent + self.max_descent;
+        self.ruby_height + (line_height - glyph_height) / 2.0 + self.max_ascent
+    }
+}
+
+/// Wrapping mode, defaults to [`Wrap::Word`]
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
 pub enum Wrap {
-    /// No wrapping
+    /// No wrapping, for single line inputs
     None,
-    /// Wraps at a glyph level
+    /// Wraps at a glyph level, for terminal emulators
     Glyph,
-    /// Word Wrapping
+    /// Word Wrapping, falling back to wrapping at a glyph level for words wider than the line
+    #[default]
     Word,
+    /// Word wrapping that keeps trailing punctuation, such as a period, closing quote, or dash,
+    /// on the same line as the word before it where line breaking would allow it to wrap alone
+    WordKeepPunctuation,
 }
 
 impl Display for Wrap {
@@ -110,18 +162,74 @@ impl Display for Wrap {
         match self {
             Self::None => write!(f, "No Wrap"),
             Self::Word => write!(f, "Word Wrap"),
+            Self::WordKeepPunctuation => write!(f, "Word Wrap Keeping Punctuation"),
             Self::Glyph => write!(f, "Character"),
         }
     }
 }
 
+/// Part of the text to replace with an ellipsis when truncating, see
+/// [`crate::BufferLine::truncated_text`]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Truncate {
+    /// Keep the start of the text
+    End,
+    /// Keep the end of the text
+    Start,
+    /// Keep the start and end of the text
+    Middle,
+    /// Keep the last path segment, from the last `/`, and as much of the start as fits, falling
+    /// back to [`Self::Start`] if the last segment does not fit
+    Path,
+}
+
+impl Display for Truncate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::End => write!(f, "End"),
+            Self::Start => write!(f, "Start"),
+            Self::Middle => write!(f, "Middle"),
+            Self::Path => write!(f, "Path"),
+        }
+    }
+}
+
+/// What to do with text that does not fit in the width or the maximum number of lines of a line,
+/// see [`crate::BufferLine::set_overflow`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Overflow {
+    /// Lay out all of the text, which can extend past the width
+    #[default]
+    Visible,
+    /// Replace the end of the text that does not fit with an ellipsis (`…`)
+    Ellipsis,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Visible => write!(f, "Visible"),
+            Self::Ellipsis => write!(f, "Ellipsis"),
+        }
+    }
+}
+
 /// Align or justify
+///
+/// Alignment is relative to the wrap width of the line, regardless of the direction of the text.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Align {
+    /// Align to the left edge
     Left,
+    /// Align to the right edge
     Right,
+    /// Center between the edges
     Center,
+    /// Stretch the blank space between words to fill the width, except on the last visual line
+    /// of the paragraph, which is aligned to the start of the text
     Justified,
+    /// Align to the end of the text, the right edge of a left-to-right line and the left edge of
+    /// a right-to-left line
     End,
 }
 
@@ -136,3 +244,26 @@ impl Display for Align {
         }
     }
 }
+
+/// The direction lines are laid out in, see [`crate::Buffer::set_writing_mode`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum WritingMode {
+    /// Horizontal lines, stacked from top to bottom
+    #[default]
+    HorizontalTb,
+    /// Vertical lines, stacked from right to left, as in Chinese and Japanese
+    ///
+    /// Han, kana, Hangul, and other CJK characters are shaped upright, with the vertical metrics
+    /// and the vertical alternates (`vert` and `vrt2`) of their fonts. Other text, such as Latin,
+    /// is rotated 90 degrees clockwise with the line.
+    VerticalRl,
+}
+
+impl Display for WritingMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::HorizontalTb => write!(f, "Horizontal"),
+            Self::VerticalRl => write!(f, "Vertical Right to Left"),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A stable, machine-readable description of a laid out [`Buffer`], see [`Buffer::to_layout_json`]
+//!
+//! The schema is made of the types in this module, serialized with their field names as-is.
+//! All positions and sizes are in pixels, relative to the top left of the buffer and without
+//! scrolling applied. Fields are only ever added to the schema, and [`LAYOUT_JSON_VERSION`] is
+//! incremented when the meaning of an existing field changes.
+
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::{Buffer, FontSystem, LayoutGlyph};
+
+/// Version of the layout JSON schema, stored in [`LayoutJson::version`]
+pub const LAYOUT_JSON_VERSION: u32 = 1;
+
+/// The root of the layout JSON schema
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayoutJson {
+    /// Schema version, see [`LAYOUT_JSON_VERSION`]
+    pub version: u32,
+    /// Width of the buffer
+    pub width: f32,
+    /// Height of the buffer
+    pub height: f32,
+    /// Font size of the buffer metrics
+    pub font_size: f32,
+    /// Line height of the buffer metrics
+    pub line_height: f32,
+    /// Fonts used by the glyphs, referenced by [`LayoutJsonGlyph::font`]
+    pub fonts: Vec<LayoutJsonFont>,
+    /// Visual lines from top to bottom, including the lines scrolled out of view
+    pub lines: Vec<LayoutJsonLine>,
+}
+
+/// A font used in the layout
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayoutJsonFont {
+    /// First family name of the font, or an empty string if it has none
+    pub family: String,
+    /// PostScript name of the font
+    pub post_script_name: String,
+    /// Weight of the font, from 1 to 1000
+    pub weight: u16,
+    /// Style of the font: `normal`, `italic` or `oblique`
+    pub style: String,
+}
+
+/// A visual line, one of the runs returned by [`Buffer::layout_runs`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayoutJsonLine {
+    /// Index of the buffer line this visual line belongs to
+    pub line_i: usize,
+    /// True if the paragraph direction is right to left
+    pub rtl: bool,
+    /// Y offset to the top of the line
+    pub top: f32,
+    /// Y offset to the baseline of the line
+    pub baseline: f32,
+    /// Width of the line
+    pub width: f32,
+    /// Glyphs of the line in visual order
+    pub glyphs: Vec<LayoutJsonGlyph>,
+}
+
+/// A glyph of a visual line
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayoutJsonGlyph {
+    /// Byte index of the start of the cluster in the buffer line
+    pub start: usize,
+    /// Byte index of the end of the cluster in the buffer line
+    pub end: usize,
+    /// Index into [`LayoutJson::fonts`]
+    pub font: usize,
+    /// Glyph ID in the font
+    pub glyph_id: u16,
+    /// Font size of the glyph
+    pub font_size: f32,
+    /// X offset of the glyph from the start of the line
+    pub x: f32,
+    /// Y offset of the glyph from the baseline, positive is down
+    pub y: f32,
+    /// Advance width of the glyph
+    pub w: f32,
+    /// Bidi level of the glyph, odd levels are right to left
+    pub level: u8,
+    /// Color of the glyph as `0xAARRGGBB`, or `null` for the default color
+    pub color: Option<u32>,
+    /// Metadata from the attributes of the glyph
+    pub metadata: usize,
+}
+
+impl Buffer {
+    /// Describe the current layout using the schema in [`LayoutJson`]
+    ///
+    /// Only the lines that have been laid out are included, call [`Buffer::shape_until`] first
+    /// to export lines past the scroll position.
+    pub fn to_layout(&self, font_system: &FontSystem) -> LayoutJson {
+        let (width, height) = self.size();
+        let metrics = self.metrics();
+        let mut fonts = Vec::new();
+        let mut font_ids = Vec::new();
+        let mut lines = Vec::new();
+        for run in self.layout_runs() {
+            let glyphs = run
+                .glyphs
+                .iter()
+                .map(|glyph| layout_json_glyph(glyph, font_system, &mut font_ids, &mut fonts))
+                .collect();
+            lines.push(LayoutJsonLine {
+                line_i: run.line_i,
+                rtl: run.rtl,
+                top: run.line_top,
+                baseline: run.line_y,
+                width: run.line_w,
+                glyphs,
+            });
+        }
+        LayoutJson {
+            version: LAYOUT_JSON_VERSION,
+            width,
+            height,
+            font_size: metrics.font_size,
+            line_height: metrics.line_height,
+            fonts,
+            lines,
+        }
+    }
+
+    /// Serialize the current layout to JSON using the schema in [`LayoutJson`]
+    pub fn to_layout_json(&self, font_system: &FontSystem) -> String {
+        // Serializing plain structs with string keys cannot fail
+        serde_json::to_string(&self.to_layout(font_system)).unwrap_or_default()
+    }
+}
+
+fn layout_json_glyph(
+    glyph: &LayoutGlyph,
+    font_system: &FontSystem,
+    font_ids: &mut Vec<fontdb::ID>,
+    fonts: &mut Vec<LayoutJsonFont>,
+) -> LayoutJsonGlyph {
+    let font = match font_ids.iter().position(|id| *id == glyph.font_id) {
+        Some(some) => some,
+        None => {
+            font_ids.push(glyph.font_id);
+            fonts.push(layout_json_font(font_system, glyph.font_id));
+            fonts.len() - 1
+        }
+    };
+    LayoutJsonGlyph {
+        start: glyph.start,
+        end: glyph.end,
+        font,
+        glyph_id: glyph.glyph_id,
+        font_size: glyph.font_size,
+        x: glyph.x,
+        y: glyph.y,
+        w: glyph.w,
+        level: glyph.level.number(),
+        color: glyph.color_opt.map(|color| color.0),
+        metadata: glyph.metadata,
+    }
+}
+
+fn layout_json_font(font_system: &FontSystem, id: fontdb::ID) -> LayoutJsonFont {
+    match font_system.db().face(id) {
+        Some(face) => LayoutJsonFont {
+            family: face
+                .families
+                .first()
+                .map(|(family, _)| family.clone())
+                .unwrap_or_default(),
+            post_script_name: face.post_script_name.clone(),
+            weight: face.weight.0,
+            style: String::from(match face.style {
+                fontdb::Style::Normal => "normal",
+                fontdb::Style::Italic => "italic",
+                fontdb::Style::Oblique => "oblique",
+            }),
+        },
+        None => LayoutJsonFont {
+            family: String::new(),
+            post_script_name: String::new(),
+            weight: 0,
+            style: String::new(),
+        },
+    }
+}
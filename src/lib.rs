@@ -96,6 +96,11 @@ extern crate alloc;
 #[cfg(not(any(feature = "std", feature = "no_std")))]
 compile_error!("Either the `std` or `no_std` feature must be enabled");
 
+#[cfg(feature = "swash")]
+pub use self::atlas::*;
+#[cfg(feature = "swash")]
+mod atlas;
+
 pub use self::attrs::*;
 mod attrs;
 
@@ -117,9 +122,20 @@ mod edit;
 pub use self::font::*;
 mod font;
 
+pub use self::label::*;
+mod label;
+
 pub use self::layout::*;
 mod layout;
 
+#[cfg(feature = "layout_json")]
+pub use self::layout_json::*;
+#[cfg(feature = "layout_json")]
+mod layout_json;
+
+pub use self::line_ending::*;
+mod line_ending;
+
 pub use self::shape::*;
 mod shape;
 
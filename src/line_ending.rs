@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use unicode_bidi::{bidi_class, BidiClass};
+
+/// The terminator at the end of a line of text
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineEnding {
+    /// Line feed (`\n`)
+    #[default]
+    Lf,
+    /// Carriage return and line feed (`\r\n`)
+    CrLf,
+    /// Carriage return (`\r`)
+    Cr,
+    /// Another paragraph separator, such as U+2029 PARAGRAPH SEPARATOR
+    Other(char),
+    /// No terminator, used for the last line of text
+    None,
+}
+
+impl LineEnding {
+    /// Push the terminator onto the end of `string`
+    pub fn push_to(self, string: &mut String) {
+        match self {
+            Self::Lf => string.push('\n'),
+            Self::CrLf => string.push_str("\r\n"),
+            Self::Cr => string.push('\r'),
+            Self::Other(c) => string.push(c),
+            Self::None => {}
+        }
+    }
+}
+
+/// An iterator over the lines in the input text, along with their [`LineEnding`]s
+///
+/// Lines are split at the same paragraph separators as [`crate::BidiParagraphs`], with `\r\n`
+/// treated as a single terminator.
+#[derive(Debug)]
+pub struct LineIter<'text> {
+    text: &'text str,
+}
+
+impl<'text> LineIter<'text> {
+    /// Create an iterator to split the input text into lines
+    pub fn new(text: &'text str) -> Self {
+        Self { text }
+    }
+}
+
+impl<'text> Iterator for LineIter<'text> {
+    type Item = (&'text str, LineEnding);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.text.is_empty() {
+            return None;
+        }
+
+        for (i, c) in self.text.char_indices() {
+            let (ending, len) = match c {
+                '\n' => (LineEnding::Lf, 1),
+                '\r' if self.text[i + 1..].starts_with('\n') => (LineEnding::CrLf, 2),
+                '\r' => (LineEnding::Cr, 1),
+                _ if bidi_class(c) == BidiClass::B => (LineEnding::Other(c), c.len_utf8()),
+                _ => continue,
+            };
+            let line = &self.text[..i];
+            self.text = &self.text[i + len..];
+            return Some((line, ending));
+        }
+
+        let line = self.text;
+        self.text = "";
+        Some((line, LineEnding::None))
+    }
+}
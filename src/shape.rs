@@ -2,17 +2,63 @@
 
 #![allow(clippy::too_many_arguments)]
 
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use core::cmp::{max, min};
 use core::fmt;
 use core::mem;
 use core::ops::Range;
+use unicode_bidi::{bidi_class, BidiClass};
 use unicode_script::{Script, UnicodeScript};
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::fallback::FontFallbackIter;
-use crate::{Align, AttrsList, Color, Font, FontSystem, LayoutGlyph, LayoutLine, Wrap};
+use crate::{
+    is_bidi_control, Align, AttrsList, Color, Font, FontSystem, HashMap, InlineBox, LayoutGlyph,
+    LayoutLine, UnderlineStyle, Variation, Wrap, WritingMode,
+};
+
+/// Visible placeholder shaped in place of bidi controls when they are revealed
+const BIDI_CONTROL_MARKER: char = '\u{2B1A}';
+
+/// Highlight color of revealed bidi controls
+const BIDI_CONTROL_COLOR: Color = Color::rgb(0xFF, 0x00, 0x00);
+
+/// Base shaped in front of a combining mark at the start of a line when isolated marks are shown
+const ISOLATED_MARK_BASE: char = '\u{25CC}';
+
+/// Default distance between tab stops, in spaces
+pub(crate) const DEFAULT_TAB_WIDTH: u16 = 8;
+
+/// Default number of runs kept by the [`ShapeRunCache`]
+pub(crate) const DEFAULT_SHAPE_RUN_CACHE_LIMIT: usize = 4096;
+
+/// Options for shaping lines, which a [`crate::Buffer`] uses for all of its lines
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ShapeOptions {
+    /// Shape bidi control characters as visible, highlighted placeholders instead of hiding them
+    pub reveal_bidi_controls: bool,
+    /// Shape a combining mark at the start of a line on a dotted circle base. This only applies
+    /// to [`Shaping::Advanced`].
+    pub isolated_mark_display: bool,
+    /// Distance between tab stops in spaces, see [`ShapeLine::expand_tabs`]
+    pub tab_width: u16,
+    /// Direction of lines, which shapes East Asian characters upright in vertical text
+    pub writing_mode: WritingMode,
+}
+
+impl Default for ShapeOptions {
+    fn default() -> Self {
+        Self {
+            reveal_bidi_controls: false,
+            isolated_mark_display: false,
+            tab_width: DEFAULT_TAB_WIDTH,
+            writing_mode: WritingMode::HorizontalTb,
+        }
+    }
+}
 
 /// The shaping strategy of some text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -45,10 +91,49 @@ impl Shaping {
         start_run: usize,
         end_run: usize,
         span_rtl: bool,
+        reveal_bidi_controls: bool,
+        isolated_mark_display: bool,
+        upright: bool,
     ) {
+        let attrs = attrs_list.get_span(start_run);
+        if let Some(inline_box) = attrs.inline_box_opt {
+            // Inline boxes are a single cluster that only reserves space
+            glyphs.push(ShapeGlyph {
+                start: start_run,
+                end: end_run,
+                x_advance: inline_box.width,
+                y_advance: 0.0,
+                x_offset: 0.0,
+                y_offset: 0.0,
+                ascent: inline_box.height - inline_box.baseline_offset,
+                descent: inline_box.baseline_offset,
+                line_gap: 0.0,
+                italic_angle: 0.0,
+                font_id: fontdb::ID::dummy(),
+                glyph_id: 0,
+                variations_id: 0,
+                color_opt: attrs.color_opt,
+                metadata: attrs.metadata,
+                underline_opt: attrs.underline_opt,
+                strikethrough: attrs.strikethrough,
+                inline_box_opt: Some(inline_box),
+                upright: false,
+            });
+            return;
+        }
+
         match self {
             #[cfg(feature = "swash")]
-            Self::Basic => shape_skip(font_system, glyphs, line, attrs_list, start_run, end_run),
+            Self::Basic => shape_skip(
+                font_system,
+                glyphs,
+                line,
+                attrs_list,
+                start_run,
+                end_run,
+                reveal_bidi_controls,
+                upright,
+            ),
             Self::Advanced => shape_run(
                 scratch,
                 glyphs,
@@ -58,11 +143,42 @@ impl Shaping {
                 start_run,
                 end_run,
                 span_rtl,
+                reveal_bidi_controls,
+                isolated_mark_display,
+                upright,
             ),
         }
     }
 }
 
+/// Check if text requires bidirectional processing or complex script shaping
+///
+/// This is a quick scan of the codepoints in `text`, returning true if any are right-to-left,
+/// combining, or from a script that needs contextual shaping. Text that returns false can be
+/// shaped with basic shaping and laid out without reordering.
+pub fn text_needs_complex_shaping(text: &str) -> bool {
+    if text.is_ascii() {
+        return false;
+    }
+
+    text.chars().any(|c| {
+        matches!(bidi_class(c), BidiClass::R | BidiClass::AL | BidiClass::AN)
+            || is_bidi_control(c)
+            || !matches!(
+                c.script(),
+                Script::Common
+                    | Script::Latin
+                    | Script::Greek
+                    | Script::Cyrillic
+                    | Script::Han
+                    | Script::Hiragana
+                    | Script::Katakana
+                    | Script::Bopomofo
+                    | Script::Unknown
+            )
+    })
+}
+
 /// A set of buffers containing allocations for shaped text.
 #[derive(Default)]
 pub struct ShapeBuffer {
@@ -82,54 +198,215 @@ impl fmt::Debug for ShapeBuffer {
     }
 }
 
+/// Everything that rustybuzz output depends on when shaping a run
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ShapeRunKey {
+    font_id: fontdb::ID,
+    variations_id: u32,
+    text: String,
+    rtl: bool,
+    script_opt: Option<Script>,
+    reveal_bidi_controls: bool,
+    isolated_mark_base: bool,
+    upright: bool,
+}
+
+/// A glyph of a shaped run, in font units, with its cluster relative to the start of the run
+#[derive(Clone, Copy, Debug)]
+struct ShapeRunGlyph {
+    glyph_id: u32,
+    cluster: u32,
+    x_advance: i32,
+    y_advance: i32,
+    x_offset: i32,
+    y_offset: i32,
+}
+
+/// Cache of the glyphs of runs shaped by rustybuzz, so repeated words are shaped once
+///
+/// The least recently used runs are dropped when there are more than the limit, see
+/// [`FontSystem::set_shape_run_cache_limit`].
+#[derive(Debug)]
+pub(crate) struct ShapeRunCache {
+    limit: usize,
+    age: u64,
+    runs: HashMap<ShapeRunKey, (u64, Arc<[ShapeRunGlyph]>)>,
+}
+
+impl ShapeRunCache {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            age: 0,
+            runs: HashMap::default(),
+        }
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        if self.runs.len() > limit {
+            self.evict(limit);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.runs.clear();
+    }
+
+    fn get(&mut self, key: &ShapeRunKey) -> Option<Arc<[ShapeRunGlyph]>> {
+        self.age += 1;
+        let (age, glyphs) = self.runs.get_mut(key)?;
+        *age = self.age;
+        Some(glyphs.clone())
+    }
+
+    fn insert(&mut self, key: ShapeRunKey, glyphs: Arc<[ShapeRunGlyph]>) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.runs.len() >= self.limit {
+            // Drop half of the runs at once, so this is not needed for every new run
+            self.evict(self.limit / 2);
+        }
+        self.runs.insert(key, (self.age, glyphs));
+    }
+
+    /// Drop the least recently used runs until at most `keep` are left
+    fn evict(&mut self, keep: usize) {
+        let mut ages: Vec<u64> = self.runs.values().map(|(age, _)| *age).collect();
+        ages.sort_unstable();
+        let drop = ages.len().saturating_sub(keep);
+        match ages.get(drop) {
+            Some(&cutoff) => self.runs.retain(|_, (age, _)| *age >= cutoff),
+            None => self.runs.clear(),
+        }
+    }
+}
+
+/// Get the rustybuzz face of `font` at the coordinates of `variations`
+///
+/// Coordinates on axes the font does not have are ignored by rustybuzz.
+fn variation_face<'a>(font: &'a Font, variations: &[Variation]) -> Cow<'a, rustybuzz::Face<'a>> {
+    if variations.is_empty() {
+        return Cow::Borrowed(font.rustybuzz());
+    }
+
+    let variations: Vec<rustybuzz::Variation> = variations
+        .iter()
+        .map(|variation| rustybuzz::Variation {
+            tag: variation.tag,
+            value: variation.value,
+        })
+        .collect();
+    let mut face = font.rustybuzz().clone();
+    face.set_variations(&variations);
+    Cow::Owned(face)
+}
+
 fn shape_fallback(
     scratch: &mut ShapeBuffer,
+    cache: &mut ShapeRunCache,
     glyphs: &mut Vec<ShapeGlyph>,
     font: &Font,
+    variations_id: u32,
     line: &str,
     attrs_list: &AttrsList,
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    script_opt: Option<Script>,
+    reveal_bidi_controls: bool,
+    isolated_mark_display: bool,
+    upright: bool,
 ) -> Vec<usize> {
     let run = &line[start_run..end_run];
 
-    let font_scale = font.rustybuzz().units_per_em() as f32;
-    let ascent = font.rustybuzz().ascender() as f32 / font_scale;
-    let descent = -font.rustybuzz().descender() as f32 / font_scale;
-
-    let mut buffer = scratch.rustybuzz_buffer.take().unwrap_or_default();
-    buffer.set_direction(if span_rtl {
-        rustybuzz::Direction::RightToLeft
-    } else {
-        rustybuzz::Direction::LeftToRight
-    });
-    buffer.push_str(run);
-    buffer.guess_segment_properties();
-
-    let rtl = matches!(buffer.direction(), rustybuzz::Direction::RightToLeft);
-    assert_eq!(rtl, span_rtl);
-
-    let glyph_buffer = rustybuzz::shape(font.rustybuzz(), &[], buffer);
-    let glyph_infos = glyph_buffer.glyph_infos();
-    let glyph_positions = glyph_buffer.glyph_positions();
+    // Attributes are split into runs by their variations, so they are the same for the whole run
+    let face = variation_face(font, attrs_list.get_span(start_run).variations);
+    let font_scale = face.units_per_em() as f32;
+    let ascent = face.ascender() as f32 / font_scale;
+    let descent = -face.descender() as f32 / font_scale;
+    let line_gap = face.line_gap() as f32 / font_scale;
+    let italic_angle = font.italic_angle();
+
+    let isolated_mark_base = isolated_mark_display
+        && start_run == 0
+        && run
+            .chars()
+            .next()
+            .is_some_and(|c| bidi_class(c) == BidiClass::NSM);
+    let key = ShapeRunKey {
+        font_id: font.id(),
+        variations_id,
+        text: String::from(run),
+        rtl: span_rtl,
+        script_opt,
+        reveal_bidi_controls,
+        isolated_mark_base,
+        upright,
+    };
+    let run_glyphs = match cache.get(&key) {
+        Some(some) => some,
+        None => {
+            let run_glyphs = shape_rustybuzz(
+                scratch,
+                &face,
+                run,
+                span_rtl,
+                script_opt,
+                reveal_bidi_controls,
+                isolated_mark_base,
+                upright,
+            );
+            cache.insert(key, run_glyphs.clone());
+            run_glyphs
+        }
+    };
+    let rtl = span_rtl;
 
     let mut missing = Vec::new();
-    glyphs.reserve(glyph_infos.len());
+    glyphs.reserve(run_glyphs.len());
     let glyph_start = glyphs.len();
-    for (info, pos) in glyph_infos.iter().zip(glyph_positions.iter()) {
-        let x_advance = pos.x_advance as f32 / font_scale;
-        let y_advance = pos.y_advance as f32 / font_scale;
-        let x_offset = pos.x_offset as f32 / font_scale;
-        let y_offset = pos.y_offset as f32 / font_scale;
+    for run_glyph in run_glyphs.iter() {
+        let (x_advance, y_advance, x_offset, y_offset) = if upright {
+            // Upright glyphs are shaped top to bottom, with their offsets from the center of the
+            // column, so down becomes the direction of the line and right becomes up
+            (
+                -run_glyph.y_advance as f32 / font_scale,
+                0.0,
+                -run_glyph.y_offset as f32 / font_scale,
+                run_glyph.x_offset as f32 / font_scale,
+            )
+        } else {
+            (
+                run_glyph.x_advance as f32 / font_scale,
+                run_glyph.y_advance as f32 / font_scale,
+                run_glyph.x_offset as f32 / font_scale,
+                run_glyph.y_offset as f32 / font_scale,
+            )
+        };
 
-        let start_glyph = start_run + info.cluster as usize;
+        let start_glyph = start_run + run_glyph.cluster as usize;
 
-        if info.glyph_id == 0 {
+        if run_glyph.glyph_id == 0 {
             missing.push(start_glyph);
         }
 
         let attrs = attrs_list.get_span(start_glyph);
+        let color_opt = if reveal_bidi_controls
+            && line[start_glyph..]
+                .chars()
+                .next()
+                .is_some_and(is_bidi_control)
+        {
+            Some(BIDI_CONTROL_COLOR)
+        } else {
+            attrs.color_opt
+        };
         glyphs.push(ShapeGlyph {
             start: start_glyph,
             end: end_run, // Set later
@@ -139,11 +416,21 @@ fn shape_fallback(
             y_offset,
             ascent,
             descent,
+            line_gap,
+            italic_angle,
             font_id: font.id(),
-            glyph_id: info.glyph_id.try_into().expect("failed to cast glyph ID"),
+            glyph_id: run_glyph
+                .glyph_id
+                .try_into()
+                .expect("failed to cast glyph ID"),
+            variations_id,
             //TODO: color should not be related to shaping
-            color_opt: attrs.color_opt,
+            color_opt,
             metadata: attrs.metadata,
+            underline_opt: attrs.underline_opt,
+            strikethrough: attrs.strikethrough,
+            inline_box_opt: None,
+            upright,
         });
     }
 
@@ -172,10 +459,115 @@ fn shape_fallback(
         }
     }
 
+    missing
+}
+
+/// Check if a character is shown upright in vertical text, instead of rotated with the line
+///
+/// This approximates the `Vertical_Orientation` property of Unicode, which is upright for the Han,
+/// kana, Hangul, Bopomofo, and Yi scripts, CJK symbols and punctuation, and fullwidth forms.
+fn is_upright(c: char) -> bool {
+    matches!(
+        c.script(),
+        Script::Han
+            | Script::Hiragana
+            | Script::Katakana
+            | Script::Hangul
+            | Script::Bopomofo
+            | Script::Yi
+    ) || matches!(
+        c,
+        '\u{2E80}'..='\u{2FFF}'
+            | '\u{3000}'..='\u{33FF}'
+            | '\u{FE10}'..='\u{FE1F}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF01}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{1F000}'..='\u{1FAFF}'
+    )
+}
+
+/// Shape `run` with rustybuzz, returning the glyphs in font units
+fn shape_rustybuzz(
+    scratch: &mut ShapeBuffer,
+    face: &rustybuzz::Face,
+    run: &str,
+    span_rtl: bool,
+    script_opt: Option<Script>,
+    reveal_bidi_controls: bool,
+    isolated_mark_base: bool,
+    upright: bool,
+) -> Arc<[ShapeRunGlyph]> {
+    let mut buffer = scratch.rustybuzz_buffer.take().unwrap_or_default();
+    buffer.set_direction(if upright {
+        rustybuzz::Direction::TopToBottom
+    } else if span_rtl {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    if isolated_mark_base {
+        // Give a combining mark with no base a dotted circle to attach to, in the mark's cluster
+        buffer.add(ISOLATED_MARK_BASE, 0);
+    }
+    if reveal_bidi_controls || run.contains('\t') {
+        // Substitute a visible marker for bidi controls and a space for tabs, which are widened
+        // to the next tab stop by ShapeLine::expand_tabs, keeping clusters pointed at the original
+        for (i, c) in run.char_indices() {
+            let c = if reveal_bidi_controls && is_bidi_control(c) {
+                BIDI_CONTROL_MARKER
+            } else if c == '\t' {
+                ' '
+            } else {
+                c
+            };
+            buffer.add(c, i as u32);
+        }
+    } else {
+        buffer.push_str(run);
+    }
+    buffer.guess_segment_properties();
+    if let Some(script) = script_opt {
+        let tag = rustybuzz::ttf_parser::Tag::from_bytes_lossy(script.short_name().as_bytes());
+        match rustybuzz::Script::from_iso15924_tag(tag) {
+            Some(script) => buffer.set_script(script),
+            None => log::warn!("script {:?} is not supported for shaping", script),
+        }
+    }
+
+    let rtl = matches!(buffer.direction(), rustybuzz::Direction::RightToLeft);
+    assert_eq!(rtl, span_rtl);
+
+    // Vertical alternates, such as rotated brackets and long vowel marks, for upright glyphs
+    let vertical_features;
+    let features: &[rustybuzz::Feature] = if upright {
+        vertical_features = [
+            rustybuzz::Feature::new(rustybuzz::ttf_parser::Tag::from_bytes(b"vert"), 1, ..),
+            rustybuzz::Feature::new(rustybuzz::ttf_parser::Tag::from_bytes(b"vrt2"), 1, ..),
+        ];
+        &vertical_features
+    } else {
+        &[]
+    };
+    let glyph_buffer = rustybuzz::shape(face, features, buffer);
+    let run_glyphs = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .zip(glyph_buffer.glyph_positions().iter())
+        .map(|(info, pos)| ShapeRunGlyph {
+            glyph_id: info.glyph_id,
+            cluster: info.cluster,
+            x_advance: pos.x_advance,
+            y_advance: pos.y_advance,
+            x_offset: pos.x_offset,
+            y_offset: pos.y_offset,
+        })
+        .collect();
+
     // Restore the buffer to save an allocation.
     scratch.rustybuzz_buffer = Some(glyph_buffer.clear());
 
-    missing
+    run_glyphs
 }
 
 fn shape_run(
@@ -187,6 +579,9 @@ fn shape_run(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    reveal_bidi_controls: bool,
+    isolated_mark_display: bool,
+    upright: bool,
 ) {
     // Re-use the previous script buffer if possible.
     let mut scripts = {
@@ -194,12 +589,18 @@ fn shape_run(
         scripts.clear();
         scripts
     };
-    for c in line[start_run..end_run].chars() {
-        match c.script() {
-            Script::Common | Script::Inherited | Script::Latin | Script::Unknown => (),
-            script => {
-                if !scripts.contains(&script) {
-                    scripts.push(script);
+    let attrs = attrs_list.get_span(start_run);
+    if let Some(script) = attrs.script_opt {
+        // The script is forced, do not detect it
+        scripts.push(script);
+    } else {
+        for c in line[start_run..end_run].chars() {
+            match c.script() {
+                Script::Common | Script::Inherited | Script::Latin | Script::Unknown => (),
+                script => {
+                    if !scripts.contains(&script) {
+                        scripts.push(script);
+                    }
                 }
             }
         }
@@ -207,8 +608,7 @@ fn shape_run(
 
     log::trace!("      Run {:?}: '{}'", &scripts, &line[start_run..end_run],);
 
-    let attrs = attrs_list.get_span(start_run);
-
+    let variations_id = font_system.variations_id(attrs.variations);
     let fonts = font_system.get_font_matches(attrs);
 
     let default_families = [&attrs.family];
@@ -218,7 +618,20 @@ fn shape_run(
 
     let glyph_start = glyphs.len();
     let mut missing = shape_fallback(
-        scratch, glyphs, &font, line, attrs_list, start_run, end_run, span_rtl,
+        scratch,
+        font_iter.shape_run_cache(),
+        glyphs,
+        &font,
+        variations_id,
+        line,
+        attrs_list,
+        start_run,
+        end_run,
+        span_rtl,
+        attrs.script_opt,
+        reveal_bidi_controls,
+        isolated_mark_display,
+        upright,
     );
 
     //TODO: improve performance!
@@ -235,13 +648,19 @@ fn shape_run(
         let mut fb_glyphs = Vec::new();
         let fb_missing = shape_fallback(
             scratch,
+            font_iter.shape_run_cache(),
             &mut fb_glyphs,
             &font,
+            variations_id,
             line,
             attrs_list,
             start_run,
             end_run,
             span_rtl,
+            attrs.script_opt,
+            reveal_bidi_controls,
+            isolated_mark_display,
+            upright,
         );
 
         // Insert all matching glyphs
@@ -320,8 +739,11 @@ fn shape_skip(
     attrs_list: &AttrsList,
     start_run: usize,
     end_run: usize,
+    reveal_bidi_controls: bool,
+    upright: bool,
 ) {
     let attrs = attrs_list.get_span(start_run);
+    let variations_id = font_system.variations_id(attrs.variations);
     let fonts = font_system.get_font_matches(attrs);
 
     let default_families = [&attrs.family];
@@ -329,36 +751,68 @@ fn shape_skip(
 
     let font = font_iter.next().expect("no default font found");
     let font_id = font.id();
+    let italic_angle = font.italic_angle();
+    // Normalized coordinates of the variations, empty for static fonts
+    let coords: Vec<i16> = variation_face(&font, attrs.variations)
+        .variation_coordinates()
+        .iter()
+        .map(|coord| coord.get())
+        .collect();
     let font = font.as_swash();
 
     let charmap = font.charmap();
-    let metrics = font.metrics(&[]);
-    let glyph_metrics = font.glyph_metrics(&[]).scale(1.0);
+    let metrics = font.metrics(&coords);
+    let glyph_metrics = font.glyph_metrics(&coords).scale(1.0);
 
     let ascent = metrics.ascent / f32::from(metrics.units_per_em);
     let descent = metrics.descent / f32::from(metrics.units_per_em);
+    let line_gap = metrics.leading / f32::from(metrics.units_per_em);
 
     glyphs.extend(
         line[start_run..end_run]
-            .chars()
-            .enumerate()
+            .char_indices()
             .map(|(i, codepoint)| {
-                let glyph_id = charmap.map(codepoint);
-                let x_advance = glyph_metrics.advance_width(glyph_id);
+                let revealed = reveal_bidi_controls && is_bidi_control(codepoint);
+                let glyph_id = if revealed {
+                    charmap.map(BIDI_CONTROL_MARKER)
+                } else if codepoint == '\t' {
+                    charmap.map(' ')
+                } else {
+                    charmap.map(codepoint)
+                };
+                let advance_width = glyph_metrics.advance_width(glyph_id);
+                // Without shaping, upright glyphs use the default vertical metrics, an advance of
+                // the ascent and descent with the origin centered below the ascent
+                let (x_advance, x_offset, y_offset) = if upright {
+                    (ascent - descent, ascent, -advance_width / 2.0)
+                } else {
+                    (advance_width, 0.0, 0.0)
+                };
 
                 ShapeGlyph {
-                    start: i,
-                    end: i + 1,
+                    start: start_run + i,
+                    end: start_run + i + codepoint.len_utf8(),
                     x_advance,
                     y_advance: 0.0,
-                    x_offset: 0.0,
-                    y_offset: 0.0,
+                    x_offset,
+                    y_offset,
                     ascent,
                     descent,
+                    line_gap,
+                    italic_angle,
                     font_id,
                     glyph_id,
-                    color_opt: attrs.color_opt,
+                    variations_id,
+                    color_opt: if revealed {
+                        Some(BIDI_CONTROL_COLOR)
+                    } else {
+                        attrs.color_opt
+                    },
                     metadata: attrs.metadata,
+                    underline_opt: attrs.underline_opt,
+                    strikethrough: attrs.strikethrough,
+                    inline_box_opt: None,
+                    upright,
                 }
             }),
     );
@@ -375,10 +829,20 @@ pub struct ShapeGlyph {
     pub y_offset: f32,
     pub ascent: f32,
     pub descent: f32,
+    /// Line gap of the font, in em
+    pub line_gap: f32,
+    pub italic_angle: f32,
     pub font_id: fontdb::ID,
     pub glyph_id: u16,
+    /// Variation coordinates of the font, see [`FontSystem::variations_id`]
+    pub variations_id: u32,
     pub color_opt: Option<Color>,
     pub metadata: usize,
+    pub underline_opt: Option<UnderlineStyle>,
+    pub strikethrough: bool,
+    pub inline_box_opt: Option<InlineBox>,
+    /// True if the glyph was shaped upright for vertical text, see [`LayoutGlyph::upright`]
+    pub upright: bool,
 }
 
 impl ShapeGlyph {
@@ -396,14 +860,20 @@ impl ShapeGlyph {
             font_size,
             font_id: self.font_id,
             glyph_id: self.glyph_id,
+            variations_id: self.variations_id,
             x,
             y,
             w,
             level,
+            italic_angle: self.italic_angle,
             x_offset: self.x_offset,
             y_offset: self.y_offset,
             color_opt: self.color_opt,
             metadata: self.metadata,
+            underline_opt: self.underline_opt,
+            strikethrough: self.strikethrough,
+            inline_box_opt: self.inline_box_opt,
+            upright: self.upright,
         }
     }
 }
@@ -412,6 +882,10 @@ impl ShapeGlyph {
 #[derive(Debug)]
 pub struct ShapeWord {
     pub blank: bool,
+    /// True if the word only contains trailing punctuation, see [`Wrap::WordKeepPunctuation`]
+    pub punctuation: bool,
+    /// True if the word is a single tab, see [`ShapeLine::expand_tabs`]
+    pub tab: bool,
     pub glyphs: Vec<ShapeGlyph>,
     pub x_advance: f32,
     pub y_advance: f32,
@@ -427,7 +901,30 @@ impl ShapeWord {
         blank: bool,
         shaping: Shaping,
     ) -> Self {
-        Self::new_in_buffer(
+        Self::new_with_options(
+            font_system,
+            line,
+            attrs_list,
+            word_range,
+            level,
+            blank,
+            shaping,
+            ShapeOptions::default(),
+        )
+    }
+
+    /// Shape a word into a set of glyphs with the given [`ShapeOptions`]
+    pub fn new_with_options(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        shaping: Shaping,
+        options: ShapeOptions,
+    ) -> Self {
+        Self::new_in_buffer_with_options(
             &mut ShapeBuffer::default(),
             font_system,
             line,
@@ -436,6 +933,7 @@ impl ShapeWord {
             level,
             blank,
             shaping,
+            options,
         )
     }
 
@@ -450,6 +948,32 @@ impl ShapeWord {
         level: unicode_bidi::Level,
         blank: bool,
         shaping: Shaping,
+    ) -> Self {
+        Self::new_in_buffer_with_options(
+            scratch,
+            font_system,
+            line,
+            attrs_list,
+            word_range,
+            level,
+            blank,
+            shaping,
+            ShapeOptions::default(),
+        )
+    }
+
+    /// Shape a word into a set of glyphs with the given [`ShapeOptions`], using a scratch
+    /// buffer.
+    pub fn new_in_buffer_with_options(
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        shaping: Shaping,
+        options: ShapeOptions,
     ) -> Self {
         let word = &line[word_range.clone()];
 
@@ -462,12 +986,18 @@ impl ShapeWord {
         let mut glyphs = Vec::new();
         let span_rtl = level.is_rtl();
 
+        // In vertical text, upright and rotated characters are shaped in separate runs
+        let vertical = options.writing_mode == WritingMode::VerticalRl && !span_rtl;
+        let upright_egc = |egc: &str| vertical && egc.chars().next().is_some_and(is_upright);
+
         let mut start_run = word_range.start;
-        let mut attrs = attrs_list.defaults();
-        for (egc_i, _egc) in word.grapheme_indices(true) {
+        // Start with the attributes of the first cluster, so no empty run is shaped before it
+        let mut attrs = attrs_list.get_span(word_range.start);
+        let mut upright = upright_egc(word);
+        for (egc_i, egc) in word.grapheme_indices(true) {
             let start_egc = word_range.start + egc_i;
             let attrs_egc = attrs_list.get_span(start_egc);
-            if !attrs.compatible(&attrs_egc) {
+            if !attrs.compatible(&attrs_egc) || upright_egc(egc) != upright {
                 shaping.run(
                     scratch,
                     &mut glyphs,
@@ -477,10 +1007,14 @@ impl ShapeWord {
                     start_run,
                     start_egc,
                     span_rtl,
+                    options.reveal_bidi_controls,
+                    options.isolated_mark_display,
+                    upright,
                 );
 
                 start_run = start_egc;
                 attrs = attrs_egc;
+                upright = upright_egc(egc);
             }
         }
         if start_run < word_range.end {
@@ -493,9 +1027,78 @@ impl ShapeWord {
                 start_run,
                 word_range.end,
                 span_rtl,
+                options.reveal_bidi_controls,
+                options.isolated_mark_display,
+                upright,
+            );
+        }
+
+        Self::from_glyphs(word, blank, glyphs)
+    }
+
+    /// Shape a word of a [`PreparedRun`] with its font and script, without font fallback
+    #[allow(clippy::too_many_arguments)]
+    fn new_prepared(
+        scratch: &mut ShapeBuffer,
+        cache: &mut ShapeRunCache,
+        font: &Font,
+        variations_id: u32,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        script: Script,
+        options: ShapeOptions,
+    ) -> Self {
+        let word = &line[word_range.clone()];
+
+        log::trace!(
+            "      Prepared word{}: '{}'",
+            if blank { " BLANK" } else { "" },
+            word
+        );
+
+        let vertical = options.writing_mode == WritingMode::VerticalRl && !level.is_rtl();
+        let upright_egc = |egc: &str| vertical && egc.chars().next().is_some_and(is_upright);
+
+        let mut glyphs = Vec::new();
+        let mut shape = |glyphs: &mut Vec<ShapeGlyph>, range: Range<usize>, upright: bool| {
+            shape_fallback(
+                scratch,
+                cache,
+                glyphs,
+                font,
+                variations_id,
+                line,
+                attrs_list,
+                range.start,
+                range.end,
+                level.is_rtl(),
+                Some(script),
+                options.reveal_bidi_controls,
+                options.isolated_mark_display,
+                upright,
             );
+        };
+        let mut start_run = word_range.start;
+        let mut upright = upright_egc(word);
+        if vertical {
+            for (egc_i, egc) in word.grapheme_indices(true) {
+                if upright_egc(egc) != upright {
+                    let start_egc = word_range.start + egc_i;
+                    shape(&mut glyphs, start_run..start_egc, upright);
+                    start_run = start_egc;
+                    upright = !upright;
+                }
+            }
         }
+        shape(&mut glyphs, start_run..word_range.end, upright);
+
+        Self::from_glyphs(word, blank, glyphs)
+    }
 
+    fn from_glyphs(word: &str, blank: bool, glyphs: Vec<ShapeGlyph>) -> Self {
         let mut x_advance = 0.0;
         let mut y_advance = 0.0;
         for glyph in &glyphs {
@@ -505,6 +1108,8 @@ impl ShapeWord {
 
         Self {
             blank,
+            punctuation: !blank && word.chars().all(|c| TRAILING_PUNCTUATION.contains(&c)),
+            tab: blank && word == "\t",
             glyphs,
             x_advance,
             y_advance,
@@ -529,7 +1134,30 @@ impl ShapeSpan {
         level: unicode_bidi::Level,
         shaping: Shaping,
     ) -> Self {
-        Self::new_in_buffer(
+        Self::new_with_options(
+            font_system,
+            line,
+            attrs_list,
+            span_range,
+            line_rtl,
+            level,
+            shaping,
+            ShapeOptions::default(),
+        )
+    }
+
+    /// Shape a span into a set of words with the given [`ShapeOptions`]
+    pub fn new_with_options(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
+        options: ShapeOptions,
+    ) -> Self {
+        Self::new_in_buffer_with_options(
             &mut ShapeBuffer::default(),
             font_system,
             line,
@@ -538,6 +1166,7 @@ impl ShapeSpan {
             line_rtl,
             level,
             shaping,
+            options,
         )
     }
 
@@ -552,14 +1181,129 @@ impl ShapeSpan {
         level: unicode_bidi::Level,
         shaping: Shaping,
     ) -> Self {
-        let span = &line[span_range.start..span_range.end];
+        Self::new_in_buffer_with_options(
+            scratch,
+            font_system,
+            line,
+            attrs_list,
+            span_range,
+            line_rtl,
+            level,
+            shaping,
+            ShapeOptions::default(),
+        )
+    }
 
+    /// Shape a span into a set of words with the given [`ShapeOptions`], using a scratch
+    /// buffer.
+    pub fn new_in_buffer_with_options(
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
+        options: ShapeOptions,
+    ) -> Self {
         log::trace!(
             "  Span {}: '{}'",
             if level.is_rtl() { "RTL" } else { "LTR" },
-            span
+            &line[span_range.clone()]
+        );
+
+        Self::from_words(line, span_range, line_rtl, level, |word_range, blank| {
+            ShapeWord::new_in_buffer_with_options(
+                scratch,
+                font_system,
+                line,
+                attrs_list,
+                word_range,
+                level,
+                blank,
+                shaping,
+                options,
+            )
+        })
+    }
+
+    /// Shape a [`PreparedRun`] into a set of words with its font, direction, and script
+    ///
+    /// If the font of the run is not found, it is shaped like other spans.
+    #[allow(clippy::too_many_arguments)]
+    fn new_prepared(
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        run: &PreparedRun,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
+        options: ShapeOptions,
+    ) -> Self {
+        let font = match font_system.get_font(run.font_id) {
+            Some(some) => some,
+            None => {
+                log::warn!("did not find font {:?} of prepared run", run.font_id);
+                return Self::new_in_buffer_with_options(
+                    scratch,
+                    font_system,
+                    line,
+                    attrs_list,
+                    run.range.clone(),
+                    line_rtl,
+                    level,
+                    shaping,
+                    options,
+                );
+            }
+        };
+
+        log::trace!(
+            "  Prepared span {} {:?}: '{}'",
+            if level.is_rtl() { "RTL" } else { "LTR" },
+            run.script,
+            &line[run.range.clone()]
         );
 
+        let variations_id =
+            font_system.variations_id(attrs_list.get_span(run.range.start).variations);
+
+        Self::from_words(
+            line,
+            run.range.clone(),
+            line_rtl,
+            level,
+            |word_range, blank| {
+                ShapeWord::new_prepared(
+                    scratch,
+                    font_system.shape_run_cache(),
+                    &font,
+                    variations_id,
+                    line,
+                    attrs_list,
+                    word_range,
+                    level,
+                    blank,
+                    run.script,
+                    options,
+                )
+            },
+        )
+    }
+
+    /// Split a span into words at line break opportunities, shaping each with `shape_word`
+    fn from_words(
+        line: &str,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        mut shape_word: impl FnMut(Range<usize>, bool) -> ShapeWord,
+    ) -> Self {
+        let span = &line[span_range.start..span_range.end];
+
         let mut words = Vec::new();
 
         let mut start_word = 0;
@@ -577,30 +1321,18 @@ impl ShapeSpan {
                 }
             }
             if start_word < start_lb {
-                words.push(ShapeWord::new_in_buffer(
-                    scratch,
-                    font_system,
-                    line,
-                    attrs_list,
+                words.push(shape_word(
                     (span_range.start + start_word)..(span_range.start + start_lb),
-                    level,
                     false,
-                    shaping,
                 ));
             }
             if start_lb < end_lb {
                 for (i, c) in span[start_lb..end_lb].char_indices() {
                     // assert!(c.is_whitespace());
-                    words.push(ShapeWord::new_in_buffer(
-                        scratch,
-                        font_system,
-                        line,
-                        attrs_list,
+                    words.push(shape_word(
                         (span_range.start + start_lb + i)
                             ..(span_range.start + start_lb + i + c.len_utf8()),
-                        level,
                         true,
-                        shaping,
                     ));
                 }
             }
@@ -623,6 +1355,36 @@ impl ShapeSpan {
     }
 }
 
+/// Punctuation that ends a word, such as a period, closing quote, or dash
+const TRAILING_PUNCTUATION: &[char] = &[
+    '.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\'', '‐', '‑', '‒', '–', '—', '―', '’', '”',
+    '»', '›', '…', '、', '。',
+];
+
+/// Get the width of the punctuation words at the start of `words`, which are kept on the same
+/// line as the word before them
+fn keep_punctuation_width<'a>(words: impl Iterator<Item = &'a ShapeWord>, font_size: f32) -> f32 {
+    words
+        .take_while(|word| word.punctuation)
+        .map(|word| font_size * word.x_advance)
+        .sum()
+}
+
+/// A run of text that was itemized by the caller, see [`crate::Buffer::set_prepared_runs`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PreparedRun {
+    /// Index of the line in the buffer
+    pub line_i: usize,
+    /// Byte range of the run in the line
+    pub range: Range<usize>,
+    /// Font to shape the run with, without font fallback
+    pub font_id: fontdb::ID,
+    /// True if the run is right-to-left
+    pub rtl: bool,
+    /// Script to shape the run with
+    pub script: Script,
+}
+
 /// A shaped line (or paragraph)
 #[derive(Debug)]
 pub struct ShapeLine {
@@ -650,12 +1412,34 @@ impl ShapeLine {
         attrs_list: &AttrsList,
         shaping: Shaping,
     ) -> Self {
-        Self::new_in_buffer(
+        Self::new_with_options(
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            ShapeOptions::default(),
+        )
+    }
+
+    /// Shape a line into a set of spans with the given [`ShapeOptions`]
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `line` contains more than one paragraph.
+    pub fn new_with_options(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        options: ShapeOptions,
+    ) -> Self {
+        Self::new_in_buffer_with_options(
             &mut ShapeBuffer::default(),
             font_system,
             line,
             attrs_list,
             shaping,
+            options,
         )
     }
 
@@ -670,6 +1454,32 @@ impl ShapeLine {
         line: &str,
         attrs_list: &AttrsList,
         shaping: Shaping,
+    ) -> Self {
+        Self::new_in_buffer_with_options(
+            scratch,
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            ShapeOptions::default(),
+        )
+    }
+
+    /// Shape a line into a set of spans with the given [`ShapeOptions`], using a scratch buffer.
+    ///
+    /// See [`ShapeOptions`] for how bidi controls, isolated marks, tabs, and vertical text are
+    /// shaped.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `line` contains more than one paragraph.
+    pub fn new_in_buffer_with_options(
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        options: ShapeOptions,
     ) -> Self {
         let mut spans = Vec::new();
 
@@ -700,7 +1510,7 @@ impl ShapeLine {
             {
                 if new_level != run_level {
                     // End of the previous run, start of a new one.
-                    spans.push(ShapeSpan::new_in_buffer(
+                    spans.push(ShapeSpan::new_in_buffer_with_options(
                         scratch,
                         font_system,
                         line,
@@ -709,12 +1519,13 @@ impl ShapeLine {
                         line_rtl,
                         run_level,
                         shaping,
+                        options,
                     ));
                     start = i;
                     run_level = new_level;
                 }
             }
-            spans.push(ShapeSpan::new_in_buffer(
+            spans.push(ShapeSpan::new_in_buffer_with_options(
                 scratch,
                 font_system,
                 line,
@@ -723,11 +1534,113 @@ impl ShapeLine {
                 line_rtl,
                 run_level,
                 shaping,
+                options,
             ));
             line_rtl
         };
 
-        Self { rtl, spans }
+        let mut shape = Self { rtl, spans };
+        shape.expand_tabs(line, options.tab_width);
+        shape
+    }
+
+    /// Shape a line as the given runs, using a scratch buffer, instead of itemizing it
+    ///
+    /// The runs must be sorted and not overlap. The first run sets the direction of the line, and
+    /// text that is not in any run is itemized and shaped as usual, in the direction of the line.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new_prepared(
+        scratch: &mut ShapeBuffer,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        runs: &[PreparedRun],
+        shaping: Shaping,
+        options: ShapeOptions,
+    ) -> Self {
+        let rtl = runs.first().is_some_and(|run| run.rtl);
+        let line_level = if rtl {
+            unicode_bidi::Level::rtl()
+        } else {
+            unicode_bidi::Level::ltr()
+        };
+
+        log::trace!(
+            "Prepared line {}: '{}'",
+            if rtl { "RTL" } else { "LTR" },
+            line
+        );
+
+        let mut spans = Vec::with_capacity(runs.len());
+        let gap_span =
+            |scratch: &mut ShapeBuffer, font_system: &mut FontSystem, range: Range<usize>| {
+                ShapeSpan::new_in_buffer_with_options(
+                    scratch,
+                    font_system,
+                    line,
+                    attrs_list,
+                    range,
+                    rtl,
+                    line_level,
+                    shaping,
+                    options,
+                )
+            };
+        let mut start = 0;
+        for run in runs {
+            if start < run.range.start {
+                spans.push(gap_span(scratch, font_system, start..run.range.start));
+            }
+            // Runs against the direction of the line are embedded one level deeper
+            let level = match (rtl, run.rtl) {
+                (false, false) => 0,
+                (_, true) => 1,
+                (true, false) => 2,
+            };
+            spans.push(ShapeSpan::new_prepared(
+                scratch,
+                font_system,
+                line,
+                attrs_list,
+                run,
+                rtl,
+                unicode_bidi::Level::new(level).expect("invalid bidi level"),
+                shaping,
+                options,
+            ));
+            start = run.range.end;
+        }
+        if start < line.len() {
+            spans.push(gap_span(scratch, font_system, start..line.len()));
+        }
+
+        let mut shape = Self { rtl, spans };
+        shape.expand_tabs(line, options.tab_width);
+        shape
+    }
+
+    /// Widen the tabs in `line` so that each one ends on the next tab stop
+    ///
+    /// Tabs are shaped as spaces, and tab stops are placed every `tab_width` spaces of the tab's
+    /// font, measured from the start of the line in reading order. A `tab_width` of 0 leaves tabs
+    /// as wide as a space.
+    pub fn expand_tabs(&mut self, line: &str, tab_width: u16) {
+        if tab_width == 0 || !line.contains('\t') {
+            return;
+        }
+
+        let mut x = 0.0;
+        for word in self.spans.iter_mut().flat_map(|span| span.words.iter_mut()) {
+            if word.tab && word.glyphs.len() == 1 {
+                let glyph = &mut word.glyphs[0];
+                let stop = f32::from(tab_width) * glyph.x_advance;
+                if stop > 0.0 {
+                    glyph.x_advance = (libm::floorf(x / stop + 1.0e-4) + 1.0) * stop - x;
+                    word.x_advance = glyph.x_advance;
+                }
+            }
+            x += word.x_advance;
+        }
     }
 
     // A modified version of first part of unicode_bidi::bidi_info::visual_run
@@ -939,15 +1852,21 @@ impl ShapeLine {
                     let mut fitting_start = (span.words.len(), 0);
                     for (i, word) in span.words.iter().enumerate().rev() {
                         let word_width = font_size * word.x_advance;
+                        let keep_width = if wrap == Wrap::WordKeepPunctuation && !word.blank {
+                            keep_punctuation_width(span.words[..i].iter().rev(), font_size)
+                        } else {
+                            0.0
+                        };
 
                         // Addition in the same order used to compute the final width, so that
                         // relayouts with that width as the `line_width` will produce the same
                         // wrapping results.
-                        if current_visual_line.w + (word_range_width + word_width)
+                        if current_visual_line.w + (word_range_width + word_width + keep_width)
                             <= line_width
                             // Include one blank word over the width limit since it won't be
-                            // counted in the final width
+                            // counted in the final width, unless it is a tab that takes up space
                             || (word.blank
+                                && !word.tab
                                 && (current_visual_line.w + word_range_width) <= line_width)
                         {
                             // fits
@@ -993,16 +1912,16 @@ impl ShapeLine {
                             //
                             // Current word causing a wrap is not whitespace, so we ignore the
                             // previous word if it's a whitespace
-                            let trailing_blank = span
-                                .words
-                                .get(i + 1)
-                                .map_or(false, |previous_word| previous_word.blank);
+                            let trailing_blank =
+                                span.words.get(i + 1).is_some_and(|previous_word| {
+                                    previous_word.blank && !previous_word.tab
+                                });
                             if trailing_blank {
                                 number_of_blanks = number_of_blanks.saturating_sub(1);
                                 add_to_visual_line(
                                     &mut current_visual_line,
                                     span_index,
-                                    (i + 2, 0),
+                                    (i + 1, 0),
                                     fitting_start,
                                     width_before_last_blank,
                                     number_of_blanks,
@@ -1021,9 +1940,34 @@ impl ShapeLine {
                             current_visual_line = VisualLine::default();
 
                             number_of_blanks = 0;
-                            if word.blank {
+                            if word.blank && !word.tab {
                                 word_range_width = 0.;
                                 fitting_start = (i, 0);
+                            } else if word_width > line_width {
+                                // The word does not fit on a line of its own, so wrap its glyphs
+                                word_range_width = 0.;
+                                for (glyph_i, glyph) in word.glyphs.iter().enumerate().rev() {
+                                    let glyph_width = font_size * glyph.x_advance;
+                                    if word_range_width == 0.
+                                        || word_range_width + glyph_width <= line_width
+                                    {
+                                        word_range_width += glyph_width;
+                                    } else {
+                                        add_to_visual_line(
+                                            &mut current_visual_line,
+                                            span_index,
+                                            (i, glyph_i + 1),
+                                            fitting_start,
+                                            word_range_width,
+                                            0,
+                                        );
+                                        visual_lines.push(current_visual_line);
+                                        current_visual_line = VisualLine::default();
+
+                                        word_range_width = glyph_width;
+                                        fitting_start = (i, glyph_i + 1);
+                                    }
+                                }
                             } else {
                                 word_range_width = word_width;
                                 fitting_start = (i + 1, 0);
@@ -1043,11 +1987,17 @@ impl ShapeLine {
                     let mut fitting_start = (0, 0);
                     for (i, word) in span.words.iter().enumerate() {
                         let word_width = font_size * word.x_advance;
-                        if current_visual_line.w + (word_range_width + word_width)
+                        let keep_width = if wrap == Wrap::WordKeepPunctuation && !word.blank {
+                            keep_punctuation_width(span.words[i + 1..].iter(), font_size)
+                        } else {
+                            0.0
+                        };
+                        if current_visual_line.w + (word_range_width + word_width + keep_width)
                             <= line_width
                             // Include one blank word over the width limit since it won't be
-                            // counted in the final width.
+                            // counted in the final width, unless it is a tab that takes up space.
                             || (word.blank
+                                && !word.tab
                                 && (current_visual_line.w + word_range_width) <= line_width)
                         {
                             // fits
@@ -1087,14 +2037,15 @@ impl ShapeLine {
 
                             // Current word causing a wrap is not whitespace, so we ignore the
                             // previous word if it's a whitespace
-                            let trailing_blank = i > 0 && span.words[i - 1].blank;
+                            let trailing_blank =
+                                i > 0 && span.words[i - 1].blank && !span.words[i - 1].tab;
                             if trailing_blank {
                                 number_of_blanks = number_of_blanks.saturating_sub(1);
                                 add_to_visual_line(
                                     &mut current_visual_line,
                                     span_index,
                                     fitting_start,
-                                    (i - 1, 0),
+                                    (i, 0),
                                     width_before_last_blank,
                                     number_of_blanks,
                                 );
@@ -1112,9 +2063,35 @@ impl ShapeLine {
                             current_visual_line = VisualLine::default();
                             number_of_blanks = 0;
 
-                            if word.blank {
+                            if word.blank && !word.tab {
                                 word_range_width = 0.;
                                 fitting_start = (i + 1, 0);
+                            } else if word_width > line_width {
+                                // The word does not fit on a line of its own, so wrap its glyphs
+                                word_range_width = 0.;
+                                fitting_start = (i, 0);
+                                for (glyph_i, glyph) in word.glyphs.iter().enumerate() {
+                                    let glyph_width = font_size * glyph.x_advance;
+                                    if word_range_width == 0.
+                                        || word_range_width + glyph_width <= line_width
+                                    {
+                                        word_range_width += glyph_width;
+                                    } else {
+                                        add_to_visual_line(
+                                            &mut current_visual_line,
+                                            span_index,
+                                            fitting_start,
+                                            (i, glyph_i),
+                                            word_range_width,
+                                            0,
+                                        );
+                                        visual_lines.push(current_visual_line);
+                                        current_visual_line = VisualLine::default();
+
+                                        word_range_width = glyph_width;
+                                        fitting_start = (i, glyph_i);
+                                    }
+                                }
                             } else {
                                 word_range_width = word_width;
                                 fitting_start = (i, 0);
@@ -1159,6 +2136,7 @@ impl ShapeLine {
             let mut y = 0.;
             let mut max_ascent: f32 = 0.;
             let mut max_descent: f32 = 0.;
+            let mut max_line_gap: f32 = 0.;
             let alignment_correction = match (align, self.rtl) {
                 (Align::Left, true) => line_width - visual_line.w,
                 (Align::Left, false) => 0.,
@@ -1222,15 +2200,19 @@ impl ShapeLine {
                                     0.0
                                 };
                             let y_advance = font_size * glyph.y_advance;
-                            glyphs.push(glyph.layout(font_size, x, y, x_advance, span.level));
+                            // Right-to-left lines are laid out from the right, so each glyph
+                            // starts one advance to the left of the pen
                             if self.rtl {
                                 x -= x_advance;
-                            } else {
+                            }
+                            glyphs.push(glyph.layout(font_size, x, y, x_advance, span.level));
+                            if !self.rtl {
                                 x += x_advance;
                             }
                             y += y_advance;
                             max_ascent = max_ascent.max(glyph.ascent);
                             max_descent = max_descent.max(glyph.descent);
+                            max_line_gap = max_line_gap.max(glyph.line_gap);
                         }
                     }
                 }
@@ -1259,7 +2241,12 @@ impl ShapeLine {
                 },
                 max_ascent: max_ascent * font_size,
                 max_descent: max_descent * font_size,
+                max_line_gap: max_line_gap * font_size,
                 glyphs,
+                ruby_height: 0.0,
+                ruby_glyphs: Vec::new(),
+                prefix_w: 0.0,
+                prefix_glyphs: Vec::new(),
             });
         }
 
@@ -1269,7 +2256,12 @@ impl ShapeLine {
                 w: 0.0,
                 max_ascent: 0.0,
                 max_descent: 0.0,
+                max_line_gap: 0.0,
                 glyphs: Default::default(),
+                ruby_height: 0.0,
+                ruby_glyphs: Vec::new(),
+                prefix_w: 0.0,
+                prefix_glyphs: Vec::new(),
             });
         }
 
@@ -1277,3 +2269,15 @@ impl ShapeLine {
         scratch.visual_lines = visual_lines;
     }
 }
+
+#[test]
+fn test_text_needs_complex_shaping() {
+    assert!(!text_needs_complex_shaping(""));
+    assert!(!text_needs_complex_shaping("Hello, world!"));
+    assert!(!text_needs_complex_shaping("Привет, καλημέρα, Grüße"));
+    assert!(text_needs_complex_shaping("مرحبا"));
+    assert!(text_needs_complex_shaping("Hello مرحبا"));
+    assert!(text_needs_complex_shaping("שלום"));
+    assert!(text_needs_complex_shaping("नमस्ते"));
+    assert!(text_needs_complex_shaping("e\u{0301}"));
+}
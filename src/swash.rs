@@ -16,10 +16,51 @@ use crate::{CacheKey, Color, FontSystem};
 pub use swash::scale::image::{Content as SwashContent, Image as SwashImage};
 pub use swash::zeno::{Command, Placement};
 
+/// Glyph hinting mode used when rasterizing, see [`SwashCache::set_hinting`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Hinting {
+    /// Glyph outlines are not hinted, preserving their shape at all sizes
+    None,
+    /// Glyph outlines are hinted to the pixel grid, improving legibility of small text
+    #[default]
+    Full,
+}
+
+/// Antialiasing mode used when rasterizing, see [`SwashCache::set_antialiasing`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Antialiasing {
+    /// Glyph outlines are rasterized to alpha masks, with one coverage value per pixel
+    #[default]
+    Grayscale,
+    /// Glyph outlines are rasterized to [`SwashContent::SubpixelMask`] images, with a coverage
+    /// value for each of the red, green, and blue subpixels of a horizontal RGB display
+    ///
+    /// This sharpens small text on LCD displays, but causes color fringes on other displays, when
+    /// the text is scaled, or when it is drawn on a transparent background.
+    Subpixel,
+}
+
+/// Default for [`SwashCache::max_image_pixels`], the area of a 4096 by 4096 image
+const DEFAULT_MAX_IMAGE_PIXELS: u32 = 4096 * 4096;
+
+/// Get the variation coordinates of a cache key as swash settings
+fn variation_settings(
+    font_system: &FontSystem,
+    cache_key: CacheKey,
+) -> impl Iterator<Item = (u32, f32)> + '_ {
+    font_system
+        .variations(cache_key.variations_id)
+        .iter()
+        .map(|variation| (variation.tag.0, variation.value))
+}
+
 fn swash_image(
     font_system: &mut FontSystem,
     context: &mut ScaleContext,
     cache_key: CacheKey,
+    hinting: Hinting,
+    antialiasing: Antialiasing,
+    max_pixels: u32,
 ) -> Option<SwashImage> {
     let font = match font_system.get_font(cache_key.font_id) {
         Some(some) => some,
@@ -28,19 +69,43 @@ fn swash_image(
             return None;
         }
     };
+    let font_size = f32::from_bits(cache_key.font_size_bits);
+
+    // Estimate the image size from the glyph bounds, or the font bounds for bitmap glyphs, with a
+    // pixel of padding on each side for antialiasing and the subpixel offset
+    let face = font.rustybuzz();
+    let bounds = face
+        .glyph_bounding_box(rustybuzz::ttf_parser::GlyphId(cache_key.glyph_id))
+        .unwrap_or_else(|| face.global_bounding_box());
+    let scale = font_size / face.units_per_em() as f32;
+    let width = (i32::from(bounds.x_max) - i32::from(bounds.x_min)) as f32 * scale + 2.0;
+    let height = (i32::from(bounds.y_max) - i32::from(bounds.y_min)) as f32 * scale + 2.0;
+    let pixels = width * height;
+    if pixels.is_nan() || pixels > max_pixels as f32 {
+        log::warn!(
+            "glyph {} at size {} would be about {} pixels, more than the maximum of {}",
+            cache_key.glyph_id,
+            font_size,
+            pixels,
+            max_pixels
+        );
+        return None;
+    }
 
     // Build the scaler
     let mut scaler = context
         .builder(font.as_swash())
-        .size(f32::from_bits(cache_key.font_size_bits))
-        .hint(true)
+        .size(font_size)
+        .hint(hinting != Hinting::None)
+        .variations(variation_settings(font_system, cache_key))
         .build();
 
     // Compute the fractional offset-- you'll likely want to quantize this
     // in a real renderer
     let offset = Vector::new(cache_key.x_bin.as_float(), cache_key.y_bin.as_float());
 
-    // Select our source order
+    // Select our source order, each source is tried in turn until one renders, so color glyphs
+    // (COLR, CBDT, and sbix) become RGBA images and other glyphs fall back to an alpha mask
     Render::new(&[
         // Color outline with the first palette
         Source::ColorOutline(0),
@@ -49,8 +114,11 @@ fn swash_image(
         // Standard scalable outline
         Source::Outline,
     ])
-    // Select a subpixel format
-    .format(Format::Alpha)
+    // Select a subpixel format, which only applies to outlines
+    .format(match antialiasing {
+        Antialiasing::Grayscale => Format::Alpha,
+        Antialiasing::Subpixel => Format::Subpixel,
+    })
     // Apply the fractional offset
     .offset(offset)
     // Render the image
@@ -76,6 +144,7 @@ fn swash_outline_commands(
     let mut scaler = context
         .builder(font.as_swash())
         .size(f32::from_bits(cache_key.font_size_bits))
+        .variations(variation_settings(font_system, cache_key))
         .build();
 
     // Scale the outline
@@ -90,11 +159,27 @@ fn swash_outline_commands(
     Some(path.commands().collect())
 }
 
+/// Get a table adjusting the coverage of glyph pixels for `gamma`, see [`SwashCache::set_gamma`]
+fn gamma_table(gamma: f32) -> [u8; 256] {
+    let mut table = [0; 256];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = (libm::powf(i as f32 / 255.0, 1.0 / gamma) * 255.0 + 0.5) as u8;
+    }
+    table
+}
+
 /// Cache for rasterizing with the swash scaler
 pub struct SwashCache {
     context: ScaleContext,
     pub image_cache: Map<CacheKey, Option<SwashImage>>,
     pub outline_command_cache: Map<CacheKey, Option<Vec<swash::zeno::Command>>>,
+    premultiplied: bool,
+    hinting: Hinting,
+    antialiasing: Antialiasing,
+    subpixel_background: Option<Color>,
+    gamma: f32,
+    gamma_table: [u8; 256],
+    max_image_pixels: u32,
 }
 
 impl fmt::Debug for SwashCache {
@@ -110,6 +195,125 @@ impl SwashCache {
             context: ScaleContext::new(),
             image_cache: Map::new(),
             outline_command_cache: Map::new(),
+            premultiplied: false,
+            hinting: Hinting::default(),
+            antialiasing: Antialiasing::default(),
+            subpixel_background: None,
+            gamma: 1.0,
+            gamma_table: gamma_table(1.0),
+            max_image_pixels: DEFAULT_MAX_IMAGE_PIXELS,
+        }
+    }
+
+    /// Get whether [`Self::with_pixels`] emits colors with premultiplied alpha
+    pub fn premultiplied(&self) -> bool {
+        self.premultiplied
+    }
+
+    /// Set whether [`Self::with_pixels`] emits colors with premultiplied alpha
+    ///
+    /// This also applies to the glyphs and rectangles, such as selections, underlines, and
+    /// strikethroughs, of the `draw` functions of [`crate::Buffer`] and [`crate::Edit`]
+    /// implementations. Images returned by [`Self::get_image`] are not affected.
+    pub fn set_premultiplied(&mut self, premultiplied: bool) {
+        self.premultiplied = premultiplied;
+    }
+
+    /// Get the color to fill a rectangle drawn with glyphs from [`Self::with_pixels`]
+    pub(crate) fn rect_color(&self, color: Color) -> Color {
+        if self.premultiplied {
+            color.premultiplied()
+        } else {
+            color
+        }
+    }
+
+    /// Get the [`Hinting`] mode used when rasterizing images
+    pub fn hinting(&self) -> Hinting {
+        self.hinting
+    }
+
+    /// Set the [`Hinting`] mode used when rasterizing images
+    ///
+    /// Hinting improves the legibility of small text, but distorts outlines, which is unwanted for
+    /// large or animated text. Changing the mode clears cached images.
+    pub fn set_hinting(&mut self, hinting: Hinting) {
+        if hinting != self.hinting {
+            self.hinting = hinting;
+            self.image_cache.clear();
+        }
+    }
+
+    /// Get the [`Antialiasing`] mode used when rasterizing images
+    pub fn antialiasing(&self) -> Antialiasing {
+        self.antialiasing
+    }
+
+    /// Set the [`Antialiasing`] mode used when rasterizing images
+    ///
+    /// With [`Antialiasing::Subpixel`], [`Self::get_image`] returns the coverage of each
+    /// subpixel, for renderers that blend it themselves, and [`Self::with_pixels`] blends it as
+    /// described in [`Self::set_subpixel_background`]. Color glyphs, like emoji, are not affected.
+    /// Changing the mode clears cached images.
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        if antialiasing != self.antialiasing {
+            self.antialiasing = antialiasing;
+            self.image_cache.clear();
+        }
+    }
+
+    /// Get the background color subpixel images are blended against, see
+    /// [`Self::set_subpixel_background`]
+    pub fn subpixel_background(&self) -> Option<Color> {
+        self.subpixel_background
+    }
+
+    /// Set the background color subpixel images are blended against by [`Self::with_pixels`]
+    ///
+    /// The coverage of each subpixel can only be expressed as a single color when the color
+    /// behind the text is known. With a background, pixels of subpixel images are the text color
+    /// blended against it, with full alpha, and pixels without coverage are skipped. Without one,
+    /// they are the text color with the average coverage as alpha, like grayscale antialiasing.
+    pub fn set_subpixel_background(&mut self, subpixel_background: Option<Color>) {
+        self.subpixel_background = subpixel_background;
+    }
+
+    /// Get the gamma used to adjust the coverage of pixels, see [`Self::set_gamma`]
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Set the gamma used to adjust the coverage of pixels emitted by [`Self::with_pixels`]
+    ///
+    /// Each coverage value from 0.0 to 1.0 is raised to the power of `1.0 / gamma`, so values
+    /// above the default of 1.0 make antialiased edges darker and text bolder, like the contrast
+    /// settings of native toolkits, and values below it make them lighter. Cached images are not
+    /// affected. Values that are not positive are ignored.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        if gamma.is_nan() || gamma <= 0.0 {
+            log::warn!("ignoring gamma {}, which is not positive", gamma);
+            return;
+        }
+        if gamma != self.gamma {
+            self.gamma = gamma;
+            self.gamma_table = gamma_table(gamma);
+        }
+    }
+
+    /// Get the maximum number of pixels in an image, see [`Self::set_max_image_pixels`]
+    pub fn max_image_pixels(&self) -> u32 {
+        self.max_image_pixels
+    }
+
+    /// Set the maximum number of pixels in an image
+    ///
+    /// Glyphs whose image would be larger, as estimated from their bounds before rasterizing, are
+    /// not rasterized and have no image, so that huge font sizes cannot exhaust memory. The
+    /// default is the area of a 4096 by 4096 image. Changing the maximum clears cached images.
+    pub fn set_max_image_pixels(&mut self, max_image_pixels: u32) {
+        if max_image_pixels != self.max_image_pixels {
+            self.max_image_pixels = max_image_pixels;
+            self.image_cache.clear();
         }
     }
 
@@ -119,18 +323,62 @@ impl SwashCache {
         font_system: &mut FontSystem,
         cache_key: CacheKey,
     ) -> Option<SwashImage> {
-        swash_image(font_system, &mut self.context, cache_key)
+        swash_image(
+            font_system,
+            &mut self.context,
+            cache_key,
+            self.hinting,
+            self.antialiasing,
+            self.max_image_pixels,
+        )
     }
 
     /// Create a swash Image from a cache key, caching results
+    ///
+    /// The image is rasterized once per [`CacheKey`] and reused by later calls, so a renderer can
+    /// upload it to a texture atlas once instead of drawing it pixel by pixel. The
+    /// [`SwashImage::placement`] is the offset of the image from the glyph position, with `top`
+    /// measured upward, and the [`SwashImage::content`] tells whether the data is an alpha mask,
+    /// [`SwashContent::Mask`], or RGBA color, [`SwashContent::Color`]. Glyphs that would have
+    /// more than [`Self::max_image_pixels`] pixels have no image.
     pub fn get_image(
         &mut self,
         font_system: &mut FontSystem,
         cache_key: CacheKey,
     ) -> &Option<SwashImage> {
-        self.image_cache
-            .entry(cache_key)
-            .or_insert_with(|| swash_image(font_system, &mut self.context, cache_key))
+        let hinting = self.hinting;
+        let antialiasing = self.antialiasing;
+        let max_pixels = self.max_image_pixels;
+        self.image_cache.entry(cache_key).or_insert_with(|| {
+            swash_image(
+                font_system,
+                &mut self.context,
+                cache_key,
+                hinting,
+                antialiasing,
+                max_pixels,
+            )
+        })
+    }
+
+    /// Create a swash Image from a cache key at `scale` times its font size, caching results
+    ///
+    /// This rasterizes glyphs for a higher resolution than the one they were laid out for, such
+    /// as for exporting text laid out for a 96 DPI screen to 300 DPI print with a scale of 3.125.
+    /// Images are cached like [`Self::get_image`], for the cache key with the scaled font size,
+    /// so each scale is rasterized once.
+    pub fn get_image_at_scale(
+        &mut self,
+        font_system: &mut FontSystem,
+        cache_key: CacheKey,
+        scale: f32,
+    ) -> &Option<SwashImage> {
+        let font_size = f32::from_bits(cache_key.font_size_bits) * scale;
+        let cache_key = CacheKey {
+            font_size_bits: font_size.to_bits(),
+            ..cache_key
+        };
+        self.get_image(font_system, cache_key)
     }
 
     pub fn get_outline_commands(
@@ -145,6 +393,11 @@ impl SwashCache {
     }
 
     /// Enumerate pixels in an Image, use `with_image` for better performance
+    ///
+    /// Pixels of alpha masks have the `base` color, with the alpha of the mask adjusted by
+    /// [`Self::gamma`]. Pixels of subpixel masks are blended as described in
+    /// [`Self::set_subpixel_background`]. Pixels of color glyphs, like emoji, have the colors of
+    /// the glyph and ignore `base`.
     pub fn with_pixels<F: FnMut(i32, i32, Color)>(
         &mut self,
         font_system: &mut FontSystem,
@@ -152,6 +405,16 @@ impl SwashCache {
         base: Color,
         mut f: F,
     ) {
+        let premultiplied = self.premultiplied;
+        let subpixel_background = self.subpixel_background;
+        let gamma_table = self.gamma_table;
+        let mut f = |x, y, color: Color| {
+            if premultiplied {
+                f(x, y, color.premultiplied());
+            } else {
+                f(x, y, color);
+            }
+        };
         if let Some(image) = self.get_image(font_system, cache_key) {
             let x = image.placement.left;
             let y = -image.placement.top;
@@ -165,7 +428,10 @@ impl SwashCache {
                             f(
                                 x + off_x,
                                 y + off_y,
-                                Color(((image.data[i] as u32) << 24) | base.0 & 0xFF_FF_FF),
+                                Color(
+                                    ((gamma_table[image.data[i] as usize] as u32) << 24)
+                                        | base.0 & 0xFF_FF_FF,
+                                ),
                             );
                             i += 1;
                         }
@@ -191,7 +457,39 @@ impl SwashCache {
                     }
                 }
                 Content::SubpixelMask => {
-                    log::warn!("TODO: SubpixelMask");
+                    let mut i = 0;
+                    for off_y in 0..image.placement.height as i32 {
+                        for off_x in 0..image.placement.width as i32 {
+                            let r = gamma_table[image.data[i] as usize];
+                            let g = gamma_table[image.data[i + 1] as usize];
+                            let b = gamma_table[image.data[i + 2] as usize];
+                            i += 4;
+                            let color = match subpixel_background {
+                                Some(background) => {
+                                    if r == 0 && g == 0 && b == 0 {
+                                        continue;
+                                    }
+                                    let blend = |fg: u8, bg: u8, coverage: u8| {
+                                        let coverage = coverage as u32;
+                                        ((fg as u32 * coverage
+                                            + bg as u32 * (255 - coverage)
+                                            + 127)
+                                            / 255) as u8
+                                    };
+                                    Color::rgb(
+                                        blend(base.r(), background.r(), r),
+                                        blend(base.g(), background.g(), g),
+                                        blend(base.b(), background.b(), b),
+                                    )
+                                }
+                                None => {
+                                    let a = (r as u32 + g as u32 + b as u32) / 3;
+                                    Color((a << 24) | base.0 & 0xFF_FF_FF)
+                                }
+                            };
+                            f(x + off_x, y + off_y, color);
+                        }
+                    }
                 }
             }
         }
@@ -0,0 +1,72 @@
+use cosmic_text::{fontdb, Align, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn aligned_buffer(font_system: &mut FontSystem, text: &str, align: Align) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 100.0, 500.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    for line in buffer.lines.iter_mut() {
+        line.set_align(Some(align));
+    }
+    buffer.shape_until_scroll(font_system);
+    buffer
+}
+
+#[test]
+fn align_shifts_glyphs_and_hit() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    for (align, x) in [
+        (Align::Left, 0.0),
+        (Align::Right, 100.0 - 3.0 * GLYPH_W),
+        (Align::End, 100.0 - 3.0 * GLYPH_W),
+        (Align::Center, (100.0 - 3.0 * GLYPH_W) / 2.0),
+    ] {
+        let buffer = aligned_buffer(&mut font_system, "abc", align);
+        let run = buffer.layout_runs().next().unwrap();
+        assert!((run.glyphs[0].x - x).abs() < 0.01, "{align}");
+
+        // Hitting either side of the shifted glyphs places the cursor at the nearest end
+        let hit = |x| {
+            let cursor = buffer.hit(x, 10.0).unwrap();
+            (cursor.line, cursor.index)
+        };
+        assert_eq!(hit(x + GLYPH_W * 0.25), (0, 0), "{align}");
+        assert_eq!(hit(x + GLYPH_W * 1.75), (0, 2), "{align}");
+        if x > 1.0 {
+            assert_eq!(hit(0.5), (0, 0), "{align}");
+        }
+        assert_eq!(hit(99.5), (0, 3), "{align}");
+    }
+}
+
+#[test]
+fn justify_all_but_last_line() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let buffer = aligned_buffer(&mut font_system, "aa bb cc dddddddd", Align::Justified);
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 2);
+
+    // The blank space of the first line is stretched, so the last word moves right
+    let first = &runs[0];
+    assert_eq!(first.glyphs[0].x, 0.0);
+    let c = first.glyphs.iter().find(|glyph| glyph.start == 6).unwrap();
+    assert!(c.x > 6.0 * GLYPH_W + 1.0);
+
+    // The last line is not stretched
+    let last = &runs[1];
+    for (i, glyph) in last.glyphs.iter().enumerate() {
+        assert!((glyph.x - i as f32 * GLYPH_W).abs() < 0.01);
+    }
+}
@@ -0,0 +1,31 @@
+use cosmic_text::{fontdb, Buffer, FontSystem, Metrics};
+
+#[test]
+fn ambiguous_width_cells() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+
+    // Ambiguous characters occupy one cell by default
+    assert_eq!(buffer.ambiguous_width(), 1);
+    assert_eq!(buffer.cell_width('±'), 1);
+    assert_eq!(buffer.cell_width('─'), 1);
+
+    buffer.set_ambiguous_width(&mut font_system, 2);
+    assert_eq!(buffer.ambiguous_width(), 2);
+    assert_eq!(buffer.cell_width('±'), 2);
+    assert_eq!(buffer.cell_width('─'), 2);
+
+    // Narrow and wide characters are not affected
+    assert_eq!(buffer.cell_width('a'), 1);
+    assert_eq!(buffer.cell_width('中'), 2);
+    buffer.set_ambiguous_width(&mut font_system, 1);
+    assert_eq!(buffer.cell_width('a'), 1);
+    assert_eq!(buffer.cell_width('中'), 2);
+
+    // Out of range values are clamped
+    buffer.set_ambiguous_width(&mut font_system, 0);
+    assert_eq!(buffer.ambiguous_width(), 1);
+    buffer.set_ambiguous_width(&mut font_system, 3);
+    assert_eq!(buffer.ambiguous_width(), 2);
+}
@@ -0,0 +1,92 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+// Styling the selection must not move the cursor or selection, even after reshaping
+#[test]
+fn apply_attrs_preserves_cursor_and_selection() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "hello\nworld", attrs, Shaping::Advanced);
+
+    let mut editor = Editor::new(buffer);
+    editor.set_select_opt(Some(Cursor::new(0, 2)));
+    editor.set_cursor(Cursor::new(1, 3));
+    let cursor = editor.cursor();
+    let select_opt = editor.select_opt();
+
+    // The test font only has one weight, so style with a color
+    editor.apply_attrs(attrs.color(Color::rgb(0xFF, 0, 0)));
+    editor.shape_as_needed(&mut font_system);
+
+    assert_eq!(editor.cursor(), cursor);
+    assert_eq!(editor.select_opt(), select_opt);
+    let lines = &editor.buffer().lines;
+    assert_eq!(lines[0].attrs_list().get_span(1).color_opt, None);
+    assert_eq!(
+        lines[0].attrs_list().get_span(2).color_opt,
+        Some(Color::rgb(0xFF, 0, 0))
+    );
+    assert_eq!(
+        lines[1].attrs_list().get_span(2).color_opt,
+        Some(Color::rgb(0xFF, 0, 0))
+    );
+    assert_eq!(lines[1].attrs_list().get_span(3).color_opt, None);
+}
+
+// Lines whose selected text already has the attributes are not reshaped
+#[test]
+fn apply_attrs_skips_unchanged_lines() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let bold = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::BOLD);
+    let text = vec!["bold line"; 100].join("\n");
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(&mut font_system, &text, bold, Shaping::Advanced);
+    for line_i in 0..buffer.lines.len() {
+        buffer.line_shape(&mut font_system, line_i);
+    }
+    let content_version = buffer.content_version();
+
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(99, 9));
+    editor.set_select_opt(Some(Cursor::new(0, 2)));
+    editor.apply_attrs(bold);
+    assert!(editor.buffer().lines.iter().all(|line| !line.is_reset()));
+    assert_eq!(editor.buffer().content_version(), content_version);
+
+    // Only the lines that change are reshaped
+    let color = bold.color(Color::rgb(0xFF, 0, 0));
+    editor.set_cursor(Cursor::new(1, 9));
+    editor.apply_attrs(color);
+    let lines = &editor.buffer().lines;
+    assert!(lines[..2].iter().all(|line| line.is_reset()));
+    assert!(lines[2..].iter().all(|line| !line.is_reset()));
+    assert_ne!(editor.buffer().content_version(), content_version);
+
+    assert_eq!(lines[0].attrs_list().get_span(1), bold);
+    assert_eq!(lines[0].attrs_list().get_span(2), color);
+
+    // Applying the same attributes again, or to a part of the styled text, changes nothing
+    editor.buffer_mut().line_shape(&mut font_system, 0);
+    editor.buffer_mut().line_shape(&mut font_system, 1);
+    editor.apply_attrs(color);
+    editor.set_select_opt(Some(Cursor::new(0, 4)));
+    editor.apply_attrs(color);
+    assert!(editor.buffer().lines.iter().all(|line| !line.is_reset()));
+}
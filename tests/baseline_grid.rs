@@ -0,0 +1,47 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn baselines_snap_to_grid() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(500.0, 200.0);
+    buffer.set_text("abc\ndef", attrs, Shaping::Advanced);
+    // The second line is taller because of its ruby annotation
+    buffer.lines[1].set_ruby(0..3, "xyz", attrs);
+    buffer.shape_until_scroll();
+
+    let baselines = |buffer: &Buffer| {
+        buffer
+            .layout_runs()
+            .map(|run| run.line_y)
+            .collect::<Vec<_>>()
+    };
+
+    let natural = baselines(&buffer);
+    assert_eq!(natural.len(), 2);
+
+    buffer.set_baseline_grid(Some(24.0));
+    let snapped = baselines(&buffer);
+    assert_eq!(snapped.len(), 2);
+    for (natural_y, snapped_y) in natural.iter().zip(snapped.iter()) {
+        assert_eq!(
+            snapped_y % 24.0,
+            0.0,
+            "baseline {} is not on the grid",
+            snapped_y
+        );
+        assert!(snapped_y >= natural_y);
+    }
+    assert!(snapped[1] > snapped[0]);
+
+    // Each line takes whole grid rows, two for the annotated line, so fewer fit in the buffer
+    assert_eq!(buffer.visible_lines(), 7);
+}
@@ -0,0 +1,36 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+// Bidi controls are invisible by default, which allows hiding direction manipulation in source
+// code. Revealing them should produce a visible, highlighted glyph for the control.
+#[test]
+fn reveal_bidi_controls() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(500.0, 100.0);
+    // U+202E RIGHT-TO-LEFT OVERRIDE is at bytes 3..6
+    buffer.set_text("abc\u{202E}def", attrs, Shaping::Advanced);
+
+    let control_glyph = |buffer: &Buffer| {
+        buffer
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter())
+            .find(|glyph| glyph.start == 3)
+            .map(|glyph| (glyph.w, glyph.color_opt))
+    };
+
+    let hidden = control_glyph(&buffer);
+    assert_eq!(hidden.map_or(0.0, |(w, _)| w), 0.0);
+
+    buffer.set_reveal_bidi_controls(true);
+    let (w, color_opt) = control_glyph(&buffer).expect("no glyph for revealed control");
+    assert!(w > 0.0, "revealed control has no advance");
+    assert!(color_opt.is_some(), "revealed control is not highlighted");
+}
@@ -0,0 +1,82 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Rectangle, Shaping,
+    Weight,
+};
+
+fn bidi_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 300.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+// Get the left and right edges of the glyph starting at `index` in the first line
+fn glyph_edges(editor: &Editor, index: usize) -> (f32, f32) {
+    let run = editor.buffer().layout_runs().next().unwrap();
+    let glyph = run
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == index)
+        .unwrap();
+    (glyph.x, glyph.x + glyph.w)
+}
+
+fn select(editor: &mut Editor, start: usize, end: usize) -> Vec<Rectangle> {
+    editor.set_select_opt(Some(Cursor::new(0, start)));
+    editor.set_cursor(Cursor::new(0, end));
+    let mut rects = editor.selection_bounds();
+    rects.sort_by(|a, b| a.x.total_cmp(&b.x));
+    rects
+}
+
+fn assert_edges(rect: &Rectangle, left: f32, right: f32) {
+    assert!((rect.x - left).abs() < 0.01, "{:?}", rect);
+    assert!((rect.x + rect.width - right).abs() < 0.01, "{:?}", rect);
+}
+
+#[test]
+fn selection_across_direction_change() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Hebrew letters are two bytes each and are laid out right to left after "abc "
+    let mut editor = bidi_editor(&mut font_system, "abc \u{5D0}\u{5D1}\u{5D2}");
+    let c = glyph_edges(&editor, 2);
+    let alef = glyph_edges(&editor, 4);
+    let gimel = glyph_edges(&editor, 8);
+    assert!(gimel.0 < alef.0);
+
+    // Selecting all of the RTL text gives one rectangle over the boundary
+    let rects = select(&mut editor, 2, 10);
+    assert_eq!(rects.len(), 1);
+    assert_edges(&rects[0], c.0, alef.1);
+
+    // Selecting only the first RTL letter leaves a gap where the unselected letters are
+    let rects = select(&mut editor, 2, 6);
+    assert_eq!(rects.len(), 2);
+    assert_edges(&rects[0], c.0, gimel.0);
+    assert_edges(&rects[1], alef.0, alef.1);
+
+    // Selecting backwards gives the same rectangles
+    editor.set_select_opt(Some(Cursor::new(0, 6)));
+    editor.set_cursor(Cursor::new(0, 2));
+    let mut backwards = editor.selection_bounds();
+    backwards.sort_by(|a, b| a.x.total_cmp(&b.x));
+    assert_eq!(backwards, rects);
+
+    // Rectangles never overlap
+    for (start, end) in [(0, 10), (3, 8), (6, 10), (4, 6)] {
+        let rects = select(&mut editor, start, end);
+        assert!(!rects.is_empty());
+        for pair in rects.windows(2) {
+            assert!(pair[0].x + pair[0].width < pair[1].x);
+        }
+    }
+}
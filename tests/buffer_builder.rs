@@ -0,0 +1,70 @@
+use cosmic_text::{
+    fontdb, Align, Attrs, Buffer, BufferBuilder, Family, FontSystem, Metrics, Overflow, Shaping,
+    Weight, Wrap, WritingMode,
+};
+
+#[test]
+fn builder_matches_setters() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let metrics = Metrics::new(14.0, 20.0);
+    let text = "a line that wraps in the buffer\nshort\tand\there\n\nlast";
+
+    let built = BufferBuilder::new(metrics)
+        .size(120.0, 200.0)
+        .wrap(Wrap::Glyph)
+        .max_lines(Some(2))
+        .overflow(Overflow::Ellipsis)
+        .tab_width(2)
+        .align(Some(Align::Center))
+        .text(text, attrs, Shaping::Advanced)
+        .build(&mut font_system);
+
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    let mut set = buffer.borrow_with(&mut font_system);
+    set.set_size(120.0, 200.0);
+    set.set_wrap(Wrap::Glyph);
+    set.set_max_lines(Some(2));
+    set.set_overflow(Overflow::Ellipsis);
+    set.set_tab_width(2);
+    set.set_text(text, attrs, Shaping::Advanced);
+    for line in set.lines.iter_mut() {
+        line.set_align(Some(Align::Center));
+    }
+    set.shape_until_scroll();
+
+    let runs = |buffer: &Buffer| {
+        buffer
+            .layout_runs()
+            .map(|run| {
+                let glyphs = run
+                    .glyphs
+                    .iter()
+                    .map(|glyph| (glyph.start, glyph.end, glyph.x, glyph.w))
+                    .collect::<Vec<_>>();
+                (run.line_i, run.line_y, run.line_w, glyphs)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(built.size(), (120.0, 200.0));
+    assert_eq!(built.wrap(), Wrap::Glyph);
+    assert_eq!(built.tab_width(), 2);
+    assert_eq!(built.lines.len(), 4);
+    assert!(runs(&built).len() > built.lines.len());
+    assert_eq!(runs(&built), runs(&set));
+
+    // The size is clamped and rotated like with the setters
+    let built = BufferBuilder::new(metrics)
+        .writing_mode(WritingMode::VerticalRl)
+        .size(-10.0, 200.0)
+        .build(&mut font_system);
+    assert_eq!(built.writing_mode(), WritingMode::VerticalRl);
+    assert_eq!(built.size(), (0.0, 200.0));
+}
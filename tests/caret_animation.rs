@@ -0,0 +1,33 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping, Weight,
+};
+
+#[test]
+fn caret_animation_interpolates_between_positions() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "abcdefgh", attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(&mut font_system);
+    let (start_x, y, _, _) = editor.caret_rect().unwrap();
+    assert_eq!(editor.caret_animation(0.5).unwrap().0, start_x);
+
+    editor.set_cursor(Cursor::new(0, 8));
+    editor.shape_as_needed(&mut font_system);
+    let (end_x, _, _, _) = editor.caret_rect().unwrap();
+    assert!(end_x > start_x + 2);
+
+    assert_eq!(editor.caret_animation(0.0).unwrap().0, start_x);
+    assert_eq!(editor.caret_animation(1.0).unwrap().0, end_x);
+    let (mid_x, mid_y, _, _) = editor.caret_animation(0.5).unwrap();
+    assert!((mid_x - (start_x + end_x) / 2).abs() <= 1);
+    assert_eq!(mid_y, y);
+}
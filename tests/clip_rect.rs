@@ -0,0 +1,58 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, ClipRect, Color, Family, FontSystem, Metrics, Shaping, SwashCache,
+    UnderlineStyle, Weight,
+};
+
+#[test]
+fn clip_rect_limits_emitted_pixels() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(
+        &mut font_system,
+        "H",
+        attrs.underline(UnderlineStyle::Solid),
+        Shaping::Advanced,
+    );
+
+    let mut cache = SwashCache::new();
+    let color = Color::rgb(0xFF, 0xFF, 0xFF);
+    let mut unclipped = Vec::new();
+    buffer.draw(&mut font_system, &mut cache, color, |x, y, w, h, _| {
+        unclipped.push((x, y, w, h))
+    });
+    let (min_x, max_x) = unclipped
+        .iter()
+        .fold((i32::MAX, i32::MIN), |(min_x, max_x), (x, _, w, _)| {
+            (min_x.min(*x), max_x.max(x + *w as i32))
+        });
+    assert!(max_x - min_x > 2);
+
+    // Clip through the middle of the glyph
+    let mid_x = (min_x + max_x) / 2;
+    let clip = ClipRect::new(mid_x, 0, 100, 100);
+    let mut clipped = Vec::new();
+    buffer.draw_clipped(
+        &mut font_system,
+        &mut cache,
+        color,
+        clip,
+        |x, y, w, h, _| clipped.push((x, y, w, h)),
+    );
+
+    assert!(!clipped.is_empty());
+    assert!(clipped.len() < unclipped.len());
+    for (x, y, w, h) in clipped {
+        assert!(x >= mid_x && y >= 0);
+        assert!(x + w as i32 <= 100 && y + h as i32 <= 100);
+    }
+}
@@ -0,0 +1,125 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 200.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn text(editor: &Editor) -> String {
+    let lines: Vec<&str> = editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.text())
+        .collect();
+    lines.join("\n")
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn copy_in_either_order() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "héllo\nwörld\nagain");
+
+    assert_eq!(editor.copy_selection(), None);
+
+    // Anchor before the cursor
+    editor.set_select_opt(Some(Cursor::new(0, 1)));
+    editor.set_cursor(Cursor::new(2, 2));
+    assert_eq!(editor.copy_selection().as_deref(), Some("éllo\nwörld\nag"));
+
+    // Anchor after the cursor
+    editor.set_select_opt(Some(Cursor::new(2, 2)));
+    editor.set_cursor(Cursor::new(0, 1));
+    assert_eq!(editor.copy_selection().as_deref(), Some("éllo\nwörld\nag"));
+
+    // Within one line
+    editor.set_select_opt(Some(Cursor::new(1, 4)));
+    editor.set_cursor(Cursor::new(1, 1));
+    assert_eq!(editor.copy_selection().as_deref(), Some("ör"));
+
+    // Copying does not change the editor
+    editor.action(&mut font_system, Action::Copy);
+    assert_eq!(text(&editor), "héllo\nwörld\nagain");
+    assert_eq!(editor.select_opt(), Some(Cursor::new(1, 4)));
+}
+
+#[test]
+fn collapsed_selection_is_noop() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo");
+
+    editor.set_cursor(Cursor::new(1, 1));
+    editor.set_select_opt(Some(Cursor::new(1, 1)));
+    assert_eq!(editor.copy_selection(), None);
+    assert_eq!(editor.cut_selection(), None);
+    editor.action(&mut font_system, Action::Cut);
+    assert_eq!(text(&editor), "one\ntwo");
+}
+
+#[test]
+fn cut_and_paste() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo\nthree");
+
+    editor.set_select_opt(Some(Cursor::new(1, 3)));
+    editor.set_cursor(Cursor::new(0, 1));
+    assert_eq!(editor.cut_selection().as_deref(), Some("ne\ntwo"));
+    assert_eq!(text(&editor), "o\nthree");
+    assert_eq!(editor.select_opt(), None);
+    assert_eq!((editor.cursor().line, editor.cursor().index), (0, 1));
+
+    // Pasting at the cursor splits lines on newlines
+    editor.set_cursor(Cursor::new(1, 2));
+    editor.action(&mut font_system, Action::Paste("ab\ncd\n".into()));
+    assert_eq!(text(&editor), "o\nthab\ncd\nree");
+    assert_eq!((editor.cursor().line, editor.cursor().index), (3, 0));
+
+    // Pasting replaces the selection, as one change
+    editor.set_select_opt(Some(Cursor::new(0, 0)));
+    editor.set_cursor(Cursor::new(1, 2));
+    editor.action(&mut font_system, Action::Paste("x".into()));
+    assert_eq!(text(&editor), "xab\ncd\nree");
+    editor.undo();
+    assert_eq!(text(&editor), "o\nthab\ncd\nree");
+
+    // Cutting through an action deletes the selection
+    editor.set_select_opt(Some(Cursor::new(3, 3)));
+    editor.set_cursor(Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::Cut);
+    assert_eq!(text(&editor), "o\n");
+}
+
+#[test]
+fn paste_line_endings() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo");
+
+    // CRLF is one line ending, other control characters are kept
+    editor.set_select_opt(Some(Cursor::new(1, 1)));
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::Paste("a\r\nb\t\r\nc\t".into()));
+    assert_eq!(text(&editor), "oa\nb\t\nc\two");
+    assert_eq!((editor.cursor().line, editor.cursor().index), (2, 2));
+    assert_eq!(editor.select_opt(), None);
+
+    editor.action(&mut font_system, Action::Undo);
+    assert_eq!(text(&editor), "one\ntwo");
+}
@@ -0,0 +1,34 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Rectangle, Shaping, Weight};
+
+#[test]
+fn content_bounds() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 30.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+
+    // Nothing is rendered
+    assert_eq!(buffer.content_bounds(), Rectangle::default());
+
+    buffer.set_text(&mut font_system, "Hi", attrs, Shaping::Advanced);
+    let bounds = buffer.content_bounds();
+    let run = buffer.layout_runs().next().unwrap();
+
+    // Tighter than the buffer size and the line height
+    assert_eq!(bounds.x, 0.0);
+    assert!((bounds.width - run.line_w).abs() < 0.001);
+    assert!(bounds.width < 200.0);
+    assert!(bounds.y > 0.0);
+    assert!(bounds.height > 0.0);
+    assert!(bounds.height < 30.0);
+
+    // The bounds contain the baseline
+    assert!(bounds.y < run.line_y);
+    assert!(bounds.y + bounds.height > run.line_y);
+}
@@ -0,0 +1,45 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Edit, Editor, Family, FontSystem, Metrics, Shaping, Weight,
+};
+
+#[test]
+fn content_version() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 40.0);
+    let version = buffer.content_version();
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo\nthree\nfour\nfive",
+        attrs,
+        Shaping::Advanced,
+    );
+    assert_ne!(buffer.content_version(), version);
+
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(&mut font_system);
+    let saved = editor.buffer().content_version();
+
+    // View only changes do not change the content version
+    editor.action(&mut font_system, Action::Scroll { lines: 2 });
+    editor.action(&mut font_system, Action::Down);
+    editor.action(&mut font_system, Action::End);
+    editor.buffer_mut().set_size(&mut font_system, 300.0, 60.0);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.buffer().content_version(), saved);
+
+    // Typing does
+    editor.action(&mut font_system, Action::Insert('!'));
+    let typed = editor.buffer().content_version();
+    assert_ne!(typed, saved);
+
+    editor.action(&mut font_system, Action::Backspace);
+    assert_ne!(editor.buffer().content_version(), typed);
+}
@@ -0,0 +1,40 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight, Wrap,
+};
+
+#[test]
+fn content_width_follows_longest_line() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::None);
+    buffer.set_size(&mut font_system, 100.0, 200.0);
+    buffer.set_text(
+        &mut font_system,
+        "short\na much longer line of text\nmid length",
+        attrs,
+        Shaping::Advanced,
+    );
+
+    let line_w =
+        |buffer: &Buffer, line_i: usize| buffer.lines[line_i].layout_opt().as_ref().unwrap()[0].w;
+    let longest = line_w(&buffer, 1);
+    assert!(longest > buffer.size().0);
+    assert_eq!(buffer.content_width(), longest);
+
+    // Shortening the longest line makes the next longest line the widest
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(1, 1));
+    editor.set_select_opt(Some(Cursor::new(1, "a much longer line of text".len())));
+    editor.action(&mut font_system, Action::Delete);
+    editor.shape_as_needed(&mut font_system);
+    let buffer = editor.buffer();
+    assert_eq!(buffer.content_width(), line_w(buffer, 2));
+}
@@ -0,0 +1,19 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Shaping};
+
+#[test]
+fn current_line_text_and_column() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "first\nhello",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+    assert_eq!(editor.current_line(), ("first", 0));
+
+    editor.set_cursor(Cursor::new(1, 1));
+    assert_eq!(editor.current_line(), ("hello", 1));
+}
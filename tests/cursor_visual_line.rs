@@ -0,0 +1,41 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+#[test]
+fn cursor_visual_line_counts_wrapped_lines() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 200.0);
+    buffer.set_text(
+        &mut font_system,
+        "first line wraps\nsecond",
+        attrs,
+        Shaping::Advanced,
+    );
+    assert_eq!(buffer.lines[0].layout_opt().as_ref().unwrap().len(), 2);
+
+    let mut editor = Editor::new(buffer);
+    assert_eq!(editor.cursor_visual_line(), 0);
+
+    editor.set_cursor(Cursor::new(0, "first line w".len()));
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.cursor_visual_line(), 1);
+
+    editor.set_cursor(Cursor::new(1, 3));
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.cursor().line, 1);
+    assert_eq!(editor.cursor_visual_line(), 2);
+
+    // Moving up from the second line lands on the wrapped row of the first
+    editor.action(&mut font_system, Action::Up);
+    assert_eq!(editor.cursor_visual_line(), 1);
+}
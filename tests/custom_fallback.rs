@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex};
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Fallback, Family, FontSystem, Metrics, Script, Shaping, Weight,
+};
+
+struct CopyFallback;
+
+impl Fallback for CopyFallback {
+    fn common_fallback(&self) -> &[&str] {
+        &["Fira Mono Copy 3"]
+    }
+
+    fn forbidden_fallback(&self) -> &[&str] {
+        &["Fira Mono Copy 0", "Fira Mono Copy 1"]
+    }
+
+    fn script_fallback(&self, script: Script, _locale: &str) -> &[&str] {
+        match script {
+            Script::Hebrew => &["Fira Mono Copy 2"],
+            _ => &[],
+        }
+    }
+}
+
+#[test]
+fn custom_fallback() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Register the same font data under other family names, as more fallback candidates
+    let face = font_system.db().faces().next().unwrap().clone();
+    for i in 0..4 {
+        let mut copy = face.clone();
+        copy.families = vec![(
+            format!("Fira Mono Copy {}", i),
+            fontdb::Language::English_UnitedStates,
+        )];
+        font_system.db_mut().push_face_info(copy);
+    }
+
+    font_system.set_fallback(CopyFallback);
+    assert_eq!(
+        font_system.fallback().common_fallback(),
+        ["Fira Mono Copy 3"]
+    );
+
+    // Deny every candidate, recording the order they are tried in
+    let tried = Arc::new(Mutex::new(Vec::new()));
+    let hook_tried = tried.clone();
+    font_system.set_font_load_hook(Box::new(move |face| {
+        hook_tried.lock().unwrap().push(face.families[0].0.clone());
+        false
+    }));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    buffer.set_text(&mut font_system, "\u{5D0}", attrs, Shaping::Advanced);
+
+    // The script families come first, then the common ones, and forbidden ones are never used
+    let tried = tried.lock().unwrap();
+    assert_eq!(tried[..2], ["Fira Mono Copy 2", "Fira Mono Copy 3"]);
+    assert!(tried
+        .iter()
+        .all(|name| name != "Fira Mono Copy 0" && name != "Fira Mono Copy 1"));
+}
@@ -0,0 +1,54 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn damaged_lines() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 500.0);
+    buffer.set_text(
+        &mut font_system,
+        "0\n1\n2\n3\n4\n5\n6\n7\n8\n9",
+        attrs,
+        Shaping::Advanced,
+    );
+    assert_eq!(buffer.damaged_lines(), Some(0..10));
+    buffer.clear_damage();
+    assert_eq!(buffer.damaged_lines(), None);
+
+    // Only the edited line is laid out again
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(3, 1));
+    editor.action(&mut font_system, Action::Insert('x'));
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.buffer().damaged_lines(), Some(3..4));
+
+    // Inserting a line moves the lines after it
+    editor.buffer_mut().clear_damage();
+    editor.action(&mut font_system, Action::Enter);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.buffer().damaged_lines(), Some(3..11));
+
+    // Scrolling keeps the layout of every line
+    editor.buffer_mut().clear_damage();
+    editor.action(&mut font_system, Action::Scroll { lines: 1 });
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.buffer().damaged_lines(), None);
+
+    // Changing the layout settings damages every laid out line
+    editor.buffer_mut().set_size(&mut font_system, 400.0, 500.0);
+    assert_eq!(editor.buffer().damaged_lines(), Some(0..11));
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, SwashCache, Weight,
+};
+
+#[test]
+fn default_color_for_spans_without_color() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "abc", attrs, Shaping::Advanced);
+    buffer.set_default_color(Some(Color::rgb(0xFF, 0, 0)));
+
+    let mut cache = SwashCache::new();
+    let mut colors = Vec::new();
+    buffer.draw(
+        &mut font_system,
+        &mut cache,
+        Color::rgb(0xFF, 0xFF, 0xFF),
+        |_, _, _, _, color| colors.push(color),
+    );
+
+    assert!(!colors.is_empty());
+    assert!(colors
+        .iter()
+        .all(|color| (color.r(), color.g(), color.b()) == (0xFF, 0, 0)));
+}
@@ -0,0 +1,89 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, AttrsList, Buffer, BufferLine, Color, Cursor, Edit, Editor, Family,
+    FontSystem, Metrics, Shaping, Weight,
+};
+
+fn editor(font_system: &mut FontSystem) -> (Editor, Attrs<'static>, Attrs<'static>) {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let red = attrs.color(Color::rgb(0xFF, 0, 0));
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 200.0, 100.0);
+    buffer.lines = vec![
+        BufferLine::new("one", AttrsList::new(attrs), Shaping::Advanced),
+        BufferLine::new("two", AttrsList::new(red), Shaping::Advanced),
+    ];
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    (editor, attrs, red)
+}
+
+#[test]
+fn backspace_joins_lines_with_attrs() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let (mut editor, attrs, red) = editor(&mut font_system);
+
+    editor.set_cursor(Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::Backspace);
+    let cursor = editor.cursor();
+    assert_eq!((cursor.line, cursor.index), (0, 3));
+
+    let buffer = editor.buffer();
+    assert_eq!(buffer.lines.len(), 1);
+    assert_eq!(buffer.lines[0].text(), "onetwo");
+    let attrs_list = buffer.lines[0].attrs_list();
+    assert_eq!(attrs_list.get_span(0), attrs);
+    assert_eq!(attrs_list.get_span(3), red);
+    assert_eq!(attrs_list.get_span(5), red);
+}
+
+#[test]
+fn delete_at_buffer_boundaries_is_noop() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let (mut editor, _, _) = editor(&mut font_system);
+
+    // Backspace at the start of the buffer
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::Backspace);
+    let cursor = editor.cursor();
+    assert_eq!((cursor.line, cursor.index), (0, 0));
+
+    // Delete at the end of the buffer
+    editor.set_cursor(Cursor::new(1, 3));
+    editor.action(&mut font_system, Action::Delete);
+    let cursor = editor.cursor();
+    assert_eq!((cursor.line, cursor.index), (1, 3));
+
+    let buffer = editor.buffer();
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.lines[0].text(), "one");
+    assert_eq!(buffer.lines[1].text(), "two");
+}
+
+#[test]
+fn delete_with_empty_selection() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let (mut editor, _, _) = editor(&mut font_system);
+
+    // An empty selection does not stop deleting the character behind the cursor
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.set_select_opt(Some(editor.cursor()));
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(editor.buffer().lines[0].text(), "on");
+    assert_eq!(editor.select_opt(), None);
+
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.set_select_opt(Some(editor.cursor()));
+    editor.action(&mut font_system, Action::Delete);
+    assert_eq!(editor.buffer().lines[0].text(), "n");
+}
@@ -0,0 +1,113 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn text(editor: &Editor) -> String {
+    let lines: Vec<&str> = editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.text())
+        .collect();
+    lines.join("\n")
+}
+
+fn cursor(editor: &Editor) -> (usize, usize) {
+    let cursor = editor.cursor();
+    (cursor.line, cursor.index)
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn delete_word_backward() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "bar\nhello, wörld  foo");
+
+    editor.set_cursor(Cursor::new(1, "hello, wörld  foo".len()));
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "bar\nhello, wörld  ");
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "bar\nhello, ");
+    assert_eq!(cursor(&editor), (1, 7));
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "bar\n");
+
+    // At the start of a line, the line is joined to the previous one
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "bar");
+    assert_eq!(cursor(&editor), (0, 3));
+
+    // Each word is undone separately
+    editor.undo();
+    editor.undo();
+    assert_eq!(text(&editor), "bar\nhello, ");
+    assert_eq!(cursor(&editor), (1, 7));
+}
+
+#[test]
+fn delete_word_forward() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "hello, wörld  foo  \nbar");
+    editor.set_selection_includes_newline(true);
+
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), ", wörld  foo  \nbar");
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "  foo  \nbar");
+    assert_eq!(cursor(&editor), (0, 0));
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "  \nbar");
+
+    // Trailing whitespace is deleted up to the end of the line, keeping the newline
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "\nbar");
+
+    // At the end of a line, the next line is joined to it
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "bar");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn delete_word_with_selection() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one two three");
+
+    // Only the selection is deleted
+    editor.set_select_opt(Some(Cursor::new(0, 5)));
+    editor.set_cursor(Cursor::new(0, 6));
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "one to three");
+    assert_eq!(editor.select_opt(), None);
+
+    editor.set_select_opt(Some(Cursor::new(0, 4)));
+    editor.set_cursor(Cursor::new(0, 6));
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "one  three");
+
+    // An empty selection is the same as no selection
+    editor.set_select_opt(Some(Cursor::new(0, 4)));
+    editor.set_cursor(Cursor::new(0, 4));
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "one ");
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "swash")]
+
+use std::collections::BTreeSet;
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, SwashCache, Weight,
+};
+
+#[test]
+fn draw_rotated_quarter_turn() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(&mut font_system, "HIJK", attrs, Shaping::Advanced);
+
+    let mut cache = SwashCache::new();
+    let color = Color::rgb(0xFF, 0xFF, 0xFF);
+    let mut pixels = BTreeSet::new();
+    buffer.draw(&mut font_system, &mut cache, color, |x, y, w, h, color| {
+        for py in y..y + h as i32 {
+            for px in x..x + w as i32 {
+                pixels.insert((px, py, color));
+            }
+        }
+    });
+    assert!(!pixels.is_empty());
+
+    let mut rotated = BTreeSet::new();
+    buffer.draw_rotated(
+        &mut font_system,
+        &mut cache,
+        color,
+        std::f32::consts::FRAC_PI_2,
+        (0.0, 0.0),
+        |x, y, w, h, color| {
+            assert_eq!((w, h), (1, 1));
+            rotated.insert((x, y, color));
+        },
+    );
+
+    // A clockwise quarter turn maps the pixel at (x, y) to (-y - 1, x), so advances along x
+    // become advances along y
+    let expected: BTreeSet<_> = pixels
+        .iter()
+        .map(|&(x, y, color)| (-y - 1, x, color))
+        .collect();
+    assert_eq!(rotated, expected);
+
+    let x_extent = |set: &BTreeSet<(i32, i32, Color)>| {
+        let min = set.iter().map(|p| p.0).min().unwrap();
+        let max = set.iter().map(|p| p.0).max().unwrap();
+        max - min
+    };
+    let y_extent = |set: &BTreeSet<(i32, i32, Color)>| {
+        let min = set.iter().map(|p| p.1).min().unwrap();
+        let max = set.iter().map(|p| p.1).max().unwrap();
+        max - min
+    };
+    assert_eq!(y_extent(&rotated), x_extent(&pixels));
+    assert!(y_extent(&rotated) > x_extent(&rotated));
+}
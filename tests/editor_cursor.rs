@@ -0,0 +1,35 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping, Weight,
+};
+
+#[test]
+fn set_cursor_round_trip_and_clamp() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello\nw\u{F6}rld",
+        attrs,
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+
+    // A valid position round-trips
+    editor.set_cursor(Cursor::new(1, 3));
+    assert_eq!(editor.cursor(), Cursor::new(1, 3));
+
+    // An index inside a multi-byte character moves back to the character boundary
+    editor.set_cursor(Cursor::new(1, 2));
+    assert_eq!(editor.cursor(), Cursor::new(1, 1));
+
+    // Line and index past the end are clamped to the end of the buffer
+    editor.set_cursor(Cursor::new(10, 100));
+    assert_eq!(editor.cursor(), Cursor::new(1, "w\u{F6}rld".len()));
+}
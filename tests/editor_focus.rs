@@ -0,0 +1,58 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    SwashCache, Weight,
+};
+
+#[test]
+fn unfocused_editor_mutes_selection_and_hides_cursor() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let metrics = Metrics::new(14.0, 20.0);
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(&mut font_system, "hello", attrs, Shaping::Advanced);
+
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(0, 5));
+    editor.set_select_opt(Some(Cursor::new(0, 0)));
+
+    let text_color = Color::rgb(0xFF, 0xFF, 0xFF);
+    let mut cache = SwashCache::new();
+    let mut draw = |editor: &Editor| {
+        let mut rects = Vec::new();
+        editor.draw(
+            &mut font_system,
+            &mut cache,
+            text_color,
+            |x, y, w, h, color| {
+                if h == metrics.line_height as u32 {
+                    rects.push((x, y, w, color));
+                }
+            },
+        );
+        rects
+    };
+
+    let active = Color::rgba(0xFF, 0xFF, 0xFF, 0x33);
+    let inactive = Color::rgba(0x80, 0x80, 0x80, 0x33);
+
+    let rects = draw(&editor);
+    assert!(rects.iter().any(|&(_, _, _, color)| color == active));
+    assert!(rects
+        .iter()
+        .any(|&(_, _, w, color)| w == 1 && color == text_color));
+
+    editor.set_focused(false);
+    let rects = draw(&editor);
+    assert!(!rects.is_empty());
+    // Only the selection is drawn, in the inactive color
+    assert!(rects.iter().all(|&(_, _, _, color)| color == inactive));
+}
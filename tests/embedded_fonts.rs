@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+fn font_ids(buffer: &Buffer) -> Vec<fontdb::ID> {
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter().map(|glyph| glyph.font_id))
+        .collect()
+}
+
+#[test]
+fn embedded_fonts() {
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    let mut font_system =
+        FontSystem::new_with_embedded_fonts([fontdb::Source::Binary(Arc::new(font))]);
+    assert_eq!(font_system.db().len(), 1);
+    let fira_mono = font_system.db().faces().next().unwrap().id;
+
+    // A missing family falls back to the embedded font
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    let attrs = Attrs::new().family(Family::Name("Fira Mono Copy"));
+    buffer.set_text(&mut font_system, "hi", attrs, Shaping::Advanced);
+    assert_eq!(font_ids(&buffer), [fira_mono, fira_mono]);
+
+    // Fonts added after startup are used once the text is shaped again
+    let mut copy = font_system.db().face(fira_mono).unwrap().clone();
+    copy.families = vec![(
+        "Fira Mono Copy".to_string(),
+        fontdb::Language::English_UnitedStates,
+    )];
+    font_system.db_mut().push_face_info(copy);
+    let copy = font_system.db().faces().nth(1).unwrap().id;
+    buffer.shape_until_scroll(&mut font_system);
+    assert_eq!(font_ids(&buffer), [fira_mono, fira_mono]);
+
+    buffer.set_redraw(false);
+    buffer.reset_shaping_all();
+    assert!(buffer.redraw());
+    buffer.shape_until_scroll(&mut font_system);
+    assert_eq!(font_ids(&buffer), [copy, copy]);
+}
@@ -0,0 +1,65 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Family, FontSystem, LayoutGlyph, Metrics, Shaping, Weight,
+};
+
+/// Shape a CJK character, returning its glyphs and the number of fonts fallback tried
+fn shape_cjk(fallback_enabled: bool) -> (Vec<LayoutGlyph>, fontdb::ID, usize) {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let primary = font_system.db().faces().next().unwrap().id;
+
+    // Register the same font data under another family name, as a fallback candidate
+    let mut copy = font_system.db().faces().next().unwrap().clone();
+    copy.families = vec![(
+        "Fira Mono Copy".to_string(),
+        fontdb::Language::English_UnitedStates,
+    )];
+    font_system.db_mut().push_face_info(copy);
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let hook_count = count.clone();
+    font_system.set_font_load_hook(Box::new(move |_face| {
+        hook_count.fetch_add(1, Ordering::SeqCst);
+        true
+    }));
+    font_system.set_fallback_enabled(fallback_enabled);
+    assert_eq!(font_system.fallback_enabled(), fallback_enabled);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "a\u{4E2D}", attrs, Shaping::Advanced);
+
+    let glyphs = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter().cloned())
+        .collect();
+    (glyphs, primary, count.load(Ordering::SeqCst))
+}
+
+#[test]
+fn fallback_disabled() {
+    let font_system = FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    assert!(font_system.fallback_enabled());
+
+    // Fallback tries the other font for the missing glyph
+    let (_, _, tried) = shape_cjk(true);
+    assert!(tried > 0);
+
+    // Without fallback, the missing glyph is the notdef of the requested font
+    let (glyphs, primary, tried) = shape_cjk(false);
+    assert_eq!(tried, 0);
+    assert_eq!(glyphs.len(), 2);
+    assert!(glyphs.iter().all(|glyph| glyph.font_id == primary));
+    assert_ne!(glyphs[0].glyph_id, 0);
+    assert_eq!(glyphs[1].glyph_id, 0);
+}
@@ -0,0 +1,61 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+/// Count the fonts scanned for fallback while shaping text that no font has glyphs for
+fn scanned(limit: Option<usize>) -> usize {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Register the same font data under other family names, as more fallback candidates
+    let face = font_system.db().faces().next().unwrap().clone();
+    for i in 0..4 {
+        let mut copy = face.clone();
+        copy.families = vec![(
+            format!("Fira Mono Copy {}", i),
+            fontdb::Language::English_UnitedStates,
+        )];
+        font_system.db_mut().push_face_info(copy);
+    }
+    assert_eq!(font_system.db().faces().count(), 5);
+
+    if let Some(limit) = limit {
+        font_system.set_fallback_scan_limit(limit);
+    }
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+
+    // Deny every candidate, so fallback keeps scanning
+    let count = Arc::new(AtomicUsize::new(0));
+    let hook_count = count.clone();
+    font_system.set_font_load_hook(Box::new(move |_face| {
+        hook_count.fetch_add(1, Ordering::SeqCst);
+        false
+    }));
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    buffer.set_text(&mut font_system, "\u{5D0}", attrs, Shaping::Advanced);
+
+    // The missing glyph is drawn as notdef
+    assert_eq!(buffer.layout_runs().flat_map(|run| run.glyphs).count(), 1);
+
+    count.load(Ordering::SeqCst)
+}
+
+#[test]
+fn fallback_scan_limit() {
+    let font_system = FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    assert_eq!(font_system.fallback_scan_limit(), usize::MAX);
+
+    assert_eq!(scanned(None), 5);
+    assert_eq!(scanned(Some(2)), 2);
+    assert_eq!(scanned(Some(0)), 0);
+}
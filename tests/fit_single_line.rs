@@ -0,0 +1,37 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Weight};
+
+#[test]
+fn fit_single_line() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let glyph_w = 14.0 * 0.6;
+
+    assert_eq!(
+        buffer.fit_single_line(&mut font_system, "hello world", attrs, glyph_w * 8.5),
+        ("hello wo".len(), true)
+    );
+    assert_eq!(
+        buffer.fit_single_line(&mut font_system, "hello world", attrs, glyph_w * 11.5),
+        ("hello world".len(), false)
+    );
+    assert_eq!(
+        buffer.fit_single_line(&mut font_system, "hello world", attrs, glyph_w * 0.5),
+        (0, true)
+    );
+
+    // Graphemes are not split
+    assert_eq!(
+        buffer.fit_single_line(&mut font_system, "ae\u{301}i", attrs, glyph_w * 1.5),
+        ("a".len(), true)
+    );
+
+    // The buffer itself is not changed
+    assert!(buffer.lines.is_empty() || buffer.lines[0].text().is_empty());
+}
@@ -0,0 +1,32 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+use std::sync::{Arc, Mutex};
+
+// With a missing family, fallback picks the first loaded face unless the hook denies it
+#[test]
+fn font_load_hook_denies_fallback() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font.clone());
+    font_system.db_mut().load_font_data(font);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let (denied, allowed) = (ids[0], ids[1]);
+
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let hook_asked = asked.clone();
+    font_system.set_font_load_hook(Box::new(move |face| {
+        hook_asked.lock().unwrap().push(face.id);
+        face.id != denied
+    }));
+
+    let attrs = Attrs::new().family(Family::Name("Missing Family"));
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "abc", attrs, Shaping::Advanced);
+
+    assert!(asked.lock().unwrap().contains(&denied));
+    assert!(buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .all(|glyph| glyph.font_id == allowed));
+}
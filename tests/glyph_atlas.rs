@@ -0,0 +1,113 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Antialiasing, Attrs, Buffer, CacheKey, Family, FontSystem, GlyphAtlas, Metrics,
+    Shaping, SwashCache, SwashContent, Weight,
+};
+
+fn cache_keys(font_system: &mut FontSystem, text: &str, font_size: f32) -> Vec<CacheKey> {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(font_size, font_size * 1.5));
+    buffer.set_size(font_system, 1000.0, 1000.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| glyph.physical((0., 0.), 1.0).cache_key)
+        .collect()
+}
+
+#[test]
+fn glyph_atlas() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let mut swash_cache = SwashCache::new();
+    let mut atlas = GlyphAtlas::new(32);
+
+    let keys = cache_keys(&mut font_system, "ab   a", 14.0);
+    let a = atlas
+        .get_glyph(&mut font_system, &mut swash_cache, keys[0])
+        .unwrap();
+    let b = atlas
+        .get_glyph(&mut font_system, &mut swash_cache, keys[1])
+        .unwrap();
+    assert_eq!((a.page, b.page), (0, 0));
+    assert_eq!(a.content, SwashContent::Mask);
+    assert!(a.x + a.width < b.x || a.y + a.height < b.y);
+
+    // Spaces have no image, and repeated glyphs at the same subpixel offset are only added once
+    assert_eq!(
+        atlas.get_glyph(&mut font_system, &mut swash_cache, keys[2]),
+        None
+    );
+    assert_eq!(
+        atlas.get_glyph(&mut font_system, &mut swash_cache, keys[5]),
+        Some(a)
+    );
+    assert!(swash_cache.image_cache.is_empty());
+
+    // The page has the pixels of the images
+    let image = swash_cache
+        .get_image_uncached(&mut font_system, keys[0])
+        .unwrap();
+    assert_eq!(
+        (a.width, a.height),
+        (image.placement.width, image.placement.height)
+    );
+    assert_eq!((a.left, a.top), (image.placement.left, image.placement.top));
+    let page = &atlas.pages()[0];
+    assert_eq!(page.size(), 32);
+    assert_eq!(page.data().len(), 32 * 32);
+    for y in 0..a.height {
+        let start = ((a.y + y) * 32 + a.x) as usize;
+        let row = &image.data[(y * a.width) as usize..((y + 1) * a.width) as usize];
+        assert_eq!(&page.data()[start..start + a.width as usize], row);
+    }
+    let [u0, v0, u1, v1] = a.uv(atlas.page_size());
+    assert_eq!((u0, v0), (a.x as f32 / 32.0, a.y as f32 / 32.0));
+    assert_eq!(
+        (u1, v1),
+        (
+            (a.x + a.width) as f32 / 32.0,
+            (a.y + a.height) as f32 / 32.0
+        )
+    );
+
+    // Only the changed part of a page needs to be uploaded
+    let (x, y, w, h) = page.damaged().unwrap();
+    assert!(x <= a.x && y <= a.y && x + w >= b.x + b.width && y + h >= b.y + b.height);
+    atlas.clear_damage();
+    assert_eq!(atlas.pages()[0].damaged(), None);
+
+    // Full pages are followed by new ones, and images larger than a page are never added
+    for key in cache_keys(&mut font_system, "cdefghijklmnopqrstuvwxyz", 14.0) {
+        atlas
+            .get_glyph(&mut font_system, &mut swash_cache, key)
+            .unwrap();
+    }
+    assert!(atlas.pages().len() > 1);
+    assert!(atlas.pages().last().unwrap().damaged().is_some());
+    let huge = cache_keys(&mut font_system, "a", 100.0);
+    assert_eq!(
+        atlas.get_glyph(&mut font_system, &mut swash_cache, huge[0]),
+        None
+    );
+
+    atlas.clear();
+    assert!(atlas.pages().is_empty());
+
+    // Subpixel masks are added to their own pages, with four bytes per pixel
+    swash_cache.set_antialiasing(Antialiasing::Subpixel);
+    let keys = cache_keys(&mut font_system, "a", 14.0);
+    let glyph = atlas
+        .get_glyph(&mut font_system, &mut swash_cache, keys[0])
+        .unwrap();
+    assert_eq!(glyph.content, SwashContent::SubpixelMask);
+    let page = &atlas.pages()[glyph.page];
+    assert_eq!(page.content(), SwashContent::SubpixelMask);
+    assert_eq!(page.data().len(), 32 * 32 * 4);
+}
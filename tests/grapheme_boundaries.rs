@@ -0,0 +1,14 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, Shaping};
+
+#[test]
+fn grapheme_boundaries() {
+    let line = BufferLine::new(
+        "e\u{0301}f",
+        AttrsList::new(Attrs::new()),
+        Shaping::Advanced,
+    );
+    assert_eq!(line.grapheme_boundaries(), vec![0, 3, 4]);
+
+    let line = BufferLine::new("", AttrsList::new(Attrs::new()), Shaping::Advanced);
+    assert_eq!(line.grapheme_boundaries(), vec![0]);
+}
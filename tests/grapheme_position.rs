@@ -0,0 +1,46 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn new_buffer(font_system: &mut FontSystem, text: &str, width: f32) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, width, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+}
+
+#[test]
+fn grapheme_position() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let buffer = new_buffer(&mut font_system, "abc\n\u{5D0}\u{5D1}", 500.0);
+    let a = buffer.layout_runs().next().unwrap().glyphs[0].w;
+    assert_eq!(buffer.grapheme_position(0, 0), Some((0.0, 0.0, a)));
+    let (x, y, w) = buffer.grapheme_position(0, 1).unwrap();
+    assert_eq!((x, y), (a, 0.0));
+    assert!((w - GLYPH_W).abs() < 0.01);
+    assert_eq!(buffer.grapheme_position(0, 3), None);
+    assert_eq!(buffer.grapheme_position(2, 0), None);
+
+    // Right-to-left text starts from the right
+    let (alef_x, alef_y, _) = buffer.grapheme_position(1, 0).unwrap();
+    let (bet_x, _, _) = buffer.grapheme_position(1, 1).unwrap();
+    assert!(bet_x < alef_x);
+    assert_eq!(alef_y, 20.0);
+
+    // Combining marks are part of the grapheme before them, and wrapped graphemes are on the
+    // next layout run
+    let buffer = new_buffer(&mut font_system, "e\u{301}e ab", 4.5 * GLYPH_W);
+    let (x, y, _) = buffer.grapheme_position(0, 1).unwrap();
+    assert!((x - GLYPH_W).abs() < 0.01);
+    assert_eq!(y, 0.0);
+    let (x, y, w) = buffer.grapheme_position(0, 3).unwrap();
+    assert_eq!((x, y), (0.0, 20.0));
+    assert!((w - GLYPH_W).abs() < 0.01);
+}
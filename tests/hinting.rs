@@ -0,0 +1,54 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Family, FontSystem, Hinting, Metrics, Shaping, SwashCache, SwashImage,
+    Weight,
+};
+
+#[test]
+fn hinting_changes_small_glyphs() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(11.0, 14.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "e", attrs, Shaping::Advanced);
+    let cache_key = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| glyph.physical((0., 0.), 1.0).cache_key)
+        .next()
+        .unwrap();
+
+    let mut cache = SwashCache::new();
+    assert_eq!(cache.hinting(), Hinting::Full);
+    let hinted = cache
+        .get_image(&mut font_system, cache_key)
+        .clone()
+        .unwrap();
+
+    cache.set_hinting(Hinting::None);
+    let unhinted = cache
+        .get_image(&mut font_system, cache_key)
+        .clone()
+        .unwrap();
+
+    let placement = |image: &SwashImage| {
+        let placement = image.placement;
+        (
+            placement.left,
+            placement.top,
+            placement.width,
+            placement.height,
+        )
+    };
+    assert!(
+        hinted.data != unhinted.data || placement(&hinted) != placement(&unhinted),
+        "hinting did not change the rasterized glyph"
+    );
+}
@@ -0,0 +1,107 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Cursor, Family, FontSystem, Metrics, Shaping, Weight};
+
+fn buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 100.0, 200.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn hit(buffer: &Buffer, x: f32, y: f32) -> (usize, usize) {
+    let cursor = buffer.hit(x, y).unwrap();
+    (cursor.line, cursor.index)
+}
+
+/// Check that hitting the caret position of every cursor in the buffer returns that cursor
+fn assert_round_trip(buffer: &Buffer) {
+    for (line_i, line) in buffer.lines.iter().enumerate() {
+        for (index, _) in line.text().char_indices().chain([(line.text().len(), ' ')]) {
+            let cursor = Cursor::new(line_i, index);
+            let (x, y) = buffer.cursor_position(&cursor).unwrap();
+            assert_eq!(
+                hit(buffer, x as f32 + 0.5, y as f32 + 10.0),
+                (line_i, index),
+                "{cursor:?} at {x}, {y}"
+            );
+        }
+    }
+}
+
+#[test]
+fn hit_and_cursor_position_round_trip() {
+    let mut font_system = font_system();
+    let buffer = buffer(&mut font_system, "hello wrapping world\nsecond");
+    assert_eq!(buffer.layout_runs().count(), 4);
+    assert_round_trip(&buffer);
+
+    let buffer = buffer_rtl(&mut font_system);
+    assert_round_trip(&buffer);
+}
+
+fn buffer_rtl(font_system: &mut FontSystem) -> Buffer {
+    buffer(font_system, "אבג")
+}
+
+#[test]
+fn hit_picks_run_by_top() {
+    let mut font_system = font_system();
+    let buffer = buffer(&mut font_system, "one\ntwo\nthree");
+    let tops: Vec<f32> = buffer.layout_runs().map(|run| run.line_top).collect();
+    assert_eq!(tops, [0.0, 20.0, 40.0]);
+
+    // Just inside the top of each run, and just above the next
+    assert_eq!(hit(&buffer, 0.5, 0.5), (0, 0));
+    assert_eq!(hit(&buffer, 0.5, 19.5), (0, 0));
+    assert_eq!(hit(&buffer, 0.5, 20.5), (1, 0));
+    assert_eq!(hit(&buffer, 0.5, 40.5), (2, 0));
+
+    // Past the end of a line, and below the last line
+    assert_eq!(hit(&buffer, 99.5, 30.0), (1, 3));
+    assert_eq!(hit(&buffer, 0.5, 150.0), (2, 5));
+}
+
+#[test]
+fn hit_rtl_edges() {
+    let mut font_system = font_system();
+    let buffer = buffer_rtl(&mut font_system);
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.rtl);
+
+    // The start of right-to-left text is on the right
+    assert_eq!(hit(&buffer, 99.5, 10.0), (0, 0));
+    assert_eq!(hit(&buffer, 0.5, 10.0), (0, "אבג".len()));
+}
+
+#[test]
+fn hit_and_cursor_position_follow_scroll() {
+    let mut font_system = font_system();
+    let mut buffer = buffer(&mut font_system, "one\ntwo\nthree");
+    assert_eq!(buffer.cursor_position(&Cursor::new(1, 1)), Some((8, 20)));
+
+    buffer.set_scroll(1);
+    assert_eq!(buffer.cursor_position(&Cursor::new(0, 1)), None);
+    assert_eq!(buffer.cursor_position(&Cursor::new(1, 1)), Some((8, 0)));
+    assert_eq!(hit(&buffer, 9.0, 10.0), (1, 1));
+}
+
+#[test]
+fn caret_rect() {
+    let mut font_system = font_system();
+    let mut buffer = buffer(&mut font_system, "one\ntwo\nthree");
+    assert_eq!(buffer.caret_rect(&Cursor::new(2, 2)), Some((16, 40, 1, 20)));
+
+    buffer.set_scroll(1);
+    assert_eq!(buffer.caret_rect(&Cursor::new(0, 0)), None);
+    assert_eq!(buffer.caret_rect(&Cursor::new(2, 2)), Some((16, 20, 1, 20)));
+}
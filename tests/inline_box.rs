@@ -0,0 +1,41 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, Buffer, BufferLine, Family, FontSystem, InlineBox, Metrics, Shaping,
+    Weight,
+};
+
+#[test]
+fn inline_box_reserves_space() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    // At a font size of 20, a box one font size wide is 20 pixels wide
+    let inline_box = InlineBox::new(1.0, 2.0, 0.25);
+    let text = "a\u{FFFC}b";
+    let box_range = 1..1 + '\u{FFFC}'.len_utf8();
+    let mut attrs_list = AttrsList::new(attrs);
+    attrs_list.add_span(box_range.clone(), attrs.inline_box(inline_box));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 24.0));
+    buffer.set_size(&mut font_system, 500.0, 200.0);
+    buffer.lines = vec![BufferLine::new(text, attrs_list, Shaping::Advanced)];
+    buffer.shape_until_scroll(&mut font_system);
+
+    let run = buffer.layout_runs().next().unwrap();
+    assert_eq!(run.glyphs.len(), 3);
+    let (a, bx, b) = (&run.glyphs[0], &run.glyphs[1], &run.glyphs[2]);
+    assert_eq!((bx.start, bx.end), (box_range.start, box_range.end));
+    assert_eq!(bx.inline_box_opt, Some(inline_box));
+    assert_eq!(bx.w, 20.0);
+    assert!((bx.x - (a.x + a.w)).abs() < 0.01);
+    assert!((b.x - (bx.x + 20.0)).abs() < 0.01);
+
+    // The box is taller than the text, so the line reserves space for it
+    let layout = buffer.lines[0].layout_opt().as_ref().unwrap();
+    assert_eq!(layout[0].max_ascent, 35.0);
+    assert!(layout[0].max_descent >= 5.0);
+}
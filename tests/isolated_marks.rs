@@ -0,0 +1,52 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn isolated_mark_on_dotted_circle() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    // A combining acute accent with no base character, then the same accent on a base that has
+    // no precomposed form in the font
+    buffer.set_text(
+        &mut font_system,
+        "\u{301}x\u{301}",
+        attrs,
+        Shaping::Advanced,
+    );
+
+    let glyphs = |buffer: &Buffer| -> Vec<(usize, usize, u16)> {
+        let run = buffer.layout_runs().next().unwrap();
+        run.glyphs
+            .iter()
+            .map(|glyph| (glyph.start, glyph.end, glyph.glyph_id))
+            .collect()
+    };
+
+    assert!(!buffer.isolated_mark_display());
+    let plain = glyphs(&buffer);
+    assert_eq!(plain.len(), 3);
+    let mark = plain[0].2;
+
+    buffer.set_isolated_mark_display(&mut font_system, true);
+    assert!(buffer.isolated_mark_display());
+    let displayed = glyphs(&buffer);
+    assert_eq!(displayed.len(), 4);
+
+    // The dotted circle and the mark form one cluster
+    assert_eq!((displayed[0].0, displayed[0].1), (0, 2));
+    assert_eq!((displayed[1].0, displayed[1].1), (0, 2));
+    assert_eq!(displayed[1].2, mark);
+
+    // The rest of the line is unchanged
+    assert_eq!(displayed[2..], plain[1..]);
+
+    buffer.set_isolated_mark_display(&mut font_system, false);
+    assert_eq!(glyphs(&buffer), plain);
+}
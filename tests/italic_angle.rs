@@ -0,0 +1,47 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+/// Get the italic angles of the glyphs of a line shaped with the provided font data
+fn italic_angles(font: Vec<u8>) -> Vec<f32> {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(&mut font_system, "slant", attrs, Shaping::Advanced);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| glyph.italic_angle)
+        .collect()
+}
+
+#[test]
+fn italic_angle() {
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+
+    // Fira Mono Medium is upright
+    let upright = italic_angles(font.clone());
+    assert_eq!(upright.len(), 5);
+    assert!(upright.iter().all(|angle| *angle == 0.0));
+
+    // Set the italic angle in the post table to -12 degrees
+    let mut italic = font;
+    let num_tables = u16::from_be_bytes([italic[4], italic[5]]) as usize;
+    let post = (0..num_tables)
+        .map(|i| 12 + i * 16)
+        .find(|record| &italic[*record..*record + 4] == b"post")
+        .map(|record| {
+            let offset = &italic[record + 8..record + 12];
+            u32::from_be_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize
+        })
+        .unwrap();
+    italic[post + 4..post + 8].copy_from_slice(&(-12i32 << 16).to_be_bytes());
+
+    let italic = italic_angles(italic);
+    assert_eq!(italic.len(), 5);
+    assert!(italic.iter().all(|angle| *angle == -12.0));
+}
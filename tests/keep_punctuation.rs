@@ -0,0 +1,49 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight, Wrap};
+
+/// Get the start index of each layout line of `text` wrapped at `width`
+fn line_starts(font_system: &mut FontSystem, text: &str, wrap: Wrap, width: f32) -> Vec<usize> {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, wrap);
+    buffer.set_size(font_system, width, 500.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+        .layout_runs()
+        .map(|run| run.glyphs.iter().map(|glyph| glyph.start).min().unwrap())
+        .collect()
+}
+
+#[test]
+fn keep_trailing_punctuation() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Nine glyphs fit on a line, but not ten
+    let width = 14.0 * 0.6 * 9.5;
+
+    // A period and closing quote wrap together with the word
+    let text = "aaaa word.\" next";
+    assert_eq!(
+        line_starts(&mut font_system, text, Wrap::Word, width)[1],
+        text.find("word").unwrap()
+    );
+    assert_eq!(
+        line_starts(&mut font_system, text, Wrap::WordKeepPunctuation, width)[1],
+        text.find("word").unwrap()
+    );
+
+    // Line breaking allows a dash to wrap alone, unless it is kept with the word
+    let text = "aaaa word\u{2014} next";
+    assert_eq!(
+        line_starts(&mut font_system, text, Wrap::Word, width)[1],
+        text.find('\u{2014}').unwrap()
+    );
+    assert_eq!(
+        line_starts(&mut font_system, text, Wrap::WordKeepPunctuation, width)[1],
+        text.find("word").unwrap()
+    );
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use cosmic_text::{fontdb, Attrs, Family, FontSystem, LabelCache, Metrics, Weight};
+
+#[test]
+fn label_cache_hit() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let metrics = Metrics::new(14.0, 20.0);
+    let mut cache = LabelCache::new();
+
+    let first = cache.get(&mut font_system, "Open File", attrs, metrics, 200.0);
+    let second = cache.get(&mut font_system, "Open File", attrs, metrics, 200.0);
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(cache.len(), 1);
+    assert_eq!(first.layout_lines().count(), 1);
+    assert!(first.width > 0.0);
+
+    let other = cache.get(&mut font_system, "Save File", attrs, metrics, 200.0);
+    assert!(!Arc::ptr_eq(&first, &other));
+    assert_eq!(cache.len(), 2);
+    // The least recently requested label is removed when the cache is full
+    cache.set_max_len(2);
+    cache.get(&mut font_system, "Open File", attrs, metrics, 200.0);
+    let narrow = cache.get(&mut font_system, "Open File", attrs, metrics, 50.0);
+    assert!(!Arc::ptr_eq(&first, &narrow));
+    assert_eq!(cache.len(), 2);
+    let again = cache.get(&mut font_system, "Open File", attrs, metrics, 200.0);
+    assert!(Arc::ptr_eq(&first, &again));
+    let other_again = cache.get(&mut font_system, "Save File", attrs, metrics, 200.0);
+    assert!(!Arc::ptr_eq(&other, &other_again));
+
+    cache.set_max_len(0);
+    assert!(cache.is_empty());
+    assert_eq!(LabelCache::default().max_len(), LabelCache::new().max_len());
+}
@@ -0,0 +1,37 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, Weight};
+
+fn buffer(font_system: &mut FontSystem, attrs: Attrs, width: f32) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, width, 100.0);
+    buffer.set_text(
+        font_system,
+        "Hello layout\nregression tests",
+        attrs,
+        Shaping::Advanced,
+    );
+    buffer
+}
+
+#[test]
+fn layout_equals() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let a = buffer(&mut font_system, attrs, 200.0);
+    let b = buffer(&mut font_system, attrs, 200.0);
+    assert!(a.layout_equals(&b));
+    assert!(b.layout_equals(&a));
+
+    // A changed color is a different layout
+    let colored = buffer(&mut font_system, attrs.color(Color::rgb(0xFF, 0, 0)), 200.0);
+    assert!(!a.layout_equals(&colored));
+
+    // A changed size wraps differently
+    let narrow = buffer(&mut font_system, attrs, 60.0);
+    assert!(!a.layout_equals(&narrow));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "layout_json")]
+
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, Buffer, BufferLine, Color, Family, FontSystem, LayoutJson, Metrics,
+    Shaping, Weight, LAYOUT_JSON_VERSION,
+};
+
+#[test]
+fn layout_json_round_trip() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    let mut hello = AttrsList::new(attrs);
+    hello.add_span(6..11, attrs.color(Color::rgb(0xFF, 0, 0)));
+    buffer.lines = vec![
+        BufferLine::new("hello world", hello, Shaping::Advanced),
+        BufferLine::new("abc", AttrsList::new(attrs), Shaping::Advanced),
+    ];
+    buffer.shape_until_scroll(&mut font_system);
+
+    let json = buffer.to_layout_json(&font_system);
+    let layout: LayoutJson = serde_json::from_str(&json).unwrap();
+    assert_eq!(layout, buffer.to_layout(&font_system));
+    assert_eq!(layout.version, LAYOUT_JSON_VERSION);
+    assert_eq!((layout.width, layout.height), (500.0, 100.0));
+
+    assert_eq!(layout.lines.len(), 2);
+    let glyphs: usize = layout.lines.iter().map(|line| line.glyphs.len()).sum();
+    assert_eq!(glyphs, 14);
+
+    assert_eq!(layout.fonts.len(), 1);
+    assert_eq!(layout.fonts[0].family, "Fira Mono");
+    assert_eq!(layout.fonts[0].weight, 500);
+
+    let first = &layout.lines[0];
+    assert_eq!(first.glyphs[0].color, None);
+    assert_eq!(first.glyphs[6].color, Some(0xFFFF0000));
+    assert_eq!(layout.lines[1].line_i, 1);
+    assert!(layout.lines[1].top > first.top);
+}
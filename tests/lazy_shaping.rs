@@ -0,0 +1,86 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+fn shaped_lines(buffer: &Buffer) -> usize {
+    buffer
+        .lines
+        .iter()
+        .filter(|line| line.shape_opt().is_some())
+        .count()
+}
+
+#[test]
+fn shape_visible_lines_with_overscan() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    let text = vec!["line"; 1000].join("\n");
+    buffer.set_text(&mut font_system, &text, attrs, Shaping::Advanced);
+
+    // Only the five visible lines are shaped
+    assert_eq!(buffer.visible_lines(), 5);
+    assert_eq!(shaped_lines(&buffer), 5);
+    assert_eq!(buffer.estimated_height(), 1000.0 * 20.0);
+
+    buffer.set_overscan(&mut font_system, 3);
+    assert_eq!(buffer.overscan(), 3);
+    assert_eq!(shaped_lines(&buffer), 8);
+
+    // Scrolling shapes incrementally
+    buffer.set_scroll(10);
+    buffer.shape_until_scroll(&mut font_system);
+    assert_eq!(buffer.scroll(), 10);
+    assert_eq!(shaped_lines(&buffer), 18);
+}
+
+#[test]
+fn estimated_height_uses_average_of_laid_out_lines() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    // Every line wraps onto two layout lines
+    buffer.set_size(&mut font_system, 14.0 * 0.6 * 5.5, 40.0);
+    let text = vec!["aaaa bbbb"; 100].join("\n");
+    buffer.set_text(&mut font_system, &text, attrs, Shaping::Advanced);
+
+    assert_eq!(shaped_lines(&buffer), 1);
+    assert_eq!(buffer.estimated_height(), 100.0 * 2.0 * 20.0);
+}
+
+#[test]
+fn estimated_height_includes_ruby() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    let text = ["line"; 10].join("\n");
+    buffer.set_text(&mut font_system, &text, attrs, Shaping::Advanced);
+    buffer.lines[0].set_ruby(0..4, "ruby", attrs);
+    buffer.shape_until_scroll(&mut font_system);
+
+    let ruby_height = buffer.line_layout(&mut font_system, 0).unwrap()[0].ruby_height;
+    assert!(ruby_height > 0.0);
+    assert!((buffer.estimated_height() - (10.0 * 20.0 + ruby_height)).abs() < 0.01);
+    assert_eq!(
+        buffer.estimated_size(),
+        (buffer.content_width(), buffer.estimated_height())
+    );
+}
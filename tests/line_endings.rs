@@ -0,0 +1,42 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Family, FontSystem, LineEnding, Metrics, Shaping, Weight,
+};
+
+#[test]
+fn mixed_line_endings_round_trip() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+
+    let text = "unix\nwindows\r\nmac\r\n\rlast";
+    buffer.set_text(&mut font_system, text, attrs, Shaping::Advanced);
+
+    let lines: Vec<_> = buffer
+        .lines
+        .iter()
+        .map(|line| (line.text(), line.ending()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            ("unix", LineEnding::Lf),
+            ("windows", LineEnding::CrLf),
+            ("mac", LineEnding::CrLf),
+            ("", LineEnding::Cr),
+            ("last", LineEnding::None),
+        ]
+    );
+    assert_eq!(buffer.to_string_with_endings(), text);
+
+    // A trailing terminator is preserved as well
+    let text = "a\r\nb\r\n";
+    buffer.set_text(&mut font_system, text, attrs, Shaping::Advanced);
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.to_string_with_endings(), text);
+}
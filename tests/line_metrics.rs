@@ -0,0 +1,82 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn line_metrics_per_visual_line() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let metrics = Metrics::new(14.0, 20.0);
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    buffer.set_size(&mut font_system, 500.0, 500.0);
+    buffer.set_text(&mut font_system, "abc\ndef\nghi", attrs, Shaping::Advanced);
+    // The second line is taller because of its ruby annotation
+    buffer.lines[1].set_ruby(0..3, "xyz", attrs);
+    buffer.shape_until_scroll(&mut font_system);
+
+    let ruby_height = buffer.line_layout(&mut font_system, 1).unwrap()[0].ruby_height;
+    assert!(ruby_height > 0.0);
+    assert_eq!(buffer.line_metrics(0), metrics);
+    assert_eq!(
+        buffer.line_metrics(1),
+        Metrics::new(14.0, 20.0 + ruby_height)
+    );
+    assert_eq!(buffer.line_metrics(2), metrics);
+    assert_eq!(buffer.line_metrics(3), metrics);
+
+    // The line heights match the distance between the tops of the layout runs
+    let tops: Vec<f32> = buffer.layout_runs().map(|run| run.line_top).collect();
+    for (i, pair) in tops.windows(2).enumerate() {
+        assert!((pair[1] - pair[0] - buffer.line_metrics(i).line_height).abs() < 0.01);
+    }
+
+    // The baseline grid rounds line heights up to whole grid steps
+    buffer.set_baseline_grid(&mut font_system, Some(8.0));
+    assert_eq!(buffer.line_metrics(0), Metrics::new(14.0, 24.0));
+    let expected = ((20.0 + ruby_height) / 8.0).ceil() * 8.0;
+    assert_eq!(buffer.line_metrics(1), Metrics::new(14.0, expected));
+}
+
+#[test]
+fn layout_line_vertical_metrics() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 500.0);
+    buffer.set_text(&mut font_system, "abc\ndef", attrs, Shaping::Advanced);
+
+    // The metrics come from the font used for the glyphs
+    let font_id = buffer.layout_runs().next().unwrap().glyphs[0].font_id;
+    let font = font_system.get_font(font_id).unwrap();
+    let face = font.rustybuzz();
+    let em = |units: i16| units as f32 / face.units_per_em() as f32 * 14.0;
+    let (ascent, descent, line_gap) = (
+        em(face.ascender()),
+        -em(face.descender()),
+        em(face.line_gap()),
+    );
+
+    let layout = &buffer.line_layout(&mut font_system, 0).unwrap()[0];
+    assert!((layout.max_ascent - ascent).abs() < 0.01);
+    assert!((layout.max_descent - descent).abs() < 0.01);
+    assert!((layout.max_line_gap - line_gap).abs() < 0.01);
+    let baseline_y = layout.baseline_y(20.0);
+    let max_line_gap = layout.max_line_gap;
+    assert!((baseline_y - ((20.0 - ascent - descent) / 2.0 + ascent)).abs() < 0.01);
+
+    // The baseline offset matches the layout runs
+    for run in buffer.layout_runs() {
+        assert!((run.line_y - run.line_top - baseline_y).abs() < 0.01);
+        assert_eq!(run.max_line_gap, max_line_gap);
+    }
+}
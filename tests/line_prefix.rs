@@ -0,0 +1,63 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, AttrsList, Buffer, BufferLine, Cursor, Edit, Editor, Family, FontSystem,
+    Metrics, Shaping, Weight,
+};
+
+#[test]
+fn prefix_is_laid_out_before_editable_text() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "output", attrs, Shaping::Advanced);
+    let mut prompt = BufferLine::new("", AttrsList::new(attrs), Shaping::Advanced);
+    prompt.set_prefix(Some((">>> ".to_string(), attrs)));
+    buffer.lines.push(prompt);
+
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(1, 0));
+    for c in "ab".chars() {
+        editor.action(&mut font_system, Action::Insert(c));
+    }
+    editor.shape_as_needed(&mut font_system);
+
+    // The prefix is not part of the text
+    assert_eq!(editor.buffer().lines[1].text(), "ab");
+    assert_eq!(
+        editor.buffer().lines[1].prefix().map(|(text, _)| text),
+        Some(">>> ")
+    );
+
+    {
+        let run = editor
+            .buffer()
+            .layout_runs()
+            .find(|run| run.line_i == 1)
+            .unwrap();
+        assert_eq!(run.prefix_glyphs.len(), 4);
+        assert!(run.prefix_w > 0.0);
+        let prefix_end = run
+            .prefix_glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.w)
+            .fold(0.0, f32::max);
+        assert!(prefix_end <= run.prefix_w + 0.01);
+        assert_eq!(run.glyphs.len(), 2);
+        assert!(run.glyphs[0].x >= run.prefix_w - 0.01);
+    }
+
+    // Home goes to the start of the editable text
+    editor.action(&mut font_system, Action::Home);
+    assert_eq!(editor.cursor().index, 0);
+
+    // Backspace does not join the prompt line into the previous line
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(editor.buffer().lines.len(), 2);
+    assert_eq!((editor.cursor().line, editor.cursor().index), (1, 0));
+}
@@ -0,0 +1,52 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn line_spacing_ratio() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let metrics = Metrics::new(14.0, 20.0).with_line_spacing_ratio(2.0);
+    assert_eq!(metrics, Metrics::new(14.0, 28.0));
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, metrics.with_line_spacing_ratio(1.0));
+    buffer.set_size(&mut font_system, 500.0, 500.0);
+    buffer.set_text(&mut font_system, "one\ntwo", attrs, Shaping::Advanced);
+
+    let runs = |buffer: &Buffer| {
+        buffer
+            .layout_runs()
+            .map(|run| {
+                let glyphs: Vec<_> = run
+                    .glyphs
+                    .iter()
+                    .map(|glyph| (glyph.font_size, glyph.x, glyph.w))
+                    .collect();
+                (run.line_y, glyphs)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let single = runs(&buffer);
+    buffer.set_metrics(&mut font_system, metrics);
+    let double = runs(&buffer);
+
+    // The line advance doubles
+    assert_eq!(single.len(), 2);
+    assert_eq!(double.len(), 2);
+    let single_advance = single[1].0 - single[0].0;
+    let double_advance = double[1].0 - double[0].0;
+    assert!((single_advance - 14.0).abs() < 0.001);
+    assert!((double_advance - 28.0).abs() < 0.001);
+
+    // The glyphs stay the same size
+    assert_eq!(single[0].1, double[0].1);
+    assert_eq!(single[1].1, double[1].1);
+
+    // The extra leading is split above and below the glyphs
+    assert!((double[0].0 - single[0].0 - 7.0).abs() < 0.001);
+}
@@ -0,0 +1,92 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Family, FontSystem, LayoutGlyph, Metrics, Overflow, Shaping, Weight,
+    Wrap,
+};
+
+// Every glyph of the monospace font, including the ellipsis, has the same advance
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn new_buffer(font_system: &mut FontSystem, text: &str, wrap: Wrap) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, wrap);
+    // Room for 11 glyphs
+    buffer.set_size(font_system, 100.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+}
+
+fn runs(buffer: &Buffer) -> Vec<Vec<LayoutGlyph>> {
+    buffer
+        .layout_runs()
+        .map(|run| run.glyphs.to_vec())
+        .collect()
+}
+
+#[test]
+fn overflow_ellipsis() {
+    let mut font_system = font_system();
+    let mut buffer = new_buffer(&mut font_system, "hello world foo bar", Wrap::None);
+
+    // Visible text runs past the width
+    assert_eq!(buffer.overflow(), Overflow::Visible);
+    let visible = runs(&buffer);
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].len(), 19);
+
+    buffer.set_overflow(&mut font_system, Overflow::Ellipsis);
+    let ellipsized = runs(&buffer);
+    assert_eq!(ellipsized.len(), 1);
+    let glyphs = &ellipsized[0];
+    assert_eq!(glyphs.len(), 11);
+    assert_eq!(glyphs[9].start, 9);
+    assert!(glyphs.iter().all(|glyph| glyph.x + glyph.w <= 100.0));
+
+    // The ellipsis covers the text that was cut off
+    let ellipsis = &glyphs[10];
+    assert_eq!((ellipsis.start, ellipsis.end), (10, 19));
+    assert_ne!(ellipsis.glyph_id, visible[0][10].glyph_id);
+    assert!((ellipsis.x - 10.0 * GLYPH_W).abs() < 0.01);
+    assert!((buffer.layout_runs().next().unwrap().line_w - 11.0 * GLYPH_W).abs() < 0.01);
+
+    // Lines that fit are left alone
+    let mut buffer = new_buffer(&mut font_system, "hello world", Wrap::None);
+    buffer.set_overflow(&mut font_system, Overflow::Ellipsis);
+    assert_eq!(runs(&buffer)[0].len(), 11);
+    assert_eq!(runs(&buffer)[0][10].end, 11);
+}
+
+#[test]
+fn overflow_max_lines() {
+    let mut font_system = font_system();
+    let mut buffer = new_buffer(&mut font_system, "hello world foo bar baz", Wrap::Word);
+    assert_eq!(runs(&buffer).len(), 2);
+
+    // Wrapped lines past the maximum are dropped
+    buffer.set_max_lines(&mut font_system, Some(1));
+    assert_eq!(buffer.max_lines(), Some(1));
+    let clipped = runs(&buffer);
+    assert_eq!(clipped.len(), 1);
+    assert!(clipped[0].iter().all(|glyph| glyph.end <= 12));
+
+    // With an ellipsis, the last line kept ends with one even though it fits
+    buffer.set_overflow(&mut font_system, Overflow::Ellipsis);
+    let ellipsized = runs(&buffer);
+    assert_eq!(ellipsized.len(), 1);
+    let ellipsis = ellipsized[0].last().unwrap();
+    assert_eq!(ellipsized[0].len(), 11);
+    assert_eq!((ellipsis.start, ellipsis.end), (10, 23));
+
+    buffer.set_max_lines(&mut font_system, None);
+    assert_eq!(runs(&buffer).len(), 2);
+}
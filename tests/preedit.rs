@@ -0,0 +1,112 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    UnderlineStyle, Weight,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn text(editor: &Editor) -> String {
+    let lines: Vec<&str> = editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.text())
+        .collect();
+    lines.join("\n")
+}
+
+fn cursor(editor: &Editor) -> (usize, usize) {
+    let cursor = editor.cursor();
+    (cursor.line, cursor.index)
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn preedit_is_shown_at_cursor() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "hello world");
+    editor.set_cursor(Cursor::new(0, 5));
+
+    editor.set_preedit("abc", Some(2..2));
+    assert_eq!(editor.preedit(), Some("abc"));
+    assert_eq!(text(&editor), "helloabc world");
+    assert_eq!(cursor(&editor), (0, 7));
+    assert!(editor.caret_visible());
+
+    // Only the preedit text is underlined
+    editor.shape_as_needed(&mut font_system);
+    let run = editor.buffer().layout_runs().next().unwrap();
+    let underlines: Vec<bool> = run
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.underline_opt == Some(UnderlineStyle::Solid))
+        .collect();
+    assert_eq!(underlines.iter().filter(|underline| **underline).count(), 3);
+    assert!(underlines[5] && underlines[7] && !underlines[4] && !underlines[8]);
+
+    // The candidate window can be placed at the caret
+    let (x, _, _, _) = editor.caret_rect().unwrap();
+    assert_eq!(x, (7.0 * GLYPH_W) as i32);
+
+    // Updating the preedit replaces it, and it can hide the caret
+    editor.set_preedit("abcd", None);
+    assert_eq!(text(&editor), "helloabcd world");
+    assert_eq!(cursor(&editor), (0, 9));
+    assert!(!editor.caret_visible());
+
+    // An empty preedit cancels the composition
+    editor.set_preedit("", None);
+    assert_eq!(editor.preedit(), None);
+    assert_eq!(text(&editor), "hello world");
+    assert_eq!(cursor(&editor), (0, 5));
+    assert!(editor.caret_visible());
+    assert!(!editor.undo());
+}
+
+#[test]
+fn preedit_commit() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "hello world");
+    editor.set_cursor(Cursor::new(0, 5));
+    editor.action(&mut font_system, Action::Insert('!'));
+
+    // The preedit text is not recorded
+    editor.set_preedit("ab", Some(1..1));
+    editor.action(&mut font_system, Action::Commit("AB".into()));
+    assert_eq!(editor.preedit(), None);
+    assert_eq!(text(&editor), "hello!AB world");
+    assert_eq!(cursor(&editor), (0, 8));
+    editor.undo();
+    assert_eq!(text(&editor), "hello! world");
+    editor.undo();
+    assert_eq!(text(&editor), "hello world");
+
+    // Starting a composition deletes the selection, and other actions remove the preedit
+    editor.set_select_opt(Some(Cursor::new(0, 0)));
+    editor.set_preedit("ab", Some(2..2));
+    assert_eq!(text(&editor), "ab world");
+    editor.action(&mut font_system, Action::Right);
+    assert_eq!(text(&editor), " world");
+    assert_eq!(cursor(&editor), (0, 1));
+    editor.undo();
+    assert_eq!(text(&editor), "hello world");
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Color, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    SwashCache, Weight,
+};
+
+#[test]
+fn premultiplied_pixels() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(100.0, 100.0);
+    buffer.set_text("o", attrs, Shaping::Advanced);
+
+    let base = Color::rgb(0xFF, 0x80, 0x40);
+    let mut cache = SwashCache::new();
+    let mut straight = Vec::new();
+    buffer.draw(&mut cache, base, |_, _, _, _, color| straight.push(color));
+
+    cache.set_premultiplied(true);
+    let mut premultiplied = Vec::new();
+    buffer.draw(&mut cache, base, |_, _, _, _, color| {
+        premultiplied.push(color)
+    });
+
+    assert_eq!(straight.len(), premultiplied.len());
+    // Antialiased edges of the glyph have partial coverage
+    let (straight, premultiplied) = straight
+        .iter()
+        .zip(premultiplied.iter())
+        .find(|(color, _)| (0x70..=0x90).contains(&color.a()))
+        .expect("no pixel with half coverage");
+    let a = straight.a();
+    assert_eq!(premultiplied.a(), a);
+    assert_eq!(premultiplied.r(), a);
+    assert_eq!(premultiplied.g(), ((0x80 * a as u32 + 127) / 255) as u8);
+    assert_eq!(premultiplied.b(), ((0x40 * a as u32 + 127) / 255) as u8);
+}
+
+#[test]
+fn premultiplied_rects() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "ab", attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    let mut editor = editor.borrow_with(&mut font_system);
+    editor.action(Action::SelectAll);
+
+    // Selections are the only rectangles wider than a pixel
+    let base = Color::rgb(0xFF, 0x80, 0x40);
+    let mut cache = SwashCache::new();
+    let mut straight = Vec::new();
+    editor.draw(&mut cache, base, |_, _, w, _, color| {
+        if w > 1 {
+            straight.push(color);
+        }
+    });
+
+    cache.set_premultiplied(true);
+    let mut premultiplied = Vec::new();
+    editor.draw(&mut cache, base, |_, _, w, _, color| {
+        if w > 1 {
+            premultiplied.push(color);
+        }
+    });
+
+    assert!(!straight.is_empty());
+    assert_eq!(straight.len(), premultiplied.len());
+    for (straight, premultiplied) in straight.iter().zip(premultiplied.iter()) {
+        assert!(straight.a() < 0xFF);
+        assert_eq!(*premultiplied, straight.premultiplied());
+    }
+}
@@ -0,0 +1,80 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Family, FontSystem, Metrics, PreparedRun, Script, Shaping, Weight,
+};
+
+#[test]
+fn prepared_runs_force_fonts() {
+    // Two copies of the same font, so that font matching always picks the same one
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font.clone());
+    font_system.db_mut().load_font_data(font);
+    let ids: Vec<fontdb::ID> = font_system.db().faces().map(|face| face.id).collect();
+    assert_eq!(ids.len(), 2);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "abc def", attrs, Shaping::Advanced);
+
+    let glyphs = |buffer: &Buffer| -> Vec<(usize, fontdb::ID, f32)> {
+        buffer
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter())
+            .map(|glyph| (glyph.start, glyph.font_id, glyph.x))
+            .collect()
+    };
+
+    let auto = glyphs(&buffer);
+    let auto_id = auto[0].1;
+    assert!(auto.iter().all(|(_, font_id, _)| *font_id == auto_id));
+    let other_id = *ids.iter().find(|id| **id != auto_id).unwrap();
+
+    buffer.set_prepared_runs(
+        &mut font_system,
+        vec![
+            PreparedRun {
+                line_i: 0,
+                range: 0..3,
+                font_id: other_id,
+                rtl: false,
+                script: Script::Latin,
+            },
+            PreparedRun {
+                line_i: 0,
+                range: 4..7,
+                font_id: auto_id,
+                rtl: true,
+                script: Script::Latin,
+            },
+        ],
+    );
+    assert_eq!(buffer.lines[0].prepared_runs().len(), 2);
+
+    let prepared = glyphs(&buffer);
+    assert_eq!(prepared.len(), 7);
+    for (start, font_id, _) in prepared.iter() {
+        match start {
+            0..=2 => assert_eq!(*font_id, other_id),
+            4..=6 => assert_eq!(*font_id, auto_id),
+            _ => (),
+        }
+    }
+
+    // The right-to-left run is laid out in reverse, after the left-to-right run
+    let x = |start| prepared.iter().find(|glyph| glyph.0 == start).unwrap().2;
+    assert!(x(0) < x(1) && x(1) < x(2));
+    assert!(x(2) < x(6) && x(6) < x(5) && x(5) < x(4));
+
+    // Changing the text clears the runs, so the line is itemized again
+    let attrs_list = buffer.lines[0].attrs_list().clone();
+    buffer.lines[0].set_text("abc defg", attrs_list);
+    assert!(buffer.lines[0].prepared_runs().is_empty());
+    buffer.shape_until_scroll(&mut font_system);
+    assert!(glyphs(&buffer)
+        .iter()
+        .all(|(_, font_id, _)| *font_id == auto_id));
+}
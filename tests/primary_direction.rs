@@ -0,0 +1,25 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Direction, FontSystem, Metrics, Shaping};
+
+#[test]
+fn primary_direction_from_first_strong_character() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+
+    let mut direction = |text| {
+        buffer.set_text(&mut font_system, text, Attrs::new(), Shaping::Advanced);
+        buffer.primary_direction()
+    };
+
+    assert_eq!(direction("مرحبا بالعالم\nHello"), Direction::Rtl);
+    assert_eq!(direction("Hello world\nمرحبا"), Direction::Ltr);
+    assert_eq!(direction("עברית"), Direction::Rtl);
+    // Neutral characters and lines without strong characters are skipped
+    assert_eq!(direction("123 !?\n\n  (مرحبا) Hello"), Direction::Rtl);
+    // Characters inside isolates do not count
+    assert_eq!(direction("\u{2067}Hello\u{2069} مرحبا"), Direction::Rtl);
+    assert_eq!(direction(""), Direction::Ltr);
+    assert_eq!(direction("42"), Direction::Ltr);
+}
@@ -0,0 +1,41 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn reset_shaping_single_line() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo\nthree",
+        attrs,
+        Shaping::Advanced,
+    );
+    let unshaped = |buffer: &Buffer| -> Vec<usize> {
+        (0..buffer.lines.len())
+            .filter(|line_i| buffer.lines[*line_i].is_reset())
+            .collect()
+    };
+    assert!(unshaped(&buffer).is_empty());
+
+    buffer.set_redraw(false);
+    buffer.reset_shaping(1);
+    assert!(buffer.redraw());
+
+    // Only the reset line needs to be shaped again
+    assert_eq!(unshaped(&buffer), vec![1]);
+    buffer.shape_until_scroll(&mut font_system);
+    assert!(unshaped(&buffer).is_empty());
+    assert_eq!(buffer.layout_runs().count(), 3);
+
+    // Out of range lines are ignored
+    buffer.reset_shaping(3);
+    assert!(unshaped(&buffer).is_empty());
+}
@@ -0,0 +1,27 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Style, Weight};
+
+// The test font only has a weight of 500, so a request for 400 resolves to 500
+#[test]
+fn resolved_weight_of_matched_face() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::NORMAL);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "hello", attrs, Shaping::Advanced);
+
+    let font_id = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| glyph.font_id)
+        .next()
+        .expect("no glyphs");
+    let (weight, style, _) = font_system.face_style(font_id).unwrap();
+    assert_eq!(weight, Weight::MEDIUM);
+    assert_eq!(style, Style::Normal);
+}
@@ -0,0 +1,81 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Family, FontSystem, LineEnding, Metrics, Shaping, Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn rich_text_spans_over_lines() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let bold = attrs.weight(Weight::BOLD);
+    let red = attrs.color(Color::rgb(0xFF, 0, 0));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("one ", attrs),
+            ("two\r\nthr", bold),
+            ("ee\n", red),
+            ("four", attrs),
+        ],
+        attrs,
+        Shaping::Advanced,
+    );
+
+    let lines: Vec<(&str, LineEnding)> = buffer
+        .lines
+        .iter()
+        .map(|line| (line.text(), line.ending()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            ("one two", LineEnding::CrLf),
+            ("three", LineEnding::Lf),
+            ("four", LineEnding::None),
+        ]
+    );
+
+    // Byte offsets of the spans start again on each line
+    let first = buffer.lines[0].attrs_list();
+    assert_eq!(first.get_span(0), attrs);
+    assert_eq!(first.get_span(4), bold);
+    assert_eq!(first.get_span(6), bold);
+    let second = buffer.lines[1].attrs_list();
+    assert_eq!(second.get_span(2), bold);
+    assert_eq!(second.get_span(3), red);
+    assert_eq!(buffer.lines[2].attrs_list().get_span(0), attrs);
+
+    // The lines are shaped with the attributes of their spans
+    let run = buffer.layout_runs().nth(1).unwrap();
+    assert_eq!(run.glyphs[0].color_opt, None);
+    assert_eq!(run.glyphs[3].color_opt, Some(Color::rgb(0xFF, 0, 0)));
+}
+
+#[test]
+fn rich_text_empty() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new().family(Family::Name("Fira Mono"));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(&mut font_system, [], attrs, Shaping::Advanced);
+    assert_eq!(buffer.lines.len(), 1);
+    assert_eq!(buffer.lines[0].text(), "");
+
+    // A trailing line ending does not add a line, the same as set_text
+    buffer.set_rich_text(&mut font_system, [("a\n", attrs)], attrs, Shaping::Advanced);
+    let mut plain = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    plain.set_text(&mut font_system, "a\n", attrs, Shaping::Advanced);
+    assert_eq!(buffer.lines.len(), plain.lines.len());
+}
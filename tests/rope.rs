@@ -0,0 +1,60 @@
+#![cfg(feature = "ropey")]
+
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+// Loading from a rope must give the same lines as loading from a string, and saving to a rope must
+// keep the edits made to those lines
+#[test]
+fn rope_load_and_save() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let text = "first\r\nsecond\nthird\rfourth\u{2029}fifth\n".repeat(100);
+
+    let mut string_buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    string_buffer.set_text(&mut font_system, &text, attrs, Shaping::Advanced);
+    let mut rope_buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    rope_buffer.set_rope(
+        &mut font_system,
+        &ropey::Rope::from_str(&text),
+        attrs,
+        Shaping::Advanced,
+    );
+
+    let lines = |buffer: &Buffer| {
+        buffer
+            .lines
+            .iter()
+            .map(|line| (line.text().to_string(), line.ending()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(lines(&string_buffer), lines(&rope_buffer));
+    assert_eq!(rope_buffer.to_rope().to_string(), text);
+
+    // Edits in the editor are saved to a rope that matches the same edits made with ropey
+    let mut rope = ropey::Rope::from_str(&text);
+    let mut editor = Editor::new(rope_buffer);
+    editor.set_cursor(Cursor::new(2, 3));
+    editor.insert_string("inserted\nlines", None);
+    editor.action(&mut font_system, Action::Backspace);
+    editor.set_cursor(Cursor::new(7, 0));
+    editor.action(&mut font_system, Action::Backspace);
+
+    let start = rope.line_to_char(2) + 3;
+    rope.insert(start, "inserted\nlines");
+    let end = start + "inserted\nlines".chars().count();
+    rope.remove(end - 1..end);
+    // Joins the line before with its "\r\n" ending
+    let start = rope.line_to_char(7);
+    rope.remove(start - 2..start);
+
+    assert_eq!(editor.buffer().to_rope().to_string(), rope.to_string());
+}
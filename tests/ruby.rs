@@ -0,0 +1,85 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Cursor, Family, FontSystem, Metrics, Shaping, Weight};
+
+// A base character with a two kana ruby should have the ruby centered above it, and the line
+// should grow to make room for the ruby.
+#[test]
+fn ruby_centered_above_base() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let metrics = Metrics::new(20.0, 30.0);
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    buffer.set_size(&mut font_system, 500.0, 500.0);
+    buffer.set_text(&mut font_system, "\u{6F22}\nabc", attrs, Shaping::Advanced);
+    buffer.lines[0].set_ruby(0..3, "\u{304B}\u{3093}", attrs);
+    buffer.shape_until_scroll(&mut font_system);
+
+    let ruby_height = buffer.line_layout(&mut font_system, 0).unwrap()[0].ruby_height;
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 2);
+
+    let base = &runs[0].glyphs[0];
+    let ruby = runs[0].ruby_glyphs;
+    assert_eq!(ruby.len(), 2);
+
+    // Ruby is centered over the base glyph
+    let base_center = base.x + base.w / 2.0;
+    let ruby_left = ruby.iter().map(|g| g.x).fold(f32::MAX, f32::min);
+    let ruby_right = ruby.iter().map(|g| g.x + g.w).fold(f32::MIN, f32::max);
+    assert!(((ruby_left + ruby_right) / 2.0 - base_center).abs() < 0.01);
+
+    // Ruby is above the base, at a smaller size
+    assert!(ruby
+        .iter()
+        .all(|g| g.y < 0.0 && g.font_size < base.font_size));
+
+    // The annotated line is taller than the buffer line height
+    assert!(ruby_height > 0.0);
+    assert_eq!(runs[1].line_top, metrics.line_height + ruby_height);
+}
+
+// Lines with ruby annotations take more of the view, so fewer lines are visible and scrolling to
+// a line below them scrolls further.
+#[test]
+fn ruby_scroll() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let metrics = Metrics::new(20.0, 30.0);
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    buffer.set_size(&mut font_system, 500.0, 2.0 * metrics.line_height);
+    buffer.set_text(&mut font_system, "abc\ndef\nghi", attrs, Shaping::Advanced);
+
+    // Invalid ranges are ignored
+    assert!(!buffer.lines[0].set_ruby(2..2, "x", attrs));
+    assert!(!buffer.lines[0].set_ruby(0..4, "x", attrs));
+    assert!(buffer.lines[0].ruby().is_empty());
+
+    assert!(buffer.lines[0].set_ruby(0..3, "xyz", attrs));
+    buffer.shape_until_scroll(&mut font_system);
+    let ruby_height = buffer.line_layout(&mut font_system, 0).unwrap()[0].ruby_height;
+    assert!(ruby_height > 0.0);
+    assert_eq!(buffer.visible_lines(), 1);
+
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(1, 0));
+    assert_eq!(buffer.scroll(), 1);
+    assert_eq!(buffer.scroll_pixels(), metrics.line_height + ruby_height);
+    assert_eq!(buffer.visible_lines(), 2);
+
+    buffer.set_scroll_pixels(&mut font_system, metrics.line_height + ruby_height + 1.0);
+    assert_eq!(buffer.scroll(), 1);
+    assert_eq!(
+        buffer.scroll_pixels(),
+        metrics.line_height + ruby_height + 1.0
+    );
+}
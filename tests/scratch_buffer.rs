@@ -0,0 +1,44 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight, Wrap};
+
+#[test]
+fn scratch_buffer_is_separate() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(&mut font_system, "main text", attrs, Shaping::Advanced);
+    let version = buffer.content_version();
+    let glyphs = buffer.layout_runs().next().unwrap().glyphs.len();
+
+    {
+        let mut scratch = font_system.scratch_buffer(Metrics::new(10.0, 12.0));
+        assert_eq!(scratch.metrics(), Metrics::new(10.0, 12.0));
+        let mut scratch = scratch.borrow();
+        scratch.set_size(500.0, 100.0);
+        scratch.set_wrap(Wrap::None);
+        scratch.set_text("tooltip", attrs, Shaping::Advanced);
+        let run = scratch.layout_runs().next().unwrap();
+        assert_eq!(run.glyphs.len(), 7);
+        assert!((run.line_w - 7.0 * 10.0 * 0.6).abs() < 0.01);
+    }
+
+    // The main buffer keeps its text and shaping
+    assert_eq!(buffer.content_version(), version);
+    assert_eq!(buffer.lines.len(), 1);
+    assert_eq!(buffer.lines[0].text(), "main text");
+    assert!(buffer.lines[0].shape_opt().is_some());
+    assert_eq!(buffer.layout_runs().next().unwrap().glyphs.len(), glyphs);
+
+    // The next scratch buffer starts empty, with its settings reset
+    let scratch = font_system.scratch_buffer(Metrics::new(14.0, 20.0));
+    assert_eq!(scratch.lines.len(), 1);
+    assert_eq!(scratch.lines[0].text(), "");
+    assert_eq!(scratch.wrap(), Wrap::Word);
+    assert_eq!(scratch.metrics(), Metrics::new(14.0, 20.0));
+}
@@ -0,0 +1,58 @@
+// The fallback families used below are those for Linux
+#![cfg(target_os = "linux")]
+
+use std::sync::{Arc, Mutex};
+
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Script, Shaping, Weight};
+
+/// Get the families tried for fallback while shaping text that the font has no glyphs for
+fn fallback_families(attrs: Attrs) -> Vec<String> {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Register the same font data as the preset fallback for the Arabic script
+    let mut copy = font_system.db().faces().next().unwrap().clone();
+    copy.families = vec![(
+        "Noto Sans Arabic".to_string(),
+        fontdb::Language::English_UnitedStates,
+    )];
+    font_system.db_mut().push_face_info(copy);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+
+    let families = Arc::new(Mutex::new(Vec::new()));
+    let hook_families = families.clone();
+    font_system.set_font_load_hook(Box::new(move |face| {
+        hook_families
+            .lock()
+            .unwrap()
+            .push(face.families[0].0.clone());
+        false
+    }));
+
+    buffer.set_text(&mut font_system, "\u{5D0}", attrs, Shaping::Advanced);
+
+    let families = families.lock().unwrap();
+    families.clone()
+}
+
+#[test]
+fn script_override() {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+
+    // Detected as Hebrew, which has no preset fallback font loaded
+    let detected = fallback_families(attrs);
+    assert_ne!(
+        detected.first().map(String::as_str),
+        Some("Noto Sans Arabic")
+    );
+
+    // Forced to Arabic, so the preset fallback for Arabic is tried first
+    let forced = fallback_families(attrs.script(Script::Arabic));
+    assert_eq!(forced.first().map(String::as_str), Some("Noto Sans Arabic"));
+}
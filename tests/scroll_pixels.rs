@@ -0,0 +1,61 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Cursor, Family, FontSystem, Metrics, Shaping, Weight};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn runs(buffer: &Buffer) -> Vec<(usize, f32)> {
+    buffer
+        .layout_runs()
+        .map(|run| (run.line_i, run.line_top))
+        .collect()
+}
+
+#[test]
+fn scroll_pixels() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 50.0);
+    buffer.set_text(
+        &mut font_system,
+        "0\n1\n2\n3\n4\n5",
+        attrs,
+        Shaping::Advanced,
+    );
+    assert_eq!(runs(&buffer), [(0, 0.0), (1, 20.0)]);
+
+    // Part of the first line is scrolled out of view, and part of another one comes into it
+    buffer.set_scroll_pixels(&mut font_system, 30.0);
+    assert_eq!(buffer.scroll(), 1);
+    assert_eq!(buffer.scroll_pixels(), 30.0);
+    assert_eq!(runs(&buffer), [(1, -10.0), (2, 10.0), (3, 30.0)]);
+    assert_eq!(buffer.cursor_position(&Cursor::new(1, 0)), Some((0, -10)));
+    let cursor = buffer.hit(1.0, 0.0).unwrap();
+    assert_eq!((cursor.line, cursor.index), (1, 0));
+
+    // Scrolling by lines removes the offset
+    buffer.set_scroll(2);
+    assert_eq!(buffer.scroll_pixels(), 40.0);
+    assert_eq!(runs(&buffer), [(2, 0.0), (3, 20.0)]);
+
+    // The scroll is clamped to the lines of the buffer
+    buffer.set_scroll_pixels(&mut font_system, 1000.0);
+    assert_eq!(buffer.scroll_pixels(), 100.0);
+    buffer.set_scroll_pixels(&mut font_system, -5.0);
+    assert_eq!(buffer.scroll_pixels(), 0.0);
+
+    // A cursor on a line partly out of view scrolls it back in
+    buffer.set_scroll_pixels(&mut font_system, 30.0);
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(1, 0));
+    assert_eq!(buffer.scroll_pixels(), 20.0);
+    buffer.set_scroll_pixels(&mut font_system, 30.0);
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(2, 0));
+    assert_eq!(buffer.scroll_pixels(), 30.0);
+}
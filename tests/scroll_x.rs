@@ -0,0 +1,65 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Color, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    SwashCache, Weight, Wrap,
+};
+
+#[test]
+fn scroll_x_offsets_glyphs_and_follows_cursor() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::None);
+    buffer.set_size(&mut font_system, 50.0, 100.0);
+    buffer.set_text(
+        &mut font_system,
+        "a line that is much wider than the buffer",
+        attrs,
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+    editor.set_focused(false);
+
+    let mut cache = SwashCache::new();
+    let mut min_x = |editor: &Editor, font_system: &mut FontSystem| {
+        let mut min_x = i32::MAX;
+        editor.draw(
+            font_system,
+            &mut cache,
+            Color::rgb(0xFF, 0xFF, 0xFF),
+            |x, _, _, _, _| min_x = min_x.min(x),
+        );
+        min_x
+    };
+
+    let unscrolled = min_x(&editor, &mut font_system);
+    editor.buffer_mut().set_scroll_x(20.0);
+    assert_eq!(editor.buffer().scroll_x(), 20.0);
+    assert_eq!(min_x(&editor, &mut font_system), unscrolled - 20);
+
+    // Scrolling is clamped to the content width
+    editor.buffer_mut().set_scroll_x(-5.0);
+    assert_eq!(editor.buffer().scroll_x(), 0.0);
+    editor.buffer_mut().set_scroll_x(1.0e6);
+    let max_scroll_x = editor.buffer().content_width() - 50.0;
+    assert_eq!(editor.buffer().scroll_x(), max_scroll_x);
+
+    // Moving the cursor off the right edge scrolls to show it
+    editor.buffer_mut().set_scroll_x(0.0);
+    editor.action(&mut font_system, Action::End);
+    editor.shape_as_needed(&mut font_system);
+    assert!(editor.buffer().scroll_x() > 0.0);
+    assert!(editor.buffer().scroll_x() <= max_scroll_x);
+
+    // And back off the left edge
+    editor.action(&mut font_system, Action::Home);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.buffer().scroll_x(), 0.0);
+}
@@ -0,0 +1,151 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, SearchOptions,
+    Shaping,
+};
+
+fn search_buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 500.0);
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    buffer
+}
+
+fn ranges(matches: &[(Cursor, Cursor)]) -> Vec<(usize, usize, usize)> {
+    matches
+        .iter()
+        .map(|(start, end)| {
+            assert_eq!(start.line, end.line);
+            (start.line, start.index, end.index)
+        })
+        .collect()
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn search_matches() {
+    let mut font_system = font_system();
+    let buffer = search_buffer(&mut font_system, "Hello hello\nHELLO world\naaaa");
+
+    assert_eq!(ranges(&buffer.search("hello", true)), [(0, 6, 11)]);
+    assert_eq!(
+        ranges(&buffer.search("hello", false)),
+        [(0, 0, 5), (0, 6, 11), (1, 0, 5)]
+    );
+    assert!(buffer.search("", false).is_empty());
+    assert!(buffer.search("o\nH", false).is_empty());
+
+    // Matches do not overlap
+    assert_eq!(ranges(&buffer.search("aa", true)), [(2, 0, 2), (2, 2, 4)]);
+    assert_eq!(ranges(&buffer.search("aaa", true)), [(2, 0, 3)]);
+
+    // Cursors are byte offsets into the original text
+    let buffer = search_buffer(&mut font_system, "\u{C4}BC \u{E4}bc");
+    assert_eq!(
+        ranges(&buffer.search("\u{E4}bc", false)),
+        [(0, 0, 4), (0, 5, 9)]
+    );
+    assert_eq!(ranges(&buffer.search("\u{E4}bc", true)), [(0, 5, 9)]);
+}
+
+#[test]
+fn search_next_wraps() {
+    let mut font_system = font_system();
+    let buffer = search_buffer(&mut font_system, "one two one\nthree\none");
+
+    let next = |line, index| {
+        let (start, end) = buffer
+            .search_next(Cursor::new(line, index), "one", true)
+            .unwrap();
+        (start.line, start.index, end.index)
+    };
+    assert_eq!(next(0, 0), (0, 0, 3));
+    assert_eq!(next(0, 1), (0, 8, 11));
+    assert_eq!(next(0, 9), (2, 0, 3));
+    assert_eq!(next(1, 0), (2, 0, 3));
+    assert_eq!(next(2, 1), (0, 0, 3));
+    assert!(buffer
+        .search_next(Cursor::new(0, 0), "four", true)
+        .is_none());
+
+    // Matches can be selected in an editor
+    let (start, end) = buffer.search_next(Cursor::new(1, 0), "one", true).unwrap();
+    let mut editor = Editor::new(buffer);
+    editor.set_select_opt(Some(start));
+    editor.set_cursor(end);
+    assert_eq!(editor.copy_selection().as_deref(), Some("one"));
+}
+
+#[test]
+fn search_previous_wraps() {
+    let mut font_system = font_system();
+    let buffer = search_buffer(&mut font_system, "one two one\nthree\none");
+
+    let previous = |line, index| {
+        let (start, end) = buffer
+            .search_previous(Cursor::new(line, index), "one", true)
+            .unwrap();
+        (start.line, start.index, end.index)
+    };
+    assert_eq!(previous(0, 9), (0, 8, 11));
+    assert_eq!(previous(0, 8), (0, 0, 3));
+    assert_eq!(previous(0, 0), (2, 0, 3));
+    assert_eq!(previous(1, 0), (0, 8, 11));
+    assert!(buffer
+        .search_previous(Cursor::new(0, 0), "four", true)
+        .is_none());
+}
+
+#[test]
+fn editor_search_actions() {
+    let mut font_system = font_system();
+    let buffer = search_buffer(&mut font_system, "foo bar foo\nbaz Foo");
+    let mut editor = Editor::new(buffer);
+    let selected = |editor: &Editor| {
+        let (start, end) = editor.selection().unwrap();
+        (start.line, start.index, end.index)
+    };
+
+    let matches: Vec<_> = editor.search("foo", SearchOptions::default()).collect();
+    assert_eq!(ranges(&matches), [(0, 0, 3), (0, 8, 11), (1, 4, 7)]);
+    assert_eq!(
+        editor.search_pattern(),
+        Some(("foo", SearchOptions::default()))
+    );
+
+    // Each step selects the following match, wrapping around the buffer
+    editor.action(&mut font_system, Action::SearchNext);
+    assert_eq!(selected(&editor), (0, 0, 3));
+    assert_eq!(editor.cursor().index, 3);
+    editor.action(&mut font_system, Action::SearchNext);
+    assert_eq!(selected(&editor), (0, 8, 11));
+    editor.action(&mut font_system, Action::SearchNext);
+    assert_eq!(selected(&editor), (1, 4, 7));
+    editor.action(&mut font_system, Action::SearchNext);
+    assert_eq!(selected(&editor), (0, 0, 3));
+    editor.action(&mut font_system, Action::SearchPrevious);
+    assert_eq!(selected(&editor), (1, 4, 7));
+
+    // Without wrapping around, the search stops at the ends of the buffer
+    let options = SearchOptions {
+        case_sensitive: true,
+        wrap_around: false,
+    };
+    assert_eq!(editor.search("foo", options).count(), 2);
+    editor.action(&mut font_system, Action::SearchNext);
+    assert_eq!(selected(&editor), (1, 4, 7));
+    editor.action(&mut font_system, Action::SearchPrevious);
+    assert_eq!(selected(&editor), (0, 8, 11));
+
+    // An empty pattern clears the search
+    assert_eq!(editor.search("", options).count(), 0);
+    assert_eq!(editor.search_pattern(), None);
+    editor.action(&mut font_system, Action::SearchNext);
+    assert_eq!(selected(&editor), (0, 8, 11));
+}
@@ -0,0 +1,45 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+#[test]
+fn select_lines_drag() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 200.0);
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo\nthree\nfour",
+        attrs,
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+
+    // Dragging down from the first line to the second selects both, with their newlines
+    editor.action(&mut font_system, Action::SelectLinesDrag { y: 5 });
+    assert_eq!(editor.select_opt(), Some(Cursor::new(0, 0)));
+    assert_eq!(editor.cursor(), Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::SelectLinesDrag { y: 25 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("one\ntwo\n"));
+
+    // Dragging to the last line selects to the end of the buffer
+    editor.action(&mut font_system, Action::SelectLinesDrag { y: 65 });
+    assert_eq!(
+        editor.copy_selection().as_deref(),
+        Some("one\ntwo\nthree\nfour")
+    );
+
+    // Dragging up from a new anchor selects from the anchor line upwards
+    editor.action(&mut font_system, Action::Escape);
+    editor.action(&mut font_system, Action::SelectLinesDrag { y: 45 });
+    editor.action(&mut font_system, Action::SelectLinesDrag { y: 25 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("two\nthree\n"));
+}
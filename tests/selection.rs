@@ -0,0 +1,67 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn selection(editor: &Editor) -> Option<((usize, usize), (usize, usize))> {
+    editor
+        .selection()
+        .map(|(start, end)| ((start.line, start.index), (end.line, end.index)))
+}
+
+#[test]
+fn select_all() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo");
+    assert_eq!(selection(&editor), None);
+
+    editor.action(&mut font_system, Action::SelectAll);
+    assert_eq!(selection(&editor), Some(((0, 0), (1, 3))));
+    assert_eq!(editor.copy_selection().as_deref(), Some("one\ntwo"));
+
+    editor.action(&mut font_system, Action::Escape);
+    editor.select_all();
+    assert_eq!(editor.cursor(), Cursor::new(1, 3));
+    assert_eq!(selection(&editor), Some(((0, 0), (1, 3))));
+}
+
+#[test]
+fn select_with_movement() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one two\nthree");
+    editor.set_cursor(Cursor::new(0, 1));
+
+    // The selection starts where the cursor was
+    editor.action(&mut font_system, Action::Select(Box::new(Action::Right)));
+    editor.action(&mut font_system, Action::Select(Box::new(Action::Right)));
+    assert_eq!(selection(&editor), Some(((0, 1), (0, 3))));
+
+    // Moving back past the start reverses the selection
+    editor.action(&mut font_system, Action::Select(Box::new(Action::Home)));
+    assert_eq!(selection(&editor), Some(((0, 0), (0, 1))));
+    editor.action(&mut font_system, Action::Select(Box::new(Action::Down)));
+    assert_eq!(editor.copy_selection().as_deref(), Some("ne two\n"));
+
+    editor.action(&mut font_system, Action::Select(Box::new(Action::End)));
+    assert_eq!(editor.copy_selection().as_deref(), Some("ne two\nthree"));
+}
@@ -0,0 +1,114 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Rectangle, Shaping,
+    Weight, Wrap,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn new_editor(font_system: &mut FontSystem, text: &str, width: f32, wrap: Wrap) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, width, 100.0);
+    buffer.set_wrap(font_system, wrap);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn assert_rect(rect: &Rectangle, x: f32, y: f32, width: f32) {
+    assert!((rect.x - x).abs() < 0.01, "{:?}", rect);
+    assert!((rect.y - y).abs() < 0.01, "{:?}", rect);
+    assert!((rect.width - width).abs() < 0.01, "{:?}", rect);
+    assert_eq!(rect.height, 20.0);
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn selection_bounds_wrapped_line() {
+    let mut font_system = font_system();
+    let view_w = 6.0 * GLYPH_W;
+    let mut editor = new_editor(&mut font_system, "abcd efgh ijkl", view_w, Wrap::Word);
+    assert!(editor.selection_bounds().is_empty());
+
+    // Rows that the selection continues past extend to the edge of the view
+    editor.set_select_opt(Some(Cursor::new(0, 1)));
+    editor.set_cursor(Cursor::new(0, 12));
+    let rects = editor.selection_bounds();
+    assert_eq!(rects.len(), 3);
+    assert_rect(&rects[0], GLYPH_W, 0.0, view_w - GLYPH_W);
+    assert_rect(&rects[1], 0.0, 20.0, view_w);
+    assert_rect(&rects[2], 0.0, 40.0, 2.0 * GLYPH_W);
+
+    // A selection ending at a wrap does not extend past it
+    editor.set_cursor(Cursor::new(0, 10));
+    let rects = editor.selection_bounds();
+    assert_eq!(rects.len(), 2);
+    assert_rect(&rects[1], 0.0, 20.0, 5.0 * GLYPH_W);
+
+    // Every row of a wrapped line extends when the selection continues on the next line
+    let mut editor = new_editor(&mut font_system, "abcd efgh ijkl\nxyz", view_w, Wrap::Word);
+    editor.set_select_opt(Some(Cursor::new(0, 1)));
+    editor.set_cursor(Cursor::new(1, 2));
+    let rects = editor.selection_bounds();
+    assert_eq!(rects.len(), 4);
+    assert_rect(&rects[0], GLYPH_W, 0.0, view_w - GLYPH_W);
+    assert_rect(&rects[1], 0.0, 20.0, view_w);
+    assert_rect(&rects[2], 0.0, 40.0, view_w);
+    assert_rect(&rects[3], 0.0, 60.0, 2.0 * GLYPH_W);
+}
+
+#[test]
+fn selection_bounds_multiple_lines() {
+    let mut font_system = font_system();
+    let view_w = 20.0 * GLYPH_W;
+    let mut editor = new_editor(&mut font_system, "abc\n\ndef", view_w, Wrap::Word);
+
+    editor.set_select_opt(Some(Cursor::new(2, 2)));
+    editor.set_cursor(Cursor::new(0, 2));
+    let rects = editor.selection_bounds();
+    assert_eq!(rects.len(), 3);
+    assert_rect(&rects[0], 2.0 * GLYPH_W, 0.0, view_w - 2.0 * GLYPH_W);
+    assert_rect(&rects[1], 0.0, 20.0, view_w);
+    assert_rect(&rects[2], 0.0, 40.0, 2.0 * GLYPH_W);
+}
+
+#[test]
+fn selection_bounds_scrolled() {
+    let mut font_system = font_system();
+    let view_w = 10.0 * GLYPH_W;
+    let mut editor = new_editor(&mut font_system, "abcdefghijklmnopqrst", view_w, Wrap::None);
+    editor.buffer_mut().set_scroll_x(4.0 * GLYPH_W);
+    let scroll_x = editor.buffer().scroll_x().round();
+    assert!(scroll_x > 0.0);
+
+    // Rectangles are relative to the view, like the cursor position
+    editor.set_select_opt(Some(Cursor::new(0, 5)));
+    editor.set_cursor(Cursor::new(0, 8));
+    let rects = editor.selection_bounds();
+    assert_eq!(rects.len(), 1);
+    assert_rect(&rects[0], 5.0 * GLYPH_W - scroll_x, 0.0, 3.0 * GLYPH_W);
+    let (cursor_x, _) = editor.buffer().cursor_position(&Cursor::new(0, 5)).unwrap();
+    assert_eq!(cursor_x, rects[0].x as i32);
+
+    let mut drawn = Vec::new();
+    editor.draw_selection(|x, y, w, h, _| drawn.push((x, y, w, h)));
+    assert_eq!(
+        drawn,
+        [(
+            rects[0].x as i32,
+            0,
+            ((5.0 + 3.0) * GLYPH_W) as u32 - (5.0 * GLYPH_W) as u32,
+            20
+        )]
+    );
+}
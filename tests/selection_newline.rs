@@ -0,0 +1,39 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+#[test]
+fn selection_includes_newline() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "foo\nbar", attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(&mut font_system);
+
+    let mut select_line = |editor: &mut Editor| {
+        editor.set_cursor(Cursor::new(0, 0));
+        editor.set_select_opt(Some(editor.cursor()));
+        editor.action(&mut font_system, Action::End);
+    };
+
+    select_line(&mut editor);
+    assert_eq!(editor.copy_selection().as_deref(), Some("foo"));
+
+    editor.set_selection_includes_newline(true);
+    select_line(&mut editor);
+    assert_eq!(editor.copy_selection().as_deref(), Some("foo\n"));
+
+    // Deleting removes the newline too
+    assert!(editor.delete_selection());
+    assert_eq!(editor.buffer().lines.len(), 1);
+    assert_eq!(editor.buffer().lines[0].text(), "bar");
+}
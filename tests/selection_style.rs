@@ -0,0 +1,34 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, SelectionStyle,
+    Shaping, Weight,
+};
+
+#[test]
+fn selection_rects_carry_border_radius() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 100.0);
+    buffer.set_text(&mut font_system, "hello\nworld", attrs, Shaping::Advanced);
+
+    let mut editor = Editor::new(buffer);
+    editor.set_select_opt(Some(Cursor::new(0, 1)));
+    editor.set_cursor(Cursor::new(1, 3));
+    editor.set_selection_style(SelectionStyle {
+        border_radius: 4.0,
+        ..SelectionStyle::default()
+    });
+
+    let mut rects = Vec::new();
+    editor.draw_selection(|x, y, w, h, border_radius| rects.push((x, y, w, h, border_radius)));
+
+    // One rectangle for each selected visual line
+    assert_eq!(rects.len(), 2);
+    assert!(rects.iter().all(|rect| rect.4 == 4.0));
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "std")]
+
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+use std::time::Instant;
+
+#[test]
+fn shape_until_deadline() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    // No height, so setting text does not shape anything
+    buffer.set_size(500.0, 0.0);
+    let text = "The quick brown fox jumps over the lazy dog\n".repeat(1000);
+    buffer.set_text(&text, attrs, Shaping::Advanced);
+
+    let laid_out = |buffer: &Buffer| {
+        buffer
+            .lines
+            .iter()
+            .filter(|line| line.layout_opt().is_some())
+            .count()
+    };
+    let before = laid_out(&buffer);
+
+    // A deadline that has already passed still makes progress, but not much
+    assert!(buffer.shape_until_deadline(Instant::now()));
+    let after = laid_out(&buffer);
+    assert!(after > before);
+    assert!(after < buffer.lines.len());
+
+    while buffer.shape_until_deadline(Instant::now()) {}
+    assert_eq!(laid_out(&buffer), buffer.lines.len());
+}
@@ -0,0 +1,51 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Family, FontSystem, LayoutGlyph, Metrics, Shaping, Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn glyphs(font_system: &mut FontSystem, text: &str) -> Vec<LayoutGlyph> {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 500.0);
+    buffer.set_rich_text(
+        font_system,
+        [(text, attrs), (" let", attrs.color(Color::rgb(0xFF, 0, 0)))],
+        attrs,
+        Shaping::Advanced,
+    );
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.to_vec())
+        .collect()
+}
+
+#[test]
+fn shape_run_cache() {
+    let text = "let a = 1;\nlet b = a;\n\tlet c = \"let\";\nשלום let";
+
+    let mut uncached = font_system();
+    uncached.set_shape_run_cache_limit(0);
+    assert_eq!(uncached.shape_run_cache_limit(), 0);
+    let expected = glyphs(&mut uncached, text);
+
+    // Repeated words reuse their glyphs, at their own position and with their own attributes
+    let mut cached = font_system();
+    assert_eq!(cached.shape_run_cache_limit(), 4096);
+    assert_eq!(glyphs(&mut cached, text), expected);
+    assert_eq!(glyphs(&mut cached, text), expected);
+
+    // A limit smaller than the number of runs drops the least recently used ones
+    cached.set_shape_run_cache_limit(2);
+    assert_eq!(glyphs(&mut cached, text), expected);
+    cached.clear_shape_run_cache();
+    assert_eq!(glyphs(&mut cached, text), expected);
+}
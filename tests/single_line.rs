@@ -0,0 +1,57 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    SingleLineNewlines, Weight,
+};
+
+fn single_line_editor(font_system: &mut FontSystem, newlines: SingleLineNewlines) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 200.0, 100.0);
+    buffer.set_text(font_system, "", attrs, Shaping::Advanced);
+    buffer.set_single_line(true);
+    buffer.set_single_line_newlines(newlines);
+    Editor::new(buffer)
+}
+
+fn lines(editor: &Editor) -> Vec<&str> {
+    editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.text())
+        .collect()
+}
+
+#[test]
+fn single_line_insert() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let mut editor = single_line_editor(&mut font_system, SingleLineNewlines::Space);
+    assert!(editor.buffer().single_line());
+    editor.insert_string("a\nb", None);
+    assert_eq!(lines(&editor), ["a b"]);
+    editor.action(&mut font_system, Action::Paste("\r\nc\rd\n".to_string()));
+    assert_eq!(lines(&editor), ["a b c d "]);
+
+    // Enter and typed newlines do nothing
+    editor.action(&mut font_system, Action::Enter);
+    editor.action(&mut font_system, Action::Insert('\n'));
+    assert_eq!(lines(&editor), ["a b c d "]);
+    assert_eq!(editor.cursor().index, 8);
+
+    let mut editor = single_line_editor(&mut font_system, SingleLineNewlines::Truncate);
+    editor.insert_string("a\nb", None);
+    assert_eq!(lines(&editor), ["a"]);
+    editor.insert_string("bc", None);
+    assert_eq!(lines(&editor), ["abc"]);
+
+    // Without single line mode, newlines split lines
+    editor.buffer_mut().set_single_line(false);
+    editor.insert_string("d\ne", None);
+    assert_eq!(lines(&editor), ["abcd", "e"]);
+}
@@ -0,0 +1,68 @@
+use cosmic_text::{
+    fontdb, Affinity, Attrs, Buffer, Cursor, Direction, Family, FontSystem, Metrics, Shaping,
+    SplitCaret, Weight,
+};
+
+fn new_buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+}
+
+#[test]
+fn split_caret_positions() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // The right-to-left run is shown between x 33.6 and 58.8
+    let buffer = new_buffer(&mut font_system, "abc \u{5D0}\u{5D1}\u{5D2} def");
+    assert_eq!(buffer.lines[0].direction(), Some(Direction::Ltr));
+    let layout_glyphs = &buffer.layout_runs().next().unwrap().glyphs;
+    assert!(layout_glyphs[4].level.is_rtl());
+    assert!(!layout_glyphs[3].level.is_rtl());
+
+    // Between the space and the alef, the caret is split between the two
+    let before = Cursor::new_with_affinity(0, 4, Affinity::Before);
+    assert_eq!(
+        buffer.split_caret_positions(&before),
+        Some(SplitCaret {
+            primary: (33, 0),
+            secondary_opt: Some((58, 0))
+        })
+    );
+    let after = Cursor::new_with_affinity(0, 4, Affinity::After);
+    assert_eq!(
+        buffer.split_caret_positions(&after),
+        Some(SplitCaret {
+            primary: (58, 0),
+            secondary_opt: Some((33, 0))
+        })
+    );
+    assert_eq!(
+        buffer.cursor_position(&after),
+        buffer
+            .split_caret_positions(&after)
+            .map(|split| split.primary)
+    );
+
+    // Inside a run, there is only one caret
+    assert_eq!(
+        buffer.split_caret_positions(&Cursor::new(0, 1)),
+        Some(SplitCaret {
+            primary: (8, 0),
+            secondary_opt: None
+        })
+    );
+
+    let buffer = new_buffer(&mut font_system, "\u{5D0}\u{5D1}\u{5D2}");
+    assert_eq!(buffer.lines[0].direction(), Some(Direction::Rtl));
+    let mut buffer = new_buffer(&mut font_system, "abc");
+    buffer.lines[0].reset();
+    assert_eq!(buffer.lines[0].direction(), None);
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Antialiasing, Attrs, Buffer, CacheKey, Color, Family, FontSystem, Metrics, Shaping,
+    SwashCache, SwashContent, Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn cache_key(font_system: &mut FontSystem) -> CacheKey {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 100.0, 100.0);
+    buffer.set_text(font_system, "a", attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs[0].physical((0., 0.), 1.0).cache_key
+}
+
+fn pixels(
+    font_system: &mut FontSystem,
+    swash_cache: &mut SwashCache,
+    cache_key: CacheKey,
+) -> Vec<Color> {
+    let mut pixels = Vec::new();
+    swash_cache.with_pixels(
+        font_system,
+        cache_key,
+        Color::rgb(0, 0, 0),
+        |_, _, color| pixels.push(color),
+    );
+    pixels
+}
+
+#[test]
+fn subpixel_antialiasing() {
+    let mut font_system = font_system();
+    let cache_key = cache_key(&mut font_system);
+    let mut swash_cache = SwashCache::new();
+    assert_eq!(swash_cache.antialiasing(), Antialiasing::Grayscale);
+    let grayscale = swash_cache
+        .get_image(&mut font_system, cache_key)
+        .clone()
+        .unwrap();
+    assert_eq!(grayscale.content, SwashContent::Mask);
+
+    // Changing the mode rasterizes again, with the coverage of each subpixel
+    swash_cache.set_antialiasing(Antialiasing::Subpixel);
+    assert!(swash_cache.image_cache.is_empty());
+    let subpixel = swash_cache
+        .get_image(&mut font_system, cache_key)
+        .clone()
+        .unwrap();
+    assert_eq!(subpixel.content, SwashContent::SubpixelMask);
+    assert_eq!(
+        subpixel.data.len(),
+        (subpixel.placement.width * subpixel.placement.height * 4) as usize
+    );
+
+    // Without a background, the average coverage is used as alpha
+    let averaged = pixels(&mut font_system, &mut swash_cache, cache_key);
+    assert_eq!(
+        averaged.len(),
+        (subpixel.placement.width * subpixel.placement.height) as usize
+    );
+    assert!(averaged
+        .iter()
+        .all(|color| (color.r(), color.g(), color.b()) == (0, 0, 0)));
+
+    // With a background, pixels are blended against it, and edges have color fringes
+    swash_cache.set_subpixel_background(Some(Color::rgb(0xFF, 0xFF, 0xFF)));
+    let blended = pixels(&mut font_system, &mut swash_cache, cache_key);
+    assert!(!blended.is_empty() && blended.len() < averaged.len());
+    assert!(blended.iter().all(|color| color.a() == 0xFF));
+    assert!(blended
+        .iter()
+        .any(|color| color.r() != color.g() || color.g() != color.b()));
+}
+
+#[test]
+fn gamma() {
+    let mut font_system = font_system();
+    let cache_key = cache_key(&mut font_system);
+    let mut swash_cache = SwashCache::new();
+    let coverage =
+        |pixels: Vec<Color>| -> u32 { pixels.iter().map(|color| color.a() as u32).sum() };
+    let linear = coverage(pixels(&mut font_system, &mut swash_cache, cache_key));
+
+    // A higher gamma makes antialiased edges darker, without rasterizing again
+    swash_cache.set_gamma(2.2);
+    assert_eq!(swash_cache.gamma(), 2.2);
+    assert_eq!(swash_cache.image_cache.len(), 1);
+    let darker = coverage(pixels(&mut font_system, &mut swash_cache, cache_key));
+    assert!(darker > linear);
+
+    swash_cache.set_gamma(0.0);
+    assert_eq!(swash_cache.gamma(), 2.2);
+    swash_cache.set_gamma(1.0);
+    assert_eq!(
+        coverage(pixels(&mut font_system, &mut swash_cache, cache_key)),
+        linear
+    );
+}
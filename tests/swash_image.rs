@@ -0,0 +1,158 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, CacheKey, Family, FontSystem, Metrics, Shaping, SwashCache,
+    SwashContent, Weight,
+};
+
+#[test]
+fn get_image_is_cached() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "g", attrs, Shaping::Advanced);
+    let cache_key = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| glyph.physical((0., 0.), 1.0).cache_key)
+        .next()
+        .unwrap();
+
+    let mut cache = SwashCache::new();
+    let first = cache
+        .get_image(&mut font_system, cache_key)
+        .as_ref()
+        .unwrap() as *const _;
+    let image = cache
+        .get_image(&mut font_system, cache_key)
+        .as_ref()
+        .unwrap();
+    assert!(core::ptr::eq(first, image));
+
+    // Outline fonts rasterize to an alpha mask, with a descender below the origin
+    assert!(matches!(image.content, SwashContent::Mask));
+    let placement = image.placement;
+    assert!(placement.width > 0 && placement.height > 0);
+    assert!(placement.top > 0);
+    assert!((placement.height as i32) > placement.top);
+    assert_eq!(
+        image.data.len(),
+        (placement.width * placement.height) as usize
+    );
+    assert_eq!(cache.image_cache.len(), 1);
+}
+
+#[test]
+fn get_image_respects_max_pixels() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "g", attrs, Shaping::Advanced);
+    let glyph = buffer.layout_runs().next().unwrap().glyphs[0].clone();
+    let cache_key = |font_size| CacheKey::new(glyph.font_id, glyph.glyph_id, font_size, (0., 0.)).0;
+
+    let mut cache = SwashCache::new();
+    assert_eq!(cache.max_image_pixels(), 4096 * 4096);
+
+    // An absurd size is refused instead of allocating a huge bitmap
+    assert!(cache.get_image(&mut font_system, cache_key(1e9)).is_none());
+    assert!(cache
+        .get_image_uncached(&mut font_system, cache_key(f32::INFINITY))
+        .is_none());
+
+    // Large sizes within the limit are still rasterized
+    let image = cache
+        .get_image(&mut font_system, cache_key(1000.0))
+        .as_ref()
+        .unwrap();
+    let pixels = image.placement.width * image.placement.height;
+    assert!(pixels > 0 && pixels <= 4096 * 4096);
+
+    // Lowering the limit clears cached images that no longer fit
+    cache.set_max_image_pixels(pixels / 2);
+    assert_eq!(cache.max_image_pixels(), pixels / 2);
+    assert!(cache.image_cache.is_empty());
+    assert!(cache
+        .get_image(&mut font_system, cache_key(1000.0))
+        .is_none());
+    let image = cache
+        .get_image(&mut font_system, cache_key(14.0))
+        .as_ref()
+        .unwrap();
+    assert!(image.placement.width * image.placement.height <= pixels / 2);
+}
+
+#[test]
+fn get_image_at_scale() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "g", attrs, Shaping::Advanced);
+    let cache_key = buffer.layout_runs().next().unwrap().glyphs[0]
+        .physical((0., 0.), 1.0)
+        .cache_key;
+
+    let mut cache = SwashCache::new();
+    let placement = cache
+        .get_image_at_scale(&mut font_system, cache_key, 1.0)
+        .as_ref()
+        .unwrap()
+        .placement;
+    let scaled = cache
+        .get_image_at_scale(&mut font_system, cache_key, 4.0)
+        .as_ref()
+        .unwrap()
+        .placement;
+    assert_eq!(cache.image_cache.len(), 2);
+
+    // The images have a pixel of antialiasing on each side, which is not scaled
+    let (width, height) = (placement.width as f32, placement.height as f32);
+    assert!(
+        (scaled.width as f32 - 4.0 * width).abs() <= 8.0,
+        "{:?}",
+        scaled
+    );
+    assert!(
+        (scaled.height as f32 - 4.0 * height).abs() <= 8.0,
+        "{:?}",
+        scaled
+    );
+
+    // Each scale is cached, and scale 1 is the same as the unscaled image
+    cache.get_image_at_scale(&mut font_system, cache_key, 4.0);
+    assert_eq!(cache.image_cache.len(), 2);
+    let image = cache
+        .get_image(&mut font_system, cache_key)
+        .as_ref()
+        .unwrap();
+    assert_eq!(
+        (image.placement.left, image.placement.top),
+        (placement.left, placement.top)
+    );
+    assert_eq!(
+        (image.placement.width, image.placement.height),
+        (placement.width, placement.height)
+    );
+    assert_eq!(cache.image_cache.len(), 2);
+}
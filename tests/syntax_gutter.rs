@@ -0,0 +1,71 @@
+#![cfg(feature = "syntect")]
+
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Family, FontSystem, GutterStyle, Metrics, Shaping,
+    SyntaxEditor, SyntaxSystem, Weight,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn numbers(editor: &SyntaxEditor) -> Vec<Option<String>> {
+    editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.prefix().map(|(text, _)| text.to_string()))
+        .collect()
+}
+
+#[test]
+fn gutter_line_numbers() {
+    let mut font_system = font_system();
+    let syntax_system = SyntaxSystem::new();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "ab\ncd\nef", attrs, Shaping::Advanced);
+    let mut editor = SyntaxEditor::new(buffer, &syntax_system, "base16-eighties.dark").unwrap();
+
+    editor.set_gutter(3, GutterStyle::Absolute);
+    assert_eq!(editor.gutter(), Some((3, GutterStyle::Absolute)));
+    editor.shape_as_needed(&mut font_system);
+    let some = |text: &str| Some(text.to_string());
+    assert_eq!(numbers(&editor), [some("  1 "), some("  2 "), some("  3 ")]);
+
+    // The text starts after the gutter, and clicks are offset with it
+    let run = editor.buffer().layout_runs().next().unwrap();
+    assert!((run.prefix_w - 4.0 * GLYPH_W).abs() < 0.01);
+    assert!((run.glyphs[0].x - 4.0 * GLYPH_W).abs() < 0.01);
+    editor.action(
+        &mut font_system,
+        Action::Click {
+            x: (5.75 * GLYPH_W) as i32,
+            y: 25,
+        },
+    );
+    assert_eq!((editor.cursor().line, editor.cursor().index), (1, 2));
+    editor.action(&mut font_system, Action::Click { x: 2, y: 25 });
+    assert_eq!(editor.cursor().index, 0);
+
+    // Relative numbers follow the cursor line
+    editor.set_gutter(3, GutterStyle::Relative);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(numbers(&editor), [some("  1 "), some("  2 "), some("  1 ")]);
+    editor.set_cursor(Cursor::new(2, 0));
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(numbers(&editor), [some("  2 "), some("  1 "), some("  3 ")]);
+
+    editor.set_gutter(0, GutterStyle::Absolute);
+    assert_eq!(editor.gutter(), None);
+    assert_eq!(numbers(&editor), [None, None, None]);
+}
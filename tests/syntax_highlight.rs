@@ -0,0 +1,65 @@
+#![cfg(feature = "syntect")]
+
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Color, Cursor, Edit, Family, FontSystem, Metrics, SyntaxEditor,
+    SyntaxSystem, Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn color(editor: &SyntaxEditor, line_i: usize) -> Option<Color> {
+    editor.buffer().lines[line_i]
+        .attrs_list()
+        .get_span(0)
+        .color_opt
+}
+
+#[test]
+fn highlight_after_inserted_line() {
+    let mut font_system = font_system();
+    let syntax_system = SyntaxSystem::new();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 200.0);
+    let mut editor = SyntaxEditor::new(buffer, &syntax_system, "base16-eighties.dark").unwrap();
+
+    let path = std::env::temp_dir().join("cosmic_text_syntax_highlight.rs");
+    std::fs::write(&path, "fn a() {}\n/*\ncomment\n*/\nfn b() {}").unwrap();
+    editor.load_text(&mut font_system, &path, attrs).unwrap();
+    editor.shape_as_needed(&mut font_system);
+    let comment = color(&editor, 2);
+    assert_eq!(color(&editor, 3), comment);
+    assert_ne!(color(&editor, 4), comment);
+
+    // Only the lines up to where the states match again are highlighted after the new line
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::Enter);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(color(&editor, 3), comment);
+
+    // Editing a later line starts from the state of the line before it
+    editor.set_cursor(Cursor::new(4, 2));
+    editor.action(&mut font_system, Action::Insert(' '));
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(color(&editor, 4), comment);
+    assert_ne!(color(&editor, 5), comment);
+
+    // Removing lines keeps the states lined up too
+    editor.set_cursor(Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::Backspace);
+    editor.shape_as_needed(&mut font_system);
+    editor.set_cursor(Cursor::new(3, 2));
+    editor.action(&mut font_system, Action::Insert(' '));
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(color(&editor, 3), comment);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "syntect")]
+
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Edit, Family, FontSystem, Metrics, Shaping, SyntaxEditor,
+    SyntaxSystem, Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn text_color(editor: &SyntaxEditor) -> Option<Color> {
+    editor.buffer().lines[0].attrs_list().get_span(0).color_opt
+}
+
+#[test]
+fn update_theme() {
+    let mut font_system = font_system();
+    let syntax_system = SyntaxSystem::new();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(&mut font_system, "hello", attrs, Shaping::Advanced);
+
+    let missing = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    assert!(SyntaxEditor::new(missing, &syntax_system, "missing").is_none());
+    let mut editor = SyntaxEditor::new(buffer, &syntax_system, "base16-eighties.dark").unwrap();
+    editor.shape_as_needed(&mut font_system);
+    let dark = editor.background_color();
+    assert_eq!(text_color(&editor), Some(editor.foreground_color()));
+
+    // A missing theme keeps the current one
+    assert!(!editor.update_theme("missing"));
+    assert_eq!(editor.background_color(), dark);
+
+    // Switching themes highlights the text again with the new colors
+    editor.buffer_mut().set_redraw(false);
+    assert!(editor.update_theme("base16-ocean.light"));
+    assert!(editor.buffer().redraw());
+    assert_ne!(editor.background_color(), dark);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(text_color(&editor), Some(editor.foreground_color()));
+}
@@ -0,0 +1,56 @@
+use cosmic_text::{fontdb, Align, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn tab_leader_fills_tab_gap() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 200.0, 500.0);
+    // The long word wraps, so justification widens the tab on the first line
+    buffer.set_text(
+        &mut font_system,
+        "Intro\tPage wrapping_beyond_the_buffer_width",
+        attrs,
+        Shaping::Advanced,
+    );
+    buffer.lines[0].set_align(Some(Align::Justified));
+    buffer.shape_until_scroll(&mut font_system);
+
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.tab_leader.is_empty());
+    assert!(run.tab_leader_glyphs().is_empty());
+
+    buffer.set_tab_leader(&mut font_system, Some('.'));
+    assert_eq!(buffer.tab_leader(), Some('.'));
+
+    let run = buffer.layout_runs().next().unwrap();
+    let tab = run
+        .glyphs
+        .iter()
+        .find(|glyph| &run.text[glyph.start..glyph.end] == "\t")
+        .unwrap();
+    assert!(tab.w > 14.0 * 0.6 * 2.0);
+
+    let dot = run.tab_leader[0].glyph_id;
+    let leaders = run.tab_leader_glyphs();
+    assert!(leaders.len() > 1);
+    for pair in leaders.windows(2) {
+        assert!(pair[0].x < pair[1].x);
+    }
+    for glyph in leaders.iter() {
+        assert_eq!(glyph.glyph_id, dot);
+        assert_eq!((glyph.start, glyph.end), (tab.start, tab.end));
+        assert!(glyph.x >= tab.x);
+        assert!(glyph.x + glyph.w <= tab.x + tab.w);
+    }
+
+    // Lines without tabs have no leaders
+    let run = buffer.layout_runs().nth(1).unwrap();
+    assert!(run.tab_leader_glyphs().is_empty());
+}
@@ -0,0 +1,131 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn tab_buffer(font_system: &mut FontSystem, text: &str, width: f32, tab_width: u16) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, width, 500.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer.set_tab_width(font_system, tab_width);
+    buffer
+}
+
+// Get the x position of the last glyph in the first layout run
+fn last_x(buffer: &Buffer) -> f32 {
+    buffer
+        .layout_runs()
+        .next()
+        .unwrap()
+        .glyphs
+        .last()
+        .unwrap()
+        .x
+}
+
+#[test]
+fn tabs_snap_to_stops() {
+    let mut font_system = font_system();
+
+    let buffer = tab_buffer(&mut font_system, "a\tb", 500.0, 8);
+    assert_eq!(buffer.tab_width(), 8);
+    assert!((last_x(&buffer) - 8.0 * GLYPH_W).abs() < 0.01);
+
+    for (text, stop) in [
+        ("a\tb", 4.0),
+        ("abc\tb", 4.0),
+        ("  \tb", 4.0),
+        ("abcd\tb", 8.0),
+        ("\t\tb", 8.0),
+        ("a\t \tb", 8.0),
+    ] {
+        let buffer = tab_buffer(&mut font_system, text, 500.0, 4);
+        assert!(
+            (last_x(&buffer) - stop * GLYPH_W).abs() < 0.01,
+            "{:?}",
+            text
+        );
+    }
+
+    // A tab width of 0 is ignored
+    let mut buffer = tab_buffer(&mut font_system, "a\tb", 500.0, 4);
+    buffer.set_tab_width(&mut font_system, 0);
+    assert_eq!(buffer.tab_width(), 4);
+}
+
+#[test]
+fn tab_hit_and_cursor() {
+    let mut font_system = font_system();
+    let buffer = tab_buffer(&mut font_system, "a\tb", 500.0, 4);
+
+    // The tab covers the gap up to the stop, so hits inside it land on either side of the tab
+    let hit = |x| {
+        let cursor = buffer.hit(x, 10.0).unwrap();
+        (cursor.line, cursor.index)
+    };
+    assert_eq!(hit(1.25 * GLYPH_W), (0, 1));
+    assert_eq!(hit(2.25 * GLYPH_W), (0, 1));
+    assert_eq!(hit(3.75 * GLYPH_W), (0, 2));
+
+    let x = |index| buffer.cursor_position(&Cursor::new(0, index)).unwrap().0;
+    assert_eq!(x(1), GLYPH_W as i32);
+    assert_eq!(x(2), (4.0 * GLYPH_W) as i32);
+}
+
+#[test]
+fn tab_wraps_line() {
+    let mut font_system = font_system();
+    let buffer = tab_buffer(&mut font_system, "abc\tdef", 5.5 * GLYPH_W, 4);
+
+    // The tab fills the first run up to its stop, so the next word does not fit
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 2);
+    let tab = runs[0].glyphs.last().unwrap();
+    assert_eq!((tab.start, tab.end), (3, 4));
+    assert!((tab.w - GLYPH_W).abs() < 0.01);
+    assert_eq!(runs[1].glyphs[0].start, 4);
+    assert_eq!(runs[1].glyphs[0].x, 0.0);
+}
+
+#[test]
+fn tab_cursor_motion() {
+    let mut font_system = font_system();
+    let buffer = tab_buffer(&mut font_system, "a\tb", 500.0, 4);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(&mut font_system);
+    let x = |editor: &Editor| editor.buffer().cursor_position(&editor.cursor()).unwrap().0;
+
+    // The tab is stepped over as one cluster, from its start to the stop
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::Right);
+    assert_eq!(editor.cursor().index, 2);
+    assert_eq!(x(&editor), (4.0 * GLYPH_W) as i32);
+    editor.action(&mut font_system, Action::Left);
+    assert_eq!(editor.cursor().index, 1);
+    assert_eq!(x(&editor), GLYPH_W as i32);
+
+    // Typing before the tab narrows it, so the text after it stays on the stop
+    editor.action(&mut font_system, Action::Insert('x'));
+    editor.shape_as_needed(&mut font_system);
+    assert!((last_x(editor.buffer()) - 4.0 * GLYPH_W).abs() < 0.01);
+
+    // Deleting the tab removes all of its width at once
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Backspace);
+    editor.shape_as_needed(&mut font_system);
+    assert_eq!(editor.buffer().lines[0].text(), "axb");
+    assert!((last_x(editor.buffer()) - 2.0 * GLYPH_W).abs() < 0.01);
+}
@@ -0,0 +1,29 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn invalid_utf8_becomes_replacement_characters() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(500.0, 100.0);
+    buffer.set_text_lossy(b"a\xFFb\xC3\nc", attrs, Shaping::Advanced);
+
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.lines[0].text(), "a\u{FFFD}b\u{FFFD}");
+    assert_eq!(buffer.lines[1].text(), "c");
+
+    // Each replacement character is shaped as its own glyph at the bad position
+    let glyphs = buffer
+        .layout_runs()
+        .filter(|run| run.line_i == 0)
+        .flat_map(|run| run.glyphs.iter().map(|glyph| (glyph.start, glyph.end)))
+        .collect::<Vec<_>>();
+    assert_eq!(glyphs, vec![(0, 1), (1, 4), (4, 5), (5, 8)]);
+}
@@ -0,0 +1,50 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use cosmic_text::{fontdb, Attrs, Buffer, Editor, Family, FontSystem, Metrics, Shaping, Weight};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn shared_types_are_send() {
+    assert_send_sync::<FontSystem>();
+    assert_send_sync::<Buffer>();
+    assert_send::<Editor>();
+    #[cfg(feature = "swash")]
+    assert_send::<cosmic_text::SwashCache>();
+}
+
+#[test]
+fn shape_on_background_threads() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let font_system = Arc::new(Mutex::new(font_system));
+
+    // Each thread shapes its own buffer with the shared font system, and sends it back
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let font_system = Arc::clone(&font_system);
+            thread::spawn(move || {
+                let mut font_system = font_system.lock().unwrap();
+                let attrs = Attrs::new()
+                    .family(Family::Name("Fira Mono"))
+                    .weight(Weight::MEDIUM);
+                let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+                buffer.set_size(&mut font_system, 500.0, 100.0);
+                let text = "a".repeat(i + 1);
+                buffer.set_text(&mut font_system, &text, attrs, Shaping::Advanced);
+                buffer
+            })
+        })
+        .collect();
+
+    for (i, thread) in threads.into_iter().enumerate() {
+        let buffer = thread.join().unwrap();
+        let run = buffer.layout_runs().next().unwrap();
+        assert_eq!(run.glyphs.len(), i + 1);
+    }
+}
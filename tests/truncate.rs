@@ -0,0 +1,78 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, BufferLine, Family, FontSystem, Shaping, Truncate, Weight,
+};
+
+#[test]
+fn truncate_path() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut line = BufferLine::new(
+        "/very/long/path/to/file.txt",
+        AttrsList::new(attrs),
+        Shaping::Advanced,
+    );
+
+    // Every glyph of the monospace font, including the ellipsis, has the same advance
+    let font_size = 14.0;
+    let advance = line
+        .shape(&mut font_system)
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.glyphs.iter())
+        .next()
+        .unwrap()
+        .x_advance
+        * font_size;
+    let cells = |n: f32| advance * n + advance / 4.0;
+
+    // Text that fits is not truncated
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, cells(27.0), Truncate::Path),
+        "/very/long/path/to/file.txt"
+    );
+
+    // The file name is kept when space is tight
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, cells(10.0), Truncate::Path),
+        "\u{2026}/file.txt"
+    );
+
+    // Leftover space is filled from the start
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, cells(15.0), Truncate::Path),
+        "/very\u{2026}/file.txt"
+    );
+
+    // If the file name does not fit, the end is kept
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, cells(6.0), Truncate::Path),
+        "\u{2026}e.txt"
+    );
+
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, cells(6.0), Truncate::End),
+        "/very\u{2026}"
+    );
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, cells(7.0), Truncate::Middle),
+        "/ve\u{2026}txt"
+    );
+
+    // Nothing fits
+    assert_eq!(
+        line.truncated_text(&mut font_system, font_size, 0.0, Truncate::Path),
+        ""
+    );
+
+    // Truncating does not fill the shaping cache
+    line.reset();
+    line.truncated_text(&mut font_system, font_size, cells(6.0), Truncate::End);
+    assert!(line.is_reset());
+}
@@ -0,0 +1,97 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, UnderlineStyle, Weight,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn attrs() -> Attrs<'static> {
+    Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM)
+}
+
+fn underline_rects(style: UnderlineStyle) -> Vec<(i32, i32, u32, u32)> {
+    decoration_rects(attrs().underline(style))
+}
+
+fn decoration_rects(attrs: Attrs) -> Vec<(i32, i32, u32, u32)> {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(500.0, 100.0);
+    buffer.set_text("misspeled", attrs, Shaping::Advanced);
+
+    let mut rects = Vec::new();
+    for run in buffer.layout_runs() {
+        run.draw_underlines(Color::rgb(0xFF, 0, 0), |x, y, w, h, _| {
+            rects.push((x, y, w, h))
+        });
+    }
+    rects
+}
+
+#[test]
+fn solid_underline() {
+    let rects = underline_rects(UnderlineStyle::Solid);
+    assert_eq!(rects.len(), 1);
+}
+
+#[test]
+fn wavy_underline() {
+    let rects = underline_rects(UnderlineStyle::Wavy);
+    assert!(rects.len() > 1);
+    let min_y = rects.iter().map(|rect| rect.1).min().unwrap();
+    let max_y = rects.iter().map(|rect| rect.1).max().unwrap();
+    assert!(min_y < max_y, "wavy underline is flat");
+}
+
+#[test]
+fn strikethrough() {
+    let rects = decoration_rects(attrs().strikethrough(true));
+    assert_eq!(rects.len(), 1);
+    let (x, y, w, _) = rects[0];
+    assert_eq!((x, w), (0, (9.0 * GLYPH_W).round() as u32));
+
+    // The strikethrough is above the underline
+    let both = decoration_rects(attrs().underline(UnderlineStyle::Solid).strikethrough(true));
+    assert_eq!(both.len(), 2);
+    assert!(both[1].1 < both[0].1);
+    assert_eq!(both[1].1, y);
+}
+
+#[test]
+fn metadata_glyph_at() {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    let link = attrs().metadata(7).underline(UnderlineStyle::Solid);
+    buffer.set_rich_text(
+        &mut font_system,
+        [("see ", attrs()), ("docs", link), (" here", attrs())],
+        attrs(),
+        Shaping::Advanced,
+    );
+
+    // A position maps back to the metadata of the glyph under it
+    let metadata = |x: f32, y: f32| {
+        buffer
+            .glyph_at(x, y)
+            .map(|(line_i, glyph)| (line_i, glyph.metadata))
+    };
+    assert_eq!(metadata(4.5 * GLYPH_W, 10.0), Some((0, 7)));
+    assert_eq!(metadata(7.9 * GLYPH_W, 10.0), Some((0, 7)));
+    assert_eq!(metadata(8.1 * GLYPH_W, 10.0), Some((0, 0)));
+    assert_eq!(metadata(1.0, 10.0), Some((0, 0)));
+
+    // Past the end of the text or below the last line there is no glyph
+    assert_eq!(metadata(14.0 * GLYPH_W, 10.0), None);
+    assert_eq!(metadata(1.0, 50.0), None);
+}
@@ -0,0 +1,236 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 200.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn text(editor: &Editor) -> String {
+    let lines: Vec<&str> = editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.text())
+        .collect();
+    lines.join("\n")
+}
+
+fn cursor(editor: &Editor) -> (usize, usize) {
+    let cursor = editor.cursor();
+    (cursor.line, cursor.index)
+}
+
+fn type_str(editor: &mut Editor, font_system: &mut FontSystem, s: &str) {
+    for c in s.chars() {
+        editor.action(font_system, Action::Insert(c));
+    }
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn undo_typing_by_word() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "");
+
+    type_str(&mut editor, &mut font_system, "hello world");
+    assert_eq!(text(&editor), "hello world");
+
+    editor.action(&mut font_system, Action::Undo);
+    assert_eq!(text(&editor), "hello ");
+    assert_eq!(cursor(&editor), (0, 6));
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert!(!editor.undo());
+
+    editor.action(&mut font_system, Action::Redo);
+    assert_eq!(text(&editor), "hello ");
+    assert!(editor.redo());
+    assert_eq!(text(&editor), "hello world");
+    assert_eq!(cursor(&editor), (0, 11));
+    assert!(!editor.redo());
+}
+
+#[test]
+fn undo_cursor_jump_ends_typing() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "");
+
+    type_str(&mut editor, &mut font_system, "ab");
+    editor.action(&mut font_system, Action::Left);
+    editor.action(&mut font_system, Action::Right);
+    type_str(&mut editor, &mut font_system, "cd");
+    assert_eq!(text(&editor), "abcd");
+
+    editor.undo();
+    assert_eq!(text(&editor), "ab");
+    editor.undo();
+    assert_eq!(text(&editor), "");
+}
+
+#[test]
+fn undo_deletions() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo");
+
+    // Joining lines
+    editor.set_cursor(Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "onetwo");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwo");
+    assert_eq!(cursor(&editor), (1, 0));
+
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Delete);
+    assert_eq!(text(&editor), "onetwo");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwo");
+
+    // Single characters
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "on\ntwo");
+    editor.set_cursor(Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::Delete);
+    assert_eq!(text(&editor), "on\nwo");
+    editor.undo();
+    assert_eq!(text(&editor), "on\ntwo");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwo");
+    assert_eq!(cursor(&editor), (0, 3));
+
+    // Replacing a selection is one change
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.set_select_opt(Some(Cursor::new(1, 2)));
+    editor.action(&mut font_system, Action::Insert('x'));
+    assert_eq!(text(&editor), "oxo");
+    editor.action(&mut font_system, Action::Enter);
+    assert_eq!(text(&editor), "ox\no");
+    editor.undo();
+    assert_eq!(text(&editor), "oxo");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwo");
+    editor.redo();
+    editor.redo();
+    assert_eq!(text(&editor), "ox\no");
+}
+
+#[test]
+fn undo_deletions_together() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo three");
+
+    // Backspacing across a line is one change
+    editor.set_cursor(Cursor::new(1, 2));
+    editor.action(&mut font_system, Action::Backspace);
+    editor.action(&mut font_system, Action::Backspace);
+    editor.action(&mut font_system, Action::Backspace);
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "ono three");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwo three");
+    assert_eq!(cursor(&editor), (1, 2));
+    editor.redo();
+    assert_eq!(text(&editor), "ono three");
+    assert_eq!(cursor(&editor), (0, 2));
+    editor.undo();
+
+    // Deleting forward is one change, which is separate from backspacing
+    editor.set_cursor(Cursor::new(0, 2));
+    editor.action(&mut font_system, Action::Delete);
+    editor.action(&mut font_system, Action::Delete);
+    editor.action(&mut font_system, Action::Delete);
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "owo three");
+    editor.undo();
+    assert_eq!(text(&editor), "onwo three");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwo three");
+    assert_eq!(cursor(&editor), (0, 2));
+    editor.redo();
+    assert_eq!(text(&editor), "onwo three");
+    editor.undo();
+
+    // Moving the cursor starts a new change
+    editor.set_cursor(Cursor::new(1, 3));
+    editor.action(&mut font_system, Action::Delete);
+    editor.action(&mut font_system, Action::Right);
+    editor.action(&mut font_system, Action::Delete);
+    assert_eq!(text(&editor), "one\ntwotree");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwothree");
+    assert_eq!(cursor(&editor), (1, 4));
+
+    // Typing after deleting is a new change
+    editor.action(&mut font_system, Action::Backspace);
+    type_str(&mut editor, &mut font_system, "-");
+    assert_eq!(text(&editor), "one\ntwo-hree");
+    editor.undo();
+    assert_eq!(text(&editor), "one\ntwohree");
+}
+
+#[test]
+fn new_change_clears_redo() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "");
+
+    type_str(&mut editor, &mut font_system, "one two");
+    editor.undo();
+    assert_eq!(text(&editor), "one ");
+    type_str(&mut editor, &mut font_system, "three");
+    assert!(!editor.redo());
+    assert_eq!(text(&editor), "one three");
+    editor.undo();
+    assert_eq!(text(&editor), "one ");
+}
+
+#[test]
+fn history_limit() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "");
+    assert_eq!(editor.history_limit(), usize::MAX);
+
+    type_str(&mut editor, &mut font_system, "a b c");
+    editor.set_history_limit(2);
+    assert_eq!(editor.history_limit(), 2);
+    type_str(&mut editor, &mut font_system, " d");
+
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert!(!editor.undo());
+    assert_eq!(text(&editor), "a b ");
+}
+
+#[test]
+fn set_text_clears_history() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "");
+
+    type_str(&mut editor, &mut font_system, "abc");
+    editor.buffer_mut().set_text(
+        &mut font_system,
+        "new",
+        Attrs::new().family(Family::Name("Fira Mono")),
+        Shaping::Advanced,
+    );
+    editor.set_cursor(Cursor::new(0, 3));
+    assert!(!editor.undo());
+    assert_eq!(text(&editor), "new");
+}
@@ -0,0 +1,22 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Cursor, FontSystem, Metrics, Shaping};
+
+#[test]
+fn unmatched_brackets() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+
+    buffer.set_text("(a (b)", Attrs::new(), Shaping::Advanced);
+    assert_eq!(buffer.unmatched_brackets(), vec![Cursor::new(0, 0)]);
+
+    // Brackets are matched across lines, and mismatched closing brackets are reported
+    buffer.set_text("{\n  [a)]\n}]", Attrs::new(), Shaping::Advanced);
+    assert_eq!(
+        buffer.unmatched_brackets(),
+        vec![Cursor::new(1, 4), Cursor::new(2, 1)]
+    );
+
+    buffer.set_text("fn f() { [0] }", Attrs::new(), Shaping::Advanced);
+    assert!(buffer.unmatched_brackets().is_empty());
+}
@@ -0,0 +1,99 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, Buffer, BufferLine, Family, FontSystem, Metrics, Shaping, Weight,
+};
+
+#[test]
+fn used_fonts_reports_fonts_and_glyphs() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Register the same font data again under another family name, as a second font
+    let mut copy = font_system.db().faces().next().unwrap().clone();
+    copy.families = vec![(
+        "Fira Mono Copy".to_string(),
+        fontdb::Language::English_UnitedStates,
+    )];
+    font_system.db_mut().push_face_info(copy);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    assert_eq!(ids.len(), 2);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let copy_attrs = attrs.family(Family::Name("Fira Mono Copy"));
+    let mut attrs_list = AttrsList::new(attrs);
+    attrs_list.add_span(4..7, copy_attrs);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.lines = vec![BufferLine::new("aab bcc", attrs_list, Shaping::Advanced)];
+    buffer.shape_until_scroll(&mut font_system);
+
+    let glyph_id = |buffer: &Buffer, start: usize| {
+        buffer
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter())
+            .find(|glyph| glyph.start == start)
+            .map(|glyph| (glyph.font_id, glyph.glyph_id))
+            .unwrap()
+    };
+    let (first_id, a) = glyph_id(&buffer, 0);
+    let (_, b) = glyph_id(&buffer, 2);
+    let (_, space) = glyph_id(&buffer, 3);
+    let (second_id, c) = glyph_id(&buffer, 5);
+    assert_ne!(first_id, second_id);
+
+    let mut first_glyphs = vec![a, b, space];
+    first_glyphs.sort();
+    let mut second_glyphs = vec![b, c];
+    second_glyphs.sort();
+    let mut expected = vec![(first_id, first_glyphs), (second_id, second_glyphs)];
+    expected.sort();
+    assert_eq!(buffer.used_fonts(), expected);
+}
+
+// The wrap indicator and tab leader are drawn with the default attributes, which can select a
+// different font than the text
+#[test]
+fn used_fonts_reports_wrap_indicator_and_tab_leader() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let mut copy = font_system.db().faces().next().unwrap().clone();
+    copy.families = vec![(
+        "Fira Mono Copy".to_string(),
+        fontdb::Language::English_UnitedStates,
+    )];
+    font_system.db_mut().push_face_info(copy);
+    font_system.db_mut().set_sans_serif_family("Fira Mono Copy");
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    assert_eq!(ids.len(), 2);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 100.0, 100.0);
+    buffer.set_text(&mut font_system, "a\tb ccc ddd", attrs, Shaping::Advanced);
+    buffer.set_wrap_indicator(&mut font_system, Some('>'));
+    buffer.set_tab_leader(&mut font_system, Some('.'));
+    buffer.shape_until_scroll(&mut font_system);
+    assert!(buffer.layout_runs().count() > 1);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let indicator = &run.wrap_indicator_glyphs[0];
+    let leader = &run.tab_leader[0];
+    assert_eq!(indicator.font_id, ids[1]);
+    assert_eq!(leader.font_id, ids[1]);
+    assert!(run.glyphs.iter().all(|glyph| glyph.font_id == ids[0]));
+
+    let mut copy_glyphs = vec![indicator.glyph_id, leader.glyph_id];
+    copy_glyphs.sort();
+    let used = buffer.used_fonts();
+    assert_eq!(used.len(), 2);
+    assert!(used.contains(&(ids[1], copy_glyphs)));
+}
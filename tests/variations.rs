@@ -0,0 +1,108 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, Buffer, BufferLine, CacheKey, Family, FontSystem, Metrics, Shaping,
+    SwashCache, Variation, Weight, Wrap,
+};
+
+const LIGHT: [Variation; 2] = [
+    Variation::new(b"wght", 350.0),
+    Variation::new(b"wdth", 75.0),
+];
+const HEAVY: [Variation; 1] = [Variation::new(b"wght", 800.0)];
+
+fn cache_keys(font_system: &mut FontSystem, attrs: Attrs, shaping: Shaping) -> Vec<CacheKey> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, "Ag", attrs, shaping);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| glyph.physical((0., 0.), 1.0).cache_key)
+        .collect()
+}
+
+#[test]
+fn variations_in_cache_keys() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let plain = cache_keys(&mut font_system, attrs, Shaping::Advanced);
+    assert!(plain.iter().all(|key| key.variations_id == 0));
+
+    // Different coordinates get different keys
+    let light = cache_keys(
+        &mut font_system,
+        attrs.variations(&LIGHT),
+        Shaping::Advanced,
+    );
+    let heavy = cache_keys(
+        &mut font_system,
+        attrs.variations(&HEAVY),
+        Shaping::Advanced,
+    );
+    assert_eq!(light.len(), plain.len());
+    assert_ne!(light[0].variations_id, 0);
+    assert_ne!(light[0].variations_id, heavy[0].variations_id);
+    assert_ne!(light[0], plain[0]);
+    assert_eq!(font_system.variations(light[0].variations_id), &LIGHT);
+    assert_eq!(font_system.variations(heavy[0].variations_id), &HEAVY);
+    assert_eq!(font_system.variations(0), &[]);
+
+    // The same coordinates get the same key, with either shaping strategy
+    let basic = cache_keys(&mut font_system, attrs.variations(&LIGHT), Shaping::Basic);
+    assert_eq!(basic, light);
+
+    // The static font has none of the axes, so they are ignored
+    let mut cache = SwashCache::new();
+    let plain_image = cache
+        .get_image_uncached(&mut font_system, plain[0])
+        .unwrap();
+    let light_image = cache
+        .get_image_uncached(&mut font_system, light[0])
+        .unwrap();
+    let (plain_placement, light_placement) = (plain_image.placement, light_image.placement);
+    assert_eq!(
+        (plain_placement.left, plain_placement.top),
+        (light_placement.left, light_placement.top)
+    );
+    assert_eq!(
+        (plain_placement.width, plain_placement.height),
+        (light_placement.width, light_placement.height)
+    );
+    assert_eq!(plain_image.data, light_image.data);
+}
+
+#[test]
+fn variations_split_runs() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut attrs_list = AttrsList::new(attrs);
+    attrs_list.add_span(2..4, attrs.variations(&HEAVY));
+    let mut line = BufferLine::new("abcdef", attrs_list, Shaping::Advanced);
+    let layout = line.layout(&mut font_system, 14.0, 500.0, Wrap::Word);
+
+    let ids: Vec<u32> = layout[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.variations_id)
+        .collect();
+    let heavy = font_system.variations_id(&HEAVY);
+    assert_eq!(ids, [0, 0, heavy, heavy, 0, 0]);
+
+    // Glyphs are positioned as if the axes were not set
+    for (i, glyph) in layout[0].glyphs.iter().enumerate() {
+        assert!((glyph.x - i as f32 * 14.0 * 0.6).abs() < 0.01);
+    }
+}
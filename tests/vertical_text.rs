@@ -0,0 +1,63 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Family, FontSystem, Metrics, Shaping, Weight, WritingMode,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn new_buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 100.0, 60.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    buffer
+}
+
+#[test]
+fn vertical_columns() {
+    let mut font_system = font_system();
+    let mut buffer = new_buffer(&mut font_system, "hello world");
+    assert_eq!(buffer.writing_mode(), WritingMode::HorizontalTb);
+    assert_eq!(buffer.layout_runs().count(), 1);
+
+    // Lines wrap at the height of the buffer, and its physical size is kept
+    buffer.set_writing_mode(&mut font_system, WritingMode::VerticalRl);
+    assert_eq!(buffer.size(), (100.0, 60.0));
+    assert_eq!(buffer.layout_runs().count(), 2);
+
+    // The first column is at the right, with the caret across it
+    assert_eq!(buffer.cursor_position(&Cursor::new(0, 0)), Some((80, 0)));
+    assert_eq!(buffer.caret_rect(&Cursor::new(0, 0)), Some((80, 0, 20, 1)));
+    let cursor = buffer.hit(85.0, 1.0).unwrap();
+    assert_eq!((cursor.line, cursor.index), (0, 0));
+    let cursor = buffer.hit(65.0, 1.0).unwrap();
+    assert_eq!((cursor.line, cursor.index), (0, 6));
+
+    buffer.set_writing_mode(&mut font_system, WritingMode::HorizontalTb);
+    assert_eq!(buffer.size(), (100.0, 60.0));
+    assert_eq!(buffer.layout_runs().count(), 1);
+}
+
+#[test]
+fn vertical_upright() {
+    let mut font_system = font_system();
+    let mut buffer = new_buffer(&mut font_system, "ab\u{4E00}\u{3042}");
+    let upright = |buffer: &Buffer| -> Vec<bool> {
+        buffer
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter().map(|glyph| glyph.upright))
+            .collect()
+    };
+    assert_eq!(upright(&buffer), [false, false, false, false]);
+
+    // Only the East Asian characters stand upright
+    buffer.set_writing_mode(&mut font_system, WritingMode::VerticalRl);
+    assert_eq!(upright(&buffer), [false, false, true, true]);
+}
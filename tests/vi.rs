@@ -0,0 +1,95 @@
+#![cfg(feature = "vi")]
+
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Edit, Family, FontSystem, Metrics, Shaping, SyntaxEditor,
+    SyntaxSystem, ViEditor, Weight,
+};
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn text(editor: &ViEditor) -> String {
+    let lines: Vec<&str> = editor
+        .buffer()
+        .lines
+        .iter()
+        .map(|line| line.text())
+        .collect();
+    lines.join("\n")
+}
+
+fn cursor(editor: &ViEditor) -> (usize, usize) {
+    let cursor = editor.cursor();
+    (cursor.line, cursor.index)
+}
+
+fn keys(editor: &mut ViEditor, font_system: &mut FontSystem, keys: &str) {
+    for c in keys.chars() {
+        editor.action(font_system, Action::Insert(c));
+    }
+}
+
+#[test]
+fn vi_motions_and_operators() {
+    let mut font_system = font_system();
+    let syntax_system = SyntaxSystem::new();
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, 500.0, 100.0);
+    buffer.set_text(
+        &mut font_system,
+        "one two three\nfour five\nsix",
+        attrs,
+        Shaping::Advanced,
+    );
+    let editor = SyntaxEditor::new(buffer, &syntax_system, "base16-eighties.dark").unwrap();
+    let mut editor = ViEditor::new(editor);
+    editor.shape_as_needed(&mut font_system);
+    let f = &mut font_system;
+
+    // Operators apply to the following motion
+    keys(&mut editor, f, "dw");
+    assert_eq!(text(&editor), "two three\nfour five\nsix");
+    assert_eq!(editor.register(), Some("one "));
+    keys(&mut editor, f, "P0");
+    assert_eq!(text(&editor), "one two three\nfour five\nsix");
+
+    // Counts repeat motions, and gg and G go to lines
+    keys(&mut editor, f, "2w");
+    assert_eq!(cursor(&editor), (0, 8));
+    keys(&mut editor, f, "G");
+    assert_eq!(cursor(&editor), (2, 0));
+    keys(&mut editor, f, "gg");
+    assert_eq!(cursor(&editor), (0, 0));
+    keys(&mut editor, f, "2G");
+    assert_eq!(cursor(&editor), (1, 0));
+
+    // Doubled operators apply to whole lines
+    keys(&mut editor, f, "dd");
+    assert_eq!(text(&editor), "one two three\nsix");
+    assert_eq!(editor.register(), Some("four five\n"));
+    keys(&mut editor, f, "P");
+    assert_eq!(text(&editor), "one two three\nfour five\nsix");
+    assert_eq!(cursor(&editor), (1, 0));
+    keys(&mut editor, f, "yy");
+    assert_eq!(editor.register(), Some("four five\n"));
+
+    // Change deletes and enters insert mode
+    keys(&mut editor, f, "c$new");
+    editor.shape_as_needed(f);
+    editor.action(f, Action::Escape);
+    assert_eq!(text(&editor), "one two three\nnew\nsix");
+
+    // An operator on a visual selection, which ends before the cursor
+    keys(&mut editor, f, "ggvllx");
+    assert_eq!(text(&editor), "e two three\nnew\nsix");
+    keys(&mut editor, f, "3x");
+    assert_eq!(text(&editor), "wo three\nnew\nsix");
+}
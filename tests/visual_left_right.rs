@@ -0,0 +1,89 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, Shaping,
+    Weight,
+};
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+#[test]
+fn visual_left_right_in_rtl_run() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Left-to-right paragraph containing a right-to-left run
+    let text = "ab \u{5D0}\u{5D1}\u{5D2} cd";
+    let bet = text.find('\u{5D1}').unwrap();
+    let mut editor = new_editor(&mut font_system, text);
+
+    // Left moves visually left, which is forward in the right-to-left run
+    editor.set_cursor(Cursor::new(0, bet));
+    editor.action(&mut font_system, Action::Left);
+    assert_eq!(editor.cursor().index, bet + '\u{5D1}'.len_utf8());
+
+    // Right moves visually right, which is backward in the right-to-left run
+    editor.set_cursor(Cursor::new(0, bet));
+    editor.action(&mut font_system, Action::Right);
+    assert_eq!(editor.cursor().index, bet - '\u{5D0}'.len_utf8());
+
+    // Logical movement is still available
+    editor.set_cursor(Cursor::new(0, bet));
+    editor.action(&mut font_system, Action::Previous);
+    assert_eq!(editor.cursor().index, bet - '\u{5D0}'.len_utf8());
+
+    // Left-to-right runs are unchanged
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::Left);
+    assert_eq!(editor.cursor().index, 0);
+
+    // Right-to-left paragraph
+    let mut editor = new_editor(&mut font_system, "\u{5D0}\u{5D1}\u{5D2}");
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::Left);
+    assert_eq!(editor.cursor().index, '\u{5D0}'.len_utf8());
+}
+
+#[test]
+fn repeated_right_moves_across_rtl_run() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    // Hebrew letters are two bytes each, shown right to left between the spaces at 3 and 10
+    let mut editor = new_editor(&mut font_system, "abc \u{5D0}\u{5D1}\u{5D2} def");
+    editor.set_cursor(Cursor::new(0, 0));
+
+    let position = |editor: &Editor| editor.buffer().cursor_position(&editor.cursor()).unwrap();
+    let mut indices = vec![0];
+    let mut positions = vec![position(&editor)];
+    for _ in 0..11 {
+        editor.action(&mut font_system, Action::Right);
+        indices.push(editor.cursor().index);
+        positions.push(position(&editor));
+    }
+    assert_eq!(indices, [0, 1, 2, 3, 10, 8, 6, 4, 11, 12, 13, 14]);
+
+    // Every press moves the caret rightward on screen
+    for pair in positions.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "{:?}", positions);
+    }
+
+    // Left retraces the same positions
+    for (&index, &position_before) in indices.iter().zip(positions.iter()).rev().skip(1) {
+        editor.action(&mut font_system, Action::Left);
+        assert_eq!(editor.cursor().index, index);
+        assert_eq!(position(&editor), position_before);
+    }
+}
@@ -0,0 +1,28 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Cursor, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn word_at_cursor() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "let foo_bar = 1;",
+        attrs,
+        Shaping::Advanced,
+    );
+
+    // Underscores connect words
+    assert_eq!(buffer.word_at(Cursor::new(0, 6)), Some((4..11, "foo_bar")));
+    assert_eq!(buffer.word_at(Cursor::new(0, 11)), Some((4..11, "foo_bar")));
+    assert_eq!(buffer.word_at(Cursor::new(0, 0)), Some((0..3, "let")));
+    // Not in a word
+    assert_eq!(buffer.word_at(Cursor::new(0, 12)), None);
+    assert_eq!(buffer.word_at(Cursor::new(1, 0)), None);
+}
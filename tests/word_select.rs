@@ -0,0 +1,94 @@
+use cosmic_text::{
+    fontdb, Action, Attrs, Buffer, Edit, Editor, Family, FontSystem, Metrics, Shaping, Weight,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 500.0, 100.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    editor
+}
+
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+// The x position at a fraction of the glyph width into the line
+fn x(glyphs: f32) -> i32 {
+    (glyphs * GLYPH_W) as i32
+}
+
+#[test]
+fn double_click_selects_word() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "hello, world...  foo");
+
+    let mut double_click = |glyphs| {
+        let action = Action::DoubleClick {
+            x: x(glyphs),
+            y: 10,
+        };
+        editor.action(&mut font_system, action);
+        editor.copy_selection()
+    };
+    // Either half of a character selects its word
+    assert_eq!(double_click(1.25).as_deref(), Some("hello"));
+    assert_eq!(double_click(4.75).as_deref(), Some("hello"));
+    // Runs of punctuation and whitespace are words
+    assert_eq!(double_click(5.25).as_deref(), Some(","));
+    assert_eq!(double_click(13.5).as_deref(), Some("..."));
+    assert_eq!(double_click(15.25).as_deref(), Some("  "));
+    // Past the end of the line is the last word
+    assert_eq!(double_click(30.0).as_deref(), Some("foo"));
+}
+
+#[test]
+fn drag_after_double_click_extends_by_words() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "hello, world...  foo");
+
+    editor.action(&mut font_system, Action::DoubleClick { x: x(8.5), y: 10 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("world"));
+
+    editor.action(&mut font_system, Action::Drag { x: x(18.25), y: 10 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("world...  foo"));
+    assert_eq!(editor.cursor().index, 20);
+
+    // Dragging before the word keeps it selected, extending to the start of the word under the
+    // point
+    editor.action(&mut font_system, Action::Drag { x: x(1.25), y: 10 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("hello, world"));
+    assert_eq!(editor.cursor().index, 0);
+
+    // A single click goes back to dragging by characters
+    editor.action(&mut font_system, Action::Click { x: x(1.0), y: 10 });
+    editor.action(&mut font_system, Action::Drag { x: x(3.0), y: 10 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("el"));
+}
+
+#[test]
+fn triple_click_selects_line() {
+    let mut font_system = font_system();
+    let mut editor = new_editor(&mut font_system, "one\ntwo\nthree");
+
+    editor.action(&mut font_system, Action::TripleClick { x: x(1.5), y: 30 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("two"));
+
+    // Dragging extends by whole lines
+    editor.action(&mut font_system, Action::Drag { x: x(1.5), y: 50 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("two\nthree"));
+    editor.action(&mut font_system, Action::Drag { x: x(1.5), y: 10 });
+    assert_eq!(editor.copy_selection().as_deref(), Some("one\ntwo"));
+    assert_eq!(editor.cursor().index, 0);
+}
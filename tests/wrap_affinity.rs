@@ -0,0 +1,75 @@
+use cosmic_text::{
+    fontdb, Action, Affinity, Attrs, Buffer, Color, Cursor, Edit, Editor, Family, FontSystem,
+    Metrics, Shaping, Weight,
+};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+// "abcd " fits on the first row, and "efgh" wraps to the second
+fn wrapped_editor(font_system: &mut FontSystem) -> Editor {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, 6.0 * GLYPH_W, 100.0);
+    buffer.set_text(font_system, "abcd efgh", attrs, Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.shape_as_needed(font_system);
+    assert_eq!(editor.buffer().layout_runs().count(), 2);
+    editor
+}
+
+fn caret(editor: &Editor) -> (usize, Affinity, (i32, i32)) {
+    let cursor = editor.cursor();
+    let position = editor.buffer().cursor_position(&cursor).unwrap();
+    (cursor.index, cursor.affinity, position)
+}
+
+#[test]
+fn soft_wrap_affinity() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let mut editor = wrapped_editor(&mut font_system);
+    let row_end = (5.0 * GLYPH_W) as i32;
+
+    // Clicking past the end of the first row places the caret at its end
+    editor.action(
+        &mut font_system,
+        Action::Click {
+            x: (5.5 * GLYPH_W) as i32,
+            y: 10,
+        },
+    );
+    assert_eq!(caret(&editor), (5, Affinity::Before, (row_end, 0)));
+
+    // Clicking at the start of the second row places the caret there, at the same index
+    editor.action(&mut font_system, Action::Click { x: 1, y: 30 });
+    assert_eq!(caret(&editor), (5, Affinity::After, (0, 20)));
+
+    // Home and End stay on the row of the caret
+    editor.action(&mut font_system, Action::End);
+    assert_eq!(
+        caret(&editor),
+        (9, Affinity::Before, ((4.0 * GLYPH_W) as i32, 20))
+    );
+    editor.action(&mut font_system, Action::Home);
+    assert_eq!(caret(&editor), (5, Affinity::After, (0, 20)));
+
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::End);
+    assert_eq!(caret(&editor), (5, Affinity::Before, (row_end, 0)));
+    editor.action(&mut font_system, Action::Home);
+    assert_eq!(caret(&editor), (0, Affinity::After, (0, 0)));
+
+    // The cursor color does not change which row it is on
+    let mut cursor = Cursor::new_with_affinity(0, 5, Affinity::After);
+    cursor.color = Some(Color::rgb(0xFF, 0, 0));
+    editor.set_cursor(cursor);
+    editor.action(&mut font_system, Action::End);
+    assert_eq!(
+        caret(&editor),
+        (9, Affinity::Before, ((4.0 * GLYPH_W) as i32, 20))
+    );
+}
@@ -0,0 +1,31 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn wrap_at_columns() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(500.0, 500.0);
+    buffer.set_text("abcdef", attrs, Shaping::Advanced);
+    buffer.set_wrap_columns(Some(3));
+
+    let lines: Vec<String> = buffer
+        .layout_runs()
+        .map(|run| {
+            let start = run.glyphs.first().map_or(0, |glyph| glyph.start);
+            let end = run.glyphs.last().map_or(0, |glyph| glyph.end);
+            run.text[start..end].to_string()
+        })
+        .collect();
+    assert_eq!(lines, ["abc", "def"]);
+
+    buffer.set_wrap_columns(None);
+    assert_eq!(buffer.layout_runs().count(), 1);
+}
@@ -0,0 +1,48 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn wrap_indicator_on_soft_wraps_only() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(100.0, 200.0);
+    buffer.set_text("a line that wraps\nshort", attrs, Shaping::Advanced);
+    buffer.set_wrap_indicator(Some('>'));
+
+    let runs = buffer
+        .layout_runs()
+        .map(|run| {
+            let glyphs = run
+                .wrap_indicator_glyphs
+                .iter()
+                .map(|glyph| (glyph.x, glyph.w))
+                .collect::<Vec<_>>();
+            (run.line_i, glyphs)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(runs.len(), 3);
+
+    // The first visual line of the wrapped line has the indicator at the right edge
+    let (line_i, glyphs) = &runs[0];
+    assert_eq!(*line_i, 0);
+    assert_eq!(glyphs.len(), 1);
+    let (x, w) = glyphs[0];
+    assert!(w > 0.0);
+    assert!((x + w - 100.0).abs() < 0.01);
+
+    // The end of the wrapped line and the hard broken line do not
+    assert!(runs[1].1.is_empty());
+    assert!(runs[2].1.is_empty());
+
+    buffer.set_wrap_indicator(None);
+    assert!(buffer
+        .layout_runs()
+        .all(|run| run.wrap_indicator_glyphs.is_empty()));
+}
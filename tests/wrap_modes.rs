@@ -0,0 +1,74 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight, Wrap};
+
+const GLYPH_W: f32 = 14.0 * 0.6;
+
+/// Get the start index and glyph count of each layout run of `text` wrapped at `width`
+fn runs(font_system: &mut FontSystem, text: &str, wrap: Wrap, width: f32) -> Vec<(usize, usize)> {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, wrap);
+    assert_eq!(buffer.wrap(), wrap);
+    buffer.set_size(font_system, width, 500.0);
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+
+    let runs: Vec<(usize, usize)> = buffer
+        .layout_runs()
+        .map(|run| {
+            let start = run.glyphs.iter().map(|glyph| glyph.start).min().unwrap();
+            (start, run.glyphs.len())
+        })
+        .collect();
+    // The layout runs match the cached layout of the line
+    assert_eq!(
+        buffer.line_layout(font_system, 0).unwrap().len(),
+        runs.len()
+    );
+    runs
+}
+
+#[test]
+fn wrap_modes() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    assert_eq!(buffer.wrap(), Wrap::Word);
+    assert_eq!(Wrap::default(), Wrap::Word);
+
+    // Five glyphs fit on a line, but not six
+    let width = GLYPH_W * 5.5;
+    let text = "aaa bbb ccc";
+
+    // Words wrap at the spaces between them
+    let starts: Vec<usize> = runs(&mut font_system, text, Wrap::Word, width)
+        .iter()
+        .map(|(start, _)| *start)
+        .collect();
+    assert_eq!(starts, [0, 4, 8]);
+
+    // Glyphs wrap inside of words
+    let glyph_runs = runs(&mut font_system, text, Wrap::Glyph, width);
+    assert!(glyph_runs
+        .iter()
+        .any(|(start, _)| ![0, 4, 8].contains(start)));
+
+    // No wrapping keeps a single line wider than the buffer
+    assert_eq!(
+        runs(&mut font_system, text, Wrap::None, width),
+        [(0, text.len())]
+    );
+
+    // A word wider than the line falls back to wrapping glyphs
+    let text = "abcdefghijkl";
+    let word_runs = runs(&mut font_system, text, Wrap::Word, width);
+    assert!(word_runs.len() >= 3);
+    assert!(word_runs.iter().all(|(_, len)| (1..=5).contains(len)));
+    assert_eq!(
+        word_runs.iter().map(|(_, len)| len).sum::<usize>(),
+        text.len()
+    );
+}
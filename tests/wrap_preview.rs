@@ -0,0 +1,31 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn wrap_preview_reports_breaks_without_layout() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(1000.0, 100.0);
+    buffer.set_text("aaa bbb ccc ddd eee fff ggg", attrs, Shaping::Advanced);
+
+    let layout = buffer.line_layout(0).unwrap();
+    assert_eq!(layout.len(), 1);
+    let advance = layout[0].glyphs[0].w;
+
+    // Room for "aaa bbb ccc" on each row
+    let breaks = buffer.wrap_preview(0, advance * 11.0 + 1.0);
+    assert_eq!(breaks, vec![12, 24]);
+
+    // The committed layout is unchanged
+    assert_eq!(buffer.lines[0].layout_opt().as_ref().unwrap().len(), 1);
+
+    assert!(buffer.wrap_preview(0, 1000.0).is_empty());
+    assert!(buffer.wrap_preview(1, 100.0).is_empty());
+}